@@ -0,0 +1,69 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+// Compares SHA-256 digests byte by byte without short-circuiting, so the time taken says
+// nothing about how much of the key matched or how long it is.
+fn keys_match(given: &str, expected: &str) -> bool {
+    let (a, b) = (Sha256::digest(given.as_bytes()), Sha256::digest(expected.as_bytes()));
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Middleware for the /api routes when API_KEY is set: requires `Authorization: Bearer <key>`
+// and answers 401 otherwise.
+pub async fn require_api_key(State(api_key): State<Arc<String>>, req: Request, next: Next) -> Response {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim);
+    match token {
+        Some(token) if keys_match(token, &api_key) => next.run(req).await,
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            Json(serde_json::json!({ "error": "Missing or invalid API key" })),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn status_with(auth: Option<&str>) -> StatusCode {
+        let app = Router::new()
+            .route("/wallet/list", get(|| async { "ok" }))
+            .route_layer(middleware::from_fn_with_state(Arc::new("s3cret".to_string()), require_api_key));
+        let mut req = Request::builder().uri("/wallet/list");
+        if let Some(auth) = auth {
+            req = req.header(header::AUTHORIZATION, auth);
+        }
+        app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_api_key_required() {
+        assert_eq!(status_with(Some("Bearer s3cret")).await, StatusCode::OK);
+        assert_eq!(status_with(Some("Bearer s3cret2")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status_with(Some("s3cret")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status_with(None).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_keys_match() {
+        assert!(keys_match("abc", "abc"));
+        assert!(!keys_match("abc", "abd"));
+        assert!(!keys_match("", "abc"));
+    }
+}
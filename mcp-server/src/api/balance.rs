@@ -22,6 +22,7 @@ pub struct BalanceOutput {
     pub address: String,
     pub balance: String,
     pub denom: String,
+    pub symbol: String,
 }
 
 // The handler function for the GET /balance/{chain_id}/{address} endpoint.
@@ -41,10 +42,16 @@ pub async fn get_balance_handler(
         }
     };
 
+    let base_url = match state.config.etherscan_base_url(&path.chain_id) {
+        Ok(url) => url,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
     // Use Etherscan API directly
     let client = reqwest::Client::new();
     match crate::blockchain::services::balance::get_balance(
         &client,
+        &base_url,
         &path.chain_id,
         &path.address,
         etherscan_api_key,
@@ -57,6 +64,7 @@ pub async fn get_balance_handler(
                 address: path.address.clone(),
                 balance: balance_response.amount,
                 denom: balance_response.denom,
+                symbol: state.config.native_currency(&path.chain_id).symbol,
             };
             (StatusCode::OK, Json(output)).into_response()
         }
@@ -102,9 +102,14 @@ pub async fn get_contract_source_code_handler(
         }
     };
 
+    let base_url = match state.config.etherscan_base_url(&params.chain_id) {
+        Ok(url) => url,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
     match state
         .evm_client
-        .get_contract_source_code(&params.chain_id, &params.address, etherscan_api_key)
+        .get_contract_source_code(&base_url, &params.chain_id, &params.address, etherscan_api_key)
         .await
     {
         Ok(source_code) => (StatusCode::OK, Json(source_code)).into_response(),
@@ -207,10 +207,6 @@ mod tests {
     }
 
     fn create_test_state() -> AppState {
-        use std::path::PathBuf;
-        use std::sync::Arc;
-        use tokio::sync::Mutex;
-        use crate::blockchain::{client::EvmClient, wallet_manager::WalletManager};
         use crate::mcp::wallet_storage::WalletStorage;
 
         // Create a test config
@@ -232,17 +228,6 @@ mod tests {
         // Initialize wallet storage
         let wallet_storage = WalletStorage::new(wallet_storage_path.clone());
         
-        // Create a new EvmClient with empty config for testing
-        let mut rpc_urls = std::collections::HashMap::new();
-        rpc_urls.insert("1".to_string(), "https://mainnet.example.com".to_string());
-        
-        AppState {
-            config,
-            evm_client: EvmClient::new(&rpc_urls),
-            nonce_manager: crate::blockchain::nonce_manager::NonceManager::new(),
-            wallet_manager: WalletManager::new(wallet_storage.clone()),
-            wallet_storage: Arc::new(Mutex::new(wallet_storage)),
-            wallet_storage_path: Arc::new(wallet_storage_path),
-        }
+        AppState::new(config, wallet_storage, wallet_storage_path)
     }
 }
\ No newline at end of file
@@ -1,5 +1,74 @@
-use axum::{response::IntoResponse, Json};
+use crate::blockchain::services::chain::probe_rpc_endpoints;
+use crate::AppState;
+use axum::{extract::State, response::IntoResponse, Json};
+use std::time::Duration;
 
-pub async fn health_handler() -> impl IntoResponse {
-    Json(serde_json::json!({"status": "ok"}))
+/// Per-endpoint timeout so a hung RPC cannot stall the health check.
+const RPC_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+// The handler function for the GET /health endpoint.
+// Pings every configured RPC and reports `healthy` only when all of them respond.
+pub async fn health_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let mut targets: Vec<(String, String)> = state
+        .config
+        .chain_rpc_urls
+        .iter()
+        .map(|(id, url)| (id.clone(), url.clone()))
+        .collect();
+    targets.sort();
+
+    let client = reqwest::Client::builder()
+        .timeout(RPC_PROBE_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    let chains = probe_rpc_endpoints(&client, &targets).await;
+    let all_reachable = chains.iter().all(|c| c["reachable"] == true);
+
+    Json(serde_json::json!({
+        "status": if all_reachable { "healthy" } else { "degraded" },
+        "chains": chains,
+        "etherscan_api_key_configured": state.config.etherscan_api_key.is_some(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::wallet_storage::WalletStorage;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use mockito::{mock, Matcher};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_health_reports_degraded_when_an_rpc_is_down() {
+        let _up = mock("POST", "/health-up")
+            .match_body(Matcher::PartialJson(json!({ "method": "eth_blockNumber" })))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "jsonrpc": "2.0", "id": 1, "result": "0x2a" }).to_string())
+            .create();
+
+        let mut config = crate::config::Config::default();
+        config.chain_rpc_urls.insert("1".to_string(), format!("{}/health-up", mockito::server_url()));
+        // Nothing listens on port 1
+        config.chain_rpc_urls.insert("11155111".to_string(), "http://127.0.0.1:1".to_string());
+        let wallet_storage_path = std::env::temp_dir().join("mcp-health-test-wallets.json");
+        let wallet_storage = WalletStorage::new(wallet_storage_path.clone());
+        let state = AppState::new(config, wallet_storage, wallet_storage_path);
+
+        let app = Router::new().route("/health", get(health_handler)).with_state(state);
+        let req = Request::builder().uri("/health").body(Body::empty()).unwrap();
+        let response = app.oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let v: Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(v["status"], "degraded");
+        assert_eq!(v["etherscan_api_key_configured"], false);
+        assert_eq!(v["chains"][0]["chain_id"], "1");
+        assert_eq!(v["chains"][0]["reachable"], true);
+        assert_eq!(v["chains"][0]["block_number"], 42);
+        assert_eq!(v["chains"][1]["chain_id"], "11155111");
+        assert_eq!(v["chains"][1]["reachable"], false);
+        assert!(v["chains"][1]["error"].is_string());
+    }
 }
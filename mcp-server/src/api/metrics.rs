@@ -0,0 +1,53 @@
+use crate::AppState;
+use axum::{extract::State, http::header, response::IntoResponse};
+
+// The handler function for the GET /metrics endpoint (Prometheus text format).
+pub async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::mcp::{handler::handle_mcp_request, protocol::Request, wallet_storage::WalletStorage};
+    use axum::{body::Body, http::Request as HttpRequest, routing::get, Router};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_metrics_count_tool_calls() {
+        let config = Config::default();
+        let wallet_storage_path = std::env::temp_dir().join("mcp-metrics-test-wallets.json");
+        let wallet_storage = WalletStorage::new(wallet_storage_path.clone());
+        let state = AppState::new(config, wallet_storage, wallet_storage_path);
+
+        // Missing its required arguments, so the call fails
+        let call: Request = serde_json::from_value(json!({
+            "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+            "params": {"name": "get_balance", "arguments": {}}
+        }))
+        .unwrap();
+        handle_mcp_request(call, state.clone()).await.unwrap();
+        let bogus: Request = serde_json::from_value(json!({
+            "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+            "params": {"name": "no_such_tool", "arguments": {}}
+        }))
+        .unwrap();
+        handle_mcp_request(bogus, state.clone()).await.unwrap();
+
+        let app = Router::new().route("/metrics", get(metrics_handler)).with_state(state);
+        let req = HttpRequest::builder().uri("/metrics").body(Body::empty()).unwrap();
+        let response = app.oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(text.contains("mcp_requests_total{method=\"tools/call\"} 2"), "{}", text);
+        assert!(text.contains("mcp_tool_calls_total{tool=\"get_balance\",outcome=\"error\"} 1"), "{}", text);
+        assert!(text.contains("mcp_tool_calls_total{tool=\"unknown\",outcome=\"error\"} 1"), "{}", text);
+        assert!(!text.contains("no_such_tool"), "{}", text);
+    }
+}
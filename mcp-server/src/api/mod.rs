@@ -28,11 +28,15 @@
 //! - Discord integration endpoints
 //! - Faucet services for testnet tokens
 
+pub mod auth;
 pub mod balance;
 pub mod contract;
 pub mod faucet;
 pub mod health;
 pub mod history;
+pub mod metrics;
+pub mod rate_limit;
+pub mod timeout;
 pub mod wallet;
 pub mod tx;
 pub mod discord;
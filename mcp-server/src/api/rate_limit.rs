@@ -0,0 +1,140 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Clients remembered at once; beyond this the least recently seen one is forgotten.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+struct Window {
+    started: Instant,
+    last_seen: Instant,
+    count: u32,
+}
+
+/// Fixed-window request counter per client IP, bounded to `capacity` clients.
+#[derive(Clone)]
+pub struct RateLimiter {
+    clients: Arc<Mutex<HashMap<IpAddr, Window>>>,
+    limit: u32,
+    window: Duration,
+    capacity: usize,
+}
+
+impl RateLimiter {
+    /// `limit` requests per client per minute (HTTP_RATE_LIMIT).
+    pub fn per_minute(limit: u32) -> Self {
+        Self::new(limit, Duration::from_secs(60), MAX_TRACKED_CLIENTS)
+    }
+
+    pub fn new(limit: u32, window: Duration, capacity: usize) -> Self {
+        Self {
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            limit,
+            window,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Counts a request from `ip`. Over the limit, returns how long until its window resets.
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if !clients.contains_key(&ip) && clients.len() >= self.capacity {
+            clients.retain(|_, w| now.duration_since(w.started) < self.window);
+            if clients.len() >= self.capacity {
+                if let Some(oldest) = clients.iter().min_by_key(|(_, w)| w.last_seen).map(|(ip, _)| *ip) {
+                    clients.remove(&oldest);
+                }
+            }
+        }
+        let w = clients.entry(ip).or_insert(Window { started: now, last_seen: now, count: 0 });
+        if now.duration_since(w.started) >= self.window {
+            w.started = now;
+            w.count = 0;
+        }
+        w.last_seen = now;
+        if w.count >= self.limit {
+            return Err(self.window.saturating_sub(now.duration_since(w.started)));
+        }
+        w.count += 1;
+        Ok(())
+    }
+}
+
+// Middleware for the /api routes when HTTP_RATE_LIMIT is set: 429 with Retry-After once a
+// client IP exceeds its requests for the current minute.
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Response {
+    match limiter.check(addr.ip()) {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => {
+            // Round up so clients never retry a moment too early
+            let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, secs.max(1).to_string())],
+                Json(serde_json::json!({ "error": "Too many requests" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn request_from(ip: [u8; 4]) -> Request {
+        let mut req = Request::builder().uri("/wallet/list").body(Body::empty()).unwrap();
+        req.extensions_mut().insert(ConnectInfo(SocketAddr::from((ip, 40000))));
+        req
+    }
+
+    #[tokio::test]
+    async fn test_requests_over_limit_get_429() {
+        let app = Router::new()
+            .route("/wallet/list", get(|| async { "ok" }))
+            .route_layer(middleware::from_fn_with_state(RateLimiter::per_minute(3), rate_limit));
+
+        for _ in 0..3 {
+            let resp = app.clone().oneshot(request_from([10, 0, 0, 1])).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        let resp = app.clone().oneshot(request_from([10, 0, 0, 1])).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = resp.headers()[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+        assert!((1..=60).contains(&retry_after));
+
+        // Other clients have their own budget
+        let resp = app.oneshot(request_from([10, 0, 0, 2])).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_window_resets_and_capacity_is_bounded() {
+        let limiter = RateLimiter::new(1, Duration::from_millis(20), 2);
+        let ip = |n: u8| IpAddr::from([10, 0, 0, n]);
+        assert!(limiter.check(ip(1)).is_ok());
+        assert!(limiter.check(ip(1)).is_err());
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(limiter.check(ip(1)).is_ok());
+
+        assert!(limiter.check(ip(2)).is_ok());
+        assert!(limiter.check(ip(3)).is_ok());
+        assert_eq!(limiter.clients.lock().unwrap().len(), 2);
+    }
+}
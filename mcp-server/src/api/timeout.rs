@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::time::Duration;
+
+use crate::mcp::protocol::ErrorCategory;
+
+// Middleware for the REST routes under /api: a handler still waiting on an upstream
+// (RPC node, explorer) after TOOL_TIMEOUT_SECS is dropped and answered with 504.
+pub async fn request_timeout(State(limit): State<Duration>, req: Request, next: Next) -> Response {
+    match tokio::time::timeout(limit, next.run(req)).await {
+        Ok(response) => response,
+        Err(_) => (
+            StatusCode::GATEWAY_TIMEOUT,
+            Json(serde_json::json!({
+                "error": format!("Request timed out after {}s", limit.as_secs_f64())
+            })),
+        )
+            .into_response(),
+    }
+}
+
+// Same bound for routes that broadcast a transaction. Dropping such a handler could leave
+// a reserved nonce behind or hide a transaction that was already sent, so it runs as its
+// own task and finishes on its own; past the limit the client is told the outcome is unknown.
+pub async fn broadcast_timeout(State(limit): State<Duration>, req: Request, next: Next) -> Response {
+    let mut task = tokio::spawn(next.run(req));
+    match tokio::time::timeout(limit, &mut task).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => {
+            tracing::error!("Broadcasting request panicked: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Request failed unexpectedly" })),
+            )
+                .into_response()
+        }
+        Err(_) => (
+            StatusCode::GATEWAY_TIMEOUT,
+            Json(serde_json::json!({
+                "error": format!(
+                    "Request did not finish within {}s and is still running; the transaction may already have been broadcast. \
+                     Check the sender's transactions before retrying",
+                    limit.as_secs_f64()
+                ),
+                "category": ErrorCategory::OutcomeUnknown,
+            })),
+        )
+            .into_response(),
+    }
+}
@@ -6,8 +6,9 @@ use std::str::FromStr;
 use crate::{
     AppState,
     blockchain::{
+        chains,
         models::TransactionResponse,
-        services::transactions::send_evm_transaction,
+        services::transactions::{send_evm_transaction_with_options, GasDefaults, SendOptions},
     },
 };
 use ethers_core::types::{Address, TransactionRequest, U256};
@@ -29,6 +30,15 @@ pub struct SendTxRequest {
     // Optional EVM overrides
     pub gas_limit: Option<u64>,
     pub gas_price: Option<u64>,
+    /// Broadcast even if the balance doesn't cover value + gas
+    #[serde(default)]
+    pub skip_balance_check: bool,
+    /// Simulate first and only broadcast if the transaction would not revert
+    #[serde(default)]
+    pub safe_send: bool,
+    /// Fall back on DEFAULT_GAS_LIMIT/DEFAULT_GAS_PRICE if the node can't estimate gas;
+    /// defaults to USE_CONFIG_GAS_DEFAULTS
+    pub use_config_gas_defaults: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -58,9 +68,9 @@ pub async fn send_transaction_handler(
         // Use stored wallet
         let storage = state.wallet_storage.lock().await;
         let pk_hex = storage
-            .decrypt_private_key(name, pw)
+            .get_private_key(name, pw)
             .map_err(|e| (StatusCode::UNAUTHORIZED, format!("Wallet unlock failed: {}", e)))?;
-        LocalWallet::from_str(&pk_hex)
+        LocalWallet::from_str(pk_hex.as_str())
             .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid stored private key: {}", e)))?
     } else if let Some(pk) = &req.from_private_key {
         LocalWallet::from_str(pk)
@@ -77,12 +87,36 @@ pub async fn send_transaction_handler(
     if let Some(gl) = req.gas_limit { tx = tx.gas(U256::from(gl)); }
     if let Some(gp) = req.gas_price { tx = tx.gas_price(U256::from(gp)); }
 
-    // Send via shared nonce manager
-    let resp: TransactionResponse = send_evm_transaction(
+    // Send via shared nonce manager, pinned to the requested chain
+    let expected_chain_id = if state.config.allow_chain_id_mismatch {
+        None
+    } else {
+        Some(chains::numeric_chain_id(&req.chain_id).ok_or_else(|| {
+            (StatusCode::BAD_REQUEST, format!("Cannot tell the numeric chain ID of '{}'", req.chain_id))
+        })?)
+    };
+    let options = SendOptions {
+        skip_balance_check: req.skip_balance_check,
+        expected_chain_id,
+        simulate: req.safe_send,
+        gas_buffer_percent: state.config.gas_limit_buffer_pct(),
+        max_gas_limit: state.config.max_gas_limits.get(&req.chain_id).map(|g| U256::from(*g)),
+        gas_defaults: req
+            .use_config_gas_defaults
+            .unwrap_or(state.config.use_config_gas_defaults)
+            .then(|| GasDefaults::from_config(&state.config)),
+        fee_type: state.config.fee_type(&req.chain_id),
+        fee_support: state.fee_support.clone(),
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+        http_client: state.http_client.clone(),
+    };
+    let resp: TransactionResponse = send_evm_transaction_with_options(
         &rpc_url,
         wallet,
         tx,
         &state.nonce_manager,
+        &options,
     ).await.map_err(|e| (StatusCode::BAD_GATEWAY, format!("EVM send failed: {}", e)))?;
 
     Ok(Json(SendTxResponse { tx_hash: resp.tx_hash }))
@@ -13,7 +13,7 @@ use crate::{
         wallet_manager::WalletManager,
         models::WalletResponse,
     },
-    mcp::wallet_storage::{self, WalletStorage},
+    mcp::wallet_storage::{self, StorageError, WalletStorage},
 };
 
 // --- Request and Response Models ---
@@ -138,7 +138,7 @@ pub async fn import_wallet_handler(
         }
         Err(e) => {
             error!("Failed to import wallet: {}", e);
-            let status = if e.to_string().contains("already exists") {
+            let status = if matches!(e.downcast_ref::<StorageError>(), Some(StorageError::WalletExists(_))) {
                 axum::http::StatusCode::CONFLICT
             } else if e.to_string().contains("Invalid") || e.to_string().contains("Failed to parse") {
                 axum::http::StatusCode::BAD_REQUEST
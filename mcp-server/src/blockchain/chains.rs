@@ -0,0 +1,185 @@
+// src/blockchain/chains.rs
+
+//! Static metadata for the EVM networks this server knows by name.
+//! Chains configured via `CHAIN_RPC_URLS` but missing here still work; they just
+//! have no friendly name or aliases.
+
+use anyhow::{anyhow, Result};
+
+/// A chain's native currency, used to label balances and amounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NativeCurrency {
+    pub symbol: &'static str,
+    pub name: &'static str,
+    pub decimals: u8,
+}
+
+/// Assumed for chains without an entry here or a configured override.
+pub const ETHER: NativeCurrency = NativeCurrency { symbol: "ETH", name: "Ether", decimals: 18 };
+
+const MATIC: NativeCurrency = NativeCurrency { symbol: "MATIC", name: "Matic", decimals: 18 };
+const BNB: NativeCurrency = NativeCurrency { symbol: "BNB", name: "BNB", decimals: 18 };
+const TEST_BNB: NativeCurrency = NativeCurrency { symbol: "tBNB", name: "Test BNB", decimals: 18 };
+const AVAX: NativeCurrency = NativeCurrency { symbol: "AVAX", name: "Avalanche", decimals: 18 };
+
+/// Metadata for a well-known chain.
+#[derive(Debug, Clone, Copy)]
+pub struct ChainInfo {
+    pub chain_id: &'static str,
+    pub name: &'static str,
+    /// Lowercase, dash-separated names accepted in place of the numeric ID
+    pub aliases: &'static [&'static str],
+    /// Etherscan v2 API base URL, if Etherscan indexes this chain for us
+    pub etherscan_api_url: Option<&'static str>,
+    pub native_currency: NativeCurrency,
+}
+
+pub const KNOWN_CHAINS: &[ChainInfo] = &[
+    ChainInfo {
+        chain_id: "1",
+        name: "Ethereum Mainnet",
+        aliases: &["mainnet", "main", "m", "eth", "ethereum"],
+        etherscan_api_url: Some("https://api.etherscan.io/v2/api"),
+        native_currency: ETHER,
+    },
+    ChainInfo {
+        chain_id: "11155111",
+        name: "Sepolia",
+        // Testnet shorthands (including the zkSync ones) default to Sepolia
+        aliases: &["sepolia", "zksync-sepolia", "zk-sepolia", "testnet", "test", "t"],
+        etherscan_api_url: Some("https://api-sepolia.etherscan.io/v2/api"),
+        native_currency: ETHER,
+    },
+    ChainInfo {
+        chain_id: "17000",
+        name: "Holesky",
+        aliases: &["holesky"],
+        etherscan_api_url: None,
+        native_currency: ETHER,
+    },
+    ChainInfo {
+        chain_id: "324",
+        name: "zkSync Era",
+        aliases: &["zksync", "zk"],
+        etherscan_api_url: None,
+        native_currency: ETHER,
+    },
+    ChainInfo {
+        chain_id: "137",
+        name: "Polygon",
+        aliases: &["polygon", "matic"],
+        etherscan_api_url: None,
+        native_currency: MATIC,
+    },
+    ChainInfo {
+        chain_id: "80002",
+        name: "Polygon Amoy",
+        aliases: &["polygon-amoy", "matic-amoy", "amoy"],
+        etherscan_api_url: None,
+        native_currency: MATIC,
+    },
+    ChainInfo {
+        chain_id: "42161",
+        name: "Arbitrum One",
+        aliases: &["arbitrum", "arb", "arbitrum-one"],
+        etherscan_api_url: None,
+        native_currency: ETHER,
+    },
+    ChainInfo {
+        chain_id: "421614",
+        name: "Arbitrum Sepolia",
+        aliases: &["arbitrum-sepolia", "arb-sepolia"],
+        etherscan_api_url: None,
+        native_currency: ETHER,
+    },
+    ChainInfo {
+        chain_id: "10",
+        name: "OP Mainnet",
+        aliases: &["optimism", "op"],
+        etherscan_api_url: None,
+        native_currency: ETHER,
+    },
+    ChainInfo {
+        chain_id: "11155420",
+        name: "OP Sepolia",
+        aliases: &["optimism-sepolia", "op-sepolia"],
+        etherscan_api_url: None,
+        native_currency: ETHER,
+    },
+    ChainInfo {
+        chain_id: "8453",
+        name: "Base",
+        aliases: &["base"],
+        etherscan_api_url: None,
+        native_currency: ETHER,
+    },
+    ChainInfo {
+        chain_id: "84532",
+        name: "Base Sepolia",
+        aliases: &["base-sepolia"],
+        etherscan_api_url: None,
+        native_currency: ETHER,
+    },
+    ChainInfo {
+        chain_id: "56",
+        name: "BNB Smart Chain",
+        aliases: &["bsc", "bnb"],
+        etherscan_api_url: None,
+        native_currency: BNB,
+    },
+    ChainInfo {
+        chain_id: "97",
+        name: "BNB Smart Chain Testnet",
+        aliases: &["bsc-testnet", "bnb-testnet"],
+        etherscan_api_url: None,
+        native_currency: TEST_BNB,
+    },
+    ChainInfo {
+        chain_id: "43114",
+        name: "Avalanche C-Chain",
+        aliases: &["avalanche", "avax"],
+        etherscan_api_url: None,
+        native_currency: AVAX,
+    },
+    ChainInfo {
+        chain_id: "43113",
+        name: "Avalanche Fuji",
+        aliases: &["avalanche-fuji", "avax-fuji", "fuji"],
+        etherscan_api_url: None,
+        native_currency: AVAX,
+    },
+];
+
+/// Looks up a known chain by numeric ID.
+pub fn chain_info(chain_id: &str) -> Option<&'static ChainInfo> {
+    KNOWN_CHAINS.iter().find(|c| c.chain_id == chain_id)
+}
+
+/// Native currency of a known chain, or `ETHER` for chains not listed here.
+pub fn native_currency(chain_id: &str) -> NativeCurrency {
+    chain_info(chain_id).map(|c| c.native_currency).unwrap_or(ETHER)
+}
+
+/// Etherscan API base URL for `chain_id`, for the chains Etherscan indexes.
+pub fn etherscan_base_url(chain_id: &str) -> Result<&'static str> {
+    chain_info(chain_id)
+        .and_then(|c| c.etherscan_api_url)
+        .ok_or_else(|| anyhow!("Etherscan API not supported for chain_id '{}'", chain_id))
+}
+
+/// The numeric chain ID behind `chain_id`, given as a number or a known alias.
+pub fn numeric_chain_id(chain_id: &str) -> Option<u64> {
+    let chain_id = chain_id.trim();
+    chain_id
+        .parse()
+        .ok()
+        .or_else(|| chain_id_for_alias(&chain_id.to_lowercase())?.parse().ok())
+}
+
+/// Resolves an already-normalized alias (e.g. `"sepolia"`) to its numeric chain ID.
+pub fn chain_id_for_alias(alias: &str) -> Option<&'static str> {
+    KNOWN_CHAINS
+        .iter()
+        .find(|c| c.aliases.contains(&alias))
+        .map(|c| c.chain_id)
+}
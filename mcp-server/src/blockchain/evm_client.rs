@@ -89,6 +89,19 @@ impl EvmClient {
         private_key: &str,
         tx_request: TransactionRequest,
         nonce_manager: &NonceManager,
+    ) -> Result<TransactionResponse> {
+        self.send_transaction_with_options(chain_id, private_key, tx_request, nonce_manager, &Default::default())
+            .await
+    }
+
+    /// `send_transaction` with explicit per-send options
+    pub async fn send_transaction_with_options(
+        &self,
+        chain_id: &str,
+        private_key: &str,
+        tx_request: TransactionRequest,
+        nonce_manager: &NonceManager,
+        options: &transactions::SendOptions,
     ) -> Result<TransactionResponse> {
         use ethers_signers::LocalWallet;
         use std::str::FromStr;
@@ -103,7 +116,7 @@ impl EvmClient {
             .url()
             .to_string();
 
-        transactions::send_evm_transaction(&rpc_url, wallet, tx_request, nonce_manager).await
+        transactions::send_evm_transaction_with_options(&rpc_url, wallet, tx_request, nonce_manager, options).await
     }
 
     /// Get contract information
@@ -154,14 +167,15 @@ impl EvmClient {
         contract::is_evm_contract(&client, &rpc_url, address).await
     }
 
-    /// Get contract source code from Etherscan
+    /// Get contract source code from the Etherscan API at `base_url`
     pub async fn get_contract_source_code(
         &self,
+        base_url: &str,
         chain_id: &str,
         address: &str,
         etherscan_api_key: &str,
     ) -> Result<Value> {
         let client = reqwest::Client::new();
-        contract::get_contract_source_code(&client, chain_id, address, etherscan_api_key).await
+        contract::get_contract_source_code(&client, base_url, chain_id, address, etherscan_api_key).await
     }
 }
@@ -6,6 +6,7 @@
 //!
 //! ## Architecture
 //!
+//! - `chains`: Names, aliases and Etherscan support of well-known chains
 //! - `client`: Main blockchain client interface
 //! - `evm_client`: EVM-specific client implementation
 //! - `models`: Data models for blockchain entities
@@ -19,6 +20,7 @@ pub mod evm_client;
 pub use client::EvmClient;
 
 // Re-export other modules
+pub mod chains;
 pub mod models;
 pub mod nonce_manager;
 pub mod services;
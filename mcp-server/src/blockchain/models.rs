@@ -17,6 +17,8 @@ pub enum WalletGenerationError {
     KeyGenerationFailed(String),
     #[error("invalid address format: {0}")]
     InvalidAddress(String),
+    #[error("unsupported mnemonic word count {0}; expected 12, 15, 18, 21 or 24")]
+    InvalidWordCount(usize),
 }
 
 #[derive(Error, Debug)]
@@ -209,6 +211,21 @@ pub struct TransactionResponse {
     pub tx_hash: String,
 }
 
+/// Defines the structure for a gas and fee estimate (all values in wei).
+/// The EIP-1559 fields are only set on chains whose blocks carry a base fee.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EstimateFeesResponse {
+    pub gas_limit: String,
+    pub gas_price: String,
+    pub total_fee: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_fee: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<String>,
+}
+
 /// Defines the structure for token information response.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenInfoResponse {
@@ -73,4 +73,22 @@ impl NonceManager {
 
         Ok(nonce_to_use)
     }
+
+    /// Hands `nonce` back after the node rejected the transaction that used it, so the next
+    /// send reuses it. Ignored if a later nonce has been handed out since.
+    pub async fn release(&self, address: Address, nonce: U256) {
+        if let Some(lock) = self.nonces.get(&address).map(|entry| entry.clone()) {
+            let mut state = lock.lock().await;
+            if state.next_nonce == Some(nonce + U256::one()) {
+                state.next_nonce = Some(nonce);
+            }
+        }
+    }
+
+    /// Forgets the cached nonce for `address`; the next send asks the node again.
+    pub async fn reset(&self, address: Address) {
+        if let Some(lock) = self.nonces.get(&address).map(|entry| entry.clone()) {
+            lock.lock().await.next_nonce = None;
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,264 @@
+// src/blockchain/services/abi.rs
+
+use crate::utils::function_selector;
+use anyhow::{anyhow, Context, Result};
+use ethers_core::abi::{decode, encode, param_type::Reader, Abi, Function, HumanReadableParser, ParamType, Token};
+use ethers_core::types::{Address, Bytes, I256, U256};
+use ethers_core::utils::to_checksum;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// Selector followed by the ABI-encoded arguments.
+pub fn encode_call(sig: &str, tokens: Vec<Token>) -> Bytes {
+    let mut out = function_selector(sig).to_vec();
+    let mut tail = encode(&tokens);
+    out.append(&mut tail);
+    Bytes::from(out)
+}
+
+/// Parses a human readable function signature such as `transfer(address to, uint256 amount)`.
+/// The leading `function` keyword is optional.
+pub fn parse_function_signature(signature: &str) -> Result<Function> {
+    let s = signature.trim();
+    let s = if s.starts_with("function ") { s.to_string() } else { format!("function {}", s) };
+    HumanReadableParser::parse_function(&s).map_err(|e| anyhow!("invalid function signature '{}': {}", signature, e))
+}
+
+/// Parses Solidity type names (`address`, `uint256[]`, `(bool,bytes)`) into ABI param types.
+pub fn parse_types(types: &[String]) -> Result<Vec<ParamType>> {
+    types
+        .iter()
+        .map(|t| Reader::read(t.trim()).map_err(|e| anyhow!("invalid ABI type '{}': {}", t, e)))
+        .collect()
+}
+
+/// Canonical `name(type,...)` signature of an ABI function, used for its selector.
+pub fn function_signature(func: &Function) -> String {
+    let types: Vec<String> = func
+        .inputs
+        .iter()
+        .map(|p| param_type_to_string(&p.kind))
+        .collect();
+    format!("{}({})", func.name, types.join(","))
+}
+
+pub fn param_type_to_string(p: &ParamType) -> String {
+    match p {
+        ParamType::Address => "address".to_string(),
+        ParamType::Bytes => "bytes".to_string(),
+        ParamType::FixedBytes(n) => format!("bytes{}", n),
+        ParamType::Int(n) => format!("int{}", n),
+        ParamType::Uint(n) => format!("uint{}", n),
+        ParamType::Bool => "bool".to_string(),
+        ParamType::String => "string".to_string(),
+        ParamType::Array(inner) => format!("{}[]", param_type_to_string(inner)),
+        ParamType::FixedArray(inner, n) => format!("{}[{}]", param_type_to_string(inner), n),
+        ParamType::Tuple(components) => {
+            let inner: Vec<String> = components.iter().map(param_type_to_string).collect();
+            format!("({})", inner.join(","))
+        }
+    }
+}
+
+/// Converts a decoded ABI token to JSON (numbers as decimal strings, bytes as 0x-hex).
+pub fn token_to_json(t: &Token) -> Value {
+    match t {
+        Token::Address(a) => json!(to_checksum(a, None)),
+        Token::Uint(n) => json!(n.to_string()),
+        Token::Int(n) => json!(I256::from_raw(*n).to_string()),
+        Token::Bool(b) => json!(b),
+        Token::String(s) => json!(s),
+        Token::Bytes(b) | Token::FixedBytes(b) => json!(format!("0x{}", hex::encode(b))),
+        Token::Array(items) | Token::FixedArray(items) | Token::Tuple(items) => Value::Array(items.iter().map(token_to_json).collect()),
+    }
+}
+
+fn hex_arg(s: &str) -> Result<Vec<u8>> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).with_context(|| format!("invalid hex '{}'", s))
+}
+
+fn uint_arg(val: &Value) -> Result<U256> {
+    match val {
+        Value::String(s) if s.starts_with("0x") => Ok(U256::from_str_radix(&s[2..], 16)?),
+        Value::String(s) => Ok(U256::from_dec_str(s)?),
+        Value::Number(n) if n.is_u64() => Ok(U256::from(n.as_u64().unwrap_or_default())),
+        _ => Err(anyhow!("expected a decimal string")),
+    }
+}
+
+/// Converts one JSON argument into a token of type `ty`.
+///
+/// Numbers are accepted as decimal (or 0x-hex) strings so values above 2^53 survive JSON;
+/// `bytes` accepts 0x-hex or, as before, raw UTF-8 text.
+pub fn coerce_token(ty: &ParamType, val: &Value) -> Result<Token> {
+    let tok = match ty {
+        ParamType::Address => Token::Address(Address::from_str(val.as_str().ok_or_else(|| anyhow!("address arg must be string"))?)?),
+        ParamType::Uint(_) => Token::Uint(uint_arg(val).context("uint arg must be decimal string")?),
+        ParamType::Int(_) => {
+            let s = match val {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                _ => return Err(anyhow!("int arg must be decimal string")),
+            };
+            Token::Int(I256::from_dec_str(&s).map_err(|e| anyhow!("int arg must be decimal string: {}", e))?.into_raw())
+        }
+        ParamType::Bool => Token::Bool(val.as_bool().ok_or_else(|| anyhow!("bool arg must be boolean"))?),
+        ParamType::String => Token::String(val.as_str().unwrap_or("").to_string()),
+        ParamType::Bytes => {
+            let s = val.as_str().unwrap_or("");
+            let bytes = if s.starts_with("0x") { hex_arg(s)? } else { s.as_bytes().to_vec() };
+            Token::Bytes(bytes)
+        }
+        ParamType::FixedBytes(n) => {
+            let bytes = hex_arg(val.as_str().ok_or_else(|| anyhow!("bytes{} arg must be hex string", n))?)?;
+            if bytes.len() != *n {
+                return Err(anyhow!("bytes{} arg has {} bytes", n, bytes.len()));
+            }
+            Token::FixedBytes(bytes)
+        }
+        ParamType::Array(inner) | ParamType::FixedArray(inner, _) => {
+            let items = val.as_array().ok_or_else(|| anyhow!("{} arg must be an array", param_type_to_string(ty)))?;
+            if let ParamType::FixedArray(_, n) = ty {
+                if items.len() != *n {
+                    return Err(anyhow!("{} arg has {} elements", param_type_to_string(ty), items.len()));
+                }
+            }
+            let tokens = items.iter().map(|v| coerce_token(inner, v)).collect::<Result<Vec<_>>>()?;
+            if matches!(ty, ParamType::Array(_)) { Token::Array(tokens) } else { Token::FixedArray(tokens) }
+        }
+        ParamType::Tuple(components) => {
+            let items = val.as_array().ok_or_else(|| anyhow!("tuple arg must be an array"))?;
+            Token::Tuple(coerce_tokens(components, items)?)
+        }
+    };
+    Ok(tok)
+}
+
+/// Converts JSON arguments into tokens for `types`, reporting which argument failed.
+pub fn coerce_tokens(types: &[ParamType], args: &[Value]) -> Result<Vec<Token>> {
+    if types.len() != args.len() {
+        return Err(anyhow!("arg count mismatch: expected {}, got {}", types.len(), args.len()));
+    }
+    types
+        .iter()
+        .zip(args)
+        .enumerate()
+        .map(|(i, (ty, val))| coerce_token(ty, val).with_context(|| format!("argument {} ({})", i, param_type_to_string(ty))))
+        .collect()
+}
+
+/// Coerces `args` for `func` and returns the full calldata.
+pub fn encode_function_call(func: &Function, args: &[Value]) -> Result<Bytes> {
+    let kinds: Vec<ParamType> = func.inputs.iter().map(|p| p.kind.clone()).collect();
+    let tokens = coerce_tokens(&kinds, args)?;
+    Ok(encode_call(&function_signature(func), tokens))
+}
+
+/// Decodes ABI-encoded `data` as `types` into JSON values.
+pub fn decode_values(types: &[ParamType], data: &[u8]) -> Result<Vec<Value>> {
+    let tokens = decode(types, data).map_err(|e| anyhow!("data does not decode as ({}): {}", types.iter().map(param_type_to_string).collect::<Vec<_>>().join(","), e))?;
+    Ok(tokens.iter().map(token_to_json).collect())
+}
+
+/// The functions of a JSON ABI (the usual `[{"type":"function",...}]` array).
+pub fn parse_abi_functions(abi_json: &str) -> Result<Vec<Function>> {
+    let abi: Abi = serde_json::from_str(abi_json).context("invalid JSON ABI")?;
+    Ok(abi.functions().cloned().collect())
+}
+
+/// Matches the 4-byte selector of `calldata` against `functions` and decodes the arguments,
+/// named after the ABI parameters (`arg0`, `arg1`, ... when unnamed). `None` when no
+/// function has that selector.
+pub fn decode_calldata(functions: &[Function], calldata: &[u8]) -> Result<Option<Value>> {
+    if calldata.len() < 4 {
+        return Err(anyhow!("calldata is shorter than a 4-byte selector"));
+    }
+    let (selector, body) = calldata.split_at(4);
+    let Some(func) = functions.iter().find(|f| f.short_signature() == selector) else {
+        return Ok(None);
+    };
+    let tokens = func
+        .decode_input(body)
+        .map_err(|e| anyhow!("arguments do not decode as {}: {}", function_signature(func), e))?;
+    let args: Vec<Value> = func
+        .inputs
+        .iter()
+        .zip(&tokens)
+        .enumerate()
+        .map(|(i, (param, token))| {
+            let name = if param.name.is_empty() { format!("arg{}", i) } else { param.name.clone() };
+            json!({ "name": name, "type": param_type_to_string(&param.kind), "value": token_to_json(token) })
+        })
+        .collect();
+    Ok(Some(json!({
+        "function": func.name,
+        "signature": function_signature(func),
+        "selector": format!("0x{}", hex::encode(selector)),
+        "args": args,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_transfer_and_decode_arguments() {
+        let func = parse_function_signature("transfer(address,uint256)").unwrap();
+        let data = encode_function_call(&func, &[json!("0x000000000000000000000000000000000000dEaD"), json!("1000000")]).unwrap();
+        let hex_data = format!("0x{}", hex::encode(&data));
+        assert!(hex_data.starts_with("0xa9059cbb"));
+        assert_eq!(hex_data.len(), 2 + 8 + 64 * 2);
+
+        let types = parse_types(&["address".to_string(), "uint256".to_string()]).unwrap();
+        let values = decode_values(&types, &data[4..]).unwrap();
+        assert_eq!(values, vec![json!("0x000000000000000000000000000000000000dEaD"), json!("1000000")]);
+    }
+
+    #[test]
+    fn test_coerce_reports_argument_and_type() {
+        let func = parse_function_signature("transfer(address to, uint256 amount)").unwrap();
+        let err = encode_function_call(&func, &[json!("0xdead"), json!("1")]).unwrap_err();
+        assert!(format!("{:#}", err).contains("argument 0 (address)"));
+        let err = encode_function_call(&func, &[json!("0x000000000000000000000000000000000000dEaD")]).unwrap_err();
+        assert!(err.to_string().contains("arg count mismatch"));
+    }
+
+    #[test]
+    fn test_decode_rejects_short_data() {
+        let types = parse_types(&["uint256".to_string()]).unwrap();
+        assert!(decode_values(&types, &[0u8; 4]).is_err());
+        assert!(parse_types(&["uint7x".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_decode_erc20_transfer_calldata() {
+        let abi = r#"[
+            {"type":"function","name":"approve","stateMutability":"nonpayable","inputs":[{"name":"spender","type":"address"},{"name":"amount","type":"uint256"}],"outputs":[{"name":"","type":"bool"}]},
+            {"type":"function","name":"transfer","stateMutability":"nonpayable","inputs":[{"name":"to","type":"address"},{"name":"amount","type":"uint256"}],"outputs":[{"name":"","type":"bool"}]}
+        ]"#;
+        let functions = parse_abi_functions(abi).unwrap();
+        let calldata = hex::decode(concat!(
+            "a9059cbb",
+            "000000000000000000000000000000000000000000000000000000000000dead",
+            "00000000000000000000000000000000000000000000000000000000000f4240"
+        ))
+        .unwrap();
+
+        let decoded = decode_calldata(&functions, &calldata).unwrap().unwrap();
+        assert_eq!(decoded["function"], "transfer");
+        assert_eq!(decoded["signature"], "transfer(address,uint256)");
+        assert_eq!(
+            decoded["args"],
+            json!([
+                {"name": "to", "type": "address", "value": "0x000000000000000000000000000000000000dEaD"},
+                {"name": "amount", "type": "uint256", "value": "1000000"}
+            ])
+        );
+
+        // Unknown selector and truncated input
+        assert_eq!(decode_calldata(&functions, &[0xde, 0xad, 0xbe, 0xef]).unwrap(), None);
+        assert!(decode_calldata(&functions, &calldata[..20]).is_err());
+        assert!(decode_calldata(&functions, &[0xa9]).is_err());
+    }
+}
@@ -1,8 +1,16 @@
 use crate::blockchain::models::BalanceResponse;
+use crate::blockchain::services::rpc::{parse_hex_u256, parse_hex_u64, rpc_call};
+use crate::blockchain::services::token;
+use crate::config::NativeCurrencyConfig;
+use crate::metrics;
+use crate::utils::units::format_units;
 use anyhow::{anyhow, Result};
+use ethers_core::types::U256;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use tracing::error;
 
 #[derive(Deserialize)]
@@ -12,28 +20,21 @@ struct EtherscanResponse {
     result: String,
 }
 
+/// Latest native balance of `address` from the Etherscan-compatible API at `base_url`
+/// (see `Config::etherscan_base_url`).
 pub async fn get_balance(
     client: &Client,
+    base_url: &str,
     chain_id: &str,
     address: &str,
     etherscan_api_key: &str,
 ) -> Result<BalanceResponse> {
-    // Map chain IDs to Etherscan base URLs
-    let base_url = match chain_id {
-        "1" => "https://api.etherscan.io/v2/api",
-        "11155111" => "https://api-sepolia.etherscan.io/v2/api",
-        "324" | "300" => {
-            // zkSync chains don't have Etherscan support, return error
-            return Err(anyhow!("Etherscan API not supported for zkSync chains"));
-        }
-        _ => return Err(anyhow!("Unsupported chain ID for Etherscan: {}", chain_id)),
-    };
-
     // Build the Etherscan API URL
     let url = format!(
         "{}?chainid={}&module=account&action=balance&address={}&tag=latest&apikey={}",
         base_url, chain_id, address, etherscan_api_key
     );
+    metrics::record_etherscan_request("account", "balance");
 
     let res: EtherscanResponse = client
         .get(&url)
@@ -59,3 +60,274 @@ pub async fn get_balance(
         denom: "wei".to_string(),
     })
 }
+
+/// Fetches the native balance straight from the node, for chains Etherscan doesn't index
+/// and for reads at a historical `block` (see `rpc::block_param`).
+pub async fn get_balance_rpc(client: &Client, rpc_url: &str, address: &str, block: &Value) -> Result<BalanceResponse> {
+    let v = rpc_call(client, rpc_url, "eth_getBalance", json!([address, block])).await?;
+    Ok(BalanceResponse {
+        amount: parse_hex_u256(&v)?.to_string(),
+        denom: "wei".to_string(),
+    })
+}
+
+/// What a client needs before building a transaction from an address.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountState {
+    /// Native balance in wei
+    pub balance: U256,
+    /// Transactions mined from the address (`latest` transaction count)
+    pub nonce: u64,
+    /// Next nonce to use, counting the node's pending transactions
+    pub pending_nonce: u64,
+    /// Bytes of deployed code; zero for an externally owned account
+    pub code_size: usize,
+}
+
+/// Balance, latest and pending nonce and code size of `address`, read concurrently.
+pub async fn get_account_state(client: &Client, rpc_url: &str, address: &str) -> Result<AccountState> {
+    let (balance, nonce, pending_nonce, code) = tokio::try_join!(
+        rpc_call(client, rpc_url, "eth_getBalance", json!([address, "latest"])),
+        rpc_call(client, rpc_url, "eth_getTransactionCount", json!([address, "latest"])),
+        rpc_call(client, rpc_url, "eth_getTransactionCount", json!([address, "pending"])),
+        rpc_call(client, rpc_url, "eth_getCode", json!([address, "latest"])),
+    )?;
+    let code = code.as_str().ok_or_else(|| anyhow!("eth_getCode returned {}", code))?;
+    Ok(AccountState {
+        balance: parse_hex_u256(&balance)?,
+        nonce: parse_hex_u64(&nonce)?,
+        pending_nonce: parse_hex_u64(&pending_nonce)?,
+        code_size: code.trim_start_matches("0x").len() / 2,
+    })
+}
+
+/// Balance labelled with the chain's native currency, e.g. `{"amount": "1500000000000000000",
+/// "denom": "wei", "symbol": "MATIC", "decimals": 18, "formatted": "1.5 MATIC"}`.
+pub fn native_balance_json(balance: &BalanceResponse, currency: &NativeCurrencyConfig) -> Value {
+    let formatted = U256::from_dec_str(&balance.amount)
+        .ok()
+        .map(|n| format!("{} {}", format_units(n, currency.decimals), currency.symbol));
+    json!({
+        "amount": balance.amount,
+        "denom": balance.denom,
+        "symbol": currency.symbol,
+        "decimals": currency.decimals,
+        "formatted": formatted,
+    })
+}
+
+/// Condition `wait_for_balance` waits for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BalanceTarget {
+    /// Balance reaches at least this many base units
+    AtLeast(U256),
+    /// Balance grows by at least this many base units over the first reading
+    IncreaseBy(U256),
+}
+
+/// Outcome of `wait_for_balance`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceWait {
+    pub met: bool,
+    pub initial: U256,
+    pub balance: U256,
+    pub polls: u32,
+    pub elapsed: Duration,
+}
+
+// Native balance, or the ERC20 balance when `token` is set, in base units
+async fn current_balance(client: &Client, rpc_url: &str, address: &str, token: Option<&str>) -> Result<U256> {
+    let latest = json!("latest");
+    match token {
+        Some(token) => {
+            let v = token::erc20_balance_of(client, rpc_url, token, address, &latest).await?;
+            v["decoded"]
+                .as_str()
+                .and_then(|s| U256::from_dec_str(s).ok())
+                .ok_or_else(|| anyhow!("token {} returned no balance", token))
+        }
+        None => Ok(U256::from_dec_str(&get_balance_rpc(client, rpc_url, address, &latest).await?.amount)?),
+    }
+}
+
+/// Polls the balance of `address` every `interval` until `target` is met or `timeout`
+/// has passed. Not meeting the target is reported through `met`, not as an error.
+pub async fn wait_for_balance(
+    client: &Client,
+    rpc_url: &str,
+    address: &str,
+    token: Option<&str>,
+    target: BalanceTarget,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<BalanceWait> {
+    let started = Instant::now();
+    let initial = current_balance(client, rpc_url, address, token).await?;
+    let threshold = match target {
+        BalanceTarget::AtLeast(min) => min,
+        BalanceTarget::IncreaseBy(delta) => initial.saturating_add(delta),
+    };
+    let mut balance = initial;
+    let mut polls = 1;
+    while balance < threshold && started.elapsed() + interval <= timeout {
+        tokio::time::sleep(interval).await;
+        balance = current_balance(client, rpc_url, address, token).await?;
+        polls += 1;
+    }
+    Ok(BalanceWait { met: balance >= threshold, initial, balance, polls, elapsed: started.elapsed() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_native_balance_json_reports_chain_currency() {
+        let balance = BalanceResponse { amount: "1500000000000000000".into(), denom: "wei".into() };
+        let config = Config::default();
+
+        let eth = native_balance_json(&balance, &config.native_currency("1"));
+        assert_eq!(eth["symbol"], "ETH");
+        assert_eq!(eth["decimals"], 18);
+        assert_eq!(eth["formatted"], "1.5 ETH");
+
+        let matic = native_balance_json(&balance, &config.native_currency("137"));
+        assert_eq!(matic["symbol"], "MATIC");
+        assert_eq!(matic["formatted"], "1.5 MATIC");
+
+        let zero = BalanceResponse { amount: "0".into(), denom: "wei".into() };
+        assert_eq!(native_balance_json(&zero, &config.native_currency("1"))["formatted"], "0 ETH");
+    }
+
+    fn etherscan_balance_mock(path: &str, body: Value) -> mockito::Mock {
+        use mockito::{mock, Matcher};
+
+        mock("GET", path)
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("chainid".into(), "1".into()),
+                Matcher::UrlEncoded("module".into(), "account".into()),
+                Matcher::UrlEncoded("action".into(), "balance".into()),
+                Matcher::UrlEncoded("address".into(), "0x000000000000000000000000000000000000dEaD".into()),
+                Matcher::UrlEncoded("tag".into(), "latest".into()),
+                Matcher::UrlEncoded("apikey".into(), "test-key".into()),
+            ]))
+            .with_body(body.to_string())
+            .expect(1)
+            .create()
+    }
+
+    async fn etherscan_balance(path: &str) -> Result<BalanceResponse> {
+        let base_url = format!("{}{}", mockito::server_url(), path);
+        get_balance(&Client::new(), &base_url, "1", "0x000000000000000000000000000000000000dEaD", "test-key").await
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_from_etherscan() {
+        let m = etherscan_balance_mock(
+            "/balance-etherscan",
+            json!({"status": "1", "message": "OK", "result": "1500000000000000000"}),
+        );
+        let balance = etherscan_balance("/balance-etherscan").await.unwrap();
+        m.assert();
+        assert_eq!(balance.amount, "1500000000000000000");
+        assert_eq!(balance.denom, "wei");
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_zero() {
+        let _m = etherscan_balance_mock("/balance-etherscan-zero", json!({"status": "1", "message": "OK", "result": "0"}));
+        let balance = etherscan_balance("/balance-etherscan-zero").await.unwrap();
+        assert_eq!(balance.amount, "0");
+        assert_eq!(balance.denom, "wei");
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_reports_etherscan_error() {
+        let _m = etherscan_balance_mock(
+            "/balance-etherscan-error",
+            json!({"status": "0", "message": "NOTOK", "result": "Invalid API Key"}),
+        );
+        let err = etherscan_balance("/balance-etherscan-error").await.unwrap_err().to_string();
+        assert_eq!(err, "Etherscan API error: NOTOK - Invalid API Key");
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_rpc_reads_block() {
+        use mockito::{mock, Matcher};
+
+        let _m = mock("POST", "/balance-rpc")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_getBalance",
+                "params": ["0x000000000000000000000000000000000000dEaD", "0x10"]
+            })))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": "0xde0b6b3a7640000"}).to_string())
+            .create();
+        let rpc = format!("{}/balance-rpc", mockito::server_url());
+        let balance = get_balance_rpc(&Client::new(), &rpc, "0x000000000000000000000000000000000000dEaD", &json!("0x10"))
+            .await
+            .unwrap();
+        assert_eq!(balance.amount, "1000000000000000000");
+        assert_eq!(balance.denom, "wei");
+    }
+
+    #[tokio::test]
+    async fn test_get_account_state_reads_balance_nonces_and_code() {
+        use mockito::{mock, Matcher};
+
+        let address = "0x000000000000000000000000000000000000dEaD";
+        let rpc = |body: Value, result: &str| {
+            mock("POST", "/account-state")
+                .match_body(Matcher::PartialJson(body))
+                .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string())
+                .create()
+        };
+        let _balance = rpc(json!({"method": "eth_getBalance"}), "0x14d1120d7b160000");
+        let _latest = rpc(json!({"method": "eth_getTransactionCount", "params": [address, "latest"]}), "0x7");
+        let _pending = rpc(json!({"method": "eth_getTransactionCount", "params": [address, "pending"]}), "0x9");
+        let _code = rpc(json!({"method": "eth_getCode"}), "0x");
+        let url = format!("{}/account-state", mockito::server_url());
+
+        let state = get_account_state(&Client::new(), &url, address).await.unwrap();
+        assert_eq!(
+            state,
+            AccountState { balance: U256::exp10(17) * 15u64, nonce: 7, pending_nonce: 9, code_size: 0 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_balance_sees_increase_on_second_poll() {
+        use mockito::{mock, Matcher};
+
+        let balance = |wei: &str| {
+            mock("POST", "/wait-for-balance")
+                .match_body(Matcher::PartialJson(json!({"method": "eth_getBalance"})))
+                .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": wei}).to_string())
+                .expect(1)
+                .create()
+        };
+        // Mocks with hits still expected are served first, in creation order
+        let before = balance("0x64");
+        let after = balance("0x3e8");
+        let rpc = format!("{}/wait-for-balance", mockito::server_url());
+
+        let wait = wait_for_balance(
+            &Client::new(),
+            &rpc,
+            "0x000000000000000000000000000000000000dEaD",
+            None,
+            BalanceTarget::IncreaseBy(U256::from(500)),
+            Duration::from_secs(5),
+            Duration::from_millis(20),
+        )
+        .await
+        .unwrap();
+
+        before.assert();
+        after.assert();
+        assert!(wait.met);
+        assert_eq!(wait.initial, U256::from(100));
+        assert_eq!(wait.balance, U256::from(1000));
+        assert_eq!(wait.polls, 2);
+    }
+}
@@ -0,0 +1,94 @@
+// src/blockchain/services/chain.rs
+
+use crate::blockchain::services::rpc::{parse_hex_u64, rpc_call};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use futures::future::join_all;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+/// Default tip lag (in seconds) after which an endpoint is reported as stalled.
+pub const DEFAULT_MAX_TIP_LAG_SECS: u64 = 120;
+
+/// Fetches the latest block and returns its `(number, timestamp)`.
+pub async fn get_latest_block(client: &Client, rpc_url: &str) -> Result<(u64, u64)> {
+    let block = rpc_call(client, rpc_url, "eth_getBlockByNumber", json!(["latest", false])).await?;
+    if block.is_null() {
+        return Err(anyhow!("eth_getBlockByNumber returned no block"));
+    }
+    Ok((parse_hex_u64(&block["number"])?, parse_hex_u64(&block["timestamp"])?))
+}
+
+/// Reports how far the chain tip lags behind wall-clock time and whether
+/// that lag exceeds `max_lag_secs` (a likely stuck or archival-only node).
+pub async fn chain_tip_health(client: &Client, rpc_url: &str, max_lag_secs: u64) -> Result<Value> {
+    let (number, timestamp) = get_latest_block(client, rpc_url).await?;
+    let now = Utc::now().timestamp().max(0) as u64;
+    // Clocks can drift slightly ahead of the node; never report a negative lag.
+    let lag_secs = now.saturating_sub(timestamp);
+    Ok(json!({
+        "block_number": number,
+        "block_timestamp": timestamp,
+        "lag_secs": lag_secs,
+        "max_lag_secs": max_lag_secs,
+        "stalled": lag_secs > max_lag_secs,
+    }))
+}
+
+/// Fetches the current block number via `eth_blockNumber`.
+pub async fn block_number(client: &Client, rpc_url: &str) -> Result<u64> {
+    let v = rpc_call(client, rpc_url, "eth_blockNumber", json!([])).await?;
+    parse_hex_u64(&v)
+}
+
+/// Pings every `(chain_id, rpc_url)` target concurrently with `eth_blockNumber`.
+/// Each entry reports `reachable` plus the latest block or the error. The request
+/// timeout is taken from `client`.
+pub async fn probe_rpc_endpoints(client: &Client, targets: &[(String, String)]) -> Vec<Value> {
+    join_all(targets.iter().map(|(chain_id, rpc_url)| async move {
+        match block_number(client, rpc_url).await {
+            Ok(n) => json!({ "chain_id": chain_id, "reachable": true, "block_number": n }),
+            Err(e) => json!({ "chain_id": chain_id, "reachable": false, "error": e.to_string() }),
+        }
+    }))
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{mock, Matcher};
+
+    fn mock_block(path: &str, timestamp: i64) -> mockito::Mock {
+        mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({ "method": "eth_getBlockByNumber" })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": { "number": "0x10", "timestamp": format!("0x{:x}", timestamp) }
+                })
+                .to_string(),
+            )
+            .create()
+    }
+
+    #[tokio::test]
+    async fn test_old_tip_is_flagged_as_stalled() {
+        let _m = mock_block("/stale", Utc::now().timestamp() - 3600);
+        let url = format!("{}/stale", mockito::server_url());
+        let health = chain_tip_health(&Client::new(), &url, DEFAULT_MAX_TIP_LAG_SECS).await.unwrap();
+        assert_eq!(health["block_number"], 16);
+        assert_eq!(health["stalled"], true);
+        assert!(health["lag_secs"].as_u64().unwrap() >= 3600);
+    }
+
+    #[tokio::test]
+    async fn test_fresh_tip_is_healthy() {
+        let _m = mock_block("/fresh", Utc::now().timestamp() - 5);
+        let url = format!("{}/fresh", mockito::server_url());
+        let health = chain_tip_health(&Client::new(), &url, DEFAULT_MAX_TIP_LAG_SECS).await.unwrap();
+        assert_eq!(health["stalled"], false);
+    }
+}
@@ -1,18 +1,17 @@
 // src/blockchain/services/contract.rs
 
+use crate::blockchain::services::rpc::{self, rpc_call};
+use crate::metrics;
+use crate::utils;
 use anyhow::{anyhow, Result};
+use ethers_core::types::{Address, H256, U256};
+use ethers_core::utils::{keccak256, to_checksum};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Map, Value};
 
-#[derive(Deserialize, Debug)]
-struct EtherscanContractResponse {
-    status: String,
-    message: String,
-    result: Vec<EtherscanContractResult>,
-}
-
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Default, Serialize)]
+#[serde(default)]
 struct EtherscanContractResult {
     #[serde(rename = "SourceCode")]
     source_code: String,
@@ -101,19 +100,20 @@ pub async fn get_contract_code(client: &Client, rpc_url: &str, address: &str) ->
     let status = res.status();
     let body = res.text().await.unwrap_or_default();
 
-    match serde_json::from_str::<Value>(&body) {
-        Ok(v) => {
-            if let Some(result) = v.get("result").and_then(|r| r.as_str()) {
-                Ok(serde_json::json!({
-                    "address": address,
-                    "code": result,
-                    "runtimeCode": result
-                }))
-            } else {
-                Ok(serde_json::json!({ "status": status.as_u16(), "raw": body }))
-            }
-        }
-        Err(_) => Ok(serde_json::json!({ "status": status.as_u16(), "raw": body })),
+    let v = serde_json::from_str::<Value>(&body).ok();
+    if let Some(err) = v.as_ref().and_then(|v| v.get("error")) {
+        return Err(rpc::response_error("eth_getCode error", err));
+    }
+    if !status.is_success() {
+        return Err(anyhow!("eth_getCode failed with HTTP {}: {}", status, body));
+    }
+    match v.as_ref().and_then(|v| v.get("result")).and_then(|r| r.as_str()) {
+        Some(result) => Ok(serde_json::json!({
+            "address": address,
+            "code": result,
+            "runtimeCode": result
+        })),
+        None => Err(anyhow!("eth_getCode returned no bytecode: {}", body)),
     }
 }
 
@@ -270,58 +270,96 @@ fn normalize_contract_code(v: Value) -> Value {
     })
 }
 
+/// Parses an Etherscan `SourceCode` field. Multi-file contracts come as standard JSON input,
+/// which Etherscan wraps in an extra pair of braces (`{{...}}`), or as a bare
+/// `{name: {content}}` map. Returns the `sources` object and the standard JSON `settings`;
+/// `None` for a single flat source file.
+fn parse_standard_json_sources(source_code: &str) -> Option<(Map<String, Value>, Option<Value>)> {
+    let trimmed = source_code.trim();
+    let unwrapped = match trimmed.strip_prefix("{{").and_then(|s| s.strip_suffix("}}")) {
+        Some(inner) => format!("{{{}}}", inner),
+        None => trimmed.to_string(),
+    };
+    let Ok(Value::Object(mut input)) = serde_json::from_str::<Value>(&unwrapped) else {
+        return None;
+    };
+    match input.remove("sources") {
+        Some(Value::Object(sources)) => Some((sources, input.remove("settings"))),
+        Some(_) => None,
+        None => Some((input, None)),
+    }
+}
+
+/// Source files of an Etherscan `SourceCode` field as `[{name, sourceCode}]`, one per file
+/// of a multi-file contract, or a single file named after the contract otherwise.
+pub fn etherscan_source_files(source_code: &str, contract_name: &str) -> Vec<Value> {
+    match parse_standard_json_sources(source_code) {
+        Some((sources, _)) => sources
+            .into_iter()
+            .map(|(name, file)| {
+                let content = match file {
+                    Value::String(s) => s,
+                    file => file.get("content").and_then(|c| c.as_str()).unwrap_or("").to_string(),
+                };
+                json!({ "name": name, "sourceCode": content })
+            })
+            .collect(),
+        None => {
+            let name = if contract_name.is_empty() { "Contract.sol".to_string() } else { format!("{}.sol", contract_name) };
+            vec![json!({ "name": name, "sourceCode": source_code })]
+        }
+    }
+}
+
+/// Converts an Etherscan `getsourcecode` record into the normalized contract code schema
+/// (see `normalize_contract_code`). Etherscan doesn't return bytecode, so `runtimeCode` and
+/// `creationCode` are left empty for the caller to fill in.
+pub fn normalize_source_record(record: &Value) -> Value {
+    let field = |key: &str| record.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    let sources = etherscan_source_files(field("SourceCode"), field("ContractName"));
+    let settings = parse_standard_json_sources(field("SourceCode")).and_then(|(_, settings)| settings);
+
+    let mut compiler_settings = json!({
+        "compilerVersion": field("CompilerVersion"),
+        "optimizationUsed": field("OptimizationUsed") == "1",
+        "runs": field("Runs").parse::<u64>().ok(),
+        "evmVersion": field("EVMVersion"),
+    });
+    if let Some(settings) = settings {
+        compiler_settings["settings"] = settings;
+    }
+    // "Name:0xaddress" pairs separated by semicolons
+    let external_libraries: Vec<Value> = field("Library")
+        .split(';')
+        .filter_map(|lib| lib.split_once(':'))
+        .map(|(name, address)| json!({ "name": name.trim(), "address": address.trim() }))
+        .collect();
+
+    normalize_contract_code(json!({
+        "abi": serde_json::from_str::<Value>(field("ABI")).unwrap_or(Value::Null),
+        "compilerSettings": compiler_settings,
+        "externalLibraries": external_libraries,
+        "sources": sources,
+    }))
+}
+
 /// Get contract source code from Etherscan API
 pub async fn get_contract_source_code(
     client: &Client,
+    base_url: &str,
     chain_id: &str,
     address: &str,
     etherscan_api_key: &str,
 ) -> Result<Value> {
-    // Map chain IDs to Etherscan base URLs
-    let base_url = match chain_id {
-        "1" => "https://api.etherscan.io/v2/api",
-        "11155111" => "https://api-sepolia.etherscan.io/v2/api",
-        "324" | "300" => {
-            // zkSync chains don't have Etherscan support, return error
-            return Err(anyhow!("Etherscan API not supported for zkSync chains"));
-        }
-        _ => return Err(anyhow!("Unsupported chain ID for Etherscan: {}", chain_id)),
-    };
-
-    // Build the Etherscan API URL for getting source code
-    let url = format!(
-        "{}?chainid={}&module=contract&action=getsourcecode&address={}&apikey={}",
-        base_url, chain_id, address, etherscan_api_key
-    );
-
-    let res: EtherscanContractResponse = client
-        .get(&url)
-        .send()
-        .await?
-        .json()
-        .await
-        .map_err(|e| anyhow!("Failed to parse Etherscan response: {}", e))?;
-
-    if res.status != "1" {
-        return Err(anyhow!(
-            "Etherscan API error: {} - {}",
-            res.message,
-            if res.result.is_empty() {
-                "No result".to_string()
-            } else {
-                res.result[0].source_code.clone()
-            }
-        ));
-    }
-
-    if res.result.is_empty() {
+    let record = get_source_record(client, base_url, chain_id, address, etherscan_api_key).await?;
+    if record.is_null() {
         return Err(anyhow!(
             "No contract source code found for address: {}",
             address
         ));
     }
-
-    let contract = &res.result[0];
+    let contract: EtherscanContractResult = serde_json::from_value(record)
+        .map_err(|e| anyhow!("Failed to parse Etherscan response: {}", e))?;
 
     // Parse ABI if it's valid JSON
     let abi_value: Value = if contract.abi.is_empty() {
@@ -337,6 +375,7 @@ pub async fn get_contract_source_code(
         "chainId": chain_id,
         "contractName": contract.contract_name,
         "sourceCode": contract.source_code,
+        "sources": etherscan_source_files(&contract.source_code, &contract.contract_name),
         "abi": abi_value,
         "compiler": {
             "version": contract.compiler_version,
@@ -352,3 +391,362 @@ pub async fn get_contract_source_code(
         "swarmSource": contract.swarm_source
     }))
 }
+
+async fn etherscan_get(client: &Client, url: &str) -> Result<Value> {
+    client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| anyhow!("Etherscan API error: {}", e))?
+        .json()
+        .await
+        .map_err(|e| anyhow!("Invalid Etherscan JSON response: {}", e))
+}
+
+/// The raw `getsourcecode` record for `address` (`Null` when Etherscan returns none).
+pub async fn get_source_record(
+    client: &Client,
+    etherscan_base_url: &str,
+    chain_id: &str,
+    address: &str,
+    etherscan_api_key: &str,
+) -> Result<Value> {
+    let url = format!(
+        "{}?chainid={}&module=contract&action=getsourcecode&address={}&apikey={}",
+        etherscan_base_url, chain_id, address, etherscan_api_key
+    );
+    metrics::record_etherscan_request("contract", "getsourcecode");
+    let resp = etherscan_get(client, &url).await?;
+    if let Some(status) = resp.get("status").and_then(|v| v.as_str()) {
+        if status != "1" {
+            let message = resp.get("message").and_then(|v| v.as_str()).unwrap_or("Unknown error");
+            return Err(anyhow!("Etherscan API error: {}", message));
+        }
+    }
+    Ok(resp
+        .get("result")
+        .and_then(|v| v.as_array())
+        .and_then(|arr| arr.first())
+        .cloned()
+        .unwrap_or(Value::Null))
+}
+
+/// Whether Etherscan has verified source for a contract, and the contract's name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    pub verified: bool,
+    pub contract_name: String,
+}
+
+pub async fn is_verified(
+    client: &Client,
+    etherscan_base_url: &str,
+    chain_id: &str,
+    address: &str,
+    etherscan_api_key: &str,
+) -> Result<Verification> {
+    let record = get_source_record(client, etherscan_base_url, chain_id, address, etherscan_api_key).await?;
+    let has_source_code = record.get("SourceCode").and_then(|v| v.as_str()).is_some_and(|s| !s.is_empty());
+    let contract_name = record.get("ContractName").and_then(|v| v.as_str()).unwrap_or("Unknown");
+    Ok(Verification {
+        verified: has_source_code && !contract_name.is_empty(),
+        contract_name: contract_name.to_string(),
+    })
+}
+
+/// First page (10 transactions, oldest first) of Etherscan's `txlist` for `address`
+/// within `[start_block, end_block]`. An address with no transactions gives an empty list.
+pub async fn get_txlist(
+    client: &Client,
+    etherscan_base_url: &str,
+    chain_id: &str,
+    address: &str,
+    start_block: u64,
+    end_block: u64,
+    etherscan_api_key: &str,
+) -> Result<Value> {
+    let url = format!(
+        "{}?chainid={}&module=account&action=txlist&address={}&startblock={}&endblock={}&page=1&offset=10&sort=asc&apikey={}",
+        etherscan_base_url, chain_id, address, start_block, end_block, etherscan_api_key
+    );
+    metrics::record_etherscan_request("account", "txlist");
+    let resp = etherscan_get(client, &url).await?;
+    utils::etherscan_list_result(&resp).map_err(|message| anyhow!("Etherscan API error: {}", message))
+}
+
+/// Parses a storage slot or mapping key given as 0x-hex or decimal.
+pub fn parse_slot(slot: &str) -> Result<U256> {
+    let s = slot.trim();
+    match s.strip_prefix("0x") {
+        Some(h) if !h.is_empty() && h.len() <= 64 => {
+            U256::from_str_radix(h, 16).map_err(|_| anyhow!("invalid hex slot '{}'", slot))
+        }
+        Some(_) => Err(anyhow!("hex slot '{}' must be 1 to 32 bytes", slot)),
+        None => U256::from_dec_str(s).map_err(|_| anyhow!("invalid slot '{}'; use 0x-hex or decimal", slot)),
+    }
+}
+
+/// Storage slot of `mapping[key]` for a Solidity mapping at `base_slot`:
+/// `keccak256(pad32(key) ++ pad32(base_slot))`. Value-type keys (address, uint, bytes32)
+/// are all left-padded to 32 bytes, so `key` is parsed like a slot.
+pub fn mapping_slot(base_slot: U256, key: &str) -> Result<U256> {
+    let mut preimage = [0u8; 64];
+    parse_slot(key)?.to_big_endian(&mut preimage[..32]);
+    base_slot.to_big_endian(&mut preimage[32..]);
+    Ok(U256::from_big_endian(&keccak256(preimage)))
+}
+
+fn to_word(n: &U256) -> H256 {
+    let mut bytes = [0u8; 32];
+    n.to_big_endian(&mut bytes);
+    H256(bytes)
+}
+
+/// Reads the raw 32-byte word at `slot` of `address` via `eth_getStorageAt`, with the
+/// value also read as a uint256 and as an address (its low 20 bytes).
+pub async fn get_storage_at(
+    client: &Client,
+    rpc_url: &str,
+    address: &str,
+    slot: U256,
+    block: &Value,
+) -> Result<Value> {
+    let address = address.parse::<Address>().map_err(|_| anyhow!("invalid address '{}'", address))?;
+    let slot_hex = format!("{:?}", to_word(&slot));
+    let raw = rpc_call(client, rpc_url, "eth_getStorageAt", json!([address, slot_hex, block])).await?;
+    let raw = raw.as_str().ok_or_else(|| anyhow!("unexpected eth_getStorageAt result: {}", raw))?;
+    let word = to_word(&parse_slot(raw)?);
+    Ok(json!({
+        "address": to_checksum(&address, None),
+        "slot": slot_hex,
+        "block": block,
+        "value": format!("{:?}", word),
+        "as_uint256": U256::from_big_endian(word.as_bytes()).to_string(),
+        "as_address": to_checksum(&Address::from_slice(&word.as_bytes()[12..]), None),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{mock, Matcher};
+
+    #[test]
+    fn test_mapping_slot_matches_solidity_layout() {
+        // balances[0x...dEaD] for `mapping(address => uint256) balances` declared at slot 0
+        let key = "0x000000000000000000000000000000000000dEaD";
+        let mut preimage = H256::from(key.parse::<Address>().unwrap()).as_bytes().to_vec();
+        preimage.extend_from_slice(&[0u8; 32]);
+        let expected = U256::from_big_endian(&keccak256(&preimage));
+        assert_eq!(mapping_slot(U256::zero(), key).unwrap(), expected);
+        // Decimal keys are uint256 keys
+        assert_eq!(mapping_slot(U256::from(3), "1").unwrap(), mapping_slot(U256::from(3), "0x1").unwrap());
+        assert!(parse_slot("0xzz").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_storage_at_direct_and_mapping_slot() {
+        let owner = "0x000000000000000000000000000000000000dEaD";
+        let _direct = mock("POST", "/storage-at")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_getStorageAt",
+                "params": ["0x0000000000000000000000000000000000000abc", format!("{:?}", H256::from_low_u64_be(5)), "latest"]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{:0>64}", "dead")}).to_string())
+            .create();
+        let slot = mapping_slot(U256::from(2), owner).unwrap();
+        let _mapped = mock("POST", "/storage-at")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_getStorageAt",
+                "params": ["0x0000000000000000000000000000000000000abc", format!("{:?}", to_word(&slot)), "0x10"]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{:064x}", 1000)}).to_string())
+            .create();
+        let url = format!("{}/storage-at", mockito::server_url());
+        let client = Client::new();
+        let contract = "0x0000000000000000000000000000000000000abc";
+
+        let v = get_storage_at(&client, &url, contract, parse_slot("5").unwrap(), &json!("latest")).await.unwrap();
+        assert_eq!(v["as_uint256"], "57005");
+        assert_eq!(v["as_address"], owner);
+
+        let block = rpc::block_param(Some("16")).unwrap();
+        let v = get_storage_at(&client, &url, contract, slot, &block).await.unwrap();
+        assert_eq!(v["as_uint256"], "1000");
+        assert_eq!(v["block"], "0x10");
+    }
+
+    #[tokio::test]
+    async fn test_get_contract_code_fails_on_http_error() {
+        let _bad_gateway = mock("POST", "/code-502").with_status(502).with_body("<html>Bad Gateway</html>").create();
+        let url = format!("{}/code-502", mockito::server_url());
+        let err = get_contract_code(&Client::new(), &url, "0x0000000000000000000000000000000000000abc")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("502"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_get_source_record_and_is_verified() {
+        let _m = mock("GET", "/etherscan-source")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("action".into(), "getsourcecode".into()),
+                Matcher::UrlEncoded("address".into(), "0xabc".into()),
+            ]))
+            .with_body(
+                json!({"status": "1", "message": "OK", "result": [
+                    {"SourceCode": "contract Token {}", "ContractName": "Token", "ABI": "[]"}
+                ]})
+                .to_string(),
+            )
+            .create();
+        let _unverified = mock("GET", "/etherscan-source")
+            .match_query(Matcher::UrlEncoded("address".into(), "0xdef".into()))
+            .with_body(json!({"status": "1", "message": "OK", "result": [{"SourceCode": "", "ContractName": ""}]}).to_string())
+            .create();
+        let url = format!("{}/etherscan-source", mockito::server_url());
+        let client = Client::new();
+
+        let record = get_source_record(&client, &url, "1", "0xabc", "key").await.unwrap();
+        assert_eq!(record["ContractName"], "Token");
+        let verification = is_verified(&client, &url, "1", "0xabc", "key").await.unwrap();
+        assert_eq!(verification, Verification { verified: true, contract_name: "Token".into() });
+        assert!(!is_verified(&client, &url, "1", "0xdef", "key").await.unwrap().verified);
+
+        let source = get_contract_source_code(&client, &url, "1", "0xabc", "key").await.unwrap();
+        assert_eq!(source["contractName"], "Token");
+        assert_eq!(source["sourceCode"], "contract Token {}");
+        assert_eq!(source["abi"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_get_source_record_reports_etherscan_errors() {
+        let _m = mock("GET", "/etherscan-source-error")
+            .match_query(Matcher::Any)
+            .with_body(json!({"status": "0", "message": "NOTOK", "result": "Invalid API Key"}).to_string())
+            .create();
+        let url = format!("{}/etherscan-source-error", mockito::server_url());
+        let err = get_source_record(&Client::new(), &url, "1", "0xabc", "bad").await.unwrap_err();
+        assert_eq!(err.to_string(), "Etherscan API error: NOTOK");
+        let err = get_contract_source_code(&Client::new(), &url, "1", "0xabc", "bad").await.unwrap_err();
+        assert_eq!(err.to_string(), "Etherscan API error: NOTOK");
+    }
+
+    #[tokio::test]
+    async fn test_get_txlist_passes_block_range_and_handles_empty_history() {
+        let _m = mock("GET", "/etherscan-txlist")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("action".into(), "txlist".into()),
+                Matcher::UrlEncoded("address".into(), "0xabc".into()),
+                Matcher::UrlEncoded("startblock".into(), "100".into()),
+                Matcher::UrlEncoded("endblock".into(), "200".into()),
+            ]))
+            .with_body(json!({"status": "1", "message": "OK", "result": [{"hash": "0x1"}, {"hash": "0x2"}]}).to_string())
+            .create();
+        let _empty = mock("GET", "/etherscan-txlist")
+            .match_query(Matcher::UrlEncoded("address".into(), "0xdef".into()))
+            .with_body(json!({"status": "0", "message": "No transactions found", "result": []}).to_string())
+            .create();
+        let url = format!("{}/etherscan-txlist", mockito::server_url());
+        let client = Client::new();
+
+        let txs = get_txlist(&client, &url, "1", "0xabc", 100, 200, "key").await.unwrap();
+        assert_eq!(txs.as_array().unwrap().len(), 2);
+        let none = get_txlist(&client, &url, "1", "0xdef", 0, 99999999, "key").await.unwrap();
+        assert_eq!(none, json!([]));
+    }
+
+    #[test]
+    fn test_normalize_flat_single_file_source() {
+        let record = json!({
+            "SourceCode": "pragma solidity ^0.8.0;\ncontract Token {}",
+            "ABI": "[{\"type\":\"function\",\"name\":\"totalSupply\",\"inputs\":[],\"outputs\":[{\"name\":\"\",\"type\":\"uint256\"}]}]",
+            "ContractName": "Token",
+            "CompilerVersion": "v0.8.19+commit.7dd6d404",
+            "OptimizationUsed": "1",
+            "Runs": "200",
+            "EVMVersion": "Default",
+            "Library": ""
+        });
+        let normalized = normalize_source_record(&record);
+        assert_eq!(
+            normalized["sources"],
+            json!([{"name": "Token.sol", "sourceCode": "pragma solidity ^0.8.0;\ncontract Token {}"}])
+        );
+        assert_eq!(normalized["abi"].as_array().unwrap().len(), 1);
+        assert!(normalized["abi"][0].as_str().unwrap().contains("totalSupply"));
+        assert_eq!(normalized["compilerSettings"][0]["runs"], 200);
+        assert_eq!(normalized["compilerSettings"][0]["optimizationUsed"], true);
+        assert_eq!(normalized["externalLibraries"], json!([]));
+        assert_eq!(normalized["runtimeCode"], "");
+    }
+
+    #[test]
+    fn test_normalize_double_braced_standard_json_sources() {
+        let input = json!({
+            "language": "Solidity",
+            "sources": {
+                "contracts/Token.sol": {"content": "import \"./Lib.sol\"; contract Token {}"},
+                "contracts/Lib.sol": {"content": "library Lib {}"}
+            },
+            "settings": {"optimizer": {"enabled": true, "runs": 999}}
+        });
+        let record = json!({
+            "SourceCode": format!("{{{}}}", input),
+            "ABI": "[]",
+            "ContractName": "Token",
+            "Library": "Lib:0x0000000000000000000000000000000000000abc"
+        });
+        let normalized = normalize_source_record(&record);
+        let mut sources = normalized["sources"].as_array().unwrap().clone();
+        sources.sort_by_key(|s| s["name"].as_str().unwrap().to_string());
+        assert_eq!(
+            sources,
+            vec![
+                json!({"name": "contracts/Lib.sol", "sourceCode": "library Lib {}"}),
+                json!({"name": "contracts/Token.sol", "sourceCode": "import \"./Lib.sol\"; contract Token {}"}),
+            ]
+        );
+        assert_eq!(normalized["compilerSettings"][0]["settings"]["optimizer"]["runs"], 999);
+        assert_eq!(
+            normalized["externalLibraries"],
+            json!([{"name": "Lib", "address": "0x0000000000000000000000000000000000000abc"}])
+        );
+
+        // The older multi-file format is a bare name -> content map
+        let record = json!({"SourceCode": json!({"A.sol": {"content": "contract A {}"}}).to_string(), "ContractName": "A"});
+        assert_eq!(normalize_source_record(&record)["sources"], json!([{"name": "A.sol", "sourceCode": "contract A {}"}]));
+    }
+
+    #[test]
+    fn test_etherscan_source_files_unwraps_double_braced_input() {
+        // Shaped like Etherscan's reply for a contract verified from several files
+        let source_code = r#"{{
+  "language": "Solidity",
+  "sources": {
+    "contracts/Vault.sol": {
+      "content": "// SPDX-License-Identifier: MIT\npragma solidity 0.8.20;\nimport \"@openzeppelin/contracts/token/ERC20/IERC20.sol\";\ncontract Vault {}\n"
+    },
+    "@openzeppelin/contracts/token/ERC20/IERC20.sol": {
+      "content": "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.20;\ninterface IERC20 {}\n"
+    }
+  },
+  "settings": {
+    "optimizer": {"enabled": true, "runs": 200},
+    "outputSelection": {"*": {"*": ["evm.bytecode", "abi"]}}
+  }
+}}"#;
+        let files = etherscan_source_files(source_code, "Vault");
+        assert_eq!(files.len(), 2);
+        let vault = files.iter().find(|f| f["name"] == "contracts/Vault.sol").unwrap();
+        assert!(vault["sourceCode"].as_str().unwrap().contains("contract Vault {}"));
+        let ierc20 = files.iter().find(|f| f["name"] == "@openzeppelin/contracts/token/ERC20/IERC20.sol").unwrap();
+        assert!(ierc20["sourceCode"].as_str().unwrap().starts_with("// SPDX-License-Identifier: MIT"));
+
+        // A flat file stays whole, even when it starts with a brace-looking comment
+        let flat = "/** {not json} */\ncontract Vault {}";
+        assert_eq!(etherscan_source_files(flat, "Vault"), vec![json!({"name": "Vault.sol", "sourceCode": flat})]);
+    }
+}
@@ -0,0 +1,30 @@
+// src/blockchain/services/dex.rs
+
+use crate::blockchain::services::token::read_contract_via_signature;
+use anyhow::{anyhow, Result};
+use ethers_core::types::{Address, U256};
+use reqwest::Client;
+use serde_json::json;
+
+/// UniswapV2-style router quote: the amount received at each hop of `path`.
+pub const GET_AMOUNTS_OUT: &str = "getAmountsOut(uint256 amountIn, address[] path) returns (uint256[] amounts)";
+
+/// Quotes swapping `amount_in` of `path[0]` along `path` with the router's `getAmountsOut`.
+/// Returns one decimal amount per token in `path`, the input amount first.
+pub async fn get_amounts_out(client: &Client, rpc_url: &str, router: &str, amount_in: U256, path: &[Address]) -> Result<Vec<String>> {
+    if path.len() < 2 {
+        return Err(anyhow!("path needs at least two token addresses"));
+    }
+    let args = vec![json!(amount_in.to_string()), json!(path.iter().map(|a| format!("{:?}", a)).collect::<Vec<_>>())];
+    let read = read_contract_via_signature(client, rpc_url, router, GET_AMOUNTS_OUT, Some(args), &json!("latest")).await?;
+    let amounts: Vec<String> = read["decoded"][0]
+        .as_array()
+        .ok_or_else(|| anyhow!("router {} returned no amounts (raw {})", router, read["raw"]))?
+        .iter()
+        .filter_map(|v| v.as_str().map(str::to_string))
+        .collect();
+    if amounts.len() != path.len() {
+        return Err(anyhow!("router returned {} amounts for a {}-token path", amounts.len(), path.len()));
+    }
+    Ok(amounts)
+}
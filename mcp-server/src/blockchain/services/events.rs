@@ -0,0 +1,175 @@
+// src/blockchain/services/events.rs
+
+use crate::blockchain::services::abi::token_to_json;
+use anyhow::{anyhow, Context, Result};
+use ethers_core::abi::{Event, HumanReadableParser, RawLog};
+use ethers_core::types::{Bytes, H256};
+use serde_json::{json, Map, Value};
+use std::str::FromStr;
+
+/// Parses an event ABI given either as a JSON fragment (object or JSON string) or as a
+/// human readable signature such as `Transfer(address indexed from, address indexed to, uint256 value)`.
+/// The leading `event` keyword is optional. Unnamed parameters are called `arg0`, `arg1`, ...
+pub fn parse_event_abi(abi: &Value) -> Result<Event> {
+    let mut event: Event = match abi {
+        Value::Object(_) => serde_json::from_value(abi.clone()).context("invalid event ABI fragment")?,
+        Value::String(s) if s.trim_start().starts_with('{') => {
+            serde_json::from_str(s).context("invalid event ABI fragment")?
+        }
+        Value::String(s) => {
+            let s = s.trim();
+            let s = if s.starts_with("event ") { s.to_string() } else { format!("event {}", s) };
+            HumanReadableParser::parse_event(&s).map_err(|e| anyhow!("invalid event signature '{}': {}", abi, e))?
+        }
+        _ => return Err(anyhow!("event ABI must be a JSON fragment or a signature string")),
+    };
+    for (i, input) in event.inputs.iter_mut().enumerate() {
+        if input.name.is_empty() {
+            input.name = format!("arg{}", i);
+        }
+    }
+    Ok(event)
+}
+
+/// topic0 of a non-anonymous event: the keccak256 hash of its canonical signature.
+pub fn event_topic(event: &Event) -> String {
+    format!("{:?}", event.signature())
+}
+
+/// Computes an event's topic0 from a human readable signature such as
+/// `Transfer(address,address,uint256)`. Parameter names, `indexed` markers and
+/// whitespace are ignored.
+pub fn compute_event_topic(signature: &str) -> Result<String> {
+    let event = parse_event_abi(&Value::String(signature.to_string()))?;
+    Ok(event_topic(&event))
+}
+
+/// Decodes a raw log (JSON-RPC or Etherscan shape) against `event`, returning its
+/// parameters by name.
+///
+/// Signatures like `Transfer(address,address,uint256)` carry no `indexed` markers; in that
+/// case the leading parameters are treated as indexed, one per topic after topic0.
+pub fn decode_log(event: &Event, log: &Value) -> Result<Value> {
+    let topics = log["topics"]
+        .as_array()
+        .ok_or_else(|| anyhow!("log has no topics"))?
+        .iter()
+        .filter_map(|t| t.as_str())
+        .map(|t| H256::from_str(t).context("invalid log topic"))
+        .collect::<Result<Vec<_>>>()?;
+    let data = Bytes::from_str(log["data"].as_str().unwrap_or("0x")).context("invalid log data")?;
+
+    let mut event = event.clone();
+    if event.inputs.iter().all(|p| !p.indexed) {
+        let skip = if event.anonymous { 0 } else { 1 };
+        let indexed = topics.len().saturating_sub(skip);
+        for input in event.inputs.iter_mut().take(indexed) {
+            input.indexed = true;
+        }
+    }
+
+    let parsed = event.parse_log(RawLog { topics, data: data.to_vec() })?;
+    let mut params = Map::new();
+    for p in parsed.params {
+        params.insert(p.name, token_to_json(&p.value));
+    }
+    Ok(Value::Object(params))
+}
+
+/// Decodes every log in `logs`, keeping the location fields needed to tie each decoded
+/// entry back to its raw log. Logs that do not match the event carry an `error` instead.
+pub fn decode_logs(event: &Event, logs: &[Value]) -> Vec<Value> {
+    logs.iter()
+        .map(|log| {
+            let mut entry = json!({
+                "event": event.name,
+                "address": log.get("address"),
+                "block_number": log.get("blockNumber"),
+                "transaction_hash": log.get("transactionHash"),
+                "log_index": log.get("logIndex"),
+            });
+            match decode_log(event, log) {
+                Ok(params) => entry["params"] = params,
+                Err(e) => entry["error"] = json!(e.to_string()),
+            }
+            entry
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_core::types::{Address, U256};
+    use ethers_core::utils::to_checksum;
+
+    const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+    fn transfer_log() -> Value {
+        json!({
+            "address": "0x0000000000000000000000000000000000000abc",
+            "topics": [
+                TRANSFER_TOPIC,
+                format!("{:?}", H256::from(Address::from_low_u64_be(1))),
+                format!("{:?}", H256::from(Address::from_low_u64_be(2))),
+            ],
+            "data": format!("0x{:064x}", 1_000_000u64),
+            "blockNumber": "0x10",
+            "transactionHash": "0xfeed",
+            "logIndex": "0x0",
+        })
+    }
+
+    #[test]
+    fn test_compute_event_topic_matches_erc20_transfer() {
+        assert_eq!(compute_event_topic("Transfer(address,address,uint256)").unwrap(), TRANSFER_TOPIC);
+        assert_eq!(
+            compute_event_topic("event Transfer(address indexed from, address indexed to, uint256 value)").unwrap(),
+            TRANSFER_TOPIC
+        );
+        assert!(compute_event_topic("not a signature").is_err());
+    }
+
+    #[test]
+    fn test_decode_erc20_transfer_from_json_fragment() {
+        let abi = json!({
+            "type": "event",
+            "name": "Transfer",
+            "anonymous": false,
+            "inputs": [
+                {"name": "from", "type": "address", "indexed": true},
+                {"name": "to", "type": "address", "indexed": true},
+                {"name": "value", "type": "uint256", "indexed": false}
+            ]
+        });
+        let event = parse_event_abi(&abi).unwrap();
+        assert_eq!(event_topic(&event), TRANSFER_TOPIC);
+
+        let decoded = decode_log(&event, &transfer_log()).unwrap();
+        assert_eq!(decoded["from"], to_checksum(&Address::from_low_u64_be(1), None));
+        assert_eq!(decoded["to"], to_checksum(&Address::from_low_u64_be(2), None));
+        assert_eq!(decoded["value"], U256::from(1_000_000u64).to_string());
+    }
+
+    #[test]
+    fn test_decode_transfer_from_bare_signature() {
+        let event = parse_event_abi(&json!("Transfer(address,address,uint256)")).unwrap();
+        assert_eq!(event_topic(&event), TRANSFER_TOPIC);
+
+        let decoded = decode_logs(&event, &[transfer_log()]);
+        assert_eq!(decoded[0]["event"], "Transfer");
+        assert_eq!(decoded[0]["transaction_hash"], "0xfeed");
+        assert_eq!(decoded[0]["params"]["arg0"], to_checksum(&Address::from_low_u64_be(1), None));
+        assert_eq!(decoded[0]["params"]["arg2"], "1000000");
+    }
+
+    #[test]
+    fn test_mismatched_log_reports_error() {
+        let event = parse_event_abi(&json!("Approval(address indexed owner, address indexed spender, uint256 value)")).unwrap();
+        let mut log = transfer_log();
+        log["data"] = json!("0x");
+        let decoded = decode_logs(&event, &[log]);
+        assert!(decoded[0]["error"].is_string());
+        assert!(decoded[0].get("params").is_none());
+    }
+}
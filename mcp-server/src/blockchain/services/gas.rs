@@ -0,0 +1,258 @@
+// src/blockchain/services/gas.rs
+
+use crate::blockchain::models::EstimateFeesResponse;
+use crate::blockchain::services::rpc::{parse_hex_u256, rpc_call};
+use anyhow::{anyhow, Result};
+use ethers_core::types::U256;
+use ethers_core::utils::format_ether;
+use futures::future::join_all;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+/// Estimates the gas limit for a call object via `eth_estimateGas`.
+pub async fn estimate_gas(client: &Client, rpc_url: &str, call: &Value) -> Result<U256> {
+    let v = rpc_call(client, rpc_url, "eth_estimateGas", json!([call])).await?;
+    parse_hex_u256(&v)
+}
+
+/// Fetches the node's current legacy gas price via `eth_gasPrice`.
+pub async fn gas_price(client: &Client, rpc_url: &str) -> Result<U256> {
+    let v = rpc_call(client, rpc_url, "eth_gasPrice", json!([])).await?;
+    parse_hex_u256(&v)
+}
+
+/// Blocks of `eth_feeHistory` the priority fee suggestion looks at
+const FEE_HISTORY_BLOCKS: u64 = 5;
+/// Reward percentile taken from each of those blocks
+const PRIORITY_FEE_PERCENTILE: f64 = 50.0;
+
+/// EIP-1559 fee suggestion, all in wei.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Eip1559Fees {
+    pub base_fee: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+}
+
+/// EIP-1559 fees for the chain, or `None` when the latest block has no base fee (a legacy
+/// chain). The tip is the median of recent blocks' 50th-percentile rewards, or
+/// `legacy_price` minus the base fee where `eth_feeHistory` isn't available. The max fee is
+/// 2 × base fee + tip, which stays includable through several full blocks.
+pub async fn eip1559_fees(client: &Client, rpc_url: &str, legacy_price: U256) -> Result<Option<Eip1559Fees>> {
+    let block = rpc_call(client, rpc_url, "eth_getBlockByNumber", json!(["latest", false])).await?;
+    let Some(base_fee) = block.get("baseFeePerGas").filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+    let base_fee = parse_hex_u256(base_fee)?;
+    let tip = match history_priority_fee(client, rpc_url).await {
+        Ok(Some(tip)) => tip,
+        _ => legacy_price.saturating_sub(base_fee),
+    };
+    Ok(Some(Eip1559Fees {
+        base_fee,
+        max_priority_fee_per_gas: tip,
+        max_fee_per_gas: base_fee.saturating_mul(U256::from(2)).saturating_add(tip),
+    }))
+}
+
+// Median of the recent per-block rewards at `PRIORITY_FEE_PERCENTILE`
+async fn history_priority_fee(client: &Client, rpc_url: &str) -> Result<Option<U256>> {
+    let params = json!([format!("0x{:x}", FEE_HISTORY_BLOCKS), "latest", [PRIORITY_FEE_PERCENTILE]]);
+    let history = rpc_call(client, rpc_url, "eth_feeHistory", params).await?;
+    let mut rewards = history
+        .get("reward")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|block| block.get(0))
+        .map(parse_hex_u256)
+        .collect::<Result<Vec<U256>>>()?;
+    rewards.sort();
+    Ok(rewards.get(rewards.len() / 2).copied())
+}
+
+/// Estimates gas and the current gas price for a call and returns the total fee in wei,
+/// with the EIP-1559 breakdown when the chain has a base fee.
+pub async fn estimate_fees(client: &Client, rpc_url: &str, call: &Value) -> Result<EstimateFeesResponse> {
+    let (gas, price) = tokio::try_join!(estimate_gas(client, rpc_url, call), gas_price(client, rpc_url))?;
+    // The legacy estimate stands on its own; a node that can't report 1559 fees doesn't fail it
+    let eip1559 = eip1559_fees(client, rpc_url, price).await.ok().flatten();
+    let total_fee = gas
+        .checked_mul(price)
+        .ok_or_else(|| anyhow!("Total fee overflows uint256: gas {} at price {}", gas, price))?;
+    Ok(EstimateFeesResponse {
+        gas_limit: gas.to_string(),
+        gas_price: price.to_string(),
+        total_fee: total_fee.to_string(),
+        base_fee: eip1559.map(|f| f.base_fee.to_string()),
+        max_fee_per_gas: eip1559.map(|f| f.max_fee_per_gas.to_string()),
+        max_priority_fee_per_gas: eip1559.map(|f| f.max_priority_fee_per_gas.to_string()),
+    })
+}
+
+/// Estimates the same call on several chains concurrently and ranks them by total fee.
+///
+/// `targets` is a list of `(chain_id, rpc_url)` pairs. Chains that fail to estimate are
+/// kept in the output (with an `error`) and ranked after every successful estimate.
+pub async fn compare_gas_costs(client: &Client, targets: &[(String, String)], call: &Value) -> Vec<Value> {
+    let estimates = join_all(targets.iter().map(|(chain_id, rpc_url)| async move {
+        (chain_id.clone(), estimate_fees(client, rpc_url, call).await)
+    }))
+    .await;
+
+    let mut ok: Vec<(U256, Value)> = Vec::new();
+    let mut failed: Vec<Value> = Vec::new();
+    for (chain_id, res) in estimates {
+        match res {
+            Ok(fees) => {
+                let total = U256::from_dec_str(&fees.total_fee).unwrap_or_default();
+                let mut entry = json!({
+                    "chain_id": chain_id,
+                    "gas_limit": fees.gas_limit,
+                    "gas_price": fees.gas_price,
+                    "total_fee_wei": fees.total_fee,
+                    "total_fee": format_ether(total),
+                });
+                for (key, value) in [
+                    ("base_fee", fees.base_fee),
+                    ("max_fee_per_gas", fees.max_fee_per_gas),
+                    ("max_priority_fee_per_gas", fees.max_priority_fee_per_gas),
+                ] {
+                    if let Some(value) = value {
+                        entry[key] = json!(value);
+                    }
+                }
+                ok.push((total, entry));
+            }
+            Err(e) => failed.push(json!({ "chain_id": chain_id, "error": e.to_string() })),
+        }
+    }
+    ok.sort_by_key(|a| a.0);
+
+    ok.into_iter()
+        .map(|(_, v)| v)
+        .chain(failed)
+        .enumerate()
+        .map(|(i, mut v)| {
+            v["rank"] = json!(i + 1);
+            v
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{mock, Matcher};
+
+    fn mock_rpc(path: &str, method: &str, result: &str) -> mockito::Mock {
+        mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({ "method": method })))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "jsonrpc": "2.0", "id": 1, "result": result }).to_string())
+            .create()
+    }
+
+    #[tokio::test]
+    async fn test_compare_gas_costs_ranks_cheapest_chain_first() {
+        let _a_gas = mock_rpc("/chain-a", "eth_estimateGas", "0x5208");
+        let _a_price = mock_rpc("/chain-a", "eth_gasPrice", "0x6fc23ac00"); // 30 gwei
+        let _b_gas = mock_rpc("/chain-b", "eth_estimateGas", "0x5208");
+        let _b_price = mock_rpc("/chain-b", "eth_gasPrice", "0x2540be400"); // 10 gwei
+
+        let url = mockito::server_url();
+        let targets = vec![
+            ("1".to_string(), format!("{}/chain-a", url)),
+            ("324".to_string(), format!("{}/chain-b", url)),
+            ("999".to_string(), format!("{}/missing", url)),
+        ];
+        let call = json!({ "to": "0x000000000000000000000000000000000000dEaD", "value": "0x1" });
+        let ranked = compare_gas_costs(&Client::new(), &targets, &call).await;
+
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[0]["chain_id"], "324");
+        assert_eq!(ranked[0]["rank"], 1);
+        assert_eq!(ranked[0]["total_fee_wei"], "210000000000000");
+        assert_eq!(ranked[1]["chain_id"], "1");
+        assert_eq!(ranked[1]["total_fee_wei"], "630000000000000");
+        assert_eq!(ranked[2]["chain_id"], "999");
+        assert!(ranked[2].get("error").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_estimate_fees_reports_eip1559_breakdown() {
+        let call = json!({ "to": "0x000000000000000000000000000000000000dEaD", "value": "0x1" });
+        let gwei = U256::exp10(9);
+
+        let _gas = mock_rpc("/fees-1559", "eth_estimateGas", "0x5208");
+        let _price = mock_rpc("/fees-1559", "eth_gasPrice", "0x77359400"); // 2 gwei
+        let _block = mock("POST", "/fees-1559")
+            .match_body(Matcher::PartialJson(json!({ "method": "eth_getBlockByNumber", "params": ["latest", false] })))
+            .with_body(json!({ "jsonrpc": "2.0", "id": 1, "result": { "number": "0x10", "baseFeePerGas": "0x3b9aca00" } }).to_string())
+            .create();
+        // 0.1, 2 and 1 gwei tips; the median is 1 gwei
+        let _history = mock("POST", "/fees-1559")
+            .match_body(Matcher::PartialJson(json!({ "method": "eth_feeHistory", "params": ["0x5", "latest", [50.0]] })))
+            .with_body(
+                json!({ "jsonrpc": "2.0", "id": 1, "result": {
+                    "oldestBlock": "0xc",
+                    "baseFeePerGas": ["0x3b9aca00"],
+                    "reward": [["0x5f5e100"], ["0x77359400"], ["0x3b9aca00"]]
+                }})
+                .to_string(),
+            )
+            .create();
+        let url = format!("{}/fees-1559", mockito::server_url());
+        let fees = estimate_fees(&Client::new(), &url, &call).await.unwrap();
+        assert_eq!(fees.gas_price, (gwei * 2u64).to_string());
+        assert_eq!(fees.total_fee, (gwei * 2u64 * 21_000u64).to_string());
+        assert_eq!(fees.base_fee, Some(gwei.to_string()));
+        assert_eq!(fees.max_priority_fee_per_gas, Some(gwei.to_string()));
+        assert_eq!(fees.max_fee_per_gas, Some((gwei * 3u64).to_string()));
+
+        // Without fee history the tip is what the gas price pays above the base fee
+        let _gas = mock_rpc("/fees-1559-no-history", "eth_estimateGas", "0x5208");
+        let _price = mock_rpc("/fees-1559-no-history", "eth_gasPrice", "0x77359400");
+        let _block = mock("POST", "/fees-1559-no-history")
+            .match_body(Matcher::PartialJson(json!({ "method": "eth_getBlockByNumber" })))
+            .with_body(json!({ "jsonrpc": "2.0", "id": 1, "result": { "baseFeePerGas": "0x3b9aca00" } }).to_string())
+            .create();
+        let url = format!("{}/fees-1559-no-history", mockito::server_url());
+        let fees = estimate_fees(&Client::new(), &url, &call).await.unwrap();
+        assert_eq!(fees.max_priority_fee_per_gas, Some(gwei.to_string()));
+        assert_eq!(fees.max_fee_per_gas, Some((gwei * 3u64).to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_fees_on_legacy_chain_keeps_gas_price_only() {
+        let _gas = mock_rpc("/fees-legacy", "eth_estimateGas", "0x5208");
+        let _price = mock_rpc("/fees-legacy", "eth_gasPrice", "0x3b9aca00");
+        let _block = mock("POST", "/fees-legacy")
+            .match_body(Matcher::PartialJson(json!({ "method": "eth_getBlockByNumber" })))
+            .with_body(json!({ "jsonrpc": "2.0", "id": 1, "result": { "number": "0x10" } }).to_string())
+            .create();
+        let url = format!("{}/fees-legacy", mockito::server_url());
+        let fees = estimate_fees(&Client::new(), &url, &json!({ "to": "0x000000000000000000000000000000000000dEaD" }))
+            .await
+            .unwrap();
+        assert_eq!(fees.gas_price, "1000000000");
+        assert!(fees.base_fee.is_none() && fees.max_fee_per_gas.is_none() && fees.max_priority_fee_per_gas.is_none());
+        assert!(serde_json::to_value(&fees).unwrap().get("base_fee").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_estimate_fees_refuses_overflowing_total() {
+        let max = format!("0x{:x}", U256::MAX);
+        let _gas = mock_rpc("/fees-overflow", "eth_estimateGas", "0x5208");
+        let _price = mock_rpc("/fees-overflow", "eth_gasPrice", &max);
+        let _block = mock("POST", "/fees-overflow")
+            .match_body(Matcher::PartialJson(json!({ "method": "eth_getBlockByNumber" })))
+            .with_body(json!({ "jsonrpc": "2.0", "id": 1, "result": { "number": "0x10" } }).to_string())
+            .create();
+        let url = format!("{}/fees-overflow", mockito::server_url());
+        let err = estimate_fees(&Client::new(), &url, &json!({ "to": "0x000000000000000000000000000000000000dEaD" }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("overflows"), "{}", err);
+    }
+}
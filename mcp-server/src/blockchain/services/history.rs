@@ -1,8 +1,12 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use ethers_core::types::U256;
 use reqwest::Client;
+use serde_json::Value;
 use tracing::info;
 
 use crate::blockchain::models::{Transaction, TransactionHistoryResponse, TransactionType};
+use crate::metrics;
 
 /// Fetches transaction history for a given address.
 /// Note: Standard EVM RPC doesn't provide transaction history.
@@ -31,3 +35,154 @@ pub async fn get_transaction_history(
 
     Ok(TransactionHistoryResponse { transactions })
 }
+
+/// Optional post-fetch filters for Etherscan `txlist` results.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryFilter {
+    /// Inclusive lower bound, unix seconds
+    pub from_ts: Option<i64>,
+    /// Inclusive upper bound, unix seconds
+    pub to_ts: Option<i64>,
+    /// Minimum transferred value in wei (inclusive)
+    pub min_value_wei: Option<U256>,
+}
+
+impl HistoryFilter {
+    pub fn is_empty(&self) -> bool {
+        self.from_ts.is_none() && self.to_ts.is_none() && self.min_value_wei.is_none()
+    }
+}
+
+/// Parses an ISO 8601 date or date-time into unix seconds.
+/// A bare date (`2024-01-31`) resolves to the start of that day, or to its last
+/// second when `end_of_day` is set so that `to_date` ranges are inclusive.
+pub fn parse_iso_date(s: &str, end_of_day: bool) -> Result<i64> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.timestamp());
+    }
+    if let Ok(dt) = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S") {
+        return Ok(dt.and_utc().timestamp());
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|_| anyhow!("invalid ISO 8601 date '{}'", s))?;
+    let time = if end_of_day {
+        date.and_hms_opt(23, 59, 59)
+    } else {
+        date.and_hms_opt(0, 0, 0)
+    };
+    Ok(time.expect("valid time of day").and_utc().timestamp())
+}
+
+/// Keeps only the transactions whose `timeStamp` and `value` satisfy `filter`.
+/// Entries with unparseable fields are dropped when the corresponding filter is set.
+pub fn filter_transactions(transactions: &[Value], filter: &HistoryFilter) -> Vec<Value> {
+    transactions
+        .iter()
+        .filter(|tx| {
+            let ts = tx
+                .get("timeStamp")
+                .and_then(|v| v.as_str())
+                .and_then(|s| s.parse::<i64>().ok());
+            if let Some(from) = filter.from_ts {
+                if !matches!(ts, Some(t) if t >= from) {
+                    return false;
+                }
+            }
+            if let Some(to) = filter.to_ts {
+                if !matches!(ts, Some(t) if t <= to) {
+                    return false;
+                }
+            }
+            if let Some(min) = filter.min_value_wei {
+                let value = tx
+                    .get("value")
+                    .and_then(|v| v.as_str())
+                    .and_then(|s| U256::from_dec_str(s).ok());
+                if !matches!(value, Some(v) if v >= min) {
+                    return false;
+                }
+            }
+            true
+        })
+        .cloned()
+        .collect()
+}
+
+/// Resolves a unix timestamp to a block number via Etherscan's `getblocknobytime`.
+/// `closest` is `"before"` or `"after"`.
+pub async fn block_number_by_time(
+    client: &Client,
+    etherscan_base_url: &str,
+    chain_id: &str,
+    timestamp: i64,
+    closest: &str,
+    etherscan_api_key: &str,
+) -> Result<u64> {
+    let url = format!(
+        "{}?chainid={}&module=block&action=getblocknobytime&timestamp={}&closest={}&apikey={}",
+        etherscan_base_url, chain_id, timestamp, closest, etherscan_api_key
+    );
+    metrics::record_etherscan_request("block", "getblocknobytime");
+    let resp: Value = client.get(&url).send().await?.json().await?;
+    if resp.get("status").and_then(|v| v.as_str()) != Some("1") {
+        return Err(anyhow!(
+            "getblocknobytime failed: {}",
+            resp.get("result").cloned().unwrap_or(Value::Null)
+        ));
+    }
+    resp.get("result")
+        .and_then(|v| v.as_str())
+        .and_then(|s| s.parse::<u64>().ok())
+        .ok_or_else(|| anyhow!("getblocknobytime returned no block number"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Vec<Value> {
+        vec![
+            json!({"hash": "0xa", "timeStamp": "1704067200", "value": "0"}),                   // 2024-01-01
+            json!({"hash": "0xb", "timeStamp": "1706659200", "value": "500000000000000000"}),  // 2024-01-31
+            json!({"hash": "0xc", "timeStamp": "1709251200", "value": "2000000000000000000"}), // 2024-03-01
+        ]
+    }
+
+    fn hashes(txs: &[Value]) -> Vec<&str> {
+        txs.iter().map(|t| t["hash"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse_iso_date() {
+        assert_eq!(parse_iso_date("2024-01-31", false).unwrap(), 1706659200);
+        assert_eq!(parse_iso_date("2024-01-31", true).unwrap(), 1706745599);
+        assert_eq!(parse_iso_date("2024-01-31T00:00:00Z", false).unwrap(), 1706659200);
+        assert!(parse_iso_date("31/01/2024", false).is_err());
+    }
+
+    #[test]
+    fn test_filter_transactions_by_date_and_value() {
+        let txs = sample();
+        assert_eq!(hashes(&filter_transactions(&txs, &HistoryFilter::default())), vec!["0xa", "0xb", "0xc"]);
+
+        let by_date = HistoryFilter {
+            from_ts: Some(parse_iso_date("2024-01-15", false).unwrap()),
+            to_ts: Some(parse_iso_date("2024-01-31", true).unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(hashes(&filter_transactions(&txs, &by_date)), vec!["0xb"]);
+
+        let by_value = HistoryFilter {
+            min_value_wei: Some(U256::from_dec_str("500000000000000000").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(hashes(&filter_transactions(&txs, &by_value)), vec!["0xb", "0xc"]);
+
+        let combined = HistoryFilter {
+            from_ts: Some(parse_iso_date("2024-02-01", false).unwrap()),
+            ..by_value
+        };
+        assert_eq!(hashes(&filter_transactions(&txs, &combined)), vec!["0xc"]);
+    }
+}
@@ -0,0 +1,174 @@
+// src/blockchain/services/logs.rs
+
+use crate::blockchain::services::chain::block_number;
+use crate::blockchain::services::rpc::{parse_hex_u64, rpc_call};
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde_json::{json, Value};
+
+/// Widest block range sent in a single `eth_getLogs` request. Most hosted RPC
+/// providers reject (or silently truncate) anything larger.
+pub const MAX_BLOCK_RANGE: u64 = 10_000;
+
+/// Widest range `get_logs` scans in total (100 requests), so a search can't walk the
+/// whole chain one chunk at a time.
+pub const MAX_SCAN_RANGE: u64 = 100 * MAX_BLOCK_RANGE;
+
+/// Builds the `eth_getLogs` filter object for one block range.
+///
+/// `topics` holds topic0..topic3 in order; `None` is a wildcard. Trailing
+/// wildcards are dropped so providers see the shortest equivalent filter.
+pub fn build_filter(address: &str, topics: &[Option<String>], from_block: u64, to_block: u64) -> Value {
+    let mut topics: Vec<Value> = topics
+        .iter()
+        .map(|t| t.as_ref().map(|s| json!(s)).unwrap_or(Value::Null))
+        .collect();
+    while topics.last() == Some(&Value::Null) {
+        topics.pop();
+    }
+    let mut filter = json!({
+        "address": address,
+        "fromBlock": format!("0x{:x}", from_block),
+        "toBlock": format!("0x{:x}", to_block),
+    });
+    if !topics.is_empty() {
+        filter["topics"] = Value::Array(topics);
+    }
+    filter
+}
+
+/// Splits the inclusive range `[from, to]` into consecutive chunks of at most `max` blocks.
+pub fn block_chunks(from: u64, to: u64, max: u64) -> Vec<(u64, u64)> {
+    let mut chunks = Vec::new();
+    let mut start = from;
+    while start <= to {
+        let end = to.min(start.saturating_add(max.max(1) - 1));
+        chunks.push((start, end));
+        if end == u64::MAX {
+            break;
+        }
+        start = end + 1;
+    }
+    chunks
+}
+
+/// Resolves a block argument (decimal, 0x-hex, `earliest` or `latest`) to a number.
+/// `latest` is looked up lazily so callers only pay for it when needed.
+pub async fn resolve_block(client: &Client, rpc_url: &str, tag: Option<&str>, default_latest: bool) -> Result<u64> {
+    match tag.map(str::trim) {
+        None if default_latest => block_number(client, rpc_url).await,
+        None | Some("earliest") => Ok(0),
+        Some("latest") | Some("pending") => block_number(client, rpc_url).await,
+        Some(t) if t.starts_with("0x") => parse_hex_u64(&json!(t)),
+        Some(t) => t.parse::<u64>().map_err(|_| anyhow!("invalid block number '{}'", t)),
+    }
+}
+
+/// Checks that `[from_block, to_block]` is in order and no wider than `MAX_SCAN_RANGE`.
+pub fn check_range(from_block: u64, to_block: u64) -> Result<()> {
+    if from_block > to_block {
+        return Err(anyhow!("from_block {} is after to_block {}", from_block, to_block));
+    }
+    if to_block - from_block >= MAX_SCAN_RANGE {
+        return Err(anyhow!(
+            "block range {}..={} spans more than {} blocks; narrow from_block/to_block",
+            from_block, to_block, MAX_SCAN_RANGE
+        ));
+    }
+    Ok(())
+}
+
+/// Fetches logs for `address` and `topics` over `[from_block, to_block]` with
+/// `eth_getLogs`, splitting the range into `MAX_BLOCK_RANGE` chunks. The range is
+/// refused by `check_range` first.
+pub async fn get_logs(
+    client: &Client,
+    rpc_url: &str,
+    address: &str,
+    topics: &[Option<String>],
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<Value>> {
+    check_range(from_block, to_block)?;
+    let mut logs = Vec::new();
+    for (start, end) in block_chunks(from_block, to_block, MAX_BLOCK_RANGE) {
+        let filter = build_filter(address, topics, start, end);
+        let result = rpc_call(client, rpc_url, "eth_getLogs", json!([filter])).await?;
+        let chunk = match result {
+            Value::Array(items) => items,
+            other => return Err(anyhow!("unexpected eth_getLogs result: {}", other)),
+        };
+        logs.extend(chunk);
+    }
+    Ok(logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_core::types::{Address, H256};
+    use mockito::{mock, Matcher};
+
+    const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+    fn address_topic(n: u64) -> String {
+        format!("{:?}", H256::from(Address::from_low_u64_be(n)))
+    }
+
+    #[test]
+    fn test_build_filter_with_multiple_topics() {
+        let topics = vec![Some(TRANSFER_TOPIC.to_string()), None, Some(address_topic(2)), None];
+        let filter = build_filter("0xabc", &topics, 16, 255);
+        assert_eq!(filter["fromBlock"], "0x10");
+        assert_eq!(filter["toBlock"], "0xff");
+        assert_eq!(filter["topics"], json!([TRANSFER_TOPIC, null, address_topic(2)]));
+
+        let no_topics = build_filter("0xabc", &[None, None], 0, 1);
+        assert!(no_topics.get("topics").is_none());
+    }
+
+    #[test]
+    fn test_block_chunks_respect_max_range() {
+        assert_eq!(block_chunks(0, 24_999, 10_000), vec![(0, 9_999), (10_000, 19_999), (20_000, 24_999)]);
+        assert_eq!(block_chunks(5, 5, 10_000), vec![(5, 5)]);
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_chunks_range_and_aggregates() {
+        let log = json!({
+            "address": "0x0000000000000000000000000000000000000abc",
+            "topics": [TRANSFER_TOPIC, address_topic(1), address_topic(2)],
+            "data": format!("0x{:064x}", 1000),
+            "blockNumber": "0x2710",
+        });
+        let first = mock("POST", "/logs-chunks")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_getLogs",
+                "params": [{"fromBlock": "0x0", "toBlock": "0x270f", "topics": [TRANSFER_TOPIC, null, address_topic(2)]}]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": []}).to_string())
+            .expect(1)
+            .create();
+        let second = mock("POST", "/logs-chunks")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_getLogs",
+                "params": [{"fromBlock": "0x2710", "toBlock": "0x2ee0"}]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": [log]}).to_string())
+            .expect(1)
+            .create();
+
+        let url = format!("{}/logs-chunks", mockito::server_url());
+        let topics = vec![Some(TRANSFER_TOPIC.to_string()), None, Some(address_topic(2))];
+        let logs = get_logs(&Client::new(), &url, "0xabc", &topics, 0, 12_000)
+            .await
+            .unwrap();
+
+        first.assert();
+        second.assert();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0]["blockNumber"], "0x2710");
+    }
+}
@@ -6,3 +6,13 @@ pub mod history;
 pub mod transactions;
 pub mod wallet;
 pub mod token;
+pub mod rpc;
+pub mod gas;
+pub mod chain;
+pub mod multicall;
+pub mod portfolio;
+pub mod logs;
+pub mod events;
+pub mod abi;
+pub mod signatures;
+pub mod dex;
@@ -0,0 +1,76 @@
+// src/blockchain/services/multicall.rs
+
+use crate::blockchain::services::rpc::rpc_call;
+use anyhow::{anyhow, Result};
+use ethers_core::abi::{decode, encode, ParamType, Token};
+use ethers_core::types::{Address, Bytes};
+use crate::utils::function_selector;
+use reqwest::Client;
+use serde_json::json;
+
+/// Multicall3 is deployed at the same address on virtually every EVM chain.
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// A single call in an `aggregate3` batch.
+#[derive(Debug, Clone)]
+pub struct Call3 {
+    pub target: Address,
+    pub allow_failure: bool,
+    pub call_data: Bytes,
+}
+
+/// Encodes `aggregate3((address,bool,bytes)[])` calldata for the given calls.
+pub fn encode_aggregate3(calls: &[Call3]) -> Bytes {
+    let tuples = calls
+        .iter()
+        .map(|c| {
+            Token::Tuple(vec![
+                Token::Address(c.target),
+                Token::Bool(c.allow_failure),
+                Token::Bytes(c.call_data.to_vec()),
+            ])
+        })
+        .collect();
+    let mut out = function_selector("aggregate3((address,bool,bytes)[])").to_vec();
+    out.extend(encode(&[Token::Array(tuples)]));
+    Bytes::from(out)
+}
+
+/// Decodes the `(bool success, bytes returnData)[]` result of `aggregate3`.
+pub fn decode_aggregate3(data: &[u8]) -> Result<Vec<(bool, Vec<u8>)>> {
+    let result_type = ParamType::Array(Box::new(ParamType::Tuple(vec![ParamType::Bool, ParamType::Bytes])));
+    let tokens = decode(&[result_type], data)?;
+    let results = match tokens.into_iter().next() {
+        Some(Token::Array(items)) => items,
+        _ => return Err(anyhow!("unexpected aggregate3 return data")),
+    };
+    results
+        .into_iter()
+        .map(|item| match item {
+            Token::Tuple(fields) => match fields.as_slice() {
+                [Token::Bool(ok), Token::Bytes(ret)] => Ok((*ok, ret.clone())),
+                _ => Err(anyhow!("unexpected aggregate3 result entry")),
+            },
+            _ => Err(anyhow!("unexpected aggregate3 result entry")),
+        })
+        .collect()
+}
+
+/// Executes all calls in one `eth_call` through Multicall3 and returns `(success, returnData)` per call.
+pub async fn aggregate3(client: &Client, rpc_url: &str, calls: &[Call3]) -> Result<Vec<(bool, Vec<u8>)>> {
+    let data = encode_aggregate3(calls);
+    let result = rpc_call(
+        client,
+        rpc_url,
+        "eth_call",
+        json!([{ "to": MULTICALL3_ADDRESS, "data": format!("0x{}", hex::encode(&data)) }, "latest"]),
+    )
+    .await?;
+    let hex_str = result.as_str().ok_or_else(|| anyhow!("eth_call result not string"))?;
+    let bytes = hex::decode(hex_str.trim_start_matches("0x"))?;
+    let decoded = decode_aggregate3(&bytes)?;
+    if decoded.len() != calls.len() {
+        return Err(anyhow!("aggregate3 returned {} results for {} calls", decoded.len(), calls.len()));
+    }
+    Ok(decoded)
+}
@@ -0,0 +1,473 @@
+// src/blockchain/services/portfolio.rs
+
+use crate::blockchain::services::multicall::{aggregate3, Call3, MULTICALL3_ADDRESS};
+use crate::metrics;
+use crate::utils::{function_selector, units::format_units};
+use anyhow::{anyhow, Result};
+use ethers_core::abi::{decode, encode, ParamType, Token};
+use ethers_core::types::{Address, Bytes, U256};
+use ethers_core::utils::to_checksum;
+use futures::future::join_all;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use tokio::sync::Semaphore;
+
+/// Upper bound on chains queried at the same time, to stay polite with public RPCs.
+pub const MAX_CONCURRENT_CHAINS: usize = 4;
+
+/// Transfers fetched per Etherscan `tokentx` page during token discovery.
+pub const TOKENTX_PAGE_SIZE: usize = 1000;
+/// Pages fetched at most, so a very busy address can't turn discovery into hundreds of calls.
+/// Pages run newest first, so a history cut short loses the oldest tokens.
+pub const MAX_TOKENTX_PAGES: usize = 10;
+/// Tokens read per Multicall3 `eth_call` (three calls each), to stay under node call limits.
+pub const MAX_TOKENS_PER_MULTICALL: usize = 100;
+
+fn call_data(sig: &str, args: &[Token]) -> Bytes {
+    let mut out = function_selector(sig).to_vec();
+    out.extend(encode(args));
+    Bytes::from(out)
+}
+
+fn decode_uint(data: &[u8]) -> Option<U256> {
+    match decode(&[ParamType::Uint(256)], data).ok()?.first()? {
+        Token::Uint(n) => Some(*n),
+        _ => None,
+    }
+}
+
+fn decode_symbol(data: &[u8]) -> Option<String> {
+    if let Ok(tokens) = decode(&[ParamType::String], data) {
+        if let Some(Token::String(s)) = tokens.first() {
+            return Some(s.clone());
+        }
+    }
+    // Some older tokens (e.g. MKR) return bytes32
+    match decode(&[ParamType::FixedBytes(32)], data).ok()?.first()? {
+        Token::FixedBytes(b) => String::from_utf8(b.iter().copied().take_while(|c| *c != 0).collect()).ok(),
+        _ => None,
+    }
+}
+
+fn balance_entry(address: &str, symbol: Option<String>, decimals: u8, balance: U256) -> Value {
+    json!({
+        "address": address,
+        "symbol": symbol,
+        "decimals": decimals,
+        "balance_wei": balance.to_string(),
+        "balance": format_units(balance, decimals),
+    })
+}
+
+// Native balance via Multicall3 itself, then balanceOf/decimals/symbol for each token
+fn portfolio_calls(owner: Address, tokens: &[Address]) -> Result<Vec<Call3>> {
+    let multicall = Address::from_str(MULTICALL3_ADDRESS)?;
+    let mut calls = vec![Call3 {
+        target: multicall,
+        allow_failure: true,
+        call_data: call_data("getEthBalance(address)", &[Token::Address(owner)]),
+    }];
+    for token in tokens {
+        for data in [
+            call_data("balanceOf(address)", &[Token::Address(owner)]),
+            call_data("decimals()", &[]),
+            call_data("symbol()", &[]),
+        ] {
+            calls.push(Call3 { target: *token, allow_failure: true, call_data: data });
+        }
+    }
+    Ok(calls)
+}
+
+// `portfolio_calls` through Multicall3, `MAX_TOKENS_PER_MULTICALL` tokens per `eth_call`.
+// The results are laid out as a single call over every token would return them.
+async fn portfolio_results(client: &Client, rpc_url: &str, owner: Address, tokens: &[Address]) -> Result<Vec<(bool, Vec<u8>)>> {
+    let chunks: Vec<&[Address]> = if tokens.is_empty() {
+        vec![tokens]
+    } else {
+        tokens.chunks(MAX_TOKENS_PER_MULTICALL).collect()
+    };
+    let mut results = Vec::new();
+    for (n, chunk) in chunks.into_iter().enumerate() {
+        let mut chunk_results = aggregate3(client, rpc_url, &portfolio_calls(owner, chunk)?).await?;
+        if n > 0 && !chunk_results.is_empty() {
+            // Every chunk reads the native balance too; the first one is kept
+            chunk_results.remove(0);
+        }
+        results.extend(chunk_results);
+    }
+    Ok(results)
+}
+
+/// Reads the native balance and the given ERC20 balances of `owner` on one chain with
+/// Multicall3, in as few `eth_call`s as the batch size allows. Only non-zero balances
+/// are returned.
+pub async fn chain_token_balances(client: &Client, rpc_url: &str, owner: Address, tokens: &[Address]) -> Result<Vec<Value>> {
+    let results = portfolio_results(client, rpc_url, owner, tokens).await?;
+    let ok = |i: usize| results.get(i).filter(|(success, _)| *success).map(|(_, data)| data.as_slice());
+
+    let mut balances = Vec::new();
+    if let Some(native) = ok(0).and_then(decode_uint) {
+        if !native.is_zero() {
+            balances.push(balance_entry("native", None, 18, native));
+        }
+    }
+    for (i, token) in tokens.iter().enumerate() {
+        let base = 1 + i * 3;
+        let balance = match ok(base).and_then(decode_uint) {
+            Some(b) if !b.is_zero() => b,
+            _ => continue,
+        };
+        let decimals = ok(base + 1).and_then(decode_uint).and_then(|d| u8::try_from(d).ok()).unwrap_or(18);
+        let symbol = ok(base + 2).and_then(decode_symbol);
+        balances.push(balance_entry(&to_checksum(token, None), symbol, decimals, balance));
+    }
+    Ok(balances)
+}
+
+/// Reads the native balance and every listed ERC20 balance of `owner` on one chain with
+/// Multicall3, zero balances included. A token whose `balanceOf` fails is listed with an
+/// `error` instead of aborting the whole read.
+pub async fn token_portfolio(client: &Client, rpc_url: &str, owner: Address, tokens: &[Address]) -> Result<Value> {
+    let results = portfolio_results(client, rpc_url, owner, tokens).await?;
+    let ok = |i: usize| results.get(i).filter(|(success, _)| *success).map(|(_, data)| data.as_slice());
+
+    let native = match ok(0).and_then(decode_uint) {
+        Some(balance) => balance_entry("native", None, 18, balance),
+        None => json!({ "address": "native", "error": "native balance could not be read" }),
+    };
+    let entries: Vec<Value> = tokens
+        .iter()
+        .enumerate()
+        .map(|(i, token)| {
+            let base = 1 + i * 3;
+            let address = to_checksum(token, None);
+            match ok(base).and_then(decode_uint) {
+                Some(balance) => {
+                    let decimals = ok(base + 1).and_then(decode_uint).and_then(|d| u8::try_from(d).ok()).unwrap_or(18);
+                    balance_entry(&address, ok(base + 2).and_then(decode_symbol), decimals, balance)
+                }
+                None => json!({ "address": address, "error": "token did not answer balanceOf" }),
+            }
+        })
+        .collect();
+    Ok(json!({ "native": native, "tokens": entries }))
+}
+
+// Token contracts in `owner`'s transfer history, most recently used first, and whether
+// the history was longer than `MAX_TOKENTX_PAGES` pages and got cut short
+async fn transferred_token_contracts(
+    client: &Client,
+    etherscan_base_url: &str,
+    chain_id: &str,
+    owner: Address,
+    etherscan_api_key: &str,
+    page_size: usize,
+) -> Result<(Vec<Address>, bool)> {
+    let mut contracts: Vec<Address> = Vec::new();
+    for page in 1..=MAX_TOKENTX_PAGES {
+        let url = format!(
+            "{}?chainid={}&module=account&action=tokentx&address={:?}&page={}&offset={}&sort=desc&apikey={}",
+            etherscan_base_url, chain_id, owner, page, page_size, etherscan_api_key
+        );
+        metrics::record_etherscan_request("account", "tokentx");
+        let resp: Value = client.get(&url).send().await?.json().await?;
+        let transfers = match resp.get("result").and_then(|v| v.as_array()) {
+            Some(transfers) if resp.get("status").and_then(|v| v.as_str()) == Some("1") => transfers,
+            // Etherscan reports an address with no transfers as status 0 and an empty list
+            Some(transfers) if transfers.is_empty() => return Ok((contracts, false)),
+            _ => {
+                return Err(anyhow!(
+                    "tokentx failed: {}",
+                    resp.get("result").or_else(|| resp.get("message")).cloned().unwrap_or(Value::Null)
+                ))
+            }
+        };
+        for contract in transfers
+            .iter()
+            .filter_map(|t| t.get("contractAddress").and_then(|v| v.as_str()))
+            .filter_map(|a| Address::from_str(a).ok())
+        {
+            if !contracts.contains(&contract) {
+                contracts.push(contract);
+            }
+        }
+        if transfers.len() < page_size {
+            return Ok((contracts, false));
+        }
+    }
+    // Every page was full, so older transfers were left unread
+    Ok((contracts, true))
+}
+
+/// Finds the ERC20 tokens `owner` has held by scanning its Etherscan `tokentx` transfer
+/// history, then reads their current balances (plus native) with `token_portfolio`.
+/// Tokens with a zero balance are left out; tokens that fail to respond stay in, flagged.
+/// `truncated` is set when the history was too long to scan in full.
+pub async fn discover_tokens(
+    client: &Client,
+    etherscan_base_url: &str,
+    chain_id: &str,
+    rpc_url: &str,
+    owner: Address,
+    etherscan_api_key: &str,
+) -> Result<Value> {
+    discover_tokens_paged(client, etherscan_base_url, chain_id, rpc_url, owner, etherscan_api_key, TOKENTX_PAGE_SIZE).await
+}
+
+async fn discover_tokens_paged(
+    client: &Client,
+    etherscan_base_url: &str,
+    chain_id: &str,
+    rpc_url: &str,
+    owner: Address,
+    etherscan_api_key: &str,
+    page_size: usize,
+) -> Result<Value> {
+    let (contracts, truncated) =
+        transferred_token_contracts(client, etherscan_base_url, chain_id, owner, etherscan_api_key, page_size).await?;
+    let mut portfolio = token_portfolio(client, rpc_url, owner, &contracts).await?;
+    if let Some(tokens) = portfolio["tokens"].as_array_mut() {
+        tokens.retain(|t| t["balance_wei"] != "0");
+    }
+    portfolio["tokens_seen"] = json!(contracts.len());
+    portfolio["truncated"] = json!(truncated);
+    Ok(portfolio)
+}
+
+/// Collects non-zero balances of `owner` across several chains, grouped by chain.
+///
+/// `targets` is a list of `(chain_id, rpc_url)` pairs; at most `MAX_CONCURRENT_CHAINS` are
+/// queried at once. A chain that fails is reported with an `error` instead of balances.
+pub async fn wallet_token_balances(client: &Client, owner: Address, targets: &[(String, String)], tokens: &[Address]) -> Vec<Value> {
+    let limiter = Semaphore::new(MAX_CONCURRENT_CHAINS);
+    join_all(targets.iter().map(|(chain_id, rpc_url)| {
+        let limiter = &limiter;
+        async move {
+            let _permit = limiter.acquire().await.expect("semaphore is never closed");
+            match chain_token_balances(client, rpc_url, owner, tokens).await {
+                Ok(balances) => json!({ "chain_id": chain_id, "balances": balances }),
+                Err(e) => json!({ "chain_id": chain_id, "error": e.to_string() }),
+            }
+        }
+    }))
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{mock, Matcher};
+
+    fn aggregate_result(entries: Vec<Vec<u8>>) -> String {
+        aggregate_result_with(entries.into_iter().map(|data| (true, data)).collect())
+    }
+
+    fn aggregate_result_with(entries: Vec<(bool, Vec<u8>)>) -> String {
+        let items = entries
+            .into_iter()
+            .map(|(success, data)| Token::Tuple(vec![Token::Bool(success), Token::Bytes(data)]))
+            .collect();
+        format!("0x{}", hex::encode(encode(&[Token::Array(items)])))
+    }
+
+    fn uint(n: u64) -> Vec<u8> {
+        encode(&[Token::Uint(U256::from(n))])
+    }
+
+    fn string(s: &str) -> Vec<u8> {
+        encode(&[Token::String(s.to_string())])
+    }
+
+    fn mock_chain(path: &str, result: String) -> mockito::Mock {
+        mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call"})))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string())
+            .create()
+    }
+
+    #[tokio::test]
+    async fn test_wallet_token_balances_across_two_chains() {
+        let _a = mock_chain(
+            "/portfolio-a",
+            aggregate_result(vec![
+                uint(2_000_000_000_000_000_000),
+                uint(1_500_000), uint(6), string("USDC"),
+                uint(0), uint(18), string("DAI"),
+            ]),
+        );
+        let _b = mock_chain(
+            "/portfolio-b",
+            aggregate_result(vec![
+                uint(0),
+                uint(250_000_000_000_000_000), uint(18), string("WETH"),
+                uint(42), uint(0), string("NFTISH"),
+            ]),
+        );
+
+        let url = mockito::server_url();
+        let targets = vec![
+            ("1".to_string(), format!("{}/portfolio-a", url)),
+            ("8453".to_string(), format!("{}/portfolio-b", url)),
+        ];
+        let tokens = vec![Address::from_low_u64_be(0x10), Address::from_low_u64_be(0x20)];
+        let out = wallet_token_balances(&Client::new(), Address::from_low_u64_be(0xabc), &targets, &tokens).await;
+
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0]["chain_id"], "1");
+        let a = out[0]["balances"].as_array().unwrap();
+        assert_eq!(a.len(), 2);
+        assert_eq!(a[0]["address"], "native");
+        assert_eq!(a[0]["balance"], "2");
+        assert_eq!(a[1]["symbol"], "USDC");
+        assert_eq!(a[1]["balance"], "1.5");
+
+        assert_eq!(out[1]["chain_id"], "8453");
+        let b = out[1]["balances"].as_array().unwrap();
+        assert_eq!(b.len(), 2);
+        assert_eq!(b[0]["symbol"], "WETH");
+        assert_eq!(b[0]["balance"], "0.25");
+        assert_eq!(b[1]["symbol"], "NFTISH");
+        assert_eq!(b[1]["balance_wei"], "42");
+    }
+
+    #[tokio::test]
+    async fn test_token_portfolio_flags_tokens_that_fail() {
+        let _m = mock_chain(
+            "/token-portfolio-partial",
+            aggregate_result_with(vec![
+                (true, uint(0)),
+                (true, uint(7_000_000)), (true, uint(6)), (true, string("USDC")),
+                (false, vec![]), (false, vec![]), (false, vec![]),
+            ]),
+        );
+        let rpc = format!("{}/token-portfolio-partial", mockito::server_url());
+        let tokens = vec![Address::from_low_u64_be(0x10), Address::from_low_u64_be(0x20)];
+        let out = token_portfolio(&Client::new(), &rpc, Address::from_low_u64_be(0xabc), &tokens).await.unwrap();
+
+        assert_eq!(out["native"]["balance_wei"], "0");
+        assert_eq!(out["tokens"][0]["balance"], "7");
+        assert_eq!(out["tokens"][1]["address"], to_checksum(&tokens[1], None));
+        assert!(out["tokens"][1]["error"].is_string());
+        assert!(out["tokens"][1].get("balance").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_token_portfolio_splits_large_token_lists() {
+        let tokens: Vec<Address> = (1..=MAX_TOKENS_PER_MULTICALL as u64 + 1).map(Address::from_low_u64_be).collect();
+        // Each batch is told apart by the call count after the aggregate3 selector and offset
+        let batch = |tokens: usize, native: u64, symbol: &str| {
+            let mut entries = vec![uint(native)];
+            for _ in 0..tokens {
+                entries.extend([uint(1), uint(0), string(symbol)]);
+            }
+            mock("POST", "/token-portfolio-chunks")
+                .match_body(Matcher::Regex(format!("0x82ad56cb0{{62}}20{:064x}", 1 + 3 * tokens)))
+                .with_header("content-type", "application/json")
+                .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": aggregate_result(entries)}).to_string())
+                .expect(1)
+                .create()
+        };
+        let first = batch(MAX_TOKENS_PER_MULTICALL, 5, "A");
+        let second = batch(1, 9, "B");
+
+        let rpc = format!("{}/token-portfolio-chunks", mockito::server_url());
+        let out = token_portfolio(&Client::new(), &rpc, Address::from_low_u64_be(0xabc), &tokens).await.unwrap();
+
+        first.assert();
+        second.assert();
+        assert_eq!(out["native"]["balance_wei"], "5");
+        let entries = out["tokens"].as_array().unwrap();
+        assert_eq!(entries.len(), tokens.len());
+        assert_eq!(entries[MAX_TOKENS_PER_MULTICALL - 1]["symbol"], "A");
+        assert_eq!(entries[MAX_TOKENS_PER_MULTICALL]["symbol"], "B");
+        assert_eq!(entries[MAX_TOKENS_PER_MULTICALL]["address"], to_checksum(&tokens[MAX_TOKENS_PER_MULTICALL], None));
+    }
+
+    #[tokio::test]
+    async fn test_discover_tokens_reports_truncated_history() {
+        let full_page = json!({"status": "1", "message": "OK", "result": [
+            {"contractAddress": "0x0000000000000000000000000000000000000030"}
+        ]});
+        let pages = mock("GET", "/discover-truncated-etherscan")
+            .match_query(Matcher::UrlEncoded("action".into(), "tokentx".into()))
+            .with_body(full_page.to_string())
+            .expect(MAX_TOKENTX_PAGES)
+            .create();
+        let _rpc = mock_chain(
+            "/discover-truncated-rpc",
+            aggregate_result(vec![uint(0), uint(3), uint(0), string("OLD")]),
+        );
+
+        let url = mockito::server_url();
+        let out = discover_tokens_paged(
+            &Client::new(),
+            &format!("{}/discover-truncated-etherscan", url),
+            "1",
+            &format!("{}/discover-truncated-rpc", url),
+            Address::from_low_u64_be(0xabc),
+            "key",
+            1,
+        )
+        .await
+        .unwrap();
+
+        pages.assert();
+        assert_eq!(out["truncated"], true);
+        assert_eq!(out["tokens_seen"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_discover_tokens_pages_transfers_and_reads_balances() {
+        let owner = Address::from_low_u64_be(0xabc);
+        let transfer = |token: &str| json!({"contractAddress": token, "value": "1"});
+        let page = |n: &str, transfers: Vec<Value>| {
+            mock("GET", "/discover-etherscan")
+                .match_query(Matcher::AllOf(vec![
+                    Matcher::UrlEncoded("action".into(), "tokentx".into()),
+                    Matcher::UrlEncoded("page".into(), n.into()),
+                    Matcher::UrlEncoded("offset".into(), "2".into()),
+                    Matcher::UrlEncoded("sort".into(), "desc".into()),
+                ]))
+                .with_body(json!({"status": "1", "message": "OK", "result": transfers}).to_string())
+                .expect(1)
+                .create()
+        };
+        let usdc = "0x0000000000000000000000000000000000000010";
+        let dai = "0x0000000000000000000000000000000000000020";
+        // A full first page means there may be more; the short second page ends the scan
+        let p1 = page("1", vec![transfer(usdc), transfer(usdc)]);
+        let p2 = page("2", vec![transfer(dai)]);
+        let _rpc = mock_chain(
+            "/discover-rpc",
+            aggregate_result(vec![
+                uint(0),
+                uint(2_500_000), uint(6), string("USDC"),
+                uint(4_000_000_000_000_000_000), uint(18), string("DAI"),
+            ]),
+        );
+
+        let url = mockito::server_url();
+        let out = discover_tokens_paged(
+            &Client::new(),
+            &format!("{}/discover-etherscan", url),
+            "1",
+            &format!("{}/discover-rpc", url),
+            owner,
+            "key",
+            2,
+        )
+        .await
+        .unwrap();
+
+        p1.assert();
+        p2.assert();
+        assert_eq!(out["tokens_seen"], 2);
+        assert_eq!(out["truncated"], false);
+        let symbols: Vec<&str> = out["tokens"].as_array().unwrap().iter().map(|t| t["symbol"].as_str().unwrap()).collect();
+        assert_eq!(symbols, vec!["USDC", "DAI"]);
+        assert_eq!(out["tokens"][1]["balance"], "4");
+    }
+}
@@ -0,0 +1,188 @@
+// src/blockchain/services/rpc.rs
+
+use crate::metrics;
+use anyhow::{anyhow, Context, Result};
+use ethers_core::types::U256;
+use reqwest::Client;
+use futures::future::join_all;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Instant;
+use thiserror::Error;
+
+/// A node request that failed in a way callers may want to react to. The kind is decided
+/// where the error is raised, from the node's JSON-RPC error code where it has one.
+#[derive(Debug, Error)]
+pub enum RpcError {
+    /// The call or transaction reverted
+    #[error("{0}")]
+    Reverted(String),
+    /// The node or API is throttling requests
+    #[error("{0}")]
+    RateLimited(String),
+    /// The sender can't cover value plus gas
+    #[error("{0}")]
+    InsufficientFunds(String),
+}
+
+// JSON-RPC error codes nodes use for a revert and for exceeding a request limit
+const EXECUTION_REVERTED: i64 = 3;
+const LIMIT_EXCEEDED: i64 = -32005;
+
+fn error_text(error: &Value) -> String {
+    error.get("message").and_then(|m| m.as_str()).unwrap_or_default().to_ascii_lowercase()
+}
+
+/// Whether a JSON-RPC `error` object reports a revert. Geth-style nodes answer a revert
+/// with code 3, others with -32000 and an "execution reverted" message.
+pub fn is_revert(error: &Value) -> bool {
+    error.get("code").and_then(|c| c.as_i64()) == Some(EXECUTION_REVERTED)
+        || error_text(error).contains("execution reverted")
+}
+
+/// The error for a node's JSON-RPC `error` object, reported as `"{context}: {error}"`.
+/// Reverts, rate limits and insufficient funds come back as the matching `RpcError`.
+pub fn response_error(context: &str, error: &Value) -> anyhow::Error {
+    let message = format!("{}: {}", context, error);
+    let code = error.get("code").and_then(|c| c.as_i64());
+    // Nodes without dedicated codes report these as -32000 with a conventional message
+    let text = error_text(error);
+    if is_revert(error) {
+        RpcError::Reverted(message).into()
+    } else if code == Some(LIMIT_EXCEEDED) || code == Some(429) || text.contains("rate limit") || text.contains("too many requests") {
+        RpcError::RateLimited(message).into()
+    } else if text.contains("insufficient funds") {
+        RpcError::InsufficientFunds(message).into()
+    } else {
+        anyhow!(message)
+    }
+}
+
+/// Sends a single JSON-RPC request and returns its `result` field.
+/// RPC-level errors are surfaced as `Err` with the method name attached.
+pub async fn rpc_call(client: &Client, rpc_url: &str, method: &str, params: Value) -> Result<Value> {
+    let payload = json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params,
+        "id": 1
+    });
+    let started = Instant::now();
+    let v: Value = client
+        .post(rpc_url)
+        .json(&payload)
+        .send()
+        .await
+        .with_context(|| format!("{} request to {} failed", method, rpc_url))?
+        .json()
+        .await
+        .with_context(|| format!("{} returned an invalid JSON-RPC response", method))?;
+    metrics::record_rpc_latency(method, started.elapsed());
+    if let Some(err) = v.get("error") {
+        return Err(response_error(&format!("{} error", method), err));
+    }
+    Ok(v["result"].clone())
+}
+
+/// Parses a 0x-prefixed hex quantity (as returned by JSON-RPC) into a U256.
+pub fn parse_hex_u256(v: &Value) -> Result<U256> {
+    let s = v.as_str().ok_or_else(|| anyhow!("expected hex quantity, got {}", v))?;
+    Ok(U256::from_str_radix(s.trim_start_matches("0x"), 16)?)
+}
+
+/// Parses a 0x-prefixed hex quantity into a u64.
+pub fn parse_hex_u64(v: &Value) -> Result<u64> {
+    let s = v.as_str().ok_or_else(|| anyhow!("expected hex quantity, got {}", v))?;
+    Ok(u64::from_str_radix(s.trim_start_matches("0x"), 16)?)
+}
+
+/// Builds the block parameter for state reads (`eth_call`, `eth_getBalance`, ...) from a
+/// user-supplied block: a tag (`latest`, `pending`, `safe`, ...), a decimal or 0x-hex block
+/// number, or a 32-byte block hash (sent as an EIP-1898 `{"blockHash": ...}` object).
+/// `None` means `latest`.
+pub fn block_param(block: Option<&str>) -> Result<Value> {
+    let Some(block) = block.map(str::trim).filter(|b| !b.is_empty()) else {
+        return Ok(json!("latest"));
+    };
+    let tag = block.to_lowercase();
+    if matches!(tag.as_str(), "latest" | "earliest" | "pending" | "safe" | "finalized") {
+        return Ok(json!(tag));
+    }
+    if let Some(hex_part) = block.strip_prefix("0x") {
+        if hex_part.len() == 64 && hex::decode(hex_part).is_ok() {
+            return Ok(json!({"blockHash": block.to_lowercase()}));
+        }
+        let n = u64::from_str_radix(hex_part, 16).map_err(|_| anyhow!("invalid block '{}'", block))?;
+        return Ok(json!(format!("0x{:x}", n)));
+    }
+    let n: u64 = block
+        .parse()
+        .map_err(|_| anyhow!("invalid block '{}': expected a number, hash or tag", block))?;
+    Ok(json!(format!("0x{:x}", n)))
+}
+
+/// Calls `eth_chainId` on every RPC in `rpc_urls` (chain ID to URL) and checks the answer
+/// matches the chain ID it is configured under. Enabled at startup by VALIDATE_RPC_ON_START.
+pub async fn verify_chain_ids(client: &Client, rpc_urls: &HashMap<String, String>) -> Result<()> {
+    let mut targets: Vec<(&String, &String)> = rpc_urls.iter().collect();
+    targets.sort();
+    let results = join_all(targets.iter().map(|(chain_id, url)| async move {
+        let reported = rpc_call(client, url, "eth_chainId", json!([]))
+            .await
+            .and_then(|v| parse_hex_u64(&v));
+        match reported {
+            Ok(id) if id.to_string() == **chain_id => None,
+            Ok(id) => Some(format!("chain {}: {} reports chain ID {}", chain_id, url, id)),
+            Err(e) => Some(format!("chain {}: {} did not answer eth_chainId ({})", chain_id, url, e)),
+        }
+    }))
+    .await;
+    let problems: Vec<String> = results.into_iter().flatten().collect();
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow!("RPC endpoints failed startup validation:\n  {}", problems.join("\n  ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{mock, Matcher};
+
+    #[tokio::test]
+    async fn test_verify_chain_ids_detects_mismatch() {
+        let _m = mock("POST", "/config-chain-id")
+            .match_body(Matcher::PartialJson(json!({ "method": "eth_chainId" })))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "jsonrpc": "2.0", "id": 1, "result": "0xaa36a7" }).to_string())
+            .create();
+        let url = format!("{}/config-chain-id", mockito::server_url());
+        let client = Client::new();
+        let urls = |chain_id: &str| HashMap::from([(chain_id.to_string(), url.clone())]);
+
+        assert!(verify_chain_ids(&client, &urls("11155111")).await.is_ok());
+
+        let err = verify_chain_ids(&client, &urls("1")).await.unwrap_err().to_string();
+        assert!(err.contains("chain 1:"), "{}", err);
+        assert!(err.contains("reports chain ID 11155111"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_hex_quantities() {
+        assert_eq!(parse_hex_u256(&json!("0x5208")).unwrap(), U256::from(21000));
+        assert_eq!(parse_hex_u64(&json!("0x10")).unwrap(), 16);
+        assert!(parse_hex_u64(&json!(null)).is_err());
+    }
+
+    #[test]
+    fn test_block_param_forms() {
+        assert_eq!(block_param(None).unwrap(), json!("latest"));
+        assert_eq!(block_param(Some("Finalized")).unwrap(), json!("finalized"));
+        assert_eq!(block_param(Some("16")).unwrap(), json!("0x10"));
+        assert_eq!(block_param(Some("0x010")).unwrap(), json!("0x10"));
+        let hash = format!("0x{}", "ab".repeat(32));
+        assert_eq!(block_param(Some(&hash)).unwrap(), json!({"blockHash": hash}));
+        assert!(block_param(Some("yesterday")).is_err());
+        assert!(block_param(Some("0xzz")).is_err());
+    }
+}
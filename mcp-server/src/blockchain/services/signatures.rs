@@ -0,0 +1,223 @@
+// src/blockchain/services/signatures.rs
+
+use crate::utils::{function_selector, keccak256_hex};
+use anyhow::{anyhow, Context, Result};
+use reqwest::Client;
+use serde_json::Value;
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Public signature database used unless SIGNATURE_DB_URL points elsewhere.
+pub const DEFAULT_SIGNATURE_DB_URL: &str = "https://www.4byte.directory";
+
+/// How many directory answers `SignatureCache` keeps before evicting the oldest.
+pub const DEFAULT_SIGNATURE_CACHE_CAPACITY: usize = 1024;
+
+/// Signatures recognised even when the directory is unreachable.
+const WELL_KNOWN_FUNCTIONS: &[&str] = &[
+    "transfer(address,uint256)",
+    "transferFrom(address,address,uint256)",
+    "approve(address,uint256)",
+    "balanceOf(address)",
+    "allowance(address,address)",
+    "totalSupply()",
+    "safeTransferFrom(address,address,uint256)",
+    "safeTransferFrom(address,address,uint256,uint256,bytes)",
+    "setApprovalForAll(address,bool)",
+];
+const WELL_KNOWN_EVENTS: &[&str] = &[
+    "Transfer(address,address,uint256)",
+    "Approval(address,address,uint256)",
+    "ApprovalForAll(address,address,bool)",
+    "TransferSingle(address,address,address,uint256,uint256)",
+];
+
+/// Candidate signatures for one selector or topic.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SignatureLookup {
+    /// `function` for a 4-byte selector, `event` for a 32-byte topic
+    pub kind: &'static str,
+    pub candidates: Vec<String>,
+    /// `directory`, `cache` or `builtin` (directory unreachable)
+    pub source: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// Successful directory answers, keyed by database URL and hex. Holds at most `capacity`
+/// entries; inserting past that evicts the oldest.
+#[derive(Debug, Clone)]
+pub struct SignatureCache {
+    // Candidates and insertion sequence number
+    entries: Arc<DashMap<String, (Vec<String>, u64)>>,
+    next_seq: Arc<AtomicU64>,
+    capacity: usize,
+}
+
+impl Default for SignatureCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIGNATURE_CACHE_CAPACITY)
+    }
+}
+
+impl SignatureCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { entries: Arc::new(DashMap::new()), next_seq: Arc::new(AtomicU64::new(0)), capacity }
+    }
+
+    fn get(&self, key: &str) -> Option<Vec<String>> {
+        self.entries.get(key).map(|e| e.0.clone())
+    }
+
+    fn insert(&self, key: String, candidates: Vec<String>) {
+        while self.entries.len() >= self.capacity {
+            let oldest = self.entries.iter().min_by_key(|e| e.value().1).map(|e| e.key().clone());
+            match oldest {
+                Some(oldest) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        self.entries.insert(key, (candidates, seq));
+    }
+}
+
+/// Normalizes a selector (`0xa9059cbb`) or topic (32 bytes) to lowercase 0x-hex and
+/// reports which one it is.
+pub fn parse_selector(hex_str: &str) -> Result<(String, &'static str)> {
+    let raw = hex_str.trim().trim_start_matches("0x").to_lowercase();
+    let bytes = hex::decode(&raw).map_err(|_| anyhow!("'{}' is not valid hex", hex_str))?;
+    let kind = match bytes.len() {
+        4 => "function",
+        32 => "event",
+        n => return Err(anyhow!("expected a 4-byte selector or 32-byte event topic, got {} bytes", n)),
+    };
+    Ok((format!("0x{}", raw), kind))
+}
+
+fn builtin_candidates(hex: &str, kind: &str) -> Vec<String> {
+    let hash = |sig: &str| match kind {
+        "function" => format!("0x{}", hex::encode(function_selector(sig))),
+        _ => keccak256_hex(sig.as_bytes()),
+    };
+    let known = if kind == "function" { WELL_KNOWN_FUNCTIONS } else { WELL_KNOWN_EVENTS };
+    known.iter().filter(|sig| hash(sig) == hex).map(|s| s.to_string()).collect()
+}
+
+async fn query_directory(client: &Client, db_url: &str, hex: &str, kind: &str) -> Result<Vec<String>> {
+    let endpoint = if kind == "function" { "signatures" } else { "event-signatures" };
+    let url = format!("{}/api/v1/{}/", db_url.trim_end_matches('/'), endpoint);
+    let v: Value = client
+        .get(&url)
+        .query(&[("hex_signature", hex)])
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .with_context(|| format!("signature lookup request to {} failed", db_url))?
+        .error_for_status()?
+        .json()
+        .await
+        .context("signature directory returned invalid JSON")?;
+    let mut results: Vec<&Value> = v["results"].as_array().into_iter().flatten().collect();
+    // Oldest submission first: collisions added later are usually spam
+    results.sort_by_key(|r| r["id"].as_u64().unwrap_or(u64::MAX));
+    Ok(results
+        .into_iter()
+        .filter_map(|r| r["text_signature"].as_str().map(|s| s.to_string()))
+        .collect())
+}
+
+/// Looks up candidate signatures for a function selector or event topic in the signature
+/// directory at `db_url`. If the directory can't be reached the built-in list of common
+/// ERC-20/721/1155 signatures is used instead and a warning is returned.
+pub async fn lookup_signature(
+    client: &Client,
+    cache: &SignatureCache,
+    db_url: &str,
+    hex_str: &str,
+) -> Result<SignatureLookup> {
+    let (hex, kind) = parse_selector(hex_str)?;
+    let key = format!("{}|{}", db_url, hex);
+    if let Some(hit) = cache.get(&key) {
+        return Ok(SignatureLookup { kind, candidates: hit, source: "cache", warning: None });
+    }
+    match query_directory(client, db_url, &hex, kind).await {
+        Ok(candidates) => {
+            cache.insert(key, candidates.clone());
+            Ok(SignatureLookup { kind, candidates, source: "directory", warning: None })
+        }
+        Err(e) => Ok(SignatureLookup {
+            kind,
+            candidates: builtin_candidates(&hex, kind),
+            source: "builtin",
+            warning: Some(format!("Signature directory unavailable ({}); showing built-in matches only", e)),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_selector_distinguishes_functions_and_events() {
+        assert_eq!(parse_selector("0xA9059CBB").unwrap(), ("0xa9059cbb".to_string(), "function"));
+        let topic = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+        assert_eq!(parse_selector(topic).unwrap().1, "event");
+        assert!(parse_selector("0xa9059c").is_err());
+        assert!(parse_selector("0xzz").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_selector_from_directory_then_cache() {
+        let m = mock("GET", "/sigdb/api/v1/signatures/")
+            .match_query(Matcher::UrlEncoded("hex_signature".into(), "0xa9059cbb".into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({"count": 2, "results": [
+                    {"id": 31780, "text_signature": "many_msg_babbage(bytes1)", "hex_signature": "0xa9059cbb"},
+                    {"id": 145, "text_signature": "transfer(address,uint256)", "hex_signature": "0xa9059cbb"}
+                ]})
+                .to_string(),
+            )
+            .expect(1)
+            .create();
+        let db = format!("{}/sigdb", mockito::server_url());
+        let client = Client::new();
+        let cache = SignatureCache::default();
+
+        let found = lookup_signature(&client, &cache, &db, "0xa9059cbb").await.unwrap();
+        assert_eq!(found.source, "directory");
+        assert_eq!(found.candidates[0], "transfer(address,uint256)");
+
+        let cached = lookup_signature(&client, &cache, &db, "0xa9059cbb").await.unwrap();
+        assert_eq!(cached.source, "cache");
+        assert_eq!(cached.candidates, found.candidates);
+        m.assert();
+    }
+
+    #[test]
+    fn test_signature_cache_evicts_oldest_past_capacity() {
+        let cache = SignatureCache::new(2);
+        cache.insert("a".into(), vec!["a()".into()]);
+        cache.insert("b".into(), vec!["b()".into()]);
+        cache.insert("c".into(), vec!["c()".into()]);
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.get("c"), Some(vec!["c()".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_falls_back_to_builtin_when_unreachable() {
+        // Nothing listens on port 1
+        let topic = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+        let found = lookup_signature(&Client::new(), &SignatureCache::default(), "http://127.0.0.1:1", topic).await.unwrap();
+        assert_eq!(found.source, "builtin");
+        assert_eq!(found.candidates, vec!["Transfer(address,address,uint256)"]);
+        assert!(found.warning.is_some());
+    }
+}
@@ -1,18 +1,17 @@
 // src/blockchain/services/token.rs
 
 use anyhow::{anyhow, Result};
-use ethers_core::abi::{decode, encode, Abi, Function, ParamType, Token};
+use ethers_core::abi::{decode, Abi, Function, ParamType, Token};
+use crate::blockchain::services::multicall::{aggregate3, Call3};
 use ethers_core::types::{Address, Bytes, TransactionRequest, U256};
-use ethers_core::utils::keccak256;
+use crate::blockchain::services::abi::{encode_call, encode_function_call, parse_function_signature, token_to_json};
+use crate::blockchain::services::rpc;
+use crate::metrics;
+use crate::utils::units::format_units;
 use reqwest::Client;
 use serde_json::{json, Value};
 use std::str::FromStr;
-
-fn selector(sig: &str) -> [u8; 4] {
-    let mut sel = [0u8; 4];
-    sel.copy_from_slice(&keccak256(sig.as_bytes())[0..4]);
-    sel
-}
+use std::time::Instant;
 
 fn hex_to_bytes(v: &Value) -> Result<Vec<u8>> {
     let s = v.as_str().ok_or_else(|| anyhow!("eth_call result not string"))?;
@@ -46,45 +45,83 @@ fn decode_u256(v: &Value) -> Option<U256> {
     None
 }
 
-fn encode_call(sig: &str, tokens: Vec<Token>) -> Bytes {
-    let mut out = selector(sig).to_vec();
-    let mut tail = encode(&tokens);
-    out.append(&mut tail);
-    Bytes::from(out)
-}
-
-async fn eth_call(client: &Client, rpc_url: &str, to: &str, data: Bytes) -> Result<Value> {
-    let payload = json!({
+/// JSON-RPC body for an `eth_call` at `block`, a block parameter as built by `rpc::block_param`.
+fn eth_call_payload(to: &str, data: &Bytes, block: &Value) -> Value {
+    json!({
         "jsonrpc": "2.0",
         "method": "eth_call",
-        "params": [{"to": to, "data": format!("0x{}", hex::encode(data))}, "latest"],
+        "params": [{"to": to, "data": format!("0x{}", hex::encode(data))}, block],
         "id": 1
-    });
+    })
+}
+
+// The node's answer to an `eth_call`: its result, or the JSON-RPC error object it sent
+async fn eth_call_response(client: &Client, rpc_url: &str, to: &str, data: Bytes, block: &Value) -> Result<Result<Value, Value>> {
+    let payload = eth_call_payload(to, &data, block);
+    let started = Instant::now();
     let resp = client.post(rpc_url).json(&payload).send().await?;
     let v: Value = resp.json().await?;
-    if let Some(err) = v.get("error") { return Err(anyhow!("eth_call error: {}", err)); }
-    Ok(v["result"].clone())
+    metrics::record_rpc_latency("eth_call", started.elapsed());
+    match v.get("error") {
+        Some(err) => Ok(Err(err.clone())),
+        None => Ok(Ok(v["result"].clone())),
+    }
+}
+
+async fn eth_call(client: &Client, rpc_url: &str, to: &str, data: Bytes, block: &Value) -> Result<Value> {
+    eth_call_response(client, rpc_url, to, data, block)
+        .await?
+        .map_err(|err| rpc::response_error("eth_call error", &err))
 }
 
-pub async fn erc20_info(client: &Client, rpc_url: &str, token: &str) -> Result<Value> {
-    let name_raw = eth_call(client, rpc_url, token, encode_call("name()", vec![])).await.unwrap_or(json!(null));
-    let symbol_raw = eth_call(client, rpc_url, token, encode_call("symbol()", vec![])).await.unwrap_or(json!(null));
-    let decimals_raw = eth_call(client, rpc_url, token, encode_call("decimals()", vec![])).await.unwrap_or(json!(null));
-    let total_raw = eth_call(client, rpc_url, token, encode_call("totalSupply()", vec![])).await.unwrap_or(json!(null));
+// `eth_call` of a method the contract may not have: a revert reads as `null`, but a node
+// that can't be reached or fails in any other way is still an error
+async fn optional_eth_call(client: &Client, rpc_url: &str, to: &str, data: Bytes, block: &Value) -> Result<Value> {
+    match eth_call_response(client, rpc_url, to, data, block).await? {
+        Ok(result) => Ok(result),
+        Err(err) if rpc::is_revert(&err) => Ok(Value::Null),
+        Err(err) => Err(rpc::response_error("eth_call error", &err)),
+    }
+}
+
+pub async fn erc20_info(client: &Client, rpc_url: &str, token: &str, block: &Value) -> Result<Value> {
+    let name_raw = optional_eth_call(client, rpc_url, token, encode_call("name()", vec![]), block).await?;
+    let symbol_raw = optional_eth_call(client, rpc_url, token, encode_call("symbol()", vec![]), block).await?;
+    let decimals_raw = optional_eth_call(client, rpc_url, token, encode_call("decimals()", vec![]), block).await?;
+    let total_raw = optional_eth_call(client, rpc_url, token, encode_call("totalSupply()", vec![]), block).await?;
     let name = decode_string(&name_raw);
     let symbol = decode_string(&symbol_raw);
-    let decimals = decode_u256(&decimals_raw).map(|n| n.to_string());
-    let total_supply = decode_u256(&total_raw).map(|n| n.to_string());
+    let decimals = decode_u256(&decimals_raw);
+    let total_supply = decode_u256(&total_raw);
+    // decimals() is optional in ERC-20; without it the supply is shown unscaled
+    let scale = decimals.and_then(|d| u8::try_from(d).ok());
+    let formatted_total_supply = total_supply.map(|n| format_units(n, scale.unwrap_or(0)));
     Ok(json!({
         "raw": {"name": name_raw, "symbol": symbol_raw, "decimals": decimals_raw, "totalSupply": total_raw},
-        "decoded": {"name": name, "symbol": symbol, "decimals": decimals, "totalSupply": total_supply}
+        "decoded": {
+            "name": name,
+            "symbol": symbol,
+            "decimals": decimals.map(|n| n.to_string()),
+            "totalSupply": total_supply.map(|n| n.to_string()),
+            "formatted_total_supply": formatted_total_supply,
+            "decimals_defaulted": scale.is_none(),
+        }
     }))
 }
 
-pub async fn erc20_balance_of(client: &Client, rpc_url: &str, token: &str, owner: &str) -> Result<Value> {
+/// The token's `decimals()`, for scaling a decimal amount to base units. Unlike
+/// `erc20_info`, a token without it is an error, since any scale would be a guess.
+pub async fn erc20_decimals(client: &Client, rpc_url: &str, token: &str) -> Result<u8> {
+    let raw = optional_eth_call(client, rpc_url, token, encode_call("decimals()", vec![]), &json!("latest")).await?;
+    let decimals = decode_u256(&raw)
+        .ok_or_else(|| anyhow!("token {} has no decimals(); give the amount in base units instead", token))?;
+    u8::try_from(decimals).map_err(|_| anyhow!("token {} reports {} decimals", token, decimals))
+}
+
+pub async fn erc20_balance_of(client: &Client, rpc_url: &str, token: &str, owner: &str, block: &Value) -> Result<Value> {
     let owner_addr = Address::from_str(owner)?;
     let data = encode_call("balanceOf(address)", vec![Token::Address(owner_addr)]);
-    let raw = eth_call(client, rpc_url, token, data).await?;
+    let raw = eth_call(client, rpc_url, token, data, block).await?;
     let decoded = decode_u256(&raw).map(|n| n.to_string());
     Ok(json!({"raw": raw, "decoded": decoded}))
 }
@@ -105,33 +142,55 @@ pub fn erc20_approve_tx(token: &str, spender: &str, amount_wei: &str) -> Result<
     Ok(TransactionRequest::new().to(contract).data(data))
 }
 
+pub fn erc20_transfer_from_tx(token: &str, from: &str, to: &str, amount_wei: &str) -> Result<TransactionRequest> {
+    let from_addr = Address::from_str(from)?;
+    let to_addr = Address::from_str(to)?;
+    let amount = U256::from_dec_str(amount_wei)?;
+    let data = encode_call(
+        "transferFrom(address,address,uint256)",
+        vec![Token::Address(from_addr), Token::Address(to_addr), Token::Uint(amount)],
+    );
+    let contract = Address::from_str(token)?;
+    Ok(TransactionRequest::new().to(contract).data(data))
+}
+
+/// `increaseAllowance`/`decreaseAllowance` (OpenZeppelin extension, not part of the ERC-20 standard).
+pub fn erc20_modify_allowance_tx(token: &str, spender: &str, delta_wei: &str, increase: bool) -> Result<TransactionRequest> {
+    let spender_addr = Address::from_str(spender)?;
+    let delta = U256::from_dec_str(delta_wei)?;
+    let sig = if increase { "increaseAllowance(address,uint256)" } else { "decreaseAllowance(address,uint256)" };
+    let data = encode_call(sig, vec![Token::Address(spender_addr), Token::Uint(delta)]);
+    let contract = Address::from_str(token)?;
+    Ok(TransactionRequest::new().to(contract).data(data))
+}
+
 pub async fn is_contract(client: &Client, rpc_url: &str, address: &str) -> Result<bool> {
     let payload = json!({"jsonrpc": "2.0", "method": "eth_getCode", "params": [address, "latest"], "id": 1});
     let v: Value = client.post(rpc_url).json(&payload).send().await?.json().await?;
-    if let Some(err) = v.get("error") { return Err(anyhow!("eth_getCode error: {}", err)); }
+    if let Some(err) = v.get("error") { return Err(rpc::response_error("eth_getCode error", err)); }
     let code = v["result"].as_str().unwrap_or("0x");
     Ok(code != "0x" && code != "0x0")
 }
 
-pub async fn erc721_token_uri(client: &Client, rpc_url: &str, token: &str, token_id: &str) -> Result<Value> {
+pub async fn erc721_token_uri(client: &Client, rpc_url: &str, token: &str, token_id: &str, block: &Value) -> Result<Value> {
     let id = U256::from_dec_str(token_id)?;
     let data = encode_call("tokenURI(uint256)", vec![Token::Uint(id)]);
-    let raw = eth_call(client, rpc_url, token, data).await?;
+    let raw = eth_call(client, rpc_url, token, data, block).await?;
     let decoded = decode_string(&raw);
     Ok(json!({"raw": raw, "decoded": decoded}))
 }
 
-pub async fn erc721_owner_of(client: &Client, rpc_url: &str, token: &str, token_id: &str) -> Result<Value> {
+pub async fn erc721_owner_of(client: &Client, rpc_url: &str, token: &str, token_id: &str, block: &Value) -> Result<Value> {
     let id = U256::from_dec_str(token_id)?;
     let data = encode_call("ownerOf(uint256)", vec![Token::Uint(id)]);
-    let raw = eth_call(client, rpc_url, token, data).await?;
+    let raw = eth_call(client, rpc_url, token, data, block).await?;
     Ok(json!({"raw": raw}))
 }
 
-pub async fn erc721_balance_of(client: &Client, rpc_url: &str, token: &str, owner: &str) -> Result<Value> {
+pub async fn erc721_balance_of(client: &Client, rpc_url: &str, token: &str, owner: &str, block: &Value) -> Result<Value> {
     let owner_addr = Address::from_str(owner)?;
     let data = encode_call("balanceOf(address)", vec![Token::Address(owner_addr)]);
-    let raw = eth_call(client, rpc_url, token, data).await?;
+    let raw = eth_call(client, rpc_url, token, data, block).await?;
     let decoded = decode_u256(&raw).map(|n| n.to_string());
     Ok(json!({"raw": raw, "decoded": decoded}))
 }
@@ -150,23 +209,132 @@ pub fn erc1155_safe_transfer_from_tx(token: &str, from: &str, to: &str, token_id
     Ok(TransactionRequest::new().to(contract).data(data))
 }
 
-pub async fn erc1155_uri(client: &Client, rpc_url: &str, token: &str, token_id: &str) -> Result<Value> {
+fn uint_list(values: &[String], what: &str) -> Result<Vec<Token>> {
+    values
+        .iter()
+        .map(|v| U256::from_dec_str(v).map(Token::Uint).map_err(|_| anyhow!("invalid {} '{}'", what, v)))
+        .collect()
+}
+
+pub fn erc1155_safe_batch_transfer_from_tx(token: &str, from: &str, to: &str, token_ids: &[String], amounts: &[String]) -> Result<TransactionRequest> {
+    if token_ids.is_empty() || token_ids.len() != amounts.len() {
+        return Err(anyhow!("token_ids and amounts must be non-empty and the same length ({} vs {})", token_ids.len(), amounts.len()));
+    }
+    let from_addr = Address::from_str(from)?;
+    let to_addr = Address::from_str(to)?;
+    let data = encode_call(
+        "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+        vec![
+            Token::Address(from_addr),
+            Token::Address(to_addr),
+            Token::Array(uint_list(token_ids, "token id")?),
+            Token::Array(uint_list(amounts, "amount")?),
+            Token::Bytes(Vec::new()),
+        ],
+    );
+    let contract = Address::from_str(token)?;
+    Ok(TransactionRequest::new().to(contract).data(data))
+}
+
+pub async fn erc1155_uri(client: &Client, rpc_url: &str, token: &str, token_id: &str, block: &Value) -> Result<Value> {
     let id = U256::from_dec_str(token_id)?;
     let data = encode_call("uri(uint256)", vec![Token::Uint(id)]);
-    let raw = eth_call(client, rpc_url, token, data).await?;
+    let raw = eth_call(client, rpc_url, token, data, block).await?;
     let decoded = decode_string(&raw);
     Ok(json!({"raw": raw, "decoded": decoded}))
 }
 
-pub async fn erc1155_balance_of(client: &Client, rpc_url: &str, token: &str, owner: &str, token_id: &str) -> Result<Value> {
+pub async fn erc1155_balance_of(client: &Client, rpc_url: &str, token: &str, owner: &str, token_id: &str, block: &Value) -> Result<Value> {
     let owner_addr = Address::from_str(owner)?;
     let id = U256::from_dec_str(token_id)?;
     let data = encode_call("balanceOf(address,uint256)", vec![Token::Address(owner_addr), Token::Uint(id)]);
-    let raw = eth_call(client, rpc_url, token, data).await?;
+    let raw = eth_call(client, rpc_url, token, data, block).await?;
     let decoded = decode_u256(&raw).map(|n| n.to_string());
     Ok(json!({"raw": raw, "decoded": decoded}))
 }
 
+/// Decodes a `uint256[]` return value into decimal strings.
+fn decode_u256_array(v: &Value) -> Option<Vec<String>> {
+    let bytes = hex_to_bytes(v).ok()?;
+    match decode(&[ParamType::Array(Box::new(ParamType::Uint(256)))], &bytes).ok()?.pop()? {
+        Token::Array(items) => items.into_iter().map(|t| t.into_uint().map(|n| n.to_string())).collect(),
+        _ => None,
+    }
+}
+
+/// `balanceOfBatch(owners, ids)`: balance `i` is of `owners[i]` for `token_ids[i]`.
+pub async fn erc1155_balance_of_batch(client: &Client, rpc_url: &str, token: &str, owners: &[String], token_ids: &[String], block: &Value) -> Result<Value> {
+    if owners.is_empty() || owners.len() != token_ids.len() {
+        return Err(anyhow!("owners and token_ids must be non-empty and the same length ({} vs {})", owners.len(), token_ids.len()));
+    }
+    let owner_tokens = owners
+        .iter()
+        .map(|o| Address::from_str(o).map(Token::Address).map_err(|_| anyhow!("invalid owner address '{}'", o)))
+        .collect::<Result<Vec<_>>>()?;
+    let data = encode_call(
+        "balanceOfBatch(address[],uint256[])",
+        vec![Token::Array(owner_tokens), Token::Array(uint_list(token_ids, "token id")?)],
+    );
+    let raw = eth_call(client, rpc_url, token, data, block).await?;
+    let decoded = decode_u256_array(&raw);
+    Ok(json!({"raw": raw, "decoded": decoded}))
+}
+
+pub const ERC165_INTERFACE_ID: [u8; 4] = [0x01, 0xff, 0xc9, 0xa7];
+pub const ERC721_INTERFACE_ID: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+pub const ERC1155_INTERFACE_ID: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+/// `supportsInterface(id)`; a revert, empty result or malformed answer counts as `false`.
+/// An RPC failure is an error, so an outage doesn't read as "no standard".
+async fn supports_interface(client: &Client, rpc_url: &str, token: &str, id: [u8; 4]) -> Result<bool> {
+    let data = encode_call("supportsInterface(bytes4)", vec![Token::FixedBytes(id.to_vec())]);
+    let raw = optional_eth_call(client, rpc_url, token, data, &json!("latest")).await?;
+    Ok(hex_to_bytes(&raw)
+        .ok()
+        .and_then(|b| decode(&[ParamType::Bool], &b).ok())
+        .and_then(|t| t.into_iter().next())
+        .and_then(|t| t.into_bool())
+        .unwrap_or(false))
+}
+
+/// Classifies a contract as ERC-20, ERC-721 and/or ERC-1155.
+///
+/// ERC-721 and ERC-1155 are detected through ERC-165 (`supportsInterface`), which is only
+/// trusted if the contract also answers `false` for the invalid ID 0xffffffff. ERC-20 has no
+/// interface ID, so it is detected by `decimals()` and `symbol()` both decoding.
+pub async fn detect_token_standard(client: &Client, rpc_url: &str, token: &str) -> Result<Value> {
+    Address::from_str(token)?;
+    let erc165 = supports_interface(client, rpc_url, token, ERC165_INTERFACE_ID).await?
+        && !supports_interface(client, rpc_url, token, [0xff; 4]).await?;
+    let (erc721, erc1155) = if erc165 {
+        (
+            supports_interface(client, rpc_url, token, ERC721_INTERFACE_ID).await?,
+            supports_interface(client, rpc_url, token, ERC1155_INTERFACE_ID).await?,
+        )
+    } else {
+        (false, false)
+    };
+    let latest = json!("latest");
+    let decimals_raw = optional_eth_call(client, rpc_url, token, encode_call("decimals()", vec![]), &latest).await?;
+    let symbol_raw = optional_eth_call(client, rpc_url, token, encode_call("symbol()", vec![]), &latest).await?;
+    let decimals = decode_u256(&decimals_raw).filter(|d| *d <= U256::from(255u8)).map(|d| d.as_u32());
+    let symbol = decode_string(&symbol_raw);
+    let erc20 = decimals.is_some() && symbol.is_some() && !erc721 && !erc1155;
+
+    let standards: Vec<&str> = [("ERC-20", erc20), ("ERC-721", erc721), ("ERC-1155", erc1155)]
+        .iter()
+        .filter(|(_, yes)| *yes)
+        .map(|(name, _)| *name)
+        .collect();
+    Ok(json!({
+        "standards": standards,
+        "erc165": erc165,
+        "interfaces": {"erc721": erc721, "erc1155": erc1155},
+        "symbol": symbol,
+        "decimals": decimals,
+    }))
+}
+
 pub async fn get_block_number(client: &Client, rpc_url: &str) -> Result<Value> {
     let payload = json!({
         "jsonrpc": "2.0",
@@ -176,90 +344,390 @@ pub async fn get_block_number(client: &Client, rpc_url: &str) -> Result<Value> {
     });
     let resp = client.post(rpc_url).json(&payload).send().await?;
     let v: Value = resp.json().await?;
-    if let Some(err) = v.get("error") { return Err(anyhow!("eth_blockNumber error: {}", err)); }
+    if let Some(err) = v.get("error") { return Err(rpc::response_error("eth_blockNumber error", err)); }
     Ok(v["result"].clone())
 }
 
-pub async fn erc20_allowance(client: &Client, rpc_url: &str, token: &str, owner: &str, spender: &str) -> Result<Value> {
+pub async fn erc20_allowance(client: &Client, rpc_url: &str, token: &str, owner: &str, spender: &str, block: &Value) -> Result<Value> {
     let owner_addr = Address::from_str(owner)?;
     let spender_addr = Address::from_str(spender)?;
     let data = encode_call(
         "allowance(address,address)",
         vec![Token::Address(owner_addr), Token::Address(spender_addr)]
     );
-    let raw = eth_call(client, rpc_url, token, data).await?;
+    let raw = eth_call(client, rpc_url, token, data, block).await?;
     let decoded = decode_u256(&raw).map(|n| n.to_string());
     Ok(json!({"raw": raw, "decoded": decoded}))
 }
 
-pub async fn read_contract_via_abi(client: &Client, rpc_url: &str, contract: &str, abi_json: &str, function_name: &str, args: Option<Vec<Value>>) -> Result<Value> {
+pub async fn read_contract_via_abi(client: &Client, rpc_url: &str, contract: &str, abi_json: &str, function_name: &str, args: Option<Vec<Value>>, block: &Value) -> Result<Value> {
     let abi: Abi = serde_json::from_str(abi_json)?;
     let func: &Function = abi.functions().find(|f| f.name == function_name).ok_or_else(|| anyhow!("function not found in ABI"))?;
-    let tokens = coerce_tokens(func, args.unwrap_or_default())?;
-    let sig = function_signature(func);
-    let data = encode_call(&sig, tokens);
-    eth_call(client, rpc_url, contract, data).await
+    read_contract_function(client, rpc_url, contract, func, args, block).await
+}
+
+/// Like `read_contract_via_abi`, but the function comes from a human readable signature
+/// such as `balanceOf(address) returns (uint256)` instead of a full JSON ABI.
+pub async fn read_contract_via_signature(client: &Client, rpc_url: &str, contract: &str, signature: &str, args: Option<Vec<Value>>, block: &Value) -> Result<Value> {
+    let func = parse_function_signature(signature)?;
+    read_contract_function(client, rpc_url, contract, &func, args, block).await
+}
+
+/// Calls `func` with `eth_call` at `block` and returns the raw result plus its outputs decoded as JSON
+/// (`decoded` is null when the function declares no outputs or the data doesn't match).
+async fn read_contract_function(client: &Client, rpc_url: &str, contract: &str, func: &Function, args: Option<Vec<Value>>, block: &Value) -> Result<Value> {
+    let data = encode_function_call(func, &args.unwrap_or_default())?;
+    let raw = eth_call(client, rpc_url, contract, data, block).await?;
+    let decoded = match (func.outputs.is_empty(), hex_to_bytes(&raw)) {
+        (false, Ok(bytes)) => func
+            .decode_output(&bytes)
+            .ok()
+            .map(|tokens| Value::Array(tokens.iter().map(token_to_json).collect())),
+        _ => None,
+    };
+    Ok(json!({"raw": raw, "decoded": decoded}))
+}
+
+/// Reads several functions of one contract in a single Multicall3 round trip.
+/// Returns an object keyed by function name (repeated names get a `#n` suffix).
+pub async fn batch_read_contract_via_abi(client: &Client, rpc_url: &str, contract: &str, abi_json: &str, calls: Vec<(String, Option<Vec<Value>>)>) -> Result<Value> {
+    let abi: Abi = serde_json::from_str(abi_json)?;
+    let target = Address::from_str(contract)?;
+    let mut funcs = Vec::new();
+    let mut call3 = Vec::new();
+    for (name, args) in calls {
+        let func: &Function = abi.functions().find(|f| f.name == name).ok_or_else(|| anyhow!("function '{}' not found in ABI", name))?;
+        call3.push(Call3 { target, allow_failure: true, call_data: encode_function_call(func, &args.unwrap_or_default())? });
+        funcs.push(func);
+    }
+    let results = aggregate3(client, rpc_url, &call3).await?;
+
+    let mut out = serde_json::Map::new();
+    for (i, (func, (success, ret))) in funcs.into_iter().zip(results).enumerate() {
+        let entry = if !success {
+            json!({"success": false, "raw": format!("0x{}", hex::encode(&ret)), "error": "call reverted"})
+        } else {
+            match func.decode_output(&ret) {
+                Ok(tokens) => json!({"success": true, "decoded": tokens.iter().map(token_to_json).collect::<Vec<_>>()}),
+                Err(e) => json!({"success": false, "raw": format!("0x{}", hex::encode(&ret)), "error": e.to_string()}),
+            }
+        };
+        let key = if out.contains_key(&func.name) { format!("{}#{}", func.name, i) } else { func.name.clone() };
+        out.insert(key, entry);
+    }
+    Ok(Value::Object(out))
 }
 
-pub fn write_contract_tx(contract: &str, abi_json: &str, function_name: &str, args: Option<Vec<Value>>) -> Result<TransactionRequest> {
+/// Builds the transaction for a state-changing contract call. `value_wei` (decimal string)
+/// is attached for payable functions.
+pub fn write_contract_tx(contract: &str, abi_json: &str, function_name: &str, args: Option<Vec<Value>>, value_wei: Option<&str>) -> Result<TransactionRequest> {
     let abi: Abi = serde_json::from_str(abi_json)?;
     let func: &Function = abi.functions().find(|f| f.name == function_name).ok_or_else(|| anyhow!("function not found in ABI"))?;
-    let tokens = coerce_tokens(func, args.unwrap_or_default())?;
-    let sig = function_signature(func);
-    let data = encode_call(&sig, tokens);
+    let data = encode_function_call(func, &args.unwrap_or_default())?;
     let contract_addr = Address::from_str(contract)?;
-    Ok(TransactionRequest::new().to(contract_addr).data(data))
+    let mut tx = TransactionRequest::new().to(contract_addr).data(data);
+    if let Some(v) = value_wei {
+        let value = U256::from_dec_str(v.trim()).map_err(|_| anyhow!("invalid value '{}': expected a decimal wei amount", v))?;
+        tx = tx.value(value);
+    }
+    Ok(tx)
 }
 
-fn coerce_tokens(func: &Function, args: Vec<Value>) -> Result<Vec<Token>> {
-    if func.inputs.len() != args.len() {
-        return Err(anyhow!("arg count mismatch: expected {}, got {}", func.inputs.len(), args.len()));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_core::abi::encode;
+    use ethers_core::utils::to_checksum;
+    use crate::blockchain::services::multicall::MULTICALL3_ADDRESS;
+    use mockito::{mock, Matcher};
+
+    const PAIR_ABI: &str = r#"[
+        {"type":"function","name":"token0","stateMutability":"view","inputs":[],"outputs":[{"name":"","type":"address"}]},
+        {"type":"function","name":"token1","stateMutability":"view","inputs":[],"outputs":[{"name":"","type":"address"}]},
+        {"type":"function","name":"getReserves","stateMutability":"view","inputs":[],"outputs":[
+            {"name":"reserve0","type":"uint112"},{"name":"reserve1","type":"uint112"},{"name":"blockTimestampLast","type":"uint32"}]}
+    ]"#;
+
+    /// Mocks a contract at `path` that answers `supportsInterface` with `true` for
+    /// `interfaces` (and `false` otherwise) and, if `erc20`, `decimals()`/`symbol()`;
+    /// otherwise those two revert.
+    fn mock_contract(path: &str, interfaces: &[[u8; 4]], erc20: bool) -> Vec<mockito::Mock> {
+        let result = |hex: String| json!({"jsonrpc": "2.0", "id": 1, "result": hex}).to_string();
+        let call = |selector: String, body: String| {
+            mock("POST", path)
+                .match_body(Matcher::Regex(format!("\"data\":\"0x{}", selector)))
+                .with_header("content-type", "application/json")
+                .with_body(body)
+                .create()
+        };
+        let mut mocks = Vec::new();
+        if !interfaces.is_empty() {
+            for id in [ERC165_INTERFACE_ID, ERC721_INTERFACE_ID, ERC1155_INTERFACE_ID, [0xff; 4]] {
+                let yes = id == ERC165_INTERFACE_ID || interfaces.contains(&id);
+                mocks.push(call(
+                    format!("01ffc9a7{}", hex::encode(id)),
+                    result(format!("0x{:064x}", yes as u8)),
+                ));
+            }
+        } else {
+            // No ERC-165: supportsInterface reverts
+            mocks.push(call(
+                "01ffc9a7".to_string(),
+                json!({"jsonrpc": "2.0", "id": 1, "error": {"code": 3, "message": "execution reverted"}}).to_string(),
+            ));
+        }
+        if erc20 {
+            mocks.push(call("313ce567".to_string(), result(format!("0x{:064x}", 6))));
+            mocks.push(call("95d89b41".to_string(), result(format!("0x{}", hex::encode(encode(&[Token::String("USDC".into())]))))));
+        } else {
+            let revert = json!({"jsonrpc": "2.0", "id": 1, "error": {"code": 3, "message": "execution reverted"}}).to_string();
+            mocks.push(call("313ce567".to_string(), revert.clone()));
+            mocks.push(call("95d89b41".to_string(), revert));
+        }
+        mocks
+    }
+
+    #[test]
+    fn test_eth_call_payload_carries_block() {
+        let data = encode_call("totalSupply()", vec![]);
+        let at_block = eth_call_payload("0x0000000000000000000000000000000000000abc", &data, &json!("0x10"));
+        assert_eq!(at_block["params"][0]["data"], "0x18160ddd");
+        assert_eq!(at_block["params"][1], "0x10");
+        let pending = eth_call_payload("0x0000000000000000000000000000000000000abc", &data, &json!("pending"));
+        assert_eq!(pending["params"][1], "pending");
     }
-    let mut out = Vec::new();
-    for (i, param) in func.inputs.iter().enumerate() {
-        let ty = &param.kind;
-        let val = &args[i];
-        let tok = match ty {
-            ethers_core::abi::ParamType::Address => Token::Address(Address::from_str(val.as_str().ok_or_else(|| anyhow!("address arg must be string"))?)?),
-            ethers_core::abi::ParamType::Uint(_) => Token::Uint(U256::from_dec_str(val.as_str().ok_or_else(|| anyhow!("uint arg must be decimal string"))?)?),
-            ethers_core::abi::ParamType::Bool => Token::Bool(val.as_bool().ok_or_else(|| anyhow!("bool arg must be boolean"))?),
-            ethers_core::abi::ParamType::String => Token::String(val.as_str().unwrap_or("").to_string()),
-            ethers_core::abi::ParamType::Bytes => {
-                let s = val.as_str().unwrap_or("");
-                let bytes = if s.starts_with("0x") { hex::decode(&s[2..])? } else { s.as_bytes().to_vec() };
-                Token::Bytes(bytes)
+
+    #[tokio::test]
+    async fn test_balance_of_forwards_block_param() {
+        let token = "0x0000000000000000000000000000000000000abc";
+        let _m = mock("POST", "/token-balance-at-block")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call", "params": [{"to": token}, "0x10"]})))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{:064x}", 42)}).to_string())
+            .create();
+        let url = format!("{}/token-balance-at-block", mockito::server_url());
+        let owner = "0x0000000000000000000000000000000000000001";
+
+        let v = erc20_balance_of(&Client::new(), &url, token, owner, &json!("0x10")).await.unwrap();
+        assert_eq!(v["decoded"], "42");
+        // Any other block doesn't match the mock
+        assert!(erc20_balance_of(&Client::new(), &url, token, owner, &json!("latest")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_detect_token_standard_for_each_standard() {
+        let token = "0x0000000000000000000000000000000000000abc";
+        // Fresh connection per call, so a pooled connection the mock server already
+        // closed can't fail a probe
+        let client = Client::builder().pool_max_idle_per_host(0).build().unwrap();
+        let detect = |path: &'static str| {
+            let client = client.clone();
+            async move {
+                let url = format!("{}{}", mockito::server_url(), path);
+                detect_token_standard(&client, &url, token).await.unwrap()
             }
-            // Fallback: unsupported types for now
-            other => return Err(anyhow!("unsupported ABI param type: {:?}", other)),
         };
-        out.push(tok);
+
+        let _erc20 = mock_contract("/detect-erc20", &[], true);
+        let v = detect("/detect-erc20").await;
+        assert_eq!(v["standards"], json!(["ERC-20"]));
+        assert_eq!(v["erc165"], false);
+        assert_eq!(v["symbol"], "USDC");
+        assert_eq!(v["decimals"], 6);
+
+        let _erc721 = mock_contract("/detect-erc721", &[ERC721_INTERFACE_ID], false);
+        let v = detect("/detect-erc721").await;
+        assert_eq!(v["standards"], json!(["ERC-721"]));
+        assert_eq!(v["erc165"], true);
+
+        let _erc1155 = mock_contract("/detect-erc1155", &[ERC1155_INTERFACE_ID], false);
+        assert_eq!(detect("/detect-erc1155").await["standards"], json!(["ERC-1155"]));
+
+        // Reverts everywhere: nothing detected, no error
+        let _plain = mock_contract("/detect-none", &[], false);
+        let v = detect("/detect-none").await;
+        assert_eq!(v["standards"], json!([]));
+        assert_eq!(v["interfaces"]["erc721"], false);
+
+        // A node that can't be reached is an error, not "no standard"
+        assert!(detect_token_standard(&client, "http://127.0.0.1:1", token).await.is_err());
+        let _limited = mock("POST", "/detect-rate-limited")
+            .with_header("content-type", "application/json")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32005, "message": "rate limited"}}).to_string())
+            .create();
+        let url = format!("{}/detect-rate-limited", mockito::server_url());
+        assert!(detect_token_standard(&client, &url, token).await.is_err());
+    }
+
+    #[test]
+    fn test_erc20_delegated_spending_selectors() {
+        let token = "0x0000000000000000000000000000000000000020";
+        let owner = "0x000000000000000000000000000000000000dEaD";
+        let to = "0x000000000000000000000000000000000000bEEF";
+        let selector = |tx: TransactionRequest| hex::encode(&tx.data.unwrap()[..4]);
+
+        let tx = erc20_transfer_from_tx(token, owner, to, "1000").unwrap();
+        let data = tx.data.clone().unwrap();
+        assert_eq!(data.len(), 4 + 32 * 3);
+        assert_eq!(selector(tx), "23b872dd");
+        assert_eq!(selector(erc20_modify_allowance_tx(token, to, "5", true).unwrap()), "39509351");
+        assert_eq!(selector(erc20_modify_allowance_tx(token, to, "5", false).unwrap()), "a457c2d7");
+        assert!(erc20_modify_allowance_tx(token, to, "-5", true).is_err());
+    }
+
+    #[test]
+    fn test_erc1155_batch_transfer_encodes_ids_and_amounts() {
+        let ids = vec!["1".to_string(), "2".to_string()];
+        let amounts = vec!["10".to_string(), "20".to_string()];
+        let from = "0x000000000000000000000000000000000000dEaD";
+        let to = "0x000000000000000000000000000000000000bEEF";
+        let tx = erc1155_safe_batch_transfer_from_tx("0x0000000000000000000000000000000000001155", from, to, &ids, &amounts).unwrap();
+        let data = tx.data.unwrap().to_vec();
+        assert_eq!(data[..4], crate::utils::function_selector("safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)"));
+
+        let types = [
+            ParamType::Address,
+            ParamType::Address,
+            ParamType::Array(Box::new(ParamType::Uint(256))),
+            ParamType::Array(Box::new(ParamType::Uint(256))),
+            ParamType::Bytes,
+        ];
+        let tokens = decode(&types, &data[4..]).unwrap();
+        assert_eq!(tokens[2], Token::Array(vec![Token::Uint(1.into()), Token::Uint(2.into())]));
+        assert_eq!(tokens[3], Token::Array(vec![Token::Uint(10.into()), Token::Uint(20.into())]));
+
+        assert!(erc1155_safe_batch_transfer_from_tx("0x0000000000000000000000000000000000001155", from, to, &ids, &amounts[..1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_erc1155_balance_of_batch_decodes_array() {
+        let result = encode(&[Token::Array(vec![Token::Uint(5.into()), Token::Uint(U256::exp10(20))])]);
+        let _m = mock("POST", "/erc1155-batch")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call"})))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{}", hex::encode(result))}).to_string())
+            .create();
+        let url = format!("{}/erc1155-batch", mockito::server_url());
+        let owners = vec!["0x000000000000000000000000000000000000dEaD".to_string(); 2];
+        let ids = vec!["1".to_string(), "2".to_string()];
+        let v = erc1155_balance_of_batch(&Client::new(), &url, "0x0000000000000000000000000000000000001155", &owners, &ids, &json!("latest"))
+            .await
+            .unwrap();
+        assert_eq!(v["decoded"], json!(["5", "100000000000000000000"]));
+    }
+
+    #[test]
+    fn test_write_contract_tx_carries_value_for_payable_call() {
+        let abi = r#"[{"type":"function","name":"deposit","stateMutability":"payable","inputs":[],"outputs":[]}]"#;
+        let contract = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+        let tx = write_contract_tx(contract, abi, "deposit", None, Some("1000000000000000000")).unwrap();
+        assert_eq!(tx.value, Some(U256::exp10(18)));
+        assert_eq!(tx.data.as_ref().map(|d| d.to_vec()), Some(crate::utils::function_selector("deposit()").to_vec()));
+        assert_eq!(tx.to, Some(Address::from_str(contract).unwrap().into()));
+
+        let no_value = write_contract_tx(contract, abi, "deposit", None, None).unwrap();
+        assert_eq!(no_value.value, None);
+        assert!(write_contract_tx(contract, abi, "deposit", None, Some("1.5")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_contract_via_signature_matches_abi_path() {
+        let owner = "0x000000000000000000000000000000000000dEaD";
+        let _m = mock("POST", "/read-signature")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call"})))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{:064x}", 12345)}).to_string())
+            .expect(2)
+            .create();
+        let url = format!("{}/read-signature", mockito::server_url());
+        let client = Client::new();
+        let abi = r#"[{"type":"function","name":"balanceOf","stateMutability":"view",
+            "inputs":[{"name":"owner","type":"address"}],"outputs":[{"name":"","type":"uint256"}]}]"#;
+
+        let via_abi = read_contract_via_abi(&client, &url, owner, abi, "balanceOf", Some(vec![json!(owner)]), &json!("latest"))
+            .await
+            .unwrap();
+        let via_sig = read_contract_via_signature(&client, &url, owner, "balanceOf(address) returns (uint256)", Some(vec![json!(owner)]), &json!("latest"))
+            .await
+            .unwrap();
+        assert_eq!(via_sig["decoded"], json!(["12345"]));
+        assert_eq!(via_sig, via_abi);
+    }
+
+    #[tokio::test]
+    async fn test_batch_read_contract_decodes_each_result() {
+        let token0 = Address::from_low_u64_be(0xa0);
+        let token1 = Address::from_low_u64_be(0xb1);
+        let entry = |data: Vec<u8>| Token::Tuple(vec![Token::Bool(true), Token::Bytes(data)]);
+        let aggregate = encode(&[Token::Array(vec![
+            entry(encode(&[Token::Address(token0)])),
+            entry(encode(&[Token::Address(token1)])),
+            entry(encode(&[Token::Uint(U256::from(1000)), Token::Uint(U256::from(2000)), Token::Uint(U256::from(42))])),
+        ])]);
+        let _m = mock("POST", "/pair")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call", "params": [{"to": MULTICALL3_ADDRESS}]})))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{}", hex::encode(aggregate))}).to_string())
+            .create();
+
+        let url = format!("{}/pair", mockito::server_url());
+        let calls = vec![("token0".to_string(), None), ("token1".to_string(), None), ("getReserves".to_string(), None)];
+        let out = batch_read_contract_via_abi(&Client::new(), &url, "0x0000000000000000000000000000000000000001", PAIR_ABI, calls)
+            .await
+            .unwrap();
+
+        assert_eq!(out["token0"]["decoded"][0], to_checksum(&token0, None));
+        assert_eq!(out["token1"]["decoded"][0], to_checksum(&token1, None));
+        assert_eq!(out["getReserves"]["decoded"], json!(["1000", "2000", "42"]));
+        assert_eq!(out["getReserves"]["success"], true);
+    }
+
+    fn mock_erc20(path: &str, decimals: Option<u8>, total_supply: U256) -> Vec<mockito::Mock> {
+        let answer = |selector: &str, body: Value| {
+            mock("POST", path)
+                .match_body(Matcher::Regex(format!("\"data\":\"0x{}", selector)))
+                .with_header("content-type", "application/json")
+                .with_body(body.to_string())
+                .create()
+        };
+        let word = |tokens: Vec<Token>| json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{}", hex::encode(encode(&tokens)))});
+        let decimals_body = match decimals {
+            Some(d) => word(vec![Token::Uint(U256::from(d))]),
+            None => json!({"jsonrpc": "2.0", "id": 1, "error": {"code": 3, "message": "execution reverted"}}),
+        };
+        vec![
+            answer("06fdde03", word(vec![Token::String("Test Token".into())])),
+            answer("95d89b41", word(vec![Token::String("TST".into())])),
+            answer("313ce567", decimals_body),
+            answer("18160ddd", word(vec![Token::Uint(total_supply)])),
+        ]
     }
-    Ok(out)
-}
 
-fn function_signature(func: &Function) -> String {
-    let types: Vec<String> = func
-        .inputs
-        .iter()
-        .map(|p| param_type_to_string(&p.kind))
-        .collect();
-    format!("{}({})", func.name, types.join(","))
-}
-
-fn param_type_to_string(p: &ParamType) -> String {
-    match p {
-        ParamType::Address => "address".to_string(),
-        ParamType::Bytes => "bytes".to_string(),
-        ParamType::FixedBytes(n) => format!("bytes{}", n),
-        ParamType::Int(n) => format!("int{}", n),
-        ParamType::Uint(n) => format!("uint{}", n),
-        ParamType::Bool => "bool".to_string(),
-        ParamType::String => "string".to_string(),
-        ParamType::Array(inner) => format!("{}[]", param_type_to_string(inner)),
-        ParamType::FixedArray(inner, n) => format!("{}[{}]", param_type_to_string(inner), n),
-        ParamType::Tuple(components) => {
-            let inner: Vec<String> = components.iter().map(param_type_to_string).collect();
-            format!("({})", inner.join(","))
-        }
+    #[tokio::test]
+    async fn test_erc20_info_formats_total_supply() {
+        let _m = mock_erc20("/erc20-info-18", Some(18), U256::exp10(18) * 1_234_567u64 + U256::exp10(17) * 5u64);
+        let url = format!("{}/erc20-info-18", mockito::server_url());
+        let info = erc20_info(&Client::new(), &url, "0x000000000000000000000000000000000000dEaD", &json!("latest"))
+            .await
+            .unwrap();
+        let decoded = &info["decoded"];
+        assert_eq!(decoded["symbol"], "TST");
+        assert_eq!(decoded["decimals"], "18");
+        assert_eq!(decoded["totalSupply"], "1234567500000000000000000");
+        assert_eq!(decoded["formatted_total_supply"], "1234567.5");
+        assert_eq!(decoded["decimals_defaulted"], false);
+    }
+
+    #[tokio::test]
+    async fn test_erc20_info_without_decimals_defaults_to_zero() {
+        let _m = mock_erc20("/erc20-info-nodecimals", None, U256::from(1000u64));
+        let url = format!("{}/erc20-info-nodecimals", mockito::server_url());
+        let info = erc20_info(&Client::new(), &url, "0x000000000000000000000000000000000000dEaD", &json!("latest"))
+            .await
+            .unwrap();
+        let decoded = &info["decoded"];
+        assert!(decoded["decimals"].is_null());
+        assert_eq!(decoded["formatted_total_supply"], "1000");
+        assert_eq!(decoded["decimals_defaulted"], true);
     }
 }
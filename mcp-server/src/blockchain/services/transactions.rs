@@ -1,12 +1,129 @@
 // src/blockchain/services/transactions.rs
 
 use crate::blockchain::{models::TransactionResponse, nonce_manager::NonceManager};
+use crate::blockchain::services::chain;
+use crate::blockchain::services::gas::eip1559_fees;
+use crate::blockchain::services::rpc::{self, parse_hex_u256, parse_hex_u64, rpc_call, RpcError};
 use anyhow::{anyhow, Result, Context};
-use ethers_core::types::{TransactionRequest, U64, U256};
+use ethers_core::abi::{self, ParamType};
+use ethers_core::types::transaction::eip2718::TypedTransaction;
+use ethers_core::types::{Address, Eip1559TransactionRequest, TransactionRequest, U64, U256};
 use ethers_signers::{LocalWallet, Signer};
 use reqwest::Client;
-use serde_json::json;
-use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use dashmap::DashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+use crate::config::{Config, FeeType, DEFAULT_GAS_LIMIT_BUFFER_PCT};
+
+/// Per-send switches for `send_evm_transaction_with_options`.
+#[derive(Debug, Clone)]
+pub struct SendOptions {
+    /// Broadcast even if the sender's balance doesn't cover value + gas (e.g. when funds
+    /// are expected to land first in the same block)
+    pub skip_balance_check: bool,
+    /// Chain the caller means to send on; the send is refused if the node reports another
+    pub expected_chain_id: Option<u64>,
+    /// Run the populated transaction through `eth_call` and `eth_estimateGas` first and
+    /// only broadcast if both succeed
+    pub simulate: bool,
+    /// Percent added to the gas estimate when the caller didn't set a gas limit
+    pub gas_buffer_percent: u64,
+    /// Ceiling for the buffered gas limit (never below the estimate itself)
+    pub max_gas_limit: Option<U256>,
+    /// Gas limit and price to fall back on when the node can't estimate them
+    /// (USE_CONFIG_GAS_DEFAULTS); `None` fails the send instead
+    pub gas_defaults: Option<GasDefaults>,
+    /// Transaction type to sign (CHAIN_FEE_TYPES)
+    pub fee_type: FeeType,
+    /// Earlier `FeeType::Auto` probes, shared across sends
+    pub fee_support: FeeSupportCache,
+    /// Caller-set EIP-1559 max fee; only valid when the transaction goes out as type 2
+    pub max_fee_per_gas: Option<U256>,
+    /// Caller-set EIP-1559 tip; the max fee follows it when only the tip is given
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// HTTP client the send's RPC calls go through, so they reuse its connection pool
+    pub http_client: Client,
+}
+
+/// Configured fallback gas settings (DEFAULT_GAS_LIMIT, DEFAULT_GAS_PRICE).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasDefaults {
+    pub gas_limit: U256,
+    pub gas_price: U256,
+}
+
+impl GasDefaults {
+    pub fn from_config(config: &Config) -> Self {
+        Self { gas_limit: U256::from(config.default_gas_limit), gas_price: U256::from(config.default_gas_price) }
+    }
+}
+
+impl Default for SendOptions {
+    fn default() -> Self {
+        Self {
+            skip_balance_check: false,
+            expected_chain_id: None,
+            simulate: false,
+            gas_buffer_percent: DEFAULT_GAS_LIMIT_BUFFER_PCT,
+            max_gas_limit: None,
+            gas_defaults: None,
+            fee_type: FeeType::Auto,
+            fee_support: FeeSupportCache::default(),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            http_client: Client::new(),
+        }
+    }
+}
+
+/// How long a probed EIP-1559 answer is trusted before the node is asked again; chains
+/// do switch over at a hard fork.
+pub const DEFAULT_FEE_SUPPORT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Whether each RPC endpoint's latest block had a base fee, as probed for `FeeType::Auto`.
+/// Keyed by RPC URL rather than chain ID, so two endpoints never share an answer.
+#[derive(Debug, Clone)]
+pub struct FeeSupportCache {
+    entries: Arc<DashMap<String, (bool, Instant)>>,
+    ttl: Duration,
+}
+
+impl Default for FeeSupportCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_FEE_SUPPORT_TTL)
+    }
+}
+
+impl FeeSupportCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { entries: Arc::new(DashMap::new()), ttl }
+    }
+
+    fn get(&self, rpc_url: &str) -> Option<bool> {
+        self.entries
+            .get(rpc_url)
+            .filter(|entry| entry.1.elapsed() < self.ttl)
+            .map(|entry| entry.0)
+    }
+
+    fn insert(&self, rpc_url: &str, supported: bool) {
+        self.entries.insert(rpc_url.to_string(), (supported, Instant::now()));
+    }
+}
+
+/// Gas limit for a transaction estimated at `estimate`: `percent` extra, capped at `cap`
+/// but never below the estimate.
+pub fn buffered_gas_limit(estimate: U256, percent: u64, cap: Option<U256>) -> U256 {
+    let buffered = (estimate.saturating_mul(U256::from(percent).saturating_add(U256::from(100))) / 100).max(estimate);
+    match cap {
+        Some(cap) => buffered.min(cap).max(estimate),
+        None => buffered,
+    }
+}
 
 /// A centralized, secure function for sending any EVM transaction.
 /// It uses the NonceManager to prevent race conditions.
@@ -16,11 +133,19 @@ pub async fn send_evm_transaction(
     tx_request: TransactionRequest,
     nonce_manager: &NonceManager,
 ) -> Result<TransactionResponse> {
-    let client = Client::new();
-    let from_address = wallet.address();
+    send_evm_transaction_with_options(rpc_url, wallet, tx_request, nonce_manager, &SendOptions::default()).await
+}
 
-    // FIX: Get the next sequential nonce from the manager.
-    let nonce = nonce_manager.get_next_nonce(from_address, rpc_url).await?;
+/// `send_evm_transaction` with explicit `SendOptions`.
+pub async fn send_evm_transaction_with_options(
+    rpc_url: &str,
+    wallet: LocalWallet,
+    tx_request: TransactionRequest,
+    nonce_manager: &NonceManager,
+    options: &SendOptions,
+) -> Result<TransactionResponse> {
+    let client = &options.http_client;
+    let from_address = wallet.address();
 
     // Get chain ID from the node.
     let chain_id_payload = json!({
@@ -36,57 +161,112 @@ pub async fn send_evm_transaction(
         
     let chain_id_hex = chain_id_response["result"].as_str().context("Failed to get chain_id from RPC")?;
     let chain_id = U64::from_str_radix(chain_id_hex.trim_start_matches("0x"), 16)?;
+    if let Some(expected) = options.expected_chain_id {
+        if chain_id.as_u64() != expected {
+            return Err(anyhow!(
+                "chain ID mismatch: the RPC for chain {} reports chain ID {}; refusing to sign for the wrong network",
+                expected,
+                chain_id
+            ));
+        }
+    }
 
-    // Populate the final transaction request
+    // Populate the transaction request. The nonce is only taken once every pre-flight
+    // check has passed, so a refused send leaves no gap in the sequence.
     let mut tx = tx_request
         .from(from_address)
-        .nonce(nonce)
         .chain_id(chain_id.as_u64());
 
+    // Dry run; a revert stops here with its reason, before a nonce is reserved
+    if options.simulate {
+        let estimate = simulate_transaction(client, rpc_url, &tx).await?;
+        if tx.gas.is_none() {
+            tx = tx.gas(buffered_gas_limit(estimate, options.gas_buffer_percent, options.max_gas_limit));
+        }
+    }
+
     // If gas is not provided, estimate it via eth_estimateGas
     if tx.gas.is_none() {
-        let call_obj = serde_json::to_value(&tx)?;
-        let estimate_payload = json!({
-            "jsonrpc": "2.0",
-            "method": "eth_estimateGas",
-            "params": [call_obj],
-            "id": 1
-        });
-        let estimate_resp: serde_json::Value = client.post(rpc_url)
-            .json(&estimate_payload)
-            .send().await?
-            .json().await?;
-        if let Some(err) = estimate_resp.get("error") {
-            return Err(anyhow!("RPC Error estimating gas: {}", err));
-        }
-        let gas_hex = estimate_resp["result"].as_str().context("Failed to get gas estimate")?;
-        let gas = U256::from_str_radix(gas_hex.trim_start_matches("0x"), 16)?;
+        let gas = match (estimate_gas_limit(client, rpc_url, &tx).await, options.gas_defaults) {
+            (Ok(estimate), _) => buffered_gas_limit(estimate, options.gas_buffer_percent, options.max_gas_limit),
+            (Err(GasEstimateError::Unavailable(e)), Some(defaults)) => {
+                let limit = options.max_gas_limit.map_or(defaults.gas_limit, |cap| defaults.gas_limit.min(cap));
+                warn!("Gas estimation failed ({}); using the configured default gas limit {}", e, limit);
+                limit
+            }
+            // A revert or any other answer about the transaction itself is never papered over
+            (Err(GasEstimateError::Unavailable(e) | GasEstimateError::Rejected(e)), _) => return Err(e),
+        };
         tx = tx.gas(gas);
     }
 
     // If gas price not provided, fetch eth_gasPrice and use legacy gas_price
-    if tx.gas_price.is_none() {
-        let gp_payload = json!({
-            "jsonrpc": "2.0",
-            "method": "eth_gasPrice",
-            "params": [],
-            "id": 1
-        });
-        let gp_resp: serde_json::Value = client.post(rpc_url)
-            .json(&gp_payload)
-            .send().await?
-            .json().await?;
-        if let Some(err) = gp_resp.get("error") {
-            return Err(anyhow!("RPC Error getting gasPrice: {}", err));
+    let price_given = tx.gas_price.is_some();
+    if !price_given {
+        let gas_price = match (fetch_gas_price(client, rpc_url).await, options.gas_defaults) {
+            (Ok(price), _) => price,
+            (Err(e), Some(defaults)) => {
+                warn!("eth_gasPrice failed ({}); using the configured default gas price {}", e, defaults.gas_price);
+                defaults.gas_price
+            }
+            (Err(e), None) => return Err(e),
+        };
+        tx = tx.gas_price(gas_price);
+    }
+
+    // A type-2 transaction needs a max fee and tip. A caller-set gas price is used for both,
+    // which costs exactly that price per gas as a legacy transaction would.
+    let fee_overrides = (options.max_fee_per_gas, options.max_priority_fee_per_gas);
+    let dynamic_fees = if uses_eip1559(client, rpc_url, options.fee_type, &options.fee_support).await {
+        let price = tx.gas_price.unwrap_or_default();
+        let fees = if price_given || matches!(fee_overrides, (Some(_), Some(_))) {
+            None
+        } else {
+            match eip1559_fees(client, rpc_url, price).await {
+                Ok(fees) => fees,
+                Err(e) => {
+                    warn!("Could not fetch EIP-1559 fees ({}); paying the gas price {} as both max fee and tip", e, price);
+                    None
+                }
+            }
+        };
+        let (max_fee, tip) = match (fee_overrides, fees) {
+            ((Some(max_fee), Some(tip)), _) => (max_fee, tip),
+            ((Some(max_fee), None), fees) => (max_fee, fees.map_or(price, |f| f.max_priority_fee_per_gas).min(max_fee)),
+            // Same headroom over the base fee as the suggested max fee
+            ((None, Some(tip)), Some(f)) => (f.base_fee.saturating_mul(U256::from(2)).saturating_add(tip), tip),
+            ((None, Some(tip)), None) => (price.max(tip), tip),
+            ((None, None), fees) => fees.map_or((price, price), |f| (f.max_fee_per_gas, f.max_priority_fee_per_gas)),
+        };
+        if tip > max_fee {
+            return Err(anyhow!("priority fee {} wei is above the max fee {} wei", tip, max_fee));
         }
-        let gp_hex = gp_resp["result"].as_str().context("Failed to get gasPrice")?;
-        let gp = U256::from_str_radix(gp_hex.trim_start_matches("0x"), 16)?;
-        tx = tx.gas_price(gp);
+        // The balance check covers the most the transaction can cost
+        tx = tx.gas_price(max_fee);
+        Some((max_fee, tip))
+    } else if fee_overrides != (None, None) {
+        return Err(anyhow!(
+            "this chain takes legacy transactions; set a gas price instead of a max fee or priority fee"
+        ));
+    } else {
+        None
+    };
+
+    // Refuse up front rather than let the node reject the broadcast with an opaque error
+    if !options.skip_balance_check {
+        check_sufficient_funds(client, rpc_url, from_address, &tx).await?;
     }
 
-    // Sign the transaction
-    let signature = wallet.sign_transaction(&tx.clone().into()).await?;
-    let raw_tx = tx.rlp_signed(&signature);
+    let nonce = nonce_manager.get_next_nonce(from_address, rpc_url).await?;
+    tx = tx.nonce(nonce);
+
+    // Sign the transaction in the envelope the chain takes
+    let typed: TypedTransaction = match dynamic_fees {
+        Some((max_fee, tip)) => eip1559_request(&tx, max_fee, tip).into(),
+        None => tx.clone().into(),
+    };
+    let signature = wallet.sign_transaction(&typed).await?;
+    let raw_tx = typed.rlp_signed(&signature);
 
     // Send the raw transaction
     let params = json!([format!("0x{}", hex::encode(raw_tx))]);
@@ -97,12 +277,19 @@ pub async fn send_evm_transaction(
         "id": 1,
     });
 
-    let response: serde_json::Value = client.post(rpc_url)
-        .json(&payload)
-        .send().await?.json().await?;
+    let response: serde_json::Value = match client.post(rpc_url).json(&payload).send().await {
+        Ok(resp) => resp.json().await?,
+        Err(e) => {
+            // The node may or may not have the transaction; ask it again next time
+            nonce_manager.reset(from_address).await;
+            return Err(e.into());
+        }
+    };
 
     if let Some(error) = response.get("error") {
-        return Err(anyhow!("RPC Error sending transaction: {}", error));
+        // Rejected, so the nonce is still free
+        nonce_manager.release(from_address, nonce).await;
+        return Err(rpc::response_error("RPC Error sending transaction", error));
     }
 
     let tx_hash = response["result"]
@@ -114,7 +301,408 @@ pub async fn send_evm_transaction(
     })
 }
 
+/// Whether to send a type-2 transaction through `rpc_url`. `FeeType::Auto` looks for a
+/// `baseFeePerGas` in the latest block and keeps the answer in `cache`; if the probe fails
+/// the send goes out as legacy, which every chain accepts, and the node is probed again next time.
+pub async fn uses_eip1559(client: &Client, rpc_url: &str, fee_type: FeeType, cache: &FeeSupportCache) -> bool {
+    match fee_type {
+        FeeType::Legacy => return false,
+        FeeType::Eip1559 => return true,
+        FeeType::Auto => {}
+    }
+    if let Some(known) = cache.get(rpc_url) {
+        return known;
+    }
+    match rpc_call(client, rpc_url, "eth_getBlockByNumber", json!(["latest", false])).await {
+        Ok(block) => {
+            let supported = block.get("baseFeePerGas").is_some_and(|v| !v.is_null());
+            cache.insert(rpc_url, supported);
+            supported
+        }
+        Err(e) => {
+            warn!("Could not tell whether {} supports EIP-1559 ({}); sending a legacy transaction", rpc_url, e);
+            false
+        }
+    }
+}
+
+// `tx` as a type-2 request with the given fees
+fn eip1559_request(tx: &TransactionRequest, max_fee: U256, tip: U256) -> Eip1559TransactionRequest {
+    Eip1559TransactionRequest {
+        from: tx.from,
+        to: tx.to.clone(),
+        gas: tx.gas,
+        value: tx.value,
+        data: tx.data.clone(),
+        nonce: tx.nonce,
+        access_list: Default::default(),
+        max_priority_fee_per_gas: Some(tip),
+        max_fee_per_gas: Some(max_fee),
+        chain_id: tx.chain_id,
+    }
+}
+
+// Why `eth_estimateGas` gave no estimate. Only a node that cannot be reached or does not
+// support the method is `Unavailable`; that alone may fall back to the configured defaults.
+enum GasEstimateError {
+    Unavailable(anyhow::Error),
+    Rejected(anyhow::Error),
+}
+
+// JSON-RPC error codes for a method the node does not have or does not support
+const METHOD_NOT_FOUND: i64 = -32601;
+const METHOD_NOT_SUPPORTED: i64 = -32004;
+
+// The node's `eth_estimateGas` for `tx`, before any buffer
+async fn estimate_gas_limit(client: &Client, rpc_url: &str, tx: &TransactionRequest) -> Result<U256, GasEstimateError> {
+    let call_obj = serde_json::to_value(tx).map_err(|e| GasEstimateError::Rejected(e.into()))?;
+    let estimate_payload = json!({
+        "jsonrpc": "2.0",
+        "method": "eth_estimateGas",
+        "params": [call_obj],
+        "id": 1
+    });
+    let estimate_resp: serde_json::Value = async {
+        client.post(rpc_url)
+            .json(&estimate_payload)
+            .send().await?
+            .json().await
+    }
+    .await
+    .map_err(|e: reqwest::Error| GasEstimateError::Unavailable(e.into()))?;
+    if let Some(err) = estimate_resp.get("error") {
+        let e = rpc::response_error("RPC Error estimating gas", err);
+        return Err(match err.get("code").and_then(|c| c.as_i64()) {
+            Some(METHOD_NOT_FOUND | METHOD_NOT_SUPPORTED) => GasEstimateError::Unavailable(e),
+            _ => GasEstimateError::Rejected(e),
+        });
+    }
+    let gas_hex = estimate_resp["result"].as_str()
+        .context("Failed to get gas estimate")
+        .map_err(GasEstimateError::Rejected)?;
+    U256::from_str_radix(gas_hex.trim_start_matches("0x"), 16)
+        .map_err(|e| GasEstimateError::Rejected(e.into()))
+}
+
+// The node's legacy `eth_gasPrice`
+async fn fetch_gas_price(client: &Client, rpc_url: &str) -> Result<U256> {
+    let gp_payload = json!({
+        "jsonrpc": "2.0",
+        "method": "eth_gasPrice",
+        "params": [],
+        "id": 1
+    });
+    let gp_resp: serde_json::Value = client.post(rpc_url)
+        .json(&gp_payload)
+        .send().await?
+        .json().await?;
+    if let Some(err) = gp_resp.get("error") {
+        return Err(rpc::response_error("RPC Error getting gasPrice", err));
+    }
+    let gp_hex = gp_resp["result"].as_str().context("Failed to get gasPrice")?;
+    Ok(U256::from_str_radix(gp_hex.trim_start_matches("0x"), 16)?)
+}
+
+/// Decodes revert data returned by a failed call: `Error(string)` reasons, `Panic(uint256)`
+/// codes, or the raw hex for custom errors. `None` when there is no data.
+pub fn decode_revert_reason(data: &str) -> Option<String> {
+    let bytes = hex::decode(data.trim_start_matches("0x")).ok()?;
+    if bytes.is_empty() {
+        return None;
+    }
+    let (selector, body) = bytes.split_at(bytes.len().min(4));
+    let decoded = match selector {
+        [0x08, 0xc3, 0x79, 0xa0] => abi::decode(&[ParamType::String], body)
+            .ok()
+            .and_then(|t| t.into_iter().next())
+            .and_then(|t| t.into_string()),
+        [0x4e, 0x48, 0x7b, 0x71] => abi::decode(&[ParamType::Uint(256)], body)
+            .ok()
+            .and_then(|t| t.into_iter().next())
+            .and_then(|t| t.into_uint())
+            .map(|code| format!("panic code 0x{:x}", code)),
+        _ => None,
+    };
+    Some(decoded.unwrap_or_else(|| format!("custom error 0x{}", hex::encode(&bytes))))
+}
+
+// One dry-run JSON-RPC call; errors become "execution reverted: <reason>" when the node
+// returned revert data
+async fn simulation_call(client: &Client, rpc_url: &str, method: &str, params: Value) -> Result<Value> {
+    let resp: Value = client
+        .post(rpc_url)
+        .json(&json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let Some(err) = resp.get("error") else {
+        return Ok(resp["result"].clone());
+    };
+    let data = err.get("data").and_then(|d| d.as_str().or_else(|| d.get("data").and_then(|d| d.as_str())));
+    let message = err.get("message").and_then(|m| m.as_str()).unwrap_or("call failed");
+    Err(match data.and_then(decode_revert_reason) {
+        Some(reason) => RpcError::Reverted(format!(
+            "simulation failed ({}): execution reverted: {}; transaction not sent",
+            method, reason
+        ))
+        .into(),
+        None if rpc::is_revert(err) => {
+            RpcError::Reverted(format!("simulation failed ({}): {}; transaction not sent", method, message)).into()
+        }
+        None => anyhow!("simulation failed ({}): {}; transaction not sent", method, message),
+    })
+}
+
+/// Runs `tx` through `eth_call` and `eth_estimateGas` without broadcasting it and returns
+/// the gas estimate. Fails with the decoded revert reason if either call reverts.
+pub async fn simulate_transaction(client: &Client, rpc_url: &str, tx: &TransactionRequest) -> Result<U256> {
+    let call_obj = serde_json::to_value(tx)?;
+    simulation_call(client, rpc_url, "eth_call", json!([call_obj, "latest"])).await?;
+    parse_hex_u256(&simulation_call(client, rpc_url, "eth_estimateGas", json!([call_obj])).await?)
+}
+
+/// Errors with "insufficient funds: have X, need Y" (in wei) when `from` can't pay the
+/// transaction's value plus its maximum gas cost (gas limit × gas price).
+pub async fn check_sufficient_funds(client: &Client, rpc_url: &str, from: Address, tx: &TransactionRequest) -> Result<()> {
+    let gas_cost = tx.gas.unwrap_or_default().checked_mul(tx.gas_price.unwrap_or_default());
+    let need = gas_cost
+        .and_then(|cost| cost.checked_add(tx.value.unwrap_or_default()))
+        .ok_or_else(|| RpcError::InsufficientFunds("insufficient funds: value plus gas cost overflows uint256".into()))?;
+    let have = parse_hex_u256(&rpc_call(client, rpc_url, "eth_getBalance", json!([format!("{:?}", from), "latest"])).await?)?;
+    if have < need {
+        return Err(RpcError::InsufficientFunds(format!(
+            "insufficient funds: have {} wei, need {} wei (value {} + gas {} × price {})",
+            have,
+            need,
+            tx.value.unwrap_or_default(),
+            tx.gas.unwrap_or_default(),
+            tx.gas_price.unwrap_or_default()
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Nodes (geth and most forks) reject a replacement unless its fee is at least 10% higher.
+pub const MIN_FEE_BUMP_PERCENT: u64 = 10;
+
+/// Default bump used when the caller doesn't pick one; a little above the minimum so
+/// rounding on the node side never rejects the replacement.
+pub const DEFAULT_FEE_BUMP_PERCENT: u64 = 12;
+
+/// Result of rebroadcasting a pending transaction at the same nonce with a higher fee.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacementResponse {
+    pub tx_hash: String,
+    pub replaced_tx_hash: Option<String>,
+    pub nonce: u64,
+    pub old_gas_price: String,
+    pub new_gas_price: String,
+}
+
+/// The gas price a replacement must pay: the original price bumped by `bump_percent`
+/// (never less than `MIN_FEE_BUMP_PERCENT`), or the current network price if that is higher.
+pub fn bumped_gas_price(original: U256, network: U256, bump_percent: u64) -> U256 {
+    let pct = bump_percent.max(MIN_FEE_BUMP_PERCENT);
+    // Round up so the result is never below the node's threshold
+    let bumped = (original * U256::from(100 + pct) + U256::from(99)) / U256::from(100);
+    bumped.max(network)
+}
+
+/// Rebuilds a pending transaction (as returned by `eth_getTransactionByHash`) with the
+/// same nonce, recipient, value, data and gas limit but the given gas price.
+pub fn build_replacement(original: &Value, gas_price: U256, chain_id: u64) -> Result<TransactionRequest> {
+    let nonce = parse_hex_u256(&original["nonce"]).context("original transaction has no nonce")?;
+    let gas = parse_hex_u256(&original["gas"]).context("original transaction has no gas limit")?;
+    let value = parse_hex_u256(&original["value"]).unwrap_or_default();
+    let input = original["input"].as_str().unwrap_or("0x");
+    let data = hex::decode(input.trim_start_matches("0x"))?;
+
+    let mut tx = TransactionRequest::new()
+        .nonce(nonce)
+        .gas(gas)
+        .gas_price(gas_price)
+        .value(value)
+        .data(data)
+        .chain_id(chain_id);
+    if let Some(from) = original["from"].as_str() {
+        tx = tx.from(Address::from_str(from)?);
+    }
+    // `to` is null for contract deployments
+    if let Some(to) = original["to"].as_str() {
+        tx = tx.to(Address::from_str(to)?);
+    }
+    Ok(tx)
+}
+
+/// Finds the wallet's pending transaction with `nonce` in the node's pending block.
+async fn find_pending_by_nonce(client: &Client, rpc_url: &str, from: Address, nonce: u64) -> Result<Value> {
+    let block = rpc_call(client, rpc_url, "eth_getBlockByNumber", json!(["pending", true])).await?;
+    block["transactions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|tx| {
+            tx["from"].as_str().and_then(|f| Address::from_str(f).ok()) == Some(from)
+                && parse_hex_u64(&tx["nonce"]).ok() == Some(nonce)
+        })
+        .cloned()
+        .ok_or_else(|| anyhow!("No pending transaction with nonce {} found; pass the original tx_hash instead", nonce))
+}
+
+/// A 0-value self-transfer at `nonce`, used to cancel whatever is pending at that nonce.
+pub fn build_cancellation(from: Address, nonce: u64, gas_price: U256, chain_id: u64) -> TransactionRequest {
+    TransactionRequest::new()
+        .from(from)
+        .to(from)
+        .nonce(nonce)
+        .gas(21_000u64)
+        .gas_price(gas_price)
+        .value(U256::zero())
+        .chain_id(chain_id)
+}
+
+/// Signs `tx` with `wallet` for `chain_id` and broadcasts it, returning the new hash.
+async fn sign_and_send(client: &Client, rpc_url: &str, wallet: LocalWallet, tx: &TransactionRequest, chain_id: u64) -> Result<String> {
+    let signature = wallet.with_chain_id(chain_id).sign_transaction(&tx.clone().into()).await?;
+    let raw_tx = tx.rlp_signed(&signature);
+    let sent = rpc_call(client, rpc_url, "eth_sendRawTransaction", json!([format!("0x{}", hex::encode(raw_tx))])).await?;
+    sent.as_str()
+        .map(|h| h.to_string())
+        .ok_or_else(|| anyhow!("Failed to extract transaction hash from response"))
+}
+
+/// Speeds up a stuck transaction from `wallet` by rebroadcasting it at the same nonce with a
+/// bumped gas price. The original is located by `tx_hash`, or by `nonce` in the pending pool.
+///
+/// The nonce manager is deliberately bypassed: a replacement reuses an already-assigned nonce.
+pub async fn speed_up_transaction(
+    client: &Client,
+    rpc_url: &str,
+    wallet: LocalWallet,
+    tx_hash: Option<&str>,
+    nonce: Option<u64>,
+    bump_percent: u64,
+) -> Result<ReplacementResponse> {
+    let from = wallet.address();
+
+    let original = match (tx_hash, nonce) {
+        (Some(hash), _) => {
+            let tx = rpc_call(client, rpc_url, "eth_getTransactionByHash", json!([hash])).await?;
+            if tx.is_null() {
+                return Err(anyhow!("Transaction {} not found", hash));
+            }
+            tx
+        }
+        (None, Some(n)) => find_pending_by_nonce(client, rpc_url, from, n).await?,
+        (None, None) => return Err(anyhow!("Either tx_hash or nonce is required")),
+    };
+
+    if !original["blockNumber"].is_null() {
+        return Err(anyhow!("Transaction is already mined and cannot be replaced"));
+    }
+    let sender = original["from"].as_str().and_then(|f| Address::from_str(f).ok());
+    if sender != Some(from) {
+        return Err(anyhow!("Transaction was not sent by wallet {:?}", from));
+    }
+    let tx_nonce = parse_hex_u64(&original["nonce"])?;
+    if let Some(n) = nonce {
+        if n != tx_nonce {
+            return Err(anyhow!("tx_hash has nonce {}, not {}", tx_nonce, n));
+        }
+    }
+    let confirmed = parse_hex_u64(&rpc_call(client, rpc_url, "eth_getTransactionCount", json!([from, "latest"])).await?)?;
+    if confirmed > tx_nonce {
+        return Err(anyhow!("Nonce {} is already confirmed", tx_nonce));
+    }
+
+    // EIP-1559 originals are replaced with a legacy transaction priced above their fee cap
+    let old_gas_price = parse_hex_u256(&original["maxFeePerGas"])
+        .or_else(|_| parse_hex_u256(&original["gasPrice"]))
+        .context("original transaction has no gas price")?;
+    let network_price = parse_hex_u256(&rpc_call(client, rpc_url, "eth_gasPrice", json!([])).await?)?;
+    let new_gas_price = bumped_gas_price(old_gas_price, network_price, bump_percent);
+    let chain_id = parse_hex_u64(&rpc_call(client, rpc_url, "eth_chainId", json!([])).await?)?;
+
+    let tx = build_replacement(&original, new_gas_price, chain_id)?;
+    let new_hash = sign_and_send(client, rpc_url, wallet, &tx, chain_id).await?;
+
+    Ok(ReplacementResponse {
+        tx_hash: new_hash,
+        replaced_tx_hash: original["hash"].as_str().map(|h| h.to_string()),
+        nonce: tx_nonce,
+        old_gas_price: old_gas_price.to_string(),
+        new_gas_price: new_gas_price.to_string(),
+    })
+}
+
+/// Replaces the transaction pending at `nonce` with one paying `gas_price` (or the default
+/// bump when `None`), or with a 0-value self-transfer when `cancel` is set.
+///
+/// Like `speed_up_transaction`, this signs with the nonce given rather than one from the
+/// nonce manager, so the manager's counter is left untouched.
+pub async fn replace_transaction(
+    client: &Client,
+    rpc_url: &str,
+    wallet: LocalWallet,
+    nonce: u64,
+    gas_price: Option<U256>,
+    gas_limit: Option<U256>,
+    cancel: bool,
+) -> Result<ReplacementResponse> {
+    let from = wallet.address();
+
+    let confirmed = parse_hex_u64(&rpc_call(client, rpc_url, "eth_getTransactionCount", json!([from, "latest"])).await?)?;
+    if confirmed > nonce {
+        return Err(anyhow!("Nonce {} is already confirmed", nonce));
+    }
 
+    // A cancellation doesn't need the original, only its price if the node still has it
+    let original = match find_pending_by_nonce(client, rpc_url, from, nonce).await {
+        Ok(tx) => Some(tx),
+        Err(_) if cancel => None,
+        Err(e) => return Err(e),
+    };
+    let network_price = parse_hex_u256(&rpc_call(client, rpc_url, "eth_gasPrice", json!([])).await?)?;
+    let old_gas_price = match &original {
+        Some(tx) => parse_hex_u256(&tx["maxFeePerGas"])
+            .or_else(|_| parse_hex_u256(&tx["gasPrice"]))
+            .context("original transaction has no gas price")?,
+        None => network_price,
+    };
+    let new_gas_price = match gas_price {
+        Some(price) => {
+            let minimum = bumped_gas_price(old_gas_price, U256::zero(), MIN_FEE_BUMP_PERCENT);
+            if original.is_some() && price < minimum {
+                return Err(anyhow!(
+                    "gas_price {} is too low to replace the pending transaction; at least {} wei is required",
+                    price, minimum
+                ));
+            }
+            price
+        }
+        None => bumped_gas_price(old_gas_price, network_price, DEFAULT_FEE_BUMP_PERCENT),
+    };
+    let chain_id = parse_hex_u64(&rpc_call(client, rpc_url, "eth_chainId", json!([])).await?)?;
+
+    let mut tx = match (&original, cancel) {
+        (Some(tx), false) => build_replacement(tx, new_gas_price, chain_id)?,
+        _ => build_cancellation(from, nonce, new_gas_price, chain_id),
+    };
+    if let Some(gas) = gas_limit {
+        tx = tx.gas(gas);
+    }
+    let new_hash = sign_and_send(client, rpc_url, wallet, &tx, chain_id).await?;
+
+    Ok(ReplacementResponse {
+        tx_hash: new_hash,
+        replaced_tx_hash: original.as_ref().and_then(|tx| tx["hash"].as_str()).map(|h| h.to_string()),
+        nonce,
+        old_gas_price: old_gas_price.to_string(),
+        new_gas_price: new_gas_price.to_string(),
+    })
+}
 
 pub async fn send_transaction(
     config: &Config,  // Configuration containing default values
@@ -149,4 +737,697 @@ pub async fn send_transaction(
         nonce_manager
     ).await?;
     Ok(tx_response.tx_hash)
-}
\ No newline at end of file
+}
+/// Receipt of `tx_hash` via `eth_getTransactionReceipt`; `None` while the transaction is
+/// pending or unknown to the node.
+pub async fn get_transaction_receipt(client: &Client, rpc_url: &str, tx_hash: &str) -> Result<Option<Value>> {
+    let receipt = rpc_call(client, rpc_url, "eth_getTransactionReceipt", json!([tx_hash])).await?;
+    Ok((!receipt.is_null()).then_some(receipt))
+}
+
+/// Outcome of `wait_for_confirmations`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfirmationWait {
+    /// The requested confirmations were reached
+    pub met: bool,
+    /// The transaction was mined but reverted (`status` 0); waiting stops there
+    pub reverted: bool,
+    /// Block the transaction was mined in, once a receipt exists
+    pub block_number: Option<u64>,
+    /// Blocks mined on top of that block at the last poll
+    pub confirmations: u64,
+    pub receipt: Option<Value>,
+    pub polls: u32,
+    pub elapsed: Duration,
+}
+
+/// Polls the receipt of `tx_hash` and the chain head every `interval` until
+/// `head - receipt block >= confirmations` or `timeout` has passed. A reverted receipt ends
+/// the wait at once. Running out of time is reported through `met`, not as an error.
+pub async fn wait_for_confirmations(
+    client: &Client,
+    rpc_url: &str,
+    tx_hash: &str,
+    confirmations: u64,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<ConfirmationWait> {
+    let started = Instant::now();
+    let mut wait = ConfirmationWait {
+        met: false,
+        reverted: false,
+        block_number: None,
+        confirmations: 0,
+        receipt: None,
+        polls: 0,
+        elapsed: Duration::ZERO,
+    };
+    loop {
+        wait.polls += 1;
+        if let Some(receipt) = get_transaction_receipt(client, rpc_url, tx_hash).await? {
+            let mined_in = parse_hex_u64(&receipt["blockNumber"]).context("receipt has no block number")?;
+            wait.block_number = Some(mined_in);
+            wait.reverted = receipt["status"].as_str() == Some("0x0");
+            wait.receipt = Some(receipt);
+            if !wait.reverted {
+                wait.confirmations = chain::block_number(client, rpc_url).await?.saturating_sub(mined_in);
+                wait.met = wait.confirmations >= confirmations;
+            }
+        }
+        if wait.met || wait.reverted || started.elapsed() + interval > timeout {
+            wait.elapsed = started.elapsed();
+            return Ok(wait);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{mock, Matcher};
+
+    const KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn mock_rpc(path: &str, body: Value, result: Value) -> mockito::Mock {
+        mock("POST", path)
+            .match_body(Matcher::PartialJson(body))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "jsonrpc": "2.0", "id": 1, "result": result }).to_string())
+            .create()
+    }
+
+    #[test]
+    fn test_bumped_gas_price_respects_minimum_and_network_price() {
+        let gwei = U256::exp10(9);
+        assert_eq!(bumped_gas_price(gwei, U256::zero(), 12), U256::from(1_120_000_000u64));
+        // Bumps below the node minimum are raised to it
+        assert_eq!(bumped_gas_price(gwei, U256::zero(), 1), U256::from(1_100_000_000u64));
+        // A busier network wins over the bumped original
+        assert_eq!(bumped_gas_price(gwei, gwei * 3, 12), gwei * 3);
+    }
+
+    #[test]
+    fn test_cancellation_is_zero_value_self_transfer_at_nonce() {
+        let from = Address::from_low_u64_be(0xabc);
+        let tx = build_cancellation(from, 9, U256::from(2_000_000_000u64), 1);
+        assert_eq!(tx.to, Some(from.into()));
+        assert_eq!(tx.nonce, Some(U256::from(9)));
+        assert_eq!(tx.value, Some(U256::zero()));
+        assert_eq!(tx.gas, Some(U256::from(21_000)));
+        assert_eq!(tx.gas_price, Some(U256::from(2_000_000_000u64)));
+    }
+
+    #[tokio::test]
+    async fn test_send_refuses_when_balance_cannot_cover_value_and_gas() {
+        let path = "/send-insufficient-funds";
+        let url = format!("{}{}", mockito::server_url(), path);
+        let _count = mock_rpc(path, json!({"method": "eth_getTransactionCount"}), json!("0x0"));
+        let _chain = mock_rpc(path, json!({"method": "eth_chainId"}), json!("0x7a69"));
+        // 0.00002 ETH, below 1 wei + 21000 gas at 1 gwei
+        let _balance = mock_rpc(path, json!({"method": "eth_getBalance"}), json!("0x12309ce54000"));
+        let send = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_sendRawTransaction"})))
+            .with_body(json!({ "jsonrpc": "2.0", "id": 1, "result": "0xsent" }).to_string())
+            .expect(1)
+            .create();
+
+        let tx = TransactionRequest::new()
+            .to(Address::from_low_u64_be(0xdead))
+            .value(1)
+            .gas(21_000)
+            .gas_price(1_000_000_000u64);
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let nonces = NonceManager::new();
+
+        let err = send_evm_transaction(&url, wallet.clone(), tx.clone(), &nonces).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "insufficient funds: have 20000000000000 wei, need 21000000000001 wei (value 1 + gas 21000 × price 1000000000)"
+        );
+
+        let options = SendOptions { skip_balance_check: true, ..Default::default() };
+        let sent = send_evm_transaction_with_options(&url, wallet, tx, &nonces, &options).await.unwrap();
+        assert_eq!(sent.tx_hash, "0xsent");
+        send.assert();
+    }
+
+    #[tokio::test]
+    async fn test_refused_or_rejected_send_does_not_consume_nonce() {
+        let path = "/send-nonce-refused";
+        let url = format!("{}{}", mockito::server_url(), path);
+        let _count = mock_rpc(path, json!({"method": "eth_getTransactionCount"}), json!("0x0"));
+        let _chain = mock_rpc(path, json!({"method": "eth_chainId"}), json!("0x7a69"));
+        let _balance = mock_rpc(path, json!({"method": "eth_getBalance"}), json!("0x0"));
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let to = Address::from_low_u64_be(0xdead);
+        let tx = TransactionRequest::new().to(to).value(1).gas(21_000).gas_price(1_000_000_000u64);
+        let nonces = NonceManager::new();
+
+        let err = send_evm_transaction(&url, wallet.clone(), tx.clone(), &nonces).await.unwrap_err();
+        assert!(err.to_string().starts_with("insufficient funds"), "{}", err);
+
+        // The node turns the first broadcast down; the retry still uses nonce 0
+        let rejected = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_sendRawTransaction"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32000, "message": "txpool is full"}}).to_string())
+            .expect(1)
+            .create();
+        let options = SendOptions { skip_balance_check: true, ..Default::default() };
+        let err = send_evm_transaction_with_options(&url, wallet.clone(), tx.clone(), &nonces, &options).await.unwrap_err();
+        assert!(err.to_string().contains("txpool is full"), "{}", err);
+        rejected.assert();
+
+        let expected = tx.clone().from(wallet.address()).chain_id(31337u64).nonce(0);
+        let send = expect_broadcast(path, &wallet, expected.into()).await;
+        send_evm_transaction_with_options(&url, wallet, tx, &nonces, &options).await.unwrap();
+        send.assert();
+    }
+
+    #[tokio::test]
+    async fn test_send_refuses_when_node_reports_other_chain() {
+        let path = "/send-chain-mismatch";
+        let url = format!("{}{}", mockito::server_url(), path);
+        let _count = mock_rpc(path, json!({"method": "eth_getTransactionCount"}), json!("0x0"));
+        // Sepolia node configured where mainnet was expected
+        let _chain = mock_rpc(path, json!({"method": "eth_chainId"}), json!("0xaa36a7"));
+        let send = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_sendRawTransaction"})))
+            .expect(0)
+            .create();
+
+        let tx = TransactionRequest::new().to(Address::from_low_u64_be(0xdead)).value(1);
+        let options = SendOptions { expected_chain_id: Some(1), ..Default::default() };
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let nonces = NonceManager::new();
+        let err = send_evm_transaction_with_options(&url, wallet.clone(), tx, &nonces, &options)
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().starts_with("chain ID mismatch: the RPC for chain 1 reports chain ID 11155111"), "{}", err);
+        send.assert();
+        // The refused send didn't take a nonce
+        assert_eq!(nonces.get_next_nonce(wallet.address(), &url).await.unwrap(), U256::zero());
+    }
+
+    #[test]
+    fn test_buffered_gas_limit_respects_cap_and_estimate() {
+        let estimate = U256::from(100_000);
+        assert_eq!(buffered_gas_limit(estimate, 20, None), U256::from(120_000));
+        assert_eq!(buffered_gas_limit(estimate, 20, Some(U256::from(110_000))), U256::from(110_000));
+        assert_eq!(buffered_gas_limit(estimate, 20, Some(U256::from(90_000))), estimate);
+        assert_eq!(buffered_gas_limit(estimate, 0, None), estimate);
+        // Out-of-range inputs saturate instead of overflowing
+        assert_eq!(buffered_gas_limit(estimate, u64::MAX, Some(U256::from(500_000))), U256::from(500_000));
+        assert_eq!(buffered_gas_limit(U256::MAX, 20, None), U256::MAX);
+    }
+
+    #[tokio::test]
+    async fn test_estimated_gas_is_buffered_in_signed_tx() {
+        let path = "/send-gas-buffer";
+        let url = format!("{}{}", mockito::server_url(), path);
+        let _count = mock_rpc(path, json!({"method": "eth_getTransactionCount"}), json!("0x0"));
+        let _chain = mock_rpc(path, json!({"method": "eth_chainId"}), json!("0x7a69"));
+        let _estimate = mock_rpc(path, json!({"method": "eth_estimateGas"}), json!("0x186a0"));
+
+        // The same transaction signed locally with a 120000 gas limit
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let to = Address::from_low_u64_be(0xdead);
+        let expected = TransactionRequest::new()
+            .to(to)
+            .value(1)
+            .from(wallet.address())
+            .nonce(0)
+            .chain_id(31337u64)
+            .gas(120_000)
+            .gas_price(1_000_000_000u64);
+        let send = expect_broadcast(path, &wallet, expected.into()).await;
+
+        let tx = TransactionRequest::new().to(to).value(1).gas_price(1_000_000_000u64);
+        let options = SendOptions { skip_balance_check: true, gas_buffer_percent: 20, ..Default::default() };
+        let sent = send_evm_transaction_with_options(&url, wallet, tx, &NonceManager::new(), &options).await.unwrap();
+        assert_eq!(sent.tx_hash, "0xsent");
+        send.assert();
+    }
+
+    // Mocks a node on `path` with chain ID 31337, a 0x5208 gas estimate, a 1 gwei gas price,
+    // a 1 gwei base fee and a 2 gwei median tip
+    fn fee_node(path: &str) -> Vec<mockito::Mock> {
+        vec![
+            mock_rpc(path, json!({"method": "eth_getTransactionCount"}), json!("0x0")),
+            mock_rpc(path, json!({"method": "eth_chainId"}), json!("0x7a69")),
+            mock_rpc(path, json!({"method": "eth_estimateGas"}), json!("0x5208")),
+            mock_rpc(path, json!({"method": "eth_gasPrice"}), json!("0x3b9aca00")),
+            mock_rpc(path, json!({"method": "eth_getBlockByNumber"}), json!({"number": "0x1", "baseFeePerGas": "0x3b9aca00"})),
+            mock_rpc(path, json!({"method": "eth_feeHistory"}), json!({"reward": [["0x77359400"], ["0x77359400"]]})),
+        ]
+    }
+
+    // Expects `tx` signed by `wallet` to be the one broadcast on `path`
+    async fn expect_broadcast(path: &str, wallet: &LocalWallet, tx: TypedTransaction) -> mockito::Mock {
+        let signature = wallet.sign_transaction(&tx).await.unwrap();
+        let raw = format!("0x{}", hex::encode(tx.rlp_signed(&signature)));
+        mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_sendRawTransaction", "params": [raw]})))
+            .with_body(json!({ "jsonrpc": "2.0", "id": 1, "result": "0xsent" }).to_string())
+            .expect(1)
+            .create()
+    }
+
+    #[tokio::test]
+    async fn test_forced_legacy_chain_sends_type_0_transaction() {
+        let path = "/send-fee-legacy";
+        let _node = fee_node(path);
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let to = Address::from_low_u64_be(0xdead);
+        // The block has a base fee, but the chain is configured as legacy
+        let expected = TransactionRequest::new()
+            .to(to)
+            .value(1)
+            .from(wallet.address())
+            .nonce(0)
+            .chain_id(31337u64)
+            .gas(25_200)
+            .gas_price(1_000_000_000u64);
+        let send = expect_broadcast(path, &wallet, expected.into()).await;
+
+        let tx = TransactionRequest::new().to(to).value(1);
+        let options = SendOptions { skip_balance_check: true, fee_type: FeeType::Legacy, ..Default::default() };
+        let url = format!("{}{}", mockito::server_url(), path);
+        send_evm_transaction_with_options(&url, wallet, tx, &NonceManager::new(), &options).await.unwrap();
+        send.assert();
+    }
+
+    #[tokio::test]
+    async fn test_forced_eip1559_chain_sends_type_2_transaction() {
+        let path = "/send-fee-1559";
+        let _node = fee_node(path);
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let to = Address::from_low_u64_be(0xdead);
+        // Max fee 2 × 1 gwei base fee + 2 gwei tip
+        let expected = Eip1559TransactionRequest::new()
+            .to(to)
+            .value(1)
+            .from(wallet.address())
+            .nonce(0)
+            .chain_id(31337u64)
+            .gas(25_200)
+            .max_fee_per_gas(4_000_000_000u64)
+            .max_priority_fee_per_gas(2_000_000_000u64);
+        let typed: TypedTransaction = expected.into();
+        let signature = wallet.sign_transaction(&typed).await.unwrap();
+        assert!(typed.rlp_signed(&signature).starts_with(&[0x02]));
+        let send = expect_broadcast(path, &wallet, typed).await;
+
+        let tx = TransactionRequest::new().to(to).value(1);
+        let options = SendOptions { skip_balance_check: true, fee_type: FeeType::Eip1559, ..Default::default() };
+        let url = format!("{}{}", mockito::server_url(), path);
+        send_evm_transaction_with_options(&url, wallet, tx, &NonceManager::new(), &options).await.unwrap();
+        send.assert();
+    }
+
+    #[tokio::test]
+    async fn test_priority_fee_override_sets_tip_and_max_fee() {
+        let path = "/send-fee-1559-tip";
+        let _node = fee_node(path);
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let to = Address::from_low_u64_be(0xdead);
+        // Max fee 2 × 1 gwei base fee + the 3 gwei tip asked for
+        let expected = Eip1559TransactionRequest::new()
+            .to(to)
+            .value(1)
+            .from(wallet.address())
+            .nonce(0)
+            .chain_id(31337u64)
+            .gas(25_200)
+            .max_fee_per_gas(5_000_000_000u64)
+            .max_priority_fee_per_gas(3_000_000_000u64);
+        let send = expect_broadcast(path, &wallet, expected.into()).await;
+
+        let tx = TransactionRequest::new().to(to).value(1);
+        let options = SendOptions {
+            skip_balance_check: true,
+            fee_type: FeeType::Eip1559,
+            max_priority_fee_per_gas: Some(U256::from(3_000_000_000u64)),
+            ..Default::default()
+        };
+        let url = format!("{}{}", mockito::server_url(), path);
+        send_evm_transaction_with_options(&url, wallet, tx, &NonceManager::new(), &options).await.unwrap();
+        send.assert();
+    }
+
+    #[tokio::test]
+    async fn test_fee_overrides_are_refused_on_legacy_chain() {
+        let path = "/send-fee-legacy-overrides";
+        let _node = fee_node(path);
+        let send = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_sendRawTransaction"})))
+            .expect(0)
+            .create();
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let tx = TransactionRequest::new().to(Address::from_low_u64_be(0xdead)).value(1);
+        let options = SendOptions {
+            skip_balance_check: true,
+            fee_type: FeeType::Legacy,
+            max_fee_per_gas: Some(U256::from(5_000_000_000u64)),
+            ..Default::default()
+        };
+        let url = format!("{}{}", mockito::server_url(), path);
+        let err = send_evm_transaction_with_options(&url, wallet, tx, &NonceManager::new(), &options).await.unwrap_err();
+        assert!(err.to_string().contains("legacy transactions"), "{}", err);
+        send.assert();
+    }
+
+    #[tokio::test]
+    async fn test_auto_fee_type_probes_each_endpoint_once() {
+        let path = "/fee-type-auto";
+        let url = format!("{}{}", mockito::server_url(), path);
+        let probe = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_getBlockByNumber"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": {"number": "0x1", "baseFeePerGas": null}}).to_string())
+            .expect(1)
+            .create();
+        let client = Client::new();
+        let cache = FeeSupportCache::default();
+
+        for _ in 0..2 {
+            assert!(!uses_eip1559(&client, &url, FeeType::Auto, &cache).await);
+        }
+        assert!(uses_eip1559(&client, &url, FeeType::Eip1559, &cache).await);
+        probe.assert();
+    }
+
+    #[tokio::test]
+    async fn test_auto_fee_type_probe_expires() {
+        let path = "/fee-type-auto-expiry";
+        let url = format!("{}{}", mockito::server_url(), path);
+        let probe = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_getBlockByNumber"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": {"number": "0x1", "baseFeePerGas": "0x7"}}).to_string())
+            .expect(2)
+            .create();
+        let client = Client::new();
+        let cache = FeeSupportCache::new(Duration::ZERO);
+
+        for _ in 0..2 {
+            assert!(uses_eip1559(&client, &url, FeeType::Auto, &cache).await);
+        }
+        probe.assert();
+    }
+
+    #[tokio::test]
+    async fn test_config_gas_defaults_used_when_estimation_fails() {
+        let path = "/send-gas-defaults";
+        let url = format!("{}{}", mockito::server_url(), path);
+        let _count = mock_rpc(path, json!({"method": "eth_getTransactionCount"}), json!("0x0"));
+        let _chain = mock_rpc(path, json!({"method": "eth_chainId"}), json!("0x7a69"));
+        let _failing: Vec<_> = [("eth_estimateGas", -32601), ("eth_gasPrice", -32000)]
+            .into_iter()
+            .map(|(method, code)| {
+                mock("POST", path)
+                    .match_body(Matcher::PartialJson(json!({"method": method})))
+                    .with_body(json!({"jsonrpc": "2.0", "id": 1, "error": {"code": code, "message": "unavailable"}}).to_string())
+                    .create()
+            })
+            .collect();
+
+        // Default limit 300000 clamped to the 250000 cap; the default price is used as is
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let to = Address::from_low_u64_be(0xdead);
+        let expected = TransactionRequest::new()
+            .to(to)
+            .value(1)
+            .from(wallet.address())
+            .nonce(0)
+            .chain_id(31337u64)
+            .gas(250_000)
+            .gas_price(20_000_000_000u64);
+        let send = expect_broadcast(path, &wallet, expected.into()).await;
+
+        let tx = TransactionRequest::new().to(to).value(1);
+        let options = SendOptions { skip_balance_check: true, max_gas_limit: Some(U256::from(250_000)), ..Default::default() };
+        let err = send_evm_transaction_with_options(&url, wallet.clone(), tx.clone(), &NonceManager::new(), &options)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("RPC Error estimating gas"), "{}", err);
+
+        let defaults = GasDefaults { gas_limit: U256::from(300_000), gas_price: U256::from(20_000_000_000u64) };
+        let options = SendOptions { gas_defaults: Some(defaults), ..options };
+        let sent = send_evm_transaction_with_options(&url, wallet, tx, &NonceManager::new(), &options).await.unwrap();
+        assert_eq!(sent.tx_hash, "0xsent");
+        send.assert();
+    }
+
+    #[tokio::test]
+    async fn test_reverting_estimate_does_not_fall_back_to_gas_defaults() {
+        let path = "/send-gas-defaults-revert";
+        let url = format!("{}{}", mockito::server_url(), path);
+        let _count = mock_rpc(path, json!({"method": "eth_getTransactionCount"}), json!("0x0"));
+        let _chain = mock_rpc(path, json!({"method": "eth_chainId"}), json!("0x7a69"));
+        let _estimate = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_estimateGas"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "error": {"code": 3, "message": "execution reverted"}}).to_string())
+            .create();
+        let send = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_sendRawTransaction"})))
+            .with_body(json!({ "jsonrpc": "2.0", "id": 1, "result": "0xsent" }).to_string())
+            .expect(0)
+            .create();
+
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let tx = TransactionRequest::new().to(Address::from_low_u64_be(0xdead)).value(1);
+        let defaults = GasDefaults { gas_limit: U256::from(300_000), gas_price: U256::from(20_000_000_000u64) };
+        let options = SendOptions { skip_balance_check: true, gas_defaults: Some(defaults), ..Default::default() };
+        let err = send_evm_transaction_with_options(&url, wallet, tx, &NonceManager::new(), &options)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("execution reverted"), "{}", err);
+        send.assert();
+    }
+
+    #[test]
+    fn test_decode_revert_reason() {
+        let reason = format!(
+            "0x08c379a0{}",
+            hex::encode(abi::encode(&[abi::Token::String("Ownable: caller is not the owner".into())]))
+        );
+        assert_eq!(decode_revert_reason(&reason).unwrap(), "Ownable: caller is not the owner");
+        let panic = format!("0x4e487b71{}", hex::encode(abi::encode(&[abi::Token::Uint(U256::from(0x11))])));
+        assert_eq!(decode_revert_reason(&panic).unwrap(), "panic code 0x11");
+        assert_eq!(decode_revert_reason("0xdeadbeef").unwrap(), "custom error 0xdeadbeef");
+        assert_eq!(decode_revert_reason("0x"), None);
+    }
+
+    #[tokio::test]
+    async fn test_simulated_send_that_reverts_is_never_broadcast() {
+        let path = "/send-simulated-revert";
+        let url = format!("{}{}", mockito::server_url(), path);
+        let _count = mock_rpc(path, json!({"method": "eth_getTransactionCount"}), json!("0x0"));
+        let _chain = mock_rpc(path, json!({"method": "eth_chainId"}), json!("0x7a69"));
+        let revert = format!(
+            "0x08c379a0{}",
+            hex::encode(abi::encode(&[abi::Token::String("insufficient allowance".into())]))
+        );
+        let call = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call"})))
+            .with_body(
+                json!({"jsonrpc": "2.0", "id": 1, "error": {"code": 3, "message": "execution reverted", "data": revert}})
+                    .to_string(),
+            )
+            .expect(1)
+            .create();
+        let send = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_sendRawTransaction"})))
+            .expect(0)
+            .create();
+
+        let tx = TransactionRequest::new().to(Address::from_low_u64_be(0xdead)).data(vec![0xa9, 0x05, 0x9c, 0xbb]);
+        let options = SendOptions { simulate: true, ..Default::default() };
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let nonces = NonceManager::new();
+        let err = send_evm_transaction_with_options(&url, wallet.clone(), tx, &nonces, &options)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "simulation failed (eth_call): execution reverted: insufficient allowance; transaction not sent"
+        );
+        call.assert();
+        send.assert();
+        // Simulation runs before a nonce is reserved
+        assert_eq!(nonces.get_next_nonce(wallet.address(), &url).await.unwrap(), U256::zero());
+    }
+
+    #[tokio::test]
+    async fn test_speed_up_rebroadcasts_same_nonce_with_higher_gas_price() {
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let from = format!("{:?}", wallet.address());
+        let original = json!({
+            "hash": "0xoriginal",
+            "from": from,
+            "to": "0x000000000000000000000000000000000000dead",
+            "value": "0x1",
+            "input": "0x",
+            "gas": "0x5208",
+            "gasPrice": "0x3b9aca00",
+            "nonce": "0x7",
+            "blockNumber": null
+        });
+
+        // The replacement we expect to see broadcast: nonce 7, 1 gwei bumped by 12%
+        let expected_price = U256::from(1_120_000_000u64);
+        let expected = build_replacement(&original, expected_price, 1).unwrap();
+        assert_eq!(expected.nonce, Some(U256::from(7)));
+        let signature = wallet.clone().with_chain_id(1u64).sign_transaction(&expected.clone().into()).await.unwrap();
+        let expected_raw = format!("0x{}", hex::encode(expected.rlp_signed(&signature)));
+
+        let path = "/rbf";
+        let _tx = mock_rpc(path, json!({"method": "eth_getTransactionByHash"}), original);
+        let _count = mock_rpc(path, json!({"method": "eth_getTransactionCount"}), json!("0x7"));
+        let _price = mock_rpc(path, json!({"method": "eth_gasPrice"}), json!("0x3b9aca00"));
+        let _chain = mock_rpc(path, json!({"method": "eth_chainId"}), json!("0x1"));
+        let send = mock_rpc(
+            path,
+            json!({"method": "eth_sendRawTransaction", "params": [expected_raw]}),
+            json!("0xreplacement"),
+        );
+
+        let url = format!("{}{}", mockito::server_url(), path);
+        let out = speed_up_transaction(&Client::new(), &url, wallet, Some("0xoriginal"), None, DEFAULT_FEE_BUMP_PERCENT)
+            .await
+            .unwrap();
+
+        send.assert();
+        assert_eq!(out.tx_hash, "0xreplacement");
+        assert_eq!(out.replaced_tx_hash.as_deref(), Some("0xoriginal"));
+        assert_eq!(out.nonce, 7);
+        assert_eq!(out.old_gas_price, "1000000000");
+        assert_eq!(out.new_gas_price, expected_price.to_string());
+    }
+
+    fn stuck_tx(from: &str) -> Value {
+        json!({
+            "hash": "0xstuck",
+            "from": from,
+            "to": "0x000000000000000000000000000000000000dead",
+            "value": "0x1",
+            "input": "0x",
+            "gas": "0x5208",
+            "gasPrice": "0x3b9aca00",
+            "nonce": "0x7",
+            "blockNumber": null
+        })
+    }
+
+    fn pending_pool(path: &str, from: &str) -> Vec<mockito::Mock> {
+        vec![
+            mock_rpc(path, json!({"method": "eth_getTransactionCount"}), json!("0x7")),
+            mock_rpc(path, json!({"method": "eth_getBlockByNumber"}), json!({"transactions": [stuck_tx(from)]})),
+            mock_rpc(path, json!({"method": "eth_gasPrice"}), json!("0x3b9aca00")),
+            mock_rpc(path, json!({"method": "eth_chainId"}), json!("0x1")),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_replace_transaction_uses_given_nonce_and_gas_price() {
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let from = format!("{:?}", wallet.address());
+        let path = "/replace";
+        let _mocks = pending_pool(path, &from);
+
+        // The broadcast must carry the stuck nonce 7 and the requested 2 gwei
+        let price = U256::from(2_000_000_000u64);
+        let expected = build_replacement(&stuck_tx(&from), price, 1).unwrap();
+        assert_eq!(expected.nonce, Some(U256::from(7)));
+        assert_eq!(expected.gas_price, Some(price));
+        let signature = wallet.clone().with_chain_id(1u64).sign_transaction(&expected.clone().into()).await.unwrap();
+        let expected_raw = format!("0x{}", hex::encode(expected.rlp_signed(&signature)));
+        let send = mock_rpc(
+            path,
+            json!({"method": "eth_sendRawTransaction", "params": [expected_raw]}),
+            json!("0xreplaced"),
+        );
+
+        let url = format!("{}{}", mockito::server_url(), path);
+        let out = replace_transaction(&Client::new(), &url, wallet, 7, Some(price), None, false).await.unwrap();
+
+        send.assert();
+        assert_eq!(out.tx_hash, "0xreplaced");
+        assert_eq!(out.replaced_tx_hash.as_deref(), Some("0xstuck"));
+        assert_eq!(out.nonce, 7);
+        assert_eq!(out.old_gas_price, "1000000000");
+        assert_eq!(out.new_gas_price, price.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_replace_transaction_rejects_insufficient_gas_price() {
+        let wallet = LocalWallet::from_str(KEY).unwrap();
+        let from = format!("{:?}", wallet.address());
+        let path = "/replace-low";
+        let _mocks = pending_pool(path, &from);
+
+        let url = format!("{}{}", mockito::server_url(), path);
+        // 1.05 gwei is below the 10% bump over the pending 1 gwei
+        let err = replace_transaction(&Client::new(), &url, wallet, 7, Some(U256::from(1_050_000_000u64)), None, false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("too low"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_confirmations_follows_the_chain_head() {
+        let path = "/wait-for-confirmations";
+        let hash = "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060";
+        let _receipt = mock_rpc(
+            path,
+            json!({"method": "eth_getTransactionReceipt", "params": [hash]}),
+            json!({"transactionHash": hash, "blockNumber": "0x10", "status": "0x1"}),
+        );
+        // Mocks with hits still expected are served first, in creation order
+        let head = |n: &str| {
+            mock("POST", path)
+                .match_body(Matcher::PartialJson(json!({"method": "eth_blockNumber"})))
+                .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": n}).to_string())
+                .expect(1)
+                .create()
+        };
+        let heads = [head("0x10"), head("0x11"), head("0x12")];
+        let rpc = format!("{}{}", mockito::server_url(), path);
+
+        let wait = wait_for_confirmations(&Client::new(), &rpc, hash, 2, Duration::from_secs(5), Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert!(wait.met);
+        assert!(!wait.reverted);
+        assert_eq!(wait.block_number, Some(16));
+        assert_eq!(wait.confirmations, 2);
+        assert_eq!(wait.polls, 3);
+        for m in heads {
+            m.assert();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_confirmations_stops_on_revert() {
+        let path = "/wait-for-confirmations-reverted";
+        let _receipt = mock_rpc(
+            path,
+            json!({"method": "eth_getTransactionReceipt"}),
+            json!({"blockNumber": "0x10", "status": "0x0"}),
+        );
+        let head = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_blockNumber"})))
+            .expect(0)
+            .create();
+        let rpc = format!("{}{}", mockito::server_url(), path);
+
+        let wait = wait_for_confirmations(&Client::new(), &rpc, "0x01", 12, Duration::from_secs(5), Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert!(wait.reverted);
+        assert!(!wait.met);
+        assert_eq!(wait.polls, 1);
+        head.assert();
+    }
+}
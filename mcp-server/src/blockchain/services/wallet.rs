@@ -23,6 +23,32 @@ fn normalize_address(address: &str) -> String {
     address.trim_start_matches("0x").to_lowercase()
 }
 
+/// Mnemonic length used when the caller doesn't ask for one
+pub const DEFAULT_WORD_COUNT: usize = 12;
+
+/// BIP-39 entropy size in bytes for a mnemonic of `word_count` words
+/// (every 3 words encode 32 bits of entropy plus a 1-bit checksum)
+pub fn entropy_len_for_word_count(word_count: usize) -> Result<usize, WalletGenerationError> {
+    match word_count {
+        12 | 15 | 18 | 21 | 24 => Ok(word_count / 3 * 4),
+        other => Err(WalletGenerationError::InvalidWordCount(other)),
+    }
+}
+
+/// Checks that `private_key` is a usable secp256k1 secret key: exactly 32 bytes,
+/// non-zero and below the curve order. Returns a message suitable for users on failure.
+pub fn validate_private_key(private_key: &[u8]) -> Result<(), String> {
+    if private_key.len() != 32 {
+        return Err(format!("Private key must be 32 bytes, got {}", private_key.len()));
+    }
+    if private_key.iter().all(|b| *b == 0) {
+        return Err("Private key must not be zero".to_string());
+    }
+    SigningKey::from_slice(private_key)
+        .map(|_| ())
+        .map_err(|_| "Private key must be less than the secp256k1 curve order".to_string())
+}
+
 /// Wallet manager for EVM-compatible wallets
 #[derive(Debug, Clone)]
 pub struct EvmWalletManager;
@@ -35,17 +61,24 @@ impl EvmWalletManager {
 
     /// Generate a new EVM wallet with a mnemonic phrase
     pub fn generate_wallet(&self) -> Result<WalletResponse, WalletGenerationError> {
+        self.generate_wallet_with_options(DEFAULT_WORD_COUNT, "")
+    }
+
+    /// Generate a new EVM wallet whose mnemonic has `word_count` words (12, 15, 18, 21 or 24).
+    /// A non-empty BIP-39 `passphrase` changes the derived keys; the same passphrase is
+    /// needed to re-derive the wallet from its mnemonic later.
+    pub fn generate_wallet_with_options(&self, word_count: usize, passphrase: &str) -> Result<WalletResponse, WalletGenerationError> {
         info!("Generating new EVM wallet");
         
-        // Generate a new mnemonic phrase (12 words)
-        let mut entropy = [0u8; 16];
+        // Generate a new mnemonic phrase of the requested length
+        let mut entropy = vec![0u8; entropy_len_for_word_count(word_count)?];
         rand::thread_rng().fill_bytes(&mut entropy);
         let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy)
             .map_err(|e| WalletGenerationError::KeyGenerationFailed(e.to_string()))?;
         let phrase = mnemonic.to_string();
         
         // Derive the private key from the mnemonic (using the first account)
-        let seed = mnemonic.to_seed("");
+        let seed = mnemonic.to_seed(passphrase);
         let private_key = self.derive_private_key(&seed[..32])?;
         
         // Derive the address from the private key
@@ -64,6 +97,12 @@ impl EvmWalletManager {
 
     /// Import a wallet from a mnemonic phrase or private key
     pub fn import_wallet(&self, input: &str) -> Result<WalletResponse, ImportWalletError> {
+        self.import_wallet_with_passphrase(input, "")
+    }
+
+    /// Import a wallet from a mnemonic phrase or private key. `passphrase` is the optional
+    /// BIP-39 passphrase ("25th word") and is ignored for private keys.
+    pub fn import_wallet_with_passphrase(&self, input: &str, passphrase: &str) -> Result<WalletResponse, ImportWalletError> {
         info!("Attempting to import wallet");
         
         // Try to parse as private key first (0x-prefixed hex string)
@@ -75,7 +114,7 @@ impl EvmWalletManager {
         
         // Then try as mnemonic (space-separated words)
         if input.split_whitespace().count() >= 12 {
-            if let Ok(wallet) = self.import_mnemonic(input) {
+            if let Ok(wallet) = self.import_mnemonic(input, passphrase) {
                 return Ok(wallet);
             }
         }
@@ -94,11 +133,7 @@ impl EvmWalletManager {
         let private_key_bytes = hex::decode(private_key_hex)
             .map_err(|_| ImportWalletError::InvalidPrivateKey("Invalid hex format".to_string()))?;
             
-        if private_key_bytes.len() != 32 {
-            return Err(ImportWalletError::InvalidPrivateKey(
-                "Private key must be 32 bytes".to_string(),
-            ));
-        }
+        validate_private_key(&private_key_bytes).map_err(ImportWalletError::InvalidPrivateKey)?;
         
         // Convert to fixed-size array
         let mut private_key = [0u8; 32];
@@ -122,13 +157,13 @@ impl EvmWalletManager {
     }
     
     /// Import a wallet from a mnemonic phrase
-    fn import_mnemonic(&self, mnemonic_phrase: &str) -> Result<WalletResponse, ImportWalletError> {
+    fn import_mnemonic(&self, mnemonic_phrase: &str, passphrase: &str) -> Result<WalletResponse, ImportWalletError> {
         // Parse the mnemonic using the new bip39 2.0 API
         let mnemonic = Mnemonic::parse_in_normalized(Language::English, mnemonic_phrase)
             .map_err(|e| ImportWalletError::InvalidMnemonic(format!("Invalid mnemonic phrase: {}", e)))?;
         
         // Derive the private key from the mnemonic (using the first account)
-        let seed = mnemonic.to_seed("");
+        let seed = mnemonic.to_seed(passphrase);
         let private_key = self.derive_private_key(&seed[..32])
             .map_err(|e| ImportWalletError::InvalidMnemonic(e.to_string()))?;
         
@@ -219,12 +254,25 @@ pub fn create_wallet() -> Result<WalletResponse, WalletGenerationError> {
     manager.generate_wallet()
 }
 
+/// Create a new EVM wallet with a mnemonic of `word_count` words, protected by an
+/// optional BIP-39 passphrase (empty for none)
+pub fn create_wallet_with_options(word_count: usize, passphrase: &str) -> Result<WalletResponse, WalletGenerationError> {
+    let manager = EvmWalletManager::new();
+    manager.generate_wallet_with_options(word_count, passphrase)
+}
+
 /// Import a wallet from mnemonic or private key
 pub fn import_wallet(input: &str) -> Result<WalletResponse, ImportWalletError> {
     let manager = EvmWalletManager::new();
     manager.import_wallet(input)
 }
 
+/// Import a wallet from mnemonic (with an optional BIP-39 passphrase) or private key
+pub fn import_wallet_with_passphrase(input: &str, passphrase: &str) -> Result<WalletResponse, ImportWalletError> {
+    let manager = EvmWalletManager::new();
+    manager.import_wallet_with_passphrase(input, passphrase)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -250,6 +298,58 @@ mod tests {
         assert!(manager.validate_address(&wallet.address));
     }
     
+    #[test]
+    fn test_wallet_creation_with_24_words() {
+        let manager = EvmWalletManager::new();
+        let wallet = manager.generate_wallet_with_options(24, "").unwrap();
+        let phrase = wallet.mnemonic.clone().unwrap();
+        assert_eq!(phrase.split_whitespace().count(), 24);
+        assert!(Mnemonic::parse_in_normalized(Language::English, &phrase).is_ok());
+        assert!(manager.validate_address(&wallet.address));
+
+        for (words, bytes) in [(12, 16), (15, 20), (18, 24), (21, 28), (24, 32)] {
+            assert_eq!(entropy_len_for_word_count(words).unwrap(), bytes);
+        }
+        assert!(matches!(
+            manager.generate_wallet_with_options(13, ""),
+            Err(WalletGenerationError::InvalidWordCount(13))
+        ));
+    }
+
+    #[test]
+    fn test_mnemonic_passphrase_changes_derived_address() {
+        let manager = EvmWalletManager::new();
+        let mnemonic = "test test test test test test test test test test test junk";
+        let plain = manager.import_wallet_with_passphrase(mnemonic, "").unwrap();
+        let protected = manager.import_wallet_with_passphrase(mnemonic, "correct horse").unwrap();
+        assert_ne!(plain.address, protected.address);
+        assert_eq!(plain.address, manager.import_wallet(mnemonic).unwrap().address);
+        // Re-deriving with the same passphrase gives the same wallet
+        assert_eq!(protected.address, manager.import_wallet_with_passphrase(mnemonic, "correct horse").unwrap().address);
+
+        // Generated wallets re-derive only with their passphrase
+        let generated = manager.generate_wallet_with_options(12, "secret").unwrap();
+        let phrase = generated.mnemonic.clone().unwrap();
+        assert_eq!(manager.import_wallet_with_passphrase(&phrase, "secret").unwrap().address, generated.address);
+        assert_ne!(manager.import_wallet(&phrase).unwrap().address, generated.address);
+    }
+
+    const CURVE_ORDER: &str = "0xfffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+
+    #[test]
+    fn test_import_rejects_zero_and_out_of_range_keys() {
+        let manager = EvmWalletManager::new();
+        let zero = format!("0x{}", "0".repeat(64));
+        let err = manager.import_private_key(&zero).unwrap_err();
+        assert!(err.to_string().contains("must not be zero"), "{}", err);
+        let err = manager.import_private_key(CURVE_ORDER).unwrap_err();
+        assert!(err.to_string().contains("curve order"), "{}", err);
+
+        // n - 1 is the largest valid key
+        let max = "0xfffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140";
+        assert!(manager.import_private_key(max).is_ok());
+    }
+
     #[test]
     fn test_wallet_import_private_key() {
         let manager = EvmWalletManager::new();
@@ -267,7 +367,7 @@ mod tests {
         let manager = EvmWalletManager::new();
         let mnemonic = "test test test test test test test test test test test junk";
         
-        let wallet = manager.import_mnemonic(mnemonic).unwrap();
+        let wallet = manager.import_mnemonic(mnemonic, "").unwrap();
         
         // Should derive the expected address for this mnemonic (first account)
         assert_eq!(wallet.address, "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266");
@@ -10,12 +10,104 @@ use ethers::{
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tracing::{info, error};
+use zeroize::Zeroizing;
 
 use crate::{
-    blockchain::models::WalletResponse,
-    mcp::wallet_storage::{WalletStorage, StoredWallet},
+    blockchain::{models::WalletResponse, services::wallet::validate_private_key},
+    mcp::wallet_storage::{update_blocking, WalletStorage, StoredWallet},
 };
 
+/// Largest number of accounts `derive_accounts` returns in one call
+pub const MAX_DERIVED_ACCOUNTS: u32 = 50;
+
+/// An account derived from a mnemonic; deliberately carries no key material
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DerivedAccount {
+    pub index: u32,
+    pub path: String,
+    pub address: String,
+}
+
+/// Derive `count` accounts from `mnemonic_phrase` along the BIP-44 Ethereum path
+/// `m/44'/60'/0'/0/{index}`, starting at `start_index`.
+pub fn derive_accounts(mnemonic_phrase: &str, start_index: u32, count: u32) -> Result<Vec<DerivedAccount>> {
+    Mnemonic::parse_in_normalized(Language::English, mnemonic_phrase)
+        .map_err(|e| anyhow!("Invalid mnemonic phrase: {}", e))?;
+    if count == 0 || count > MAX_DERIVED_ACCOUNTS {
+        return Err(anyhow!("count must be between 1 and {}", MAX_DERIVED_ACCOUNTS));
+    }
+    let end = start_index
+        .checked_add(count)
+        .ok_or_else(|| anyhow!("start_index {} is too large", start_index))?;
+
+    (start_index..end)
+        .map(|index| {
+            let path = format!("m/44'/60'/0'/0/{}", index);
+            let wallet = mnemonic_wallet(mnemonic_phrase, &path, "")?;
+            Ok(DerivedAccount {
+                index,
+                path,
+                address: ethers::utils::to_checksum(&wallet.address(), None),
+            })
+        })
+        .collect()
+}
+
+/// Path `address_from_key` derives along when given a mnemonic without one
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// The wallet at `path` of `mnemonic_phrase`, salted with the BIP-39 `passphrase` (empty for none)
+fn mnemonic_wallet(mnemonic_phrase: &str, path: &str, passphrase: &str) -> Result<LocalWallet> {
+    ethers_signers::MnemonicBuilder::<ethers_signers::coins_bip39::English>::default()
+        .phrase(mnemonic_phrase)
+        .password(passphrase)
+        .derivation_path(path)
+        .map_err(|_| anyhow!("Invalid derivation path '{}'", path))?
+        .build()
+        .map_err(|e| anyhow!("Failed to derive wallet: {}", e))
+}
+
+/// The account a private key or mnemonic controls; deliberately carries no key material
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct KeyAddress {
+    pub address: String,
+    /// `"private_key"` or `"mnemonic"`
+    pub key_type: &'static str,
+    /// Derivation path used, for mnemonics only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// The checksummed address for a 0x-hex private key, or for a BIP-39 mnemonic (with an
+/// optional `passphrase`) along `derivation_path` (default `DEFAULT_DERIVATION_PATH`).
+/// Errors never echo the input.
+pub fn address_from_key(input: &str, derivation_path: Option<&str>, passphrase: &str) -> Result<KeyAddress> {
+    let input = input.trim();
+    if input.split_whitespace().count() == 1 {
+        if derivation_path.is_some() || !passphrase.is_empty() {
+            return Err(anyhow!("derivation_path and passphrase only apply to mnemonics"));
+        }
+        let bytes = hex::decode(input.trim_start_matches("0x"))
+            .map_err(|_| anyhow!("Private key must be 32 bytes of hex"))?;
+        validate_private_key(&bytes).map_err(|e| anyhow!(e))?;
+        let wallet = LocalWallet::from_bytes(&bytes).map_err(|_| anyhow!("Private key is not a valid secp256k1 key"))?;
+        return Ok(KeyAddress {
+            address: ethers::utils::to_checksum(&wallet.address(), None),
+            key_type: "private_key",
+            path: None,
+        });
+    }
+
+    Mnemonic::parse_in_normalized(Language::English, input).map_err(|e| anyhow!("Invalid mnemonic phrase: {}", e))?;
+    let path = derivation_path.unwrap_or(DEFAULT_DERIVATION_PATH);
+    let wallet = mnemonic_wallet(input, path, passphrase)?;
+    Ok(KeyAddress {
+        address: ethers::utils::to_checksum(&wallet.address(), None),
+        key_type: "mnemonic",
+        path: Some(path.to_string()),
+    })
+}
+
 /// Manages EVM wallet operations
 #[derive(Clone)]
 pub struct WalletManager {
@@ -31,6 +123,17 @@ impl WalletManager {
         }
     }
 
+    /// Saves a wallet off the async workers, since the write waits on the storage file lock
+    async fn add_to_storage(&self, name: &str, private_key: &str, address: String, master_password: &str) -> Result<()> {
+        let name = name.to_string();
+        let private_key = Zeroizing::new(private_key.to_string());
+        let master_password = Zeroizing::new(master_password.to_string());
+        update_blocking(&self.storage, move |storage| {
+            storage.add_wallet(name, &private_key, address, &master_password)
+        })
+        .await
+    }
+
     /// Generate a new EVM wallet with a mnemonic and save it to storage
     pub async fn generate_wallet(&self, name: &str, master_password: &str) -> Result<WalletResponse> {
         // Generate a new mnemonic phrase
@@ -43,13 +146,7 @@ impl WalletManager {
         let wallet = self.derive_wallet_from_mnemonic(&mnemonic_phrase).await?;
         
         // Save to storage
-        let mut storage = self.storage.lock().await;
-        storage.add_wallet(
-            name.to_string(),
-            &wallet.private_key,
-            wallet.address.clone(),
-            master_password,
-        )?;
+        self.add_to_storage(name, &wallet.private_key, wallet.address.clone(), master_password).await?;
         
         info!("Generated new wallet: {} ({})", name, wallet.address);
         
@@ -97,13 +194,7 @@ impl WalletManager {
         let address_str = format!("0x{:x}", address);
         
         // Save to storage
-        let mut storage = self.storage.lock().await;
-        storage.add_wallet(
-            name.to_string(),
-            private_key,
-            address_str.clone(),
-            master_password,
-        )?;
+        self.add_to_storage(name, private_key, address_str.clone(), master_password).await?;
         
         info!("Imported wallet from private key: {}", name);
         
@@ -127,13 +218,7 @@ impl WalletManager {
         let wallet = self.derive_wallet_from_mnemonic(mnemonic_phrase).await?;
         
         // Save to storage
-        let mut storage = self.storage.lock().await;
-        storage.add_wallet(
-            name.to_string(),
-            &wallet.private_key,
-            wallet.address.clone(),
-            master_password,
-        )?;
+        self.add_to_storage(name, &wallet.private_key, wallet.address.clone(), master_password).await?;
         
         info!("Imported wallet from mnemonic: {}", name);
         
@@ -153,12 +238,7 @@ impl WalletManager {
             .map_err(|e| anyhow!("Invalid mnemonic phrase: {}", e))?;
         
         // Derive the wallet using the default Ethereum derivation path
-        let path = "m/44'/60'/0'/0/0";
-        let wallet = ethers_signers::MnemonicBuilder::<ethers_signers::coins_bip39::English>::default()
-            .phrase(mnemonic_phrase)
-            .derivation_path(path)?
-            .build()
-            .map_err(|e| anyhow!("Failed to derive wallet: {}", e))?;
+        let wallet = mnemonic_wallet(mnemonic_phrase, DEFAULT_DERIVATION_PATH, "")?;
         
         // Get the private key and address
         let private_key = format!("0x{:x}", wallet.signer().to_bytes());
@@ -185,7 +265,7 @@ impl WalletManager {
         Ok(WalletResponse {
             name: name.to_string(),
             address: wallet.public_address.clone(),
-            private_key,
+            private_key: private_key.to_string(),
             mnemonic: None, // For security, we don't store the mnemonic after initial import
             created_at: Some(wallet.created_at),
         })
@@ -207,10 +287,83 @@ impl WalletManager {
         let wallet = self.get_wallet(name, master_password).await?;
         
         // Now remove the wallet
-        let mut storage = self.storage.lock().await;
-        storage.remove_wallet(name, master_password)?;
+        let name = name.to_string();
+        let master_password = Zeroizing::new(master_password.to_string());
+        update_blocking(&self.storage, move |storage| storage.remove_wallet(&name, &master_password)).await?;
         
         // Return the wallet details that were removed
         Ok(wallet)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_accounts_from_test_mnemonic() {
+        let mnemonic = "test test test test test test test test test test test junk";
+        let accounts = derive_accounts(mnemonic, 0, 5).unwrap();
+        let addresses: Vec<&str> = accounts.iter().map(|a| a.address.as_str()).collect();
+        assert_eq!(
+            addresses,
+            vec![
+                "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+                "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+                "0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC",
+                "0x90F79bf6EB2c4f870365E785982E1f101E93b906",
+                "0x15d34AAf54267DB7D7c367839AAf71A00a2C6A65",
+            ]
+        );
+        assert_eq!(accounts[4].path, "m/44'/60'/0'/0/4");
+
+        let offset = derive_accounts(mnemonic, 3, 1).unwrap();
+        assert_eq!(offset[0].index, 3);
+        assert_eq!(offset[0].address, accounts[3].address);
+    }
+
+    #[test]
+    fn test_derive_accounts_rejects_invalid_input() {
+        let err = derive_accounts("not a real mnemonic phrase", 0, 5).unwrap_err();
+        assert!(err.to_string().contains("Invalid mnemonic phrase"));
+        let mnemonic = "test test test test test test test test test test test junk";
+        assert!(derive_accounts(mnemonic, 0, 0).is_err());
+        assert!(derive_accounts(mnemonic, 0, MAX_DERIVED_ACCOUNTS + 1).is_err());
+    }
+
+    #[test]
+    fn test_address_from_key() {
+        // First Hardhat/Anvil account
+        let key = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+        let from_key = address_from_key(key, None, "").unwrap();
+        assert_eq!(from_key.address, "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        assert_eq!(from_key.key_type, "private_key");
+        assert_eq!(address_from_key(key.trim_start_matches("0x"), None, "").unwrap().address, from_key.address);
+
+        let mnemonic = "test test test test test test test test test test test junk";
+        let first = address_from_key(mnemonic, None, "").unwrap();
+        assert_eq!(first.address, from_key.address);
+        assert_eq!(first.path.as_deref(), Some(DEFAULT_DERIVATION_PATH));
+        let second = address_from_key(mnemonic, Some("m/44'/60'/0'/0/1"), "").unwrap();
+        assert_eq!(second.address, "0x70997970C51812dc3A010C7d01b50e0d17dc79C8");
+
+        // A BIP-39 passphrase salts the seed, so the same words control another account
+        let salted = address_from_key(mnemonic, None, "correct horse").unwrap();
+        assert_ne!(salted.address, first.address);
+        assert_eq!(address_from_key(mnemonic, None, "correct horse").unwrap().address, salted.address);
+    }
+
+    #[test]
+    fn test_address_from_key_rejects_bad_input_without_echoing_it() {
+        let short = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff";
+        let err = address_from_key(short, None, "").unwrap_err().to_string();
+        assert!(!err.contains(short.trim_start_matches("0x")), "{}", err);
+        assert!(address_from_key(&"0".repeat(64), None, "").is_err());
+        let key = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+        assert!(address_from_key(key, Some(DEFAULT_DERIVATION_PATH), "").is_err());
+        assert!(address_from_key(key, None, "correct horse").is_err());
+        assert!(address_from_key("test test test test test test test test test test test test", None, "").is_err());
+        let mnemonic = "test test test test test test test test test test test junk";
+        assert!(address_from_key(mnemonic, Some("not/a/path"), "").is_err());
+    }
+}
@@ -1,12 +1,18 @@
 // src/config.rs
 
-use anyhow::{Context, Result};
-use serde::Deserialize;
+use crate::blockchain::chains::{self, NativeCurrency};
+use anyhow::{bail, Context, Result};
+use ethers_core::types::{Address, U256};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
 use std::fs;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 use tracing::{info, warn};
+use url::Url;
 
 #[derive(Deserialize)]
 struct McpConfig {
@@ -18,11 +24,97 @@ struct McpServerConfig {
     env: HashMap<String, String>,
 }
 
+/// Native currency as reported in balance responses. Known chains come with one;
+/// custom chains can set theirs via NATIVE_CURRENCIES.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NativeCurrencyConfig {
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+}
+
+impl From<NativeCurrency> for NativeCurrencyConfig {
+    fn from(c: NativeCurrency) -> Self {
+        NativeCurrencyConfig {
+            symbol: c.symbol.to_string(),
+            name: c.name.to_string(),
+            decimals: c.decimals,
+        }
+    }
+}
+
+/// Which transaction envelope a chain takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeType {
+    /// Type-0 transactions priced by `gasPrice`, for chains without EIP-1559
+    Legacy,
+    /// Type-2 transactions with a max fee and priority fee
+    Eip1559,
+    /// EIP-1559 if the latest block has a `baseFeePerGas`, else legacy
+    #[default]
+    Auto,
+}
+
+/// Interface the HTTP server listens on when BIND_ADDRESS is unset: all of them, so
+/// container and cloud platforms (Render, Fly, Railway) can reach the port.
+pub const DEFAULT_BIND_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+/// Delay between balance reads in `wait_for_balance` when BALANCE_POLL_INTERVAL_SECS is unset.
+pub const DEFAULT_BALANCE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Delay between receipt checks in `wait_for_confirmations` when RECEIPT_POLL_INTERVAL_SECS is unset.
+pub const DEFAULT_RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Default cap on one MCP request line (4 MiB) when MCP_MAX_LINE_BYTES is unset.
+pub const DEFAULT_MAX_LINE_BYTES: usize = 4 * 1024 * 1024;
+
+/// Gas estimate headroom when GAS_LIMIT_BUFFER_PCT is unset.
+pub const DEFAULT_GAS_LIMIT_BUFFER_PCT: u64 = 20;
+
+/// Largest GAS_LIMIT_BUFFER_PCT accepted; more headroom than this is a typo, not a margin.
+pub const MAX_GAS_LIMIT_BUFFER_PCT: u64 = 1000;
+
+/// Routers `get_amounts_out` quotes with when DEX_ROUTERS names none for the chain
+/// (Uniswap V2 Router02 on Ethereum mainnet).
+pub const DEFAULT_DEX_ROUTERS: &[(&str, &str)] = &[("1", "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D")];
+
+/// Tool call time limit when TOOL_TIMEOUT_SECS is unset.
+pub const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(60);
+
 // A struct to hold all configuration, loaded once at startup from the .env file.
 #[derive(Clone, Debug, Default)]
 pub struct Config {
     // Server settings
     pub port: u16,
+    /// Interface to listen on (BIND_ADDRESS, default `DEFAULT_BIND_ADDRESS`)
+    pub bind_address: Option<IpAddr>,
+    /// Browser origins allowed to call the HTTP API (CORS_ALLOWED_ORIGINS, comma separated)
+    pub cors_allowed_origins: Vec<String>,
+    /// Methods allowed cross-origin (CORS_ALLOWED_METHODS, default `GET,POST`)
+    pub cors_allowed_methods: Vec<String>,
+    /// Request headers allowed cross-origin (CORS_ALLOWED_HEADERS, default `content-type`)
+    pub cors_allowed_headers: Vec<String>,
+    /// Allow any origin, method and header instead (CORS_PERMISSIVE, default false)
+    pub cors_permissive: bool,
+    /// Bearer token required on every /api route except /health (API_KEY); unset disables the check
+    pub api_key: Option<String>,
+    /// Upper bound on a single tool call (TOOL_TIMEOUT_SECS, default `DEFAULT_TOOL_TIMEOUT`)
+    pub tool_timeout: Option<Duration>,
+    /// Delay between balance reads in `wait_for_balance` (BALANCE_POLL_INTERVAL_SECS)
+    pub balance_poll_interval: Option<Duration>,
+    /// Delay between receipt checks in `wait_for_confirmations` (RECEIPT_POLL_INTERVAL_SECS)
+    pub receipt_poll_interval: Option<Duration>,
+    /// Longest MCP request line accepted on stdin, in bytes (MCP_MAX_LINE_BYTES)
+    pub max_line_bytes: Option<usize>,
+    /// Report failed tool runs as results flagged `isError` instead of JSON-RPC errors
+    /// (TOOL_ERRORS_AS_RESULTS, default false)
+    pub tool_errors_as_results: bool,
+    /// Requests per minute allowed from one client IP on /api routes except /health
+    /// (HTTP_RATE_LIMIT); unset or 0 disables the limit
+    pub http_rate_limit: Option<u32>,
+    /// Serve Prometheus metrics on /metrics, behind API_KEY when set (METRICS_ENABLED, default false)
+    pub metrics_enabled: bool,
 
     /// Blockchain settings for supported EVM-compatible networks
     /// Supported chains:
@@ -33,15 +125,44 @@ pub struct Config {
     pub chain_rpc_urls: HashMap<String, String>,
     pub websocket_url: String,
     pub default_chain_id: u64,
+    /// Check at startup that each RPC answers `eth_chainId` with its configured ID (VALIDATE_RPC_ON_START)
+    pub validate_rpc_on_start: bool,
+    /// Sign even when the RPC's `eth_chainId` differs from the chain the send was requested
+    /// for (ALLOW_CHAIN_ID_MISMATCH, default false)
+    pub allow_chain_id_mismatch: bool,
+    /// Tip lag in seconds after which `get_chain_tip_health` reports a stalled RPC
+    pub chain_tip_max_lag_secs: Option<u64>,
+    /// Per-chain native currency overrides, keyed by chain ID (NATIVE_CURRENCIES)
+    pub native_currencies: HashMap<String, NativeCurrencyConfig>,
 
     // Wallet settings
     pub master_password: String,
     pub wallet_storage_path: Option<String>,
+    /// Refuse `export_private_key` (set by ALLOW_KEY_EXPORT=false). Stored inverted so
+    /// that `Config::default()` allows export just as an unset ALLOW_KEY_EXPORT does.
+    pub key_export_disabled: bool,
+    /// Keep `create_wallet`/`import_wallet` from returning private keys and mnemonics (SUPPRESS_KEY_OUTPUT, default false)
+    pub suppress_key_output: bool,
 
     // Transaction settings
     pub default_gas_limit: u64,
     pub default_gas_price: u64,
+    /// Fall back on `default_gas_limit`/`default_gas_price` when the node can't estimate
+    /// them, instead of failing the send (USE_CONFIG_GAS_DEFAULTS, default false)
+    pub use_config_gas_defaults: bool,
     pub tx_private_key: Option<String>,
+    /// Upper bound in wei for a single `transfer_from_wallet` (MAX_TRANSFER_AMOUNT_WEI)
+    pub max_transfer_amount_wei: Option<U256>,
+    /// Percent added to `eth_estimateGas` results before sending
+    /// (GAS_LIMIT_BUFFER_PCT, default `DEFAULT_GAS_LIMIT_BUFFER_PCT`)
+    pub gas_limit_buffer_pct: Option<u64>,
+    /// Per-chain ceiling for buffered gas limits, keyed by chain ID (MAX_GAS_LIMITS)
+    pub max_gas_limits: HashMap<String, u64>,
+    /// Transaction type per chain ID, `legacy`, `eip1559` or `auto` (CHAIN_FEE_TYPES).
+    /// Chains not listed are `auto`.
+    pub chain_fee_types: HashMap<String, FeeType>,
+    /// UniswapV2-style router per chain ID for `get_amounts_out` (DEX_ROUTERS)
+    pub dex_routers: HashMap<String, String>,
 
     // External services
     pub faucet_api_url: Option<String>,
@@ -50,6 +171,38 @@ pub struct Config {
     pub discord_bot_token: Option<String>,
     pub discord_channel_id: Option<String>,
     pub etherscan_api_key: Option<String>,
+    /// Etherscan-compatible API per chain ID, for self-hosted or alternative explorers
+    /// (ETHERSCAN_BASE_URLS). Chains not listed use the built-in v2 endpoint.
+    pub etherscan_base_urls: HashMap<String, String>,
+    /// Signature directory used by `lookup_selector` (SIGNATURE_DB_URL, default 4byte.directory)
+    pub signature_db_url: Option<String>,
+}
+
+/// Reads a comma separated environment variable, skipping empty entries.
+fn comma_list(var: &str, default: &str) -> Vec<String> {
+    env::var(var)
+        .unwrap_or_else(|_| default.to_string())
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// DEX_ROUTERS: a JSON object of chain ID to router address, every address checked up front
+fn parse_dex_routers(raw: &str) -> Result<HashMap<String, String>> {
+    let routers: HashMap<String, String> = serde_json::from_str(raw)
+        .context("DEX_ROUTERS must be a JSON object mapping chain IDs to router addresses")?;
+    let mut problems: Vec<String> = routers
+        .iter()
+        .filter(|(_, router)| Address::from_str(router).is_err())
+        .map(|(chain_id, router)| format!("chain {}: '{}' is not a valid address", chain_id, router))
+        .collect();
+    if problems.is_empty() {
+        return Ok(routers);
+    }
+    problems.sort();
+    bail!("Invalid DEX_ROUTERS configuration:\n  {}", problems.join("\n  "))
 }
 
 /// Load Etherscan API key from mcp_config.json file
@@ -124,6 +277,66 @@ impl Config {
         self.chain_rpc_urls.contains_key(chain_id)
     }
 
+    /// Native currency of `chain_id`: the configured override, else the known chain's
+    /// currency, else ETH.
+    pub fn native_currency(&self, chain_id: &str) -> NativeCurrencyConfig {
+        self.native_currencies
+            .get(chain_id)
+            .cloned()
+            .unwrap_or_else(|| chains::native_currency(chain_id).into())
+    }
+
+    /// Router `get_amounts_out` uses on `chain_id`: DEX_ROUTERS, else `DEFAULT_DEX_ROUTERS`.
+    pub fn dex_router(&self, chain_id: &str) -> Option<String> {
+        self.dex_routers.get(chain_id).cloned().or_else(|| {
+            DEFAULT_DEX_ROUTERS.iter().find(|(id, _)| *id == chain_id).map(|(_, router)| router.to_string())
+        })
+    }
+
+    /// Etherscan API base URL for `chain_id`: ETHERSCAN_BASE_URLS, else the built-in
+    /// endpoint. Fails for chains with neither.
+    pub fn etherscan_base_url(&self, chain_id: &str) -> Result<String> {
+        match self.etherscan_base_urls.get(chain_id) {
+            Some(url) => Ok(url.clone()),
+            None => chains::etherscan_base_url(chain_id).map(str::to_string),
+        }
+    }
+
+    /// Transaction type sent on `chain_id`: CHAIN_FEE_TYPES, else `FeeType::Auto`.
+    pub fn fee_type(&self, chain_id: &str) -> FeeType {
+        self.chain_fee_types.get(chain_id).copied().unwrap_or_default()
+    }
+
+    /// Percent of headroom added to gas estimates.
+    pub fn gas_limit_buffer_pct(&self) -> u64 {
+        self.gas_limit_buffer_pct.unwrap_or(DEFAULT_GAS_LIMIT_BUFFER_PCT)
+    }
+
+    /// How long a tool call may run before it is abandoned with a timeout error.
+    pub fn tool_timeout(&self) -> Duration {
+        self.tool_timeout.unwrap_or(DEFAULT_TOOL_TIMEOUT)
+    }
+
+    /// How long `wait_for_balance` waits between balance reads.
+    pub fn balance_poll_interval(&self) -> Duration {
+        self.balance_poll_interval.unwrap_or(DEFAULT_BALANCE_POLL_INTERVAL)
+    }
+
+    /// How long `wait_for_confirmations` waits between receipt checks.
+    pub fn receipt_poll_interval(&self) -> Duration {
+        self.receipt_poll_interval.unwrap_or(DEFAULT_RECEIPT_POLL_INTERVAL)
+    }
+
+    /// Longest request line the stdin MCP server reads before answering with a parse error.
+    pub fn max_line_bytes(&self) -> usize {
+        self.max_line_bytes.unwrap_or(DEFAULT_MAX_LINE_BYTES)
+    }
+
+    /// Address the HTTP server binds: BIND_ADDRESS (or the default) on PORT.
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind_address.unwrap_or(DEFAULT_BIND_ADDRESS), self.port)
+    }
+
     /// Loads configuration from environment variables.
     pub fn from_env() -> Result<Self> {
         // Load variables from the .env file into the environment
@@ -136,12 +349,9 @@ impl Config {
 
         // Try to load from environment variable first
         if let Ok(rpc_urls_str) = env::var("CHAIN_RPC_URLS") {
-            if let Ok(urls) = serde_json::from_str::<HashMap<String, String>>(&rpc_urls_str) {
-                chain_rpc_urls = urls;
-                info!("Loaded RPC URLs from CHAIN_RPC_URLS environment variable");
-            } else {
-                warn!("Failed to parse CHAIN_RPC_URLS, using default RPC URLs");
-            }
+            chain_rpc_urls = serde_json::from_str::<HashMap<String, String>>(&rpc_urls_str)
+                .context("CHAIN_RPC_URLS must be a JSON object mapping chain IDs to RPC URLs")?;
+            info!("Loaded RPC URLs from CHAIN_RPC_URLS environment variable");
         }
 
         // Add default RPC URLs if not already set
@@ -181,21 +391,101 @@ impl Config {
             })
         });
 
-        Ok(Config {
+        let config = Config {
             // Server settings
             port: env::var("PORT")
                 .unwrap_or_else(|_| "8080".to_string())
                 .parse()
                 .context("PORT must be a valid number")?,
+            bind_address: env::var("BIND_ADDRESS")
+                .ok()
+                .map(|v| v.trim().parse())
+                .transpose()
+                .context("BIND_ADDRESS must be an IP address such as 127.0.0.1 or 0.0.0.0")?,
+            cors_allowed_origins: comma_list("CORS_ALLOWED_ORIGINS", ""),
+            cors_allowed_methods: comma_list("CORS_ALLOWED_METHODS", "GET,POST"),
+            cors_allowed_headers: comma_list("CORS_ALLOWED_HEADERS", "content-type"),
+            cors_permissive: env::var("CORS_PERMISSIVE")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .context("CORS_PERMISSIVE must be true or false")?,
+            api_key: env::var("API_KEY").ok().filter(|k| !k.trim().is_empty()),
+            tool_timeout: env::var("TOOL_TIMEOUT_SECS")
+                .ok()
+                .map(|v| v.trim().parse::<u64>())
+                .transpose()
+                .context("TOOL_TIMEOUT_SECS must be a whole number of seconds")?
+                .map(Duration::from_secs),
+            balance_poll_interval: env::var("BALANCE_POLL_INTERVAL_SECS")
+                .ok()
+                .map(|v| v.trim().parse::<u64>())
+                .transpose()
+                .context("BALANCE_POLL_INTERVAL_SECS must be a whole number of seconds")?
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+            receipt_poll_interval: env::var("RECEIPT_POLL_INTERVAL_SECS")
+                .ok()
+                .map(|v| v.trim().parse::<u64>())
+                .transpose()
+                .context("RECEIPT_POLL_INTERVAL_SECS must be a whole number of seconds")?
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+            max_line_bytes: env::var("MCP_MAX_LINE_BYTES")
+                .ok()
+                .map(|v| v.trim().parse::<usize>())
+                .transpose()
+                .context("MCP_MAX_LINE_BYTES must be a whole number of bytes")?
+                .filter(|bytes| *bytes > 0),
+            tool_errors_as_results: env::var("TOOL_ERRORS_AS_RESULTS")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .context("TOOL_ERRORS_AS_RESULTS must be true or false")?,
+            http_rate_limit: env::var("HTTP_RATE_LIMIT")
+                .ok()
+                .map(|v| v.trim().parse::<u32>())
+                .transpose()
+                .context("HTTP_RATE_LIMIT must be a whole number of requests per minute")?
+                .filter(|n| *n > 0),
+            metrics_enabled: env::var("METRICS_ENABLED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .context("METRICS_ENABLED must be true or false")?,
 
             // Blockchain settings
             chain_rpc_urls,
             websocket_url: env::var("WEBSOCKET_URL").unwrap_or_default(),
             default_chain_id,
+            validate_rpc_on_start: env::var("VALIDATE_RPC_ON_START")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .context("VALIDATE_RPC_ON_START must be true or false")?,
+            allow_chain_id_mismatch: env::var("ALLOW_CHAIN_ID_MISMATCH")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .context("ALLOW_CHAIN_ID_MISMATCH must be true or false")?,
+            chain_tip_max_lag_secs: env::var("CHAIN_TIP_MAX_LAG_SECS")
+                .ok()
+                .map(|v| v.parse())
+                .transpose()
+                .context("CHAIN_TIP_MAX_LAG_SECS must be a valid number")?,
+            native_currencies: match env::var("NATIVE_CURRENCIES") {
+                Ok(raw) => serde_json::from_str(&raw).context(
+                    "NATIVE_CURRENCIES must be a JSON object mapping chain IDs to {symbol, name, decimals}",
+                )?,
+                Err(_) => HashMap::new(),
+            },
 
             // Wallet settings
             master_password,
             wallet_storage_path,
+            key_export_disabled: !env::var("ALLOW_KEY_EXPORT")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse::<bool>()
+                .context("ALLOW_KEY_EXPORT must be true or false")?,
+            suppress_key_output: env::var("SUPPRESS_KEY_OUTPUT")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .context("SUPPRESS_KEY_OUTPUT must be true or false")?,
 
             // Transaction settings
             default_gas_limit: env::var("DEFAULT_GAS_LIMIT")
@@ -206,7 +496,42 @@ impl Config {
                 .unwrap_or_else(|_| "20000000000".to_string())
                 .parse()
                 .context("DEFAULT_GAS_PRICE must be a valid number")?,
+            use_config_gas_defaults: env::var("USE_CONFIG_GAS_DEFAULTS")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .context("USE_CONFIG_GAS_DEFAULTS must be true or false")?,
             tx_private_key: env::var("TX_PRIVATE_KEY").ok(),
+            max_transfer_amount_wei: env::var("MAX_TRANSFER_AMOUNT_WEI")
+                .ok()
+                .map(|v| U256::from_dec_str(&v))
+                .transpose()
+                .context("MAX_TRANSFER_AMOUNT_WEI must be a decimal amount in wei")?,
+            gas_limit_buffer_pct: env::var("GAS_LIMIT_BUFFER_PCT")
+                .ok()
+                .map(|v| v.trim().parse::<u64>())
+                .transpose()
+                .context("GAS_LIMIT_BUFFER_PCT must be a whole percentage")?
+                .map(|pct| {
+                    if pct > MAX_GAS_LIMIT_BUFFER_PCT {
+                        bail!("GAS_LIMIT_BUFFER_PCT must be at most {}, got {}", MAX_GAS_LIMIT_BUFFER_PCT, pct);
+                    }
+                    Ok(pct)
+                })
+                .transpose()?,
+            max_gas_limits: match env::var("MAX_GAS_LIMITS") {
+                Ok(raw) => serde_json::from_str(&raw)
+                    .context("MAX_GAS_LIMITS must be a JSON object mapping chain IDs to gas limits")?,
+                Err(_) => HashMap::new(),
+            },
+            chain_fee_types: match env::var("CHAIN_FEE_TYPES") {
+                Ok(raw) => serde_json::from_str(&raw)
+                    .context("CHAIN_FEE_TYPES must be a JSON object mapping chain IDs to legacy, eip1559 or auto")?,
+                Err(_) => HashMap::new(),
+            },
+            dex_routers: match env::var("DEX_ROUTERS") {
+                Ok(raw) => parse_dex_routers(&raw)?,
+                Err(_) => HashMap::new(),
+            },
 
             // External services - load with debug logging
             faucet_api_url: env::var("FAUCET_API_URL").ok().map(|url| {
@@ -233,6 +558,134 @@ impl Config {
                 info!("Etherscan API key configured");
                 key
             }),
-        })
+            etherscan_base_urls: match env::var("ETHERSCAN_BASE_URLS") {
+                Ok(raw) => serde_json::from_str(&raw)
+                    .context("ETHERSCAN_BASE_URLS must be a JSON object mapping chain IDs to API URLs")?,
+                Err(_) => HashMap::new(),
+            },
+            signature_db_url: env::var("SIGNATURE_DB_URL").ok(),
+        };
+
+        config.validate_rpc_urls()?;
+        Ok(config)
+    }
+
+    /// Checks that every configured RPC URL is a syntactically valid http(s) URL.
+    /// All offending entries are reported in a single error.
+    pub fn validate_rpc_urls(&self) -> Result<()> {
+        let mut problems: Vec<String> = self
+            .chain_rpc_urls
+            .iter()
+            .filter_map(|(chain_id, raw)| match Url::parse(raw) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => None,
+                Ok(url) => Some(format!("chain {}: '{}' must use http or https (got '{}')", chain_id, raw, url.scheme())),
+                Err(e) => Some(format!("chain {}: '{}' is not a valid URL ({})", chain_id, raw, e)),
+            })
+            .collect();
+        if problems.is_empty() {
+            return Ok(());
+        }
+        problems.sort();
+        bail!("Invalid RPC URL configuration:\n  {}", problems.join("\n  "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config_with(urls: &[(&str, &str)]) -> Config {
+        Config {
+            chain_rpc_urls: urls.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_rpc_urls_reports_every_bad_entry() {
+        assert!(config_with(&[("1", "https://eth.llamarpc.com"), ("31337", "http://127.0.0.1:8545")])
+            .validate_rpc_urls()
+            .is_ok());
+
+        let err = config_with(&[
+            ("1", "https://eth.llamarpc.com"),
+            ("10", "not a url"),
+            ("137", "ftp://polygon.example.com"),
+            ("8453", "wss://base.example.com"),
+        ])
+        .validate_rpc_urls()
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("chain 10: 'not a url' is not a valid URL"), "{}", err);
+        assert!(err.contains("chain 137"), "{}", err);
+        assert!(err.contains("chain 8453"), "{}", err);
+        assert!(!err.contains("chain 1:"), "{}", err);
+    }
+
+    #[test]
+    fn test_native_currency_prefers_override() {
+        let mut config = config_with(&[]);
+        assert_eq!(config.native_currency("1").symbol, "ETH");
+        assert_eq!(config.native_currency("56").symbol, "BNB");
+        assert_eq!(config.native_currency("999999").symbol, "ETH");
+
+        config.native_currencies = serde_json::from_value(json!({
+            "999999": {"symbol": "GLMR", "name": "Glimmer", "decimals": 18}
+        }))
+        .unwrap();
+        assert_eq!(config.native_currency("999999").name, "Glimmer");
+    }
+
+    #[test]
+    fn test_etherscan_base_url_prefers_override() {
+        let mut config = config_with(&[]);
+        assert_eq!(config.etherscan_base_url("1").unwrap(), "https://api.etherscan.io/v2/api");
+        assert!(config.etherscan_base_url("31337").is_err());
+        assert!(config.etherscan_base_url("324").is_err());
+
+        config.etherscan_base_urls = HashMap::from([
+            ("1".to_string(), "https://explorer.example.com/api".to_string()),
+            ("31337".to_string(), "http://127.0.0.1:4000/api".to_string()),
+        ]);
+        assert_eq!(config.etherscan_base_url("1").unwrap(), "https://explorer.example.com/api");
+        assert_eq!(config.etherscan_base_url("31337").unwrap(), "http://127.0.0.1:4000/api");
+        assert_eq!(config.etherscan_base_url("11155111").unwrap(), "https://api-sepolia.etherscan.io/v2/api");
+    }
+
+    #[test]
+    fn test_parse_dex_routers_rejects_bad_addresses() {
+        let routers = parse_dex_routers(r#"{"137": "0x1111111111111111111111111111111111111111"}"#).unwrap();
+        assert_eq!(routers["137"], "0x1111111111111111111111111111111111111111");
+
+        let err = parse_dex_routers(r#"{"1": "0x1111111111111111111111111111111111111111", "10": "uniswap"}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("chain 10: 'uniswap' is not a valid address"), "{}", err);
+        assert!(!err.contains("chain 1:"), "{}", err);
+        assert!(parse_dex_routers("[]").is_err());
+    }
+
+    #[test]
+    fn test_fee_type_defaults_to_auto() {
+        let mut config = config_with(&[]);
+        config.chain_fee_types = serde_json::from_value(json!({"56": "legacy", "1": "eip1559"})).unwrap();
+        assert_eq!(config.fee_type("56"), FeeType::Legacy);
+        assert_eq!(config.fee_type("1"), FeeType::Eip1559);
+        assert_eq!(config.fee_type("137"), FeeType::Auto);
+        assert!(serde_json::from_value::<HashMap<String, FeeType>>(json!({"1": "type2"})).is_err());
+    }
+
+    #[test]
+    fn test_socket_addr_uses_bind_address() {
+        let default = Config { port: 8080, ..Config::default() };
+        assert_eq!(default.socket_addr(), SocketAddr::from(([0, 0, 0, 0], 8080)));
+
+        let local = Config {
+            port: 9000,
+            bind_address: Some("127.0.0.1".parse().unwrap()),
+            ..Config::default()
+        };
+        assert_eq!(local.socket_addr().to_string(), "127.0.0.1:9000");
     }
 }
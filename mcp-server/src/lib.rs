@@ -12,6 +12,20 @@ pub use k256::ecdsa::SigningKey;
 // Re-export modules
 pub mod utils;
 
+/// Crate version, as reported by `--version`, `initialize` and `server_info`
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Short git commit hash of the build, when built from a git checkout
+pub const GIT_HASH: Option<&str> = option_env!("EVM_MCP_GIT_HASH");
+
+/// `VERSION` followed by the commit hash when known, e.g. `0.1.0 (3f2a9c1)`
+pub fn version_string() -> String {
+    match GIT_HASH {
+        Some(hash) => format!("{} ({})", VERSION, hash),
+        None => VERSION.to_string(),
+    }
+}
+
 /// Application state shared across all request handlers
 #[derive(Clone)]
 pub struct AppState {
@@ -27,9 +41,48 @@ pub struct AppState {
     pub wallet_storage: Arc<Mutex<mcp::wallet_storage::WalletStorage>>,
     /// Path to the wallet storage file
     pub wallet_storage_path: Arc<PathBuf>,
+    /// Results of recent transfers by idempotency key, so client retries don't double-send
+    pub idempotency: mcp::idempotency::IdempotencyStore,
+    /// Verified ABIs fetched from Etherscan for `read_contract`/`write_contract`
+    pub abi_cache: mcp::abi_cache::AbiCache,
+    /// Which RPC endpoints take EIP-1559 transactions, for chains on `FeeType::Auto`
+    pub fee_support: blockchain::services::transactions::FeeSupportCache,
+    /// Recent signature directory answers for `lookup_selector`
+    pub signature_cache: blockchain::services::signatures::SignatureCache,
+    /// Request and tool-call counters served on /metrics
+    pub metrics: Arc<metrics::Metrics>,
+    /// HTTP client for Etherscan and other outbound API calls; tests point it at mocks
+    pub http_client: reqwest::Client,
+}
+
+impl AppState {
+    /// State for `config` over `wallet_storage` (saved at `wallet_storage_path`), with
+    /// fresh nonces, caches and counters.
+    pub fn new(
+        config: config::Config,
+        wallet_storage: mcp::wallet_storage::WalletStorage,
+        wallet_storage_path: PathBuf,
+    ) -> Self {
+        Self {
+            evm_client: blockchain::client::EvmClient::new(&config.chain_rpc_urls),
+            config,
+            nonce_manager: blockchain::nonce_manager::NonceManager::new(),
+            wallet_manager: blockchain::wallet_manager::WalletManager::new(wallet_storage.clone()),
+            wallet_storage: Arc::new(Mutex::new(wallet_storage)),
+            wallet_storage_path: Arc::new(wallet_storage_path),
+            idempotency: Default::default(),
+            abi_cache: Default::default(),
+            fee_support: Default::default(),
+            signature_cache: Default::default(),
+            metrics: Default::default(),
+            http_client: Default::default(),
+        }
+    }
 }
 
 pub mod api;
 pub mod blockchain;
 pub mod config;
-pub mod mcp;
\ No newline at end of file
+pub mod logging;
+pub mod mcp;
+pub mod metrics;
\ No newline at end of file
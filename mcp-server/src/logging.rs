@@ -0,0 +1,231 @@
+//! Log output setup: which events are logged and how they are written.
+//!
+//! `RUST_LOG` always wins when set. Otherwise `LOG_LEVEL` picks the default
+//! filter, and `LOG_FORMAT` chooses between human-readable lines (`pretty`)
+//! and one JSON object per line (`json`) for log collectors.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Filter used when neither `RUST_LOG` nor `LOG_LEVEL` is set
+pub const DEFAULT_LOG_FILTER: &str = "evm_mcp_server=debug,tower_http=debug";
+
+/// How log lines are rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines, as printed by `tracing_subscriber::fmt`
+    #[default]
+    Pretty,
+    /// One JSON object per line with `timestamp`, `level`, `target`, `fields` and `spans`
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "pretty" | "text" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(anyhow!("Invalid LOG_FORMAT '{}': expected 'pretty' or 'json'", other)),
+        }
+    }
+}
+
+/// Logging configuration read from the environment before anything else starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogSettings {
+    pub format: LogFormat,
+    /// `EnvFilter` directives
+    pub filter: String,
+}
+
+impl LogSettings {
+    /// Reads `LOG_FORMAT`, `RUST_LOG` and `LOG_LEVEL`
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(
+            std::env::var("LOG_FORMAT").ok().as_deref(),
+            std::env::var("RUST_LOG").ok().as_deref(),
+            std::env::var("LOG_LEVEL").ok().as_deref(),
+        )
+    }
+
+    fn from_vars(format: Option<&str>, rust_log: Option<&str>, level: Option<&str>) -> Result<Self> {
+        let format = match format.filter(|f| !f.trim().is_empty()) {
+            Some(raw) => raw.parse()?,
+            None => LogFormat::default(),
+        };
+        let filter = match (rust_log, level) {
+            (Some(directives), _) if !directives.trim().is_empty() => directives.trim().to_string(),
+            (_, Some(level)) if !level.trim().is_empty() => default_filter(level.trim()),
+            _ => DEFAULT_LOG_FILTER.to_string(),
+        };
+        Ok(LogSettings { format, filter })
+    }
+}
+
+/// A bare level (`info`) applies to this server and its HTTP layer only, so
+/// dependencies don't flood the output; full directives are passed through.
+fn default_filter(level: &str) -> String {
+    if level.contains('=') || level.contains(',') {
+        level.to_string()
+    } else {
+        format!("evm_mcp_server={level},tower_http={level}")
+    }
+}
+
+/// Builds the formatting layer for `format`, writing to `writer`
+pub fn fmt_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync + 'static>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+    match format {
+        LogFormat::Pretty => layer.boxed(),
+        LogFormat::Json => layer.with_ansi(false).event_format(JsonFormat).boxed(),
+    }
+}
+
+/// Renders each event as a single-line JSON object
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let meta = event.metadata();
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+        let spans: Vec<Value> = ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| Value::String(span.name().to_string()))
+            .collect();
+        let line = json!({
+            "timestamp": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "level": meta.level().to_string(),
+            "target": meta.target(),
+            "fields": Value::Object(fields.0),
+            "spans": spans,
+        });
+        writeln!(writer, "{}", line)
+    }
+}
+
+/// Collects event fields, keeping numbers and booleans typed
+#[derive(Default)]
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), json!(format!("{:?}", value)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn capture(format: LogFormat) -> String {
+        let buf = Buffer::default();
+        let writer = buf.clone();
+        let subscriber = tracing_subscriber::registry().with(fmt_layer(format, move || writer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("tool_call");
+            let _guard = span.enter();
+            tracing::info!(chain_id = 1u64, cached = true, "quote served");
+        });
+        let bytes = buf.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_settings_from_vars() {
+        let defaults = LogSettings::from_vars(None, None, None).unwrap();
+        assert_eq!(defaults.format, LogFormat::Pretty);
+        assert_eq!(defaults.filter, DEFAULT_LOG_FILTER);
+
+        let json = LogSettings::from_vars(Some("JSON"), None, Some("info")).unwrap();
+        assert_eq!(json.format, LogFormat::Json);
+        assert_eq!(json.filter, "evm_mcp_server=info,tower_http=info");
+
+        let directives = LogSettings::from_vars(None, None, Some("warn,evm_mcp_server=trace")).unwrap();
+        assert_eq!(directives.filter, "warn,evm_mcp_server=trace");
+
+        let rust_log = LogSettings::from_vars(None, Some("debug"), Some("info")).unwrap();
+        assert_eq!(rust_log.filter, "debug");
+
+        assert!(LogSettings::from_vars(Some("xml"), None, None).is_err());
+    }
+
+    #[test]
+    fn test_json_layer_emits_structured_fields() {
+        let output = capture(LogFormat::Json);
+        let line: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], module_path!());
+        assert_eq!(line["fields"]["message"], "quote served");
+        assert_eq!(line["fields"]["chain_id"], 1);
+        assert_eq!(line["fields"]["cached"], true);
+        assert_eq!(line["spans"], json!(["tool_call"]));
+        assert!(line["timestamp"].as_str().unwrap().ends_with('Z'));
+    }
+
+    #[test]
+    fn test_pretty_layer_emits_text() {
+        let output = capture(LogFormat::Pretty);
+        assert!(output.contains("quote served"));
+        assert!(serde_json::from_str::<Value>(output.trim()).is_err());
+    }
+}
@@ -17,6 +17,8 @@
 //! ### MCP Mode (default for AI assistants)
 //! ```bash
 //! cargo run -- --mcp
+//! # LSP-style Content-Length framing (otherwise detected from the first request)
+//! cargo run -- --mcp --content-length
 //! ```
 //!
 //! ### HTTP Server Mode
@@ -24,6 +26,11 @@
 //! cargo run
 //! ```
 //!
+//! ### Version
+//! ```bash
+//! cargo run -- --version
+//! ```
+//!
 //! ## Configuration
 //!
 //! Configure the server using environment variables or a `.env` file.
@@ -31,11 +38,15 @@
 
 use axum::{
     extract::State,
+    http::{HeaderName, HeaderValue, Method, StatusCode},
+    middleware,
+    response::IntoResponse,
     routing::{get, post},
     Json, Router,
 };
 use evm_mcp_server::{
     api::{
+        auth,
         balance::get_balance_handler,
         contract::{
             get_contract_code_handler, get_contract_handler, get_contract_source_code_handler,
@@ -43,34 +54,88 @@ use evm_mcp_server::{
         },
         health::health_handler,
         history::get_transaction_history_handler,
+        metrics::metrics_handler,
+        rate_limit::{self, RateLimiter},
+        timeout,
         tx::send_transaction_handler,
         wallet,
     },
-    blockchain::{client::EvmClient, nonce_manager::NonceManager, wallet_manager::WalletManager},
+    blockchain::services::rpc,
     config::Config,
+    logging::{self, LogSettings},
+    utils::redact_secrets,
     mcp::{
-        handler::handle_mcp_request,
-        protocol::{error_codes, Request, Response},
-        wallet_storage::load_or_create_wallet_storage,
+        handler::handle_mcp_message,
+        line_reader::Line,
+        protocol::{error_codes, Response},
+        transport::{Framing, MessageReader},
+        wallet_storage::{self, load_or_create_wallet_storage},
     },
     AppState,
 };
+use std::collections::HashMap;
 use std::env;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt};
-use tokio::sync::Mutex;
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
-use tracing::{debug, error, info};
+use std::future::Future;
+use tokio::io::{self, AsyncBufRead, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tokio::task::{self, JoinError, JoinSet};
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    trace::TraceLayer,
+};
+use tracing::{debug, error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-// --- HTTP Server Logic ---
-async fn run_http_server(state: AppState) {
-    // Create the API router with all routes
-    let api_router = Router::new()
-        // Health check
-        .route("/health", get(health_handler))
+// CORS policy from config: permissive only when CORS_PERMISSIVE is set, otherwise just the
+// configured origins (none by default, so browsers can't call the API cross-origin)
+fn cors_layer(config: &Config) -> Result<CorsLayer, String> {
+    if config.cors_permissive {
+        return Ok(CorsLayer::permissive());
+    }
+    let origins = config
+        .cors_allowed_origins
+        .iter()
+        .map(|o| HeaderValue::from_str(o).map_err(|_| format!("invalid CORS origin '{}'", o)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let methods = config
+        .cors_allowed_methods
+        .iter()
+        .map(|m| Method::from_bytes(m.to_uppercase().as_bytes()).map_err(|_| format!("invalid CORS method '{}'", m)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let headers = config
+        .cors_allowed_headers
+        .iter()
+        .map(|h| HeaderName::from_bytes(h.as_bytes()).map_err(|_| format!("invalid CORS header '{}'", h)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods(methods)
+        .allow_headers(headers))
+}
+
+// Prometheus scrape endpoint, outside /api like scrapers expect but behind the same API key;
+// `None` unless METRICS_ENABLED is set
+fn metrics_router(config: &Config) -> Option<Router<AppState>> {
+    if !config.metrics_enabled {
+        return None;
+    }
+    let mut router = Router::new().route("/metrics", get(metrics_handler));
+    if let Some(api_key) = config.api_key.clone() {
+        router = router.route_layer(middleware::from_fn_with_state(Arc::new(api_key), auth::require_api_key));
+    }
+    Some(router)
+}
+
+// REST routes under /api, each bounded by TOOL_TIMEOUT_SECS so a hung upstream cannot
+// hold a request open forever. /tx/send is not cancelled at the limit, only answered.
+fn rest_router(config: &Config) -> Router<AppState> {
+    let send_router = Router::new()
+        .route("/tx/send", post(send_transaction_handler))
+        .route_layer(middleware::from_fn_with_state(config.tool_timeout(), timeout::broadcast_timeout));
+    Router::new()
         // Wallet management
         .merge(wallet::create_wallet_router())
         // Blockchain data
@@ -79,7 +144,6 @@ async fn run_http_server(state: AppState) {
             "/history/:chain_id/:address",
             get(get_transaction_history_handler),
         )
-        .route("/tx/send", post(send_transaction_handler))
         // Contract interaction
         .route("/contract/:chain_id/:address", get(get_contract_handler))
         .route(
@@ -98,107 +162,359 @@ async fn run_http_server(state: AppState) {
             "/contract/:chain_id/:address/is_contract",
             get(get_is_contract_handler),
         )
-        // JSON-RPC endpoint for MCP tool calls
+        .route_layer(middleware::from_fn_with_state(config.tool_timeout(), timeout::request_timeout))
+        .merge(send_router)
+}
+
+// --- HTTP Server Logic ---
+async fn run_http_server(state: AppState) {
+    let cors = match cors_layer(&state.config) {
+        Ok(cors) => cors,
+        Err(e) => {
+            error!("❌ Invalid CORS configuration: {}", e);
+            return;
+        }
+    };
+
+    // Create the API router with all routes
+    let mut api_router = rest_router(&state.config)
+        // JSON-RPC endpoint for MCP tool calls; bounded per tool by handle_tool_call
         .route("/rpc", post(rpc_handler));
+    if let Some(api_key) = state.config.api_key.clone() {
+        api_router = api_router.route_layer(middleware::from_fn_with_state(
+            Arc::new(api_key),
+            auth::require_api_key,
+        ));
+    }
+    // Added last so it runs first: rejected keys still count against the client's limit
+    if let Some(limit) = state.config.http_rate_limit {
+        api_router = api_router.route_layer(middleware::from_fn_with_state(
+            RateLimiter::per_minute(limit),
+            rate_limit::rate_limit,
+        ));
+    }
+    // Health check stays exempt so platforms can probe it freely
+    let api_router = api_router.route("/health", get(health_handler));
 
     // Create the main app with the API router under /api
-    let app = Router::new()
-        .nest("/api", api_router)
+    let mut app = Router::new().nest("/api", api_router);
+    if let Some(metrics_router) = metrics_router(&state.config) {
+        app = app.merge(metrics_router);
+    }
+    let app = app
         .with_state(state.clone())
         .layer(TraceLayer::new_for_http())
-        .layer(CorsLayer::permissive());
+        .layer(cors);
 
-    // Bind to 0.0.0.0 for container/cloud platforms (Render, Fly, Railway) to detect open port
-    let addr = SocketAddr::from(([0, 0, 0, 0], state.config.port));
+    let addr = state.config.socket_addr();
     info!("🚀 HTTP Server listening on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(
+    serve_http(listener, app, state, shutdown_signal()).await;
+}
+
+// Serve until `shutdown` resolves, letting in-flight requests finish, then flush wallet storage
+async fn serve_http(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    state: AppState,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) {
+    if let Err(e) = axum::serve(
         listener,
         app.into_make_service_with_connect_info::<SocketAddr>(),
     )
+    .with_graceful_shutdown(shutdown)
     .await
-    .unwrap();
+    {
+        error!("HTTP server error: {}", e);
+    }
+    flush_wallet_storage(&state).await;
+    info!("HTTP server stopped");
+}
+
+// Resolves on Ctrl-C (SIGINT) or, on Unix, SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("Shutdown signal received");
+}
+
+// Wallet writes happen while holding the storage lock, so taking it waits for any
+// in-flight save; then persist once more, merged with what other processes wrote.
+async fn flush_wallet_storage(state: &AppState) {
+    let flushed = wallet_storage::update_blocking(&state.wallet_storage, |storage| {
+        if storage.is_master_password_hash_empty() {
+            return Ok(());
+        }
+        storage.flush()
+    })
+    .await;
+    if let Err(e) = flushed {
+        error!("Failed to flush wallet storage on shutdown: {}", e);
+    }
 }
 
 // Forward JSON-RPC requests over HTTP to the MCP handler
-async fn rpc_handler(State(state): State<AppState>, Json(req): Json<Request>) -> Json<Response> {
-    match handle_mcp_request(req, state).await {
-        Some(resp) => Json(resp),
-        None => Json(Response::error(
-            serde_json::Value::Null,
-            error_codes::INVALID_REQUEST,
-            "Notifications are not supported over HTTP".into(),
-        )),
+async fn rpc_handler(State(state): State<AppState>, Json(msg): Json<serde_json::Value>) -> axum::response::Response {
+    match handle_mcp_message(msg, state).await {
+        Some(resp) => Json(resp).into_response(),
+        // Notifications (no `id`), alone or as a whole batch, are accepted without a body
+        None => StatusCode::ACCEPTED.into_response(),
     }
 }
 
 // --- MCP Server Logic ---
-async fn run_mcp_server(state: AppState) {
+async fn run_mcp_server(state: AppState, framing: Option<Framing>) {
     info!("🚀 Starting MCP server on stdin/stdout...");
+    serve_mcp(io::BufReader::new(io::stdin()), io::stdout(), state, framing, shutdown_signal()).await;
+}
 
-    let mut stdin = io::BufReader::new(io::stdin());
-    let mut stdout = io::stdout();
+// Read JSON-RPC requests until EOF or `shutdown` resolves, one per line or with
+// `Content-Length` framing (`framing`, or detected from the first request when `None`).
+// Each request runs in its own task so a slow tool call doesn't hold up the ones behind
+// it; responses come back over a channel and are written in completion order, framed
+// like the requests.
+async fn serve_mcp<R, W>(
+    stdin: R,
+    mut stdout: W,
+    state: AppState,
+    framing: Option<Framing>,
+    shutdown: impl Future<Output = ()>,
+) where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    tokio::pin!(shutdown);
+    let mut messages = MessageReader::new(stdin, framing, state.config.max_line_bytes());
+    let (responses, mut outbox) = mpsc::unbounded_channel::<serde_json::Value>();
+    let mut in_flight = JoinSet::new();
+    // The requests each running task answers, so a task that panics still gets a reply
+    let mut pending: HashMap<task::Id, PendingRequest> = HashMap::new();
+    let mut writable = true;
 
     loop {
-        let mut line = String::new();
-
-        match stdin.read_line(&mut line).await {
-            Ok(0) => {
-                info!("EOF received, shutting down MCP server");
+        // Only waiting for input is interrupted; requests being handled run to completion
+        tokio::select! {
+            _ = &mut shutdown => {
+                info!("Shutdown requested, stopping MCP server");
                 break;
             }
-            Ok(_) => {
-                let line = line.trim();
-                if line.is_empty() {
-                    continue;
+            Some(response) = outbox.recv() => {
+                if !write_response(&mut stdout, &response, messages.framing()).await {
+                    writable = false;
+                    break;
                 }
-
-                debug!("Received: {}", line);
-
-                let response = match serde_json::from_str::<Request>(line) {
-                    Ok(request) => handle_mcp_request(request, state.clone()).await,
-                    Err(parse_error) => {
-                        error!("JSON parse error: {}", parse_error);
-                        Some(Response::error(
-                            serde_json::Value::Null,
-                            error_codes::PARSE_ERROR,
-                            format!("Parse error: {}", parse_error),
-                        ))
+            }
+            Some(joined) = in_flight.join_next_with_id(), if !in_flight.is_empty() => {
+                task_finished(joined, &mut pending, &responses);
+            }
+            read = messages.next_message() => match read {
+                Ok(None) => {
+                    info!("EOF received, shutting down MCP server");
+                    break;
+                }
+                Ok(Some(Line::TooLong)) => {
+                    warn!("Skipped a request line over {} bytes", state.config.max_line_bytes());
+                    if let Ok(response) = serde_json::to_value(Response::error(
+                        serde_json::Value::Null,
+                        error_codes::PARSE_ERROR,
+                        format!("Parse error: request exceeds {} bytes", state.config.max_line_bytes()),
+                    )) {
+                        let _ = responses.send(response);
                     }
-                };
-
-                if let Some(response) = response {
-                    if let Ok(response_json) = serde_json::to_string(&response) {
-                        debug!("Sending: {}", response_json);
-                        if let Err(e) = stdout
-                            .write_all(format!("{}\n", response_json).as_bytes())
-                            .await
-                        {
-                            error!("Failed to write response: {}", e);
-                            break;
+                }
+                Ok(Some(Line::Text(line))) => {
+                    let line = line.trim();
+                    if line.is_empty() {
+                        continue;
+                    }
+
+                    // A single request or a batch (JSON array) of them
+                    match serde_json::from_str::<serde_json::Value>(line) {
+                        Ok(msg) => {
+                            debug!("Received: {}", redact_secrets(&msg));
+                            let request = PendingRequest::of(&msg);
+                            let state = state.clone();
+                            let responses = responses.clone();
+                            let handle = in_flight.spawn(async move {
+                                if let Some(response) = handle_mcp_message(msg, state).await {
+                                    let _ = responses.send(response);
+                                }
+                            });
+                            pending.insert(handle.id(), request);
+                        }
+                        Err(parse_error) => {
+                            error!("JSON parse error: {}", parse_error);
+                            if let Ok(response) = serde_json::to_value(Response::error(
+                                serde_json::Value::Null,
+                                error_codes::PARSE_ERROR,
+                                format!("Parse error: {}", parse_error),
+                            )) {
+                                let _ = responses.send(response);
+                            }
                         }
                     }
                 }
-            }
-            Err(e) => {
-                error!("Failed to read from stdin: {}", e);
-                break;
-            }
+                // One bad message; the reader has moved past it
+                Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                    warn!("Skipped an unreadable message: {}", e);
+                    if let Ok(response) = serde_json::to_value(Response::error(
+                        serde_json::Value::Null,
+                        error_codes::PARSE_ERROR,
+                        format!("Parse error: {}", e),
+                    )) {
+                        let _ = responses.send(response);
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to read from stdin: {}", e);
+                    break;
+                }
+            },
+        }
+    }
+
+    // Let in-flight requests finish and deliver what they produce
+    while let Some(joined) = in_flight.join_next_with_id().await {
+        task_finished(joined, &mut pending, &responses);
+    }
+    drop(responses);
+    while let Some(response) = outbox.recv().await {
+        if writable {
+            writable = write_response(&mut stdout, &response, messages.framing()).await;
         }
     }
 
+    flush_wallet_storage(&state).await;
     info!("MCP server shutting down");
 }
 
+// The ids a stdio message expects answers for: one request, or each request of a batch
+struct PendingRequest {
+    ids: Vec<serde_json::Value>,
+    batch: bool,
+}
+
+impl PendingRequest {
+    fn of(msg: &serde_json::Value) -> Self {
+        let items = match msg {
+            serde_json::Value::Array(items) => items.iter().collect(),
+            single => vec![single],
+        };
+        PendingRequest {
+            ids: items
+                .into_iter()
+                .filter_map(|item| item.get("id").filter(|id| !id.is_null()).cloned())
+                .collect(),
+            batch: msg.is_array(),
+        }
+    }
+
+    // `INTERNAL_ERROR` for each request, shaped like the message that carried them
+    fn failed(&self) -> Option<serde_json::Value> {
+        let errors: Vec<serde_json::Value> = self
+            .ids
+            .iter()
+            .filter_map(|id| {
+                serde_json::to_value(Response::error(
+                    id.clone(),
+                    error_codes::INTERNAL_ERROR,
+                    "Internal error: the request failed unexpectedly".into(),
+                ))
+                .ok()
+            })
+            .collect();
+        match (self.batch, errors.len()) {
+            (_, 0) => None,
+            (true, _) => Some(serde_json::Value::Array(errors)),
+            (false, _) => errors.into_iter().next(),
+        }
+    }
+}
+
+// Bookkeeping for a finished request task; one that panicked is answered with an error
+fn task_finished(
+    joined: Result<(task::Id, ()), JoinError>,
+    pending: &mut HashMap<task::Id, PendingRequest>,
+    responses: &mpsc::UnboundedSender<serde_json::Value>,
+) {
+    let (id, failure) = match joined {
+        Ok((id, ())) => (id, None),
+        Err(e) => (e.id(), Some(e)),
+    };
+    let request = pending.remove(&id);
+    if let Some(e) = failure {
+        error!("MCP request task failed: {}", e);
+        if let Some(response) = request.and_then(|r| r.failed()) {
+            let _ = responses.send(response);
+        }
+    }
+}
+
+// Writes one framed response; false once the output is gone
+async fn write_response<W: AsyncWrite + Unpin>(
+    stdout: &mut W,
+    response: &serde_json::Value,
+    framing: Framing,
+) -> bool {
+    let Ok(response_json) = serde_json::to_string(response) else {
+        return true;
+    };
+    debug!("Sending: {}", redact_secrets(response));
+    let written = async {
+        stdout.write_all(&framing.encode(&response_json)).await?;
+        stdout.flush().await
+    };
+    match written.await {
+        Ok(()) => true,
+        Err(e) => {
+            error!("Failed to write response: {}", e);
+            false
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
+    if env::args().any(|arg| arg == "--version" || arg == "-V") {
+        println!("evm_mcp {}", evm_mcp_server::version_string());
+        return;
+    }
+
     // Initialize tracing
+    let log_settings = match LogSettings::from_env() {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
     tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "evm_mcp_server=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(tracing_subscriber::EnvFilter::new(&log_settings.filter))
+        .with(logging::fmt_layer(log_settings.format, std::io::stderr))
         .init();
 
     // Load configuration
@@ -209,11 +525,13 @@ async fn main() {
             return;
         }
     };
-
-    // Initialize EVM client with RPC URLs
-    let evm_client = EvmClient::new(&config.chain_rpc_urls);
-
-    let nonce_manager = NonceManager::new();
+    if config.validate_rpc_on_start {
+        if let Err(e) = rpc::verify_chain_ids(&reqwest::Client::new(), &config.chain_rpc_urls).await {
+            error!("❌ {}", e);
+            return;
+        }
+        info!("All RPC endpoints answered with their configured chain IDs");
+    }
 
     // Initialize wallet storage
     let wallet_storage_path = config
@@ -253,24 +571,450 @@ async fn main() {
         wallet_storage_path.display()
     );
 
-    // Create wallet manager
-    let wallet_manager = WalletManager::new(wallet_storage.clone());
-
     // Create app state
-    let app_state = AppState {
-        config,
-        evm_client,
-        nonce_manager,
-        wallet_manager,
-        wallet_storage: Arc::new(Mutex::new(wallet_storage)),
-        wallet_storage_path: Arc::new(wallet_storage_path),
-    };
+    let app_state = AppState::new(config, wallet_storage, wallet_storage_path);
 
     // Check if running in MCP mode (stdin/stdout) or HTTP server mode
     let args: Vec<String> = env::args().collect();
     if args.contains(&"--mcp".to_string()) || env::var("MCP_MODE").is_ok() {
-        run_mcp_server(app_state).await;
+        // Content-Length framing is detected from the first request unless forced
+        let framing = args
+            .contains(&"--content-length".to_string())
+            .then_some(Framing::ContentLength);
+        run_mcp_server(app_state, framing).await;
     } else {
         run_http_server(app_state).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evm_mcp_server::mcp::wallet_storage::WalletStorage;
+    use std::path::Path;
+    use std::time::Duration;
+    use tokio::io::AsyncBufReadExt;
+    use tokio::sync::oneshot;
+
+    fn test_state(dir: &Path) -> AppState {
+        let config = Config::default();
+        let wallet_storage_path = dir.join("wallets.json");
+        let wallet_storage =
+            WalletStorage::with_encryption("test_password", wallet_storage_path.clone()).unwrap();
+        AppState::new(config, wallet_storage, wallet_storage_path)
+    }
+
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sent_responses_are_logged_without_secrets() {
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(logging::fmt_layer(logging::LogFormat::Json, move || writer.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let key = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+        let response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "private_key": key,
+                "content": [{"type": "text", "text": format!("Address: 0x2c75\nPrivate Key: {}", key)}],
+            }
+        });
+        let mut out = Vec::new();
+        assert!(write_response(&mut out, &response, Framing::Newline).await);
+
+        // The client still gets the key; the log line does not
+        assert!(String::from_utf8(out).unwrap().contains(key));
+        let logged = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logged.contains("Sending"));
+        assert!(logged.contains("[REDACTED]"));
+        assert!(!logged.contains(&key[2..]));
+    }
+
+    #[tokio::test]
+    async fn test_panicked_request_task_is_answered_with_internal_error() {
+        let (responses, mut outbox) = mpsc::unbounded_channel();
+        let mut in_flight = JoinSet::new();
+        let mut pending = HashMap::new();
+        let msg = serde_json::json!([
+            {"jsonrpc": "2.0", "id": 4, "method": "tools/list"},
+            {"jsonrpc": "2.0", "method": "notifications/initialized"}
+        ]);
+        let handle = in_flight.spawn(async { panic!("handler bug") });
+        pending.insert(handle.id(), PendingRequest::of(&msg));
+
+        let joined = in_flight.join_next_with_id().await.unwrap();
+        task_finished(joined, &mut pending, &responses);
+
+        assert!(pending.is_empty());
+        let response = outbox.try_recv().expect("the panicked batch was not answered");
+        assert_eq!(response.as_array().map(|r| r.len()), Some(1));
+        assert_eq!(response[0]["id"], 4);
+        assert_eq!(response[0]["error"]["code"], error_codes::INTERNAL_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_http_server_stops_on_shutdown_signal() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = test_state(dir.path());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let app = Router::new().route("/health", get(|| async { "ok" }));
+        let (tx, rx) = oneshot::channel::<()>();
+
+        let server = tokio::spawn(serve_http(listener, app, state, async {
+            rx.await.ok();
+        }));
+        tx.send(()).unwrap();
+
+        let finished = tokio::time::timeout(Duration::from_secs(5), server).await;
+        assert!(matches!(finished, Ok(Ok(()))), "server did not stop cleanly");
+        assert!(dir.path().join("wallets.json").exists(), "wallet storage was not flushed");
+    }
+
+    #[tokio::test]
+    async fn test_mcp_server_stops_on_shutdown_signal() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = test_state(dir.path());
+        // Keep the client end open so the server never sees EOF
+        let (_client, server_end) = tokio::io::duplex(1024);
+        let (tx, rx) = oneshot::channel::<()>();
+
+        let server = tokio::spawn(serve_mcp(
+            io::BufReader::new(server_end),
+            io::sink(),
+            state,
+            None,
+            async {
+                rx.await.ok();
+            },
+        ));
+        tx.send(()).unwrap();
+
+        let finished = tokio::time::timeout(Duration::from_secs(5), server).await;
+        assert!(matches!(finished, Ok(Ok(()))), "MCP loop did not stop cleanly");
+        assert!(dir.path().join("wallets.json").exists(), "wallet storage was not flushed");
+    }
+
+    #[tokio::test]
+    async fn test_slow_mcp_request_does_not_delay_later_ones() {
+        // An RPC node that accepts connections but never answers
+        let node = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let node_url = format!("http://{}", node.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = node.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(dir.path());
+        state.config.chain_rpc_urls.insert("137".into(), node_url);
+        let (mut client_in, server_in) = tokio::io::duplex(4096);
+        let (server_out, client_out) = tokio::io::duplex(4096);
+        tokio::spawn(serve_mcp(io::BufReader::new(server_in), server_out, state, None, std::future::pending()));
+
+        let slow = serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {
+            "name": "get_balance",
+            "arguments": {"chain_id": "137", "address": "0x000000000000000000000000000000000000dEaD"}
+        }});
+        let fast = serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+        client_in.write_all(format!("{}\n{}\n", slow, fast).as_bytes()).await.unwrap();
+
+        let mut responses = io::BufReader::new(client_out).lines();
+        let first = tokio::time::timeout(Duration::from_secs(5), responses.next_line())
+            .await
+            .expect("fast request was held up by the slow one")
+            .unwrap()
+            .unwrap();
+        let first: serde_json::Value = serde_json::from_str(&first).unwrap();
+        assert_eq!(first["id"], 2);
+        assert!(first["result"]["tools"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_mcp_line_gets_parse_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(dir.path());
+        state.config.max_line_bytes = Some(1024);
+        let (mut client_in, server_in) = tokio::io::duplex(4096);
+        let (server_out, client_out) = tokio::io::duplex(4096);
+        tokio::spawn(serve_mcp(io::BufReader::new(server_in), server_out, state, None, std::future::pending()));
+
+        // Far more than the limit and the pipe buffer, so it is only ever seen in pieces
+        let oversized = format!("{{\"padding\":\"{}\"}}\n", "x".repeat(64 * 1024));
+        let next = serde_json::json!({"jsonrpc": "2.0", "id": 7, "method": "tools/list"});
+        tokio::spawn(async move {
+            client_in.write_all(oversized.as_bytes()).await.unwrap();
+            client_in.write_all(format!("{}\n", next).as_bytes()).await.unwrap();
+            // Keep the input open; the server should not need EOF to answer
+            std::future::pending::<()>().await;
+        });
+
+        let mut responses = io::BufReader::new(client_out).lines();
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            let line = tokio::time::timeout(Duration::from_secs(5), responses.next_line())
+                .await
+                .expect("no response")
+                .unwrap()
+                .unwrap();
+            received.push(serde_json::from_str::<serde_json::Value>(&line).unwrap());
+        }
+        assert_eq!(received[0]["error"]["code"], error_codes::PARSE_ERROR);
+        assert!(received[0]["id"].is_null());
+        assert_eq!(received[1]["id"], 7);
+        assert!(received[1]["result"]["tools"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_mcp_message_gets_parse_error_and_session_continues() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = test_state(dir.path());
+        let (mut client_in, server_in) = tokio::io::duplex(4096);
+        let (server_out, client_out) = tokio::io::duplex(4096);
+        tokio::spawn(serve_mcp(io::BufReader::new(server_in), server_out, state, None, std::future::pending()));
+
+        let next = serde_json::json!({"jsonrpc": "2.0", "id": 8, "method": "tools/list"});
+        client_in.write_all(b"{\"id\":\xff}\n").await.unwrap();
+        client_in.write_all(format!("{}\n", next).as_bytes()).await.unwrap();
+
+        let mut responses = io::BufReader::new(client_out).lines();
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            let line = tokio::time::timeout(Duration::from_secs(5), responses.next_line())
+                .await
+                .expect("no response")
+                .unwrap()
+                .unwrap();
+            received.push(serde_json::from_str::<serde_json::Value>(&line).unwrap());
+        }
+        assert_eq!(received[0]["error"]["code"], error_codes::PARSE_ERROR);
+        assert_eq!(received[1]["id"], 8);
+    }
+
+    #[tokio::test]
+    async fn test_content_length_framed_request_gets_framed_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = test_state(dir.path());
+        let (mut client_in, server_in) = tokio::io::duplex(4096);
+        let (server_out, mut client_out) = tokio::io::duplex(4096);
+        tokio::spawn(serve_mcp(io::BufReader::new(server_in), server_out, state, None, std::future::pending()));
+
+        let request = serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "tools/list"}).to_string();
+        client_in
+            .write_all(format!("Content-Length: {}\r\n\r\n{}", request.len(), request).as_bytes())
+            .await
+            .unwrap();
+
+        let mut received = Vec::new();
+        let response = tokio::time::timeout(Duration::from_secs(5), async {
+            let mut chunk = [0u8; 4096];
+            loop {
+                let n = tokio::io::AsyncReadExt::read(&mut client_out, &mut chunk).await.unwrap();
+                received.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&received).to_string();
+                if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                    let len: usize = headers.strip_prefix("Content-Length: ").unwrap().parse().unwrap();
+                    if body.len() >= len {
+                        assert_eq!(body.len(), len, "unexpected bytes after the framed body");
+                        break serde_json::from_str::<serde_json::Value>(body).unwrap();
+                    }
+                }
+            }
+        })
+        .await
+        .expect("no framed response");
+        assert_eq!(response["id"], 3);
+        assert!(response["result"]["tools"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_cors_allows_only_configured_origin() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let config = Config {
+            cors_allowed_origins: vec!["https://app.example.com".into()],
+            cors_allowed_methods: vec!["get".into(), "POST".into()],
+            cors_allowed_headers: vec!["content-type".into()],
+            ..Config::default()
+        };
+        let app = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .layer(cors_layer(&config).unwrap());
+        let allow_origin = |origin: &'static str| {
+            let app = app.clone();
+            async move {
+                let req = Request::get("/health").header("origin", origin).body(Body::empty()).unwrap();
+                let resp = app.oneshot(req).await.unwrap();
+                resp.headers().get("access-control-allow-origin").cloned()
+            }
+        };
+
+        assert_eq!(allow_origin("https://app.example.com").await.unwrap(), "https://app.example.com");
+        assert!(allow_origin("https://evil.example.com").await.is_none());
+
+        let bad = Config { cors_allowed_origins: vec!["bad\norigin".into()], ..Config::default() };
+        assert!(cors_layer(&bad).is_err());
+        assert!(cors_layer(&Config { cors_permissive: true, ..Config::default() }).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_are_opt_in_and_behind_api_key() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        assert!(metrics_router(&Config::default()).is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config { metrics_enabled: true, api_key: Some("s3cret".into()), ..Config::default() };
+        let app = metrics_router(&config).unwrap().with_state(test_state(dir.path()));
+        let status = |auth: Option<&'static str>| {
+            let app = app.clone();
+            async move {
+                let mut req = Request::get("/metrics");
+                if let Some(auth) = auth {
+                    req = req.header("authorization", auth);
+                }
+                app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap().status()
+            }
+        };
+        assert_eq!(status(None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(Some("Bearer s3cret")).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_rest_route_with_hung_upstream_times_out() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        // Accepts connections but never answers, like a stalled explorer
+        let upstream = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_url = format!("http://{}/api", upstream.local_addr().unwrap());
+        let hold = tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((conn, _)) = upstream.accept().await {
+                held.push(conn);
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            etherscan_api_key: Some("key".into()),
+            etherscan_base_urls: HashMap::from([("1".to_string(), upstream_url)]),
+            tool_timeout: Some(Duration::from_millis(200)),
+            ..Config::default()
+        };
+        let mut state = test_state(dir.path());
+        state.config = config.clone();
+        let app = rest_router(&config).with_state(state);
+
+        let req = Request::get("/balance/1/0x000000000000000000000000000000000000dEaD")
+            .body(Body::empty())
+            .unwrap();
+        let resp = tokio::time::timeout(Duration::from_secs(5), app.oneshot(req))
+            .await
+            .expect("REST route was not bounded by the tool timeout")
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+        hold.abort();
+    }
+
+    #[tokio::test]
+    async fn test_tx_send_past_timeout_reports_unknown_outcome() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        // Accepts connections but never answers, like a stalled RPC node
+        let upstream = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_url = format!("http://{}", upstream.local_addr().unwrap());
+        let hold = tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((conn, _)) = upstream.accept().await {
+                held.push(conn);
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            chain_rpc_urls: HashMap::from([("31337".to_string(), upstream_url)]),
+            tool_timeout: Some(Duration::from_millis(200)),
+            ..Config::default()
+        };
+        let mut state = test_state(dir.path());
+        state.config = config.clone();
+        let app = rest_router(&config).with_state(state);
+
+        let body = serde_json::json!({
+            "chain_id": "31337",
+            "to": "0x000000000000000000000000000000000000dEaD",
+            "amount_wei": 1,
+            "from_private_key": "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        });
+        let req = Request::post("/tx/send")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let resp = tokio::time::timeout(Duration::from_secs(5), app.oneshot(req))
+            .await
+            .expect("/tx/send was not bounded by the tool timeout")
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["category"], "outcome_unknown");
+        hold.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_notification_is_accepted_without_body() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = Router::new()
+            .route("/rpc", post(rpc_handler))
+            .with_state(test_state(dir.path()));
+        let post_json = |body: serde_json::Value| {
+            Request::post("/rpc")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let notification = serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        let resp = app.clone().oneshot(post_json(notification)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+
+        // Requests with an id are still answered, and malformed ones still rejected
+        let resp = app.clone().oneshot(post_json(serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}))).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app.oneshot(post_json(serde_json::json!({"jsonrpc": "2.0", "id": 2}))).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(v["error"]["code"], error_codes::INVALID_REQUEST);
+    }
+}
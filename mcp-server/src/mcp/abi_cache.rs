@@ -0,0 +1,94 @@
+// src/mcp/abi_cache.rs
+
+//! Verified contract ABIs fetched from Etherscan, kept so `read_contract` and
+//! `write_contract` can be called with just a function name.
+//!
+//! Entries are keyed by chain and address and expire after `ttl`. Only successful
+//! fetches are cached, so an unverified contract is looked up again next time (it
+//! may have been verified since). Entries live in memory only.
+
+use dashmap::DashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long a fetched ABI is reused before Etherscan is asked again.
+pub const DEFAULT_ABI_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+struct Entry {
+    stored_at: Instant,
+    abi: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AbiCache {
+    entries: Arc<DashMap<String, Entry>>,
+    ttl: Duration,
+}
+
+impl Default for AbiCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_ABI_CACHE_TTL)
+    }
+}
+
+impl AbiCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Arc::new(DashMap::new()),
+            ttl,
+        }
+    }
+
+    /// The cached ABI for `address` on `chain_id`, or the result of `fetch`, which is
+    /// cached when it succeeds. The flag is `true` when the ABI came from the cache.
+    pub async fn get_or_fetch<F, Fut, E>(&self, chain_id: &str, address: &str, fetch: F) -> Result<(String, bool), E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String, E>>,
+    {
+        let key = format!("{}:{}", chain_id, address.to_lowercase());
+        if let Some(entry) = self.entries.get(&key).filter(|e| e.stored_at.elapsed() < self.ttl) {
+            return Ok((entry.abi.clone(), true));
+        }
+        let abi = fetch().await?;
+        self.entries.retain(|_, e| e.stored_at.elapsed() < self.ttl);
+        self.entries.insert(key, Entry { stored_at: Instant::now(), abi: abi.clone() });
+        Ok((abi, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_abi_is_reused_per_chain_and_address_until_expiry() {
+        let cache = AbiCache::new(Duration::from_millis(50));
+        let fetches = AtomicUsize::new(0);
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, String>("[]".to_string())
+        };
+
+        assert_eq!(cache.get_or_fetch("1", "0xAbC", fetch).await.unwrap(), ("[]".to_string(), false));
+        // Addresses match regardless of checksum casing
+        assert!(cache.get_or_fetch("1", "0xabc", fetch).await.unwrap().1);
+        assert!(!cache.get_or_fetch("137", "0xabc", fetch).await.unwrap().1);
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(!cache.get_or_fetch("1", "0xabc", fetch).await.unwrap().1);
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_failed_fetch_is_not_cached() {
+        let cache = AbiCache::default();
+        assert!(cache.get_or_fetch("1", "0xabc", || async { Err::<String, _>("not verified") }).await.is_err());
+        let (_, cached) = cache.get_or_fetch("1", "0xabc", || async { Ok::<_, &str>("[]".to_string()) }).await.unwrap();
+        assert!(!cached);
+    }
+}
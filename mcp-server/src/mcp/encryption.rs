@@ -11,6 +11,7 @@ use argon2::{
     Argon2,
 };
 use base64::{engine::general_purpose, Engine as _};
+use zeroize::{Zeroize, Zeroizing};
 
 // FIX: This struct holds the key derived from the master password.
 // It is created once per password verification.
@@ -46,7 +47,7 @@ impl EncryptionKey {
     }
 
     /// Decrypts data using the derived key. The nonce is extracted from the ciphertext itself.
-    pub fn decrypt(&self, encrypted_data: &[u8]) -> Result<String> {
+    pub fn decrypt(&self, encrypted_data: &[u8]) -> Result<Zeroizing<String>> {
         if encrypted_data.len() < 12 {
             return Err(anyhow!("Invalid encrypted data: too short to contain a nonce"));
         }
@@ -60,10 +61,18 @@ impl EncryptionKey {
             .map_err(|e| anyhow!("Decryption failed (likely incorrect password): {}", e))?;
 
         String::from_utf8(decrypted_bytes)
+            .map(Zeroizing::new)
             .map_err(|e| anyhow!("Failed to decode decrypted bytes to UTF-8: {}", e))
     }
 }
 
+// Wipe the derived AES key once it goes out of scope.
+impl Drop for EncryptionKey {
+    fn drop(&mut self) {
+        self.0.as_mut_slice().zeroize();
+    }
+}
+
 
 // FIX: The main encryption function now handles salt generation and storage.
 // The output format is "salt.encrypted_payload", both Base64 encoded.
@@ -80,7 +89,7 @@ pub fn encrypt_private_key(private_key: &str, master_password: &str) -> Result<S
 }
 
 // FIX: The decryption function now parses the salt from the input string.
-pub fn decrypt_private_key(encrypted_string: &str, master_password: &str) -> Result<String> {
+pub fn decrypt_private_key(encrypted_string: &str, master_password: &str) -> Result<Zeroizing<String>> {
     let parts: Vec<&str> = encrypted_string.split('.').collect();
     if parts.len() != 2 {
         return Err(anyhow!("Invalid encrypted string format"));
@@ -97,4 +106,25 @@ pub fn decrypt_private_key(encrypted_string: &str, master_password: &str) -> Res
 
     let key = EncryptionKey::new(master_password, &salt)?;
     key.decrypt(&encrypted_payload)
-}
\ No newline at end of file
+}
+
+/// Generates a fresh random salt for deriving a per-wallet encryption key.
+pub fn generate_salt() -> SaltString {
+    SaltString::generate(&mut OsRng)
+}
+
+/// Encrypts with a key derived from the password and the given salt.
+/// Returns only the Base64 payload; the caller stores the salt alongside it.
+pub fn encrypt_with_salt(plaintext: &str, master_password: &str, salt: &SaltString) -> Result<String> {
+    let key = EncryptionKey::new(master_password, salt)?;
+    let encrypted_payload = key.encrypt(plaintext)?;
+    Ok(general_purpose::STANDARD_NO_PAD.encode(encrypted_payload))
+}
+
+/// Reverses `encrypt_with_salt`; `salt` is the stored `SaltString` text.
+pub fn decrypt_with_salt(payload_b64: &str, master_password: &str, salt: &str) -> Result<Zeroizing<String>> {
+    let salt = SaltString::from_b64(salt).map_err(|e| anyhow!("Invalid salt format: {}", e))?;
+    let encrypted_payload = general_purpose::STANDARD_NO_PAD.decode(payload_b64)?;
+    let key = EncryptionKey::new(master_password, &salt)?;
+    key.decrypt(&encrypted_payload)
+}
@@ -9,54 +9,105 @@
 //! - `create_wallet` - Generate a new EVM wallet
 //! - `import_wallet` - Import wallet from private key or mnemonic
 //! - `register_wallet` - Store wallet securely with encryption
+//! - `create_and_register_wallet` - Generate and store a wallet without exposing its key
 //! - `list_wallets` - List all stored wallets
+//! - `find_wallet_by_address` - Name of the stored wallet holding an address (no key)
 //! - `transfer_from_wallet` - Send transactions from stored wallets
+//! - `set_wallet_limit` - Set a daily spending limit for a stored wallet
+//! - `change_master_password` - Rotate the master password and re-encrypt stored wallets
+//! - `reencrypt_wallet` - Re-encrypt one stored wallet under a fresh salt (upgrades legacy entries)
+//! - `speed_up_from_wallet` - Rebroadcast a stuck stored-wallet transaction with a higher fee
+//! - `replace_transaction` - Replace or cancel a pending transaction by nonce
+//! - `export_private_key` - Reveal a stored wallet's private key (if ALLOW_KEY_EXPORT)
+//! - `derive_accounts` - List addresses derived from a mnemonic (no keys)
+//! - `address_from_key` - Address a private key or mnemonic controls (no storage, no key echo)
 //!
 //! ### Blockchain Operations
 //! - `get_balance` - Query account balances
-//! - `search_events` - Search for EVM log events
+//! - `get_account_state` - Balance, latest/pending nonce and contract flag of an address in one call
+//! - `search_events` - Search for EVM log events (Etherscan or RPC `eth_getLogs`), optionally decoded
 //! - `request_faucet` - Request testnet tokens
+//! - `wait_for_balance` - Poll a native or ERC20 balance until it reaches a target
 //! - `transfer_evm` - Send EVM value transfers
 //! - `transfer_nft_evm` - Transfer ERC-721 tokens
+//! - `wait_for_confirmations` - Poll a transaction until it has N block confirmations
+//! - `list_chains` - Discover configured chains, their names and accepted aliases
+//! - `server_info` - Server version, build commit, configured chains and enabled integrations
+//! - `get_chain_tip_health` - Detect stalled or lagging RPC endpoints
+//! - `get_wallet_token_balances` - Non-zero native/ERC20 balances of a stored wallet across chains
+//! - `get_portfolio` - Native and ERC20 balances of any address for a list of tokens
+//! - `discover_tokens` - Find held ERC20 tokens from Etherscan transfer history, with balances
+//! - `compare_gas_costs` - Rank chains by the estimated fee of a transfer or call
+//! - `keccak256` - Hash a string (e.g. an event signature to get topic0)
+//! - `compute_selector` - 4-byte selector of a function signature
+//! - `lookup_selector` - Candidate signatures for a selector or event topic
+//! - `abi_encode` - Build calldata from a function signature and arguments
+//! - `abi_decode` - Decode ABI-encoded hex data given a type list
+//! - `decode_calldata` - Decode transaction input into the function called and its named arguments
 //!
 //! ### Contract Interaction
 //! - `get_contract` - Get contract information
-//! - `get_contract_code` - Get contract bytecode
+//! - `get_contract_code` - Get deployed runtime bytecode via eth_getCode
+//! - `get_contract_code_normalized` - Get verified source, ABI and compiler settings in a fixed schema
 //! - `get_contract_transactions` - Get contract transaction history
-//! - `is_contract` - Check if address is a contract
-//! - `read_contract` - Read from contract via ABI
-//! - `write_contract` - Write to contract via ABI
+//! - `is_contract` - Check if address is a contract or an EOA, and whether it is verified
+//! - `validate_address` - Check address format, EIP-55 checksum and (optionally) contract code
+//! - `read_contract` - Read from contract via ABI (fetched from Etherscan and cached if omitted)
+//! - `batch_read_contract` - Read many functions of one contract via Multicall3
+//! - `write_contract` - Write to contract via ABI (fetched if omitted), approving an ERC-20 spend first if needed
+//! - `get_storage_at` - Read a raw storage slot, optionally of a mapping entry
 //!
 //! ### Token Operations
 //! - `get_token_info` - Get ERC-20 token metadata
+//! - `detect_token_standard` - Detect ERC-20/721/1155 via ERC-165
 //! - `get_token_balance` - Check ERC-20 token balance
 //! - `transfer_token` - Transfer ERC-20 tokens
+//! - `transfer_token_from` - Transfer ERC-20 tokens using an allowance
+//! - `modify_token_allowance` - Increase or decrease an ERC-20 allowance
 //! - `get_nft_info` - Get ERC-721 token metadata
 //! - `check_nft_ownership` - Verify NFT ownership
 //! - `transfer_erc1155` - Transfer ERC-1155 tokens
+//! - `get_erc1155_balance_batch` - Check several ERC-1155 balances at once
+//! - `transfer_erc1155_batch` - Transfer several ERC-1155 token ids at once
+//! - `get_amounts_out` - Quote a swap through a UniswapV2-style router
 //!
 //! ### External Integrations
 //! - Discord messaging and health checks
 
 use crate::{
     blockchain::{
+        chains,
         models::WalletResponse,
-        services::{transactions, wallet},
+        services::{
+            abi, contract, dex, events, history, logs, rpc, signatures, token,
+            transactions::{self, GasDefaults, SendOptions},
+            wallet,
+        },
+        wallet_manager::{address_from_key, derive_accounts},
     },
     mcp::{
-        protocol::{error_codes, Request, Response},
-        wallet_storage,
+        idempotency::IdempotencyError,
+        protocol::{error_codes, ErrorCategory, Request, Response},
+        validation, wallet_storage,
     },
-    utils, AppState,
+    metrics,
+    utils::{self, units},
+    AppState,
 };
+use chrono::Utc;
 use ethers_core::abi::{encode, Token};
 use ethers_core::types::{Address, Bytes, TransactionRequest, U256};
-use ethers_core::utils::keccak256;
+use ethers_core::utils::to_checksum;
 use ethers_signers::{LocalWallet, Signer};
+use futures::future::join_all;
 use reqwest::Client;
 use serde_json::{json, Value};
+use std::future::Future;
 use std::str::FromStr;
-use tracing::{error, info};
+use std::time::{Duration, Instant};
+use tracing::{error, info, info_span, warn, Instrument};
+use uuid::Uuid;
+use zeroize::Zeroizing;
 
 // Normalize common chain_id aliases users might pass via MCP
 pub fn normalize_chain_id(input: &str) -> String {
@@ -70,17 +121,8 @@ pub fn normalize_chain_id(input: &str) -> String {
     }
 
     // Common aliases for supported EVM networks
-    if s == "mainnet" || s == "main" || s == "m" || s == "eth" || s == "ethereum" {
-        return "1".to_string();
-    }
-    if s == "sepolia" {
-        return "11155111".to_string();
-    }
-    if s == "zksync" || s == "zk" {
-        return "324".to_string();
-    }
-    if s == "zksync-sepolia" || s == "zk-sepolia" || s == "testnet" || s == "test" || s == "t" {
-        return "11155111".to_string(); // Default testnet to Sepolia
+    if let Some(chain_id) = chains::chain_id_for_alias(&s) {
+        return chain_id.to_string();
     }
 
     s
@@ -139,17 +181,534 @@ fn make_texty_result(text: String, payload: Value) -> Value {
     }
 }
 
+// Helper: make sure wallet storage is loaded and unlocked with the given master password.
+// Lazy-loads it from disk when nothing is in memory yet (same as register_wallet).
+#[allow(clippy::result_large_err)]
+async fn unlock_wallet_storage(
+    state: &AppState,
+    master_password: &str,
+    req_id: &Value,
+) -> Result<(), Response> {
+    let master_password = Zeroizing::new(master_password.to_string());
+    let path = state.wallet_storage_path.clone();
+    let unlocked = wallet_storage::update_blocking(&state.wallet_storage, move |storage| {
+        if storage.is_master_password_hash_empty() {
+            *storage = wallet_storage::load_or_create_wallet_storage(&path, &master_password)?;
+            Ok(true)
+        } else {
+            Ok(storage.verify_master_password(&master_password))
+        }
+    })
+    .await
+    .map_err(|e: anyhow::Error| {
+        Response::error_with_category(
+            req_id.clone(),
+            error_codes::INTERNAL_ERROR,
+            ErrorCategory::of_error(error_codes::INTERNAL_ERROR, &*e),
+            format!("Failed to initialize wallet storage: {}", e),
+        )
+    })?;
+    if !unlocked {
+        return Err(Response::error_with_category(
+            req_id.clone(),
+            error_codes::INTERNAL_ERROR,
+            ErrorCategory::AuthFailed,
+            "Authentication failed".into(),
+        ));
+    }
+    Ok(())
+}
+
+// Helper: unlock storage with `master_password` and add the wallet; `add_wallet` persists
+// it under the storage file lock.
+#[allow(clippy::result_large_err)]
+async fn store_wallet(
+    state: &AppState,
+    wallet_name: &str,
+    private_key: &str,
+    address: &str,
+    master_password: &str,
+    req_id: &Value,
+) -> Result<(), Response> {
+    unlock_wallet_storage(state, master_password, req_id).await?;
+    let (wallet_name, address) = (wallet_name.to_string(), address.to_string());
+    let private_key = Zeroizing::new(private_key.to_string());
+    let master_password = Zeroizing::new(master_password.to_string());
+    wallet_storage::update_blocking(&state.wallet_storage, move |storage| {
+        storage.add_wallet(wallet_name, &private_key, address, &master_password)
+    })
+    .await
+    .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))
+}
+
+// Helper: whether `create_wallet`/`import_wallet` may echo the private key and mnemonic.
+// SUPPRESS_KEY_OUTPUT wins over a per-call `reveal: true`.
+fn reveal_keys(state: &AppState, args: &Value) -> bool {
+    !state.config.suppress_key_output && args.get("reveal").and_then(|v| v.as_bool()).unwrap_or(true)
+}
+
+// Helper: the result of `create_wallet`/`import_wallet`. The wallet is stored when both
+// `wallet_name` and `master_password` are given; keys are left out unless `reveal_keys`.
+#[allow(clippy::result_large_err)]
+async fn new_wallet_result(
+    state: &AppState,
+    args: &Value,
+    req_id: &Value,
+    verb: &str,
+    wallet: &WalletResponse,
+) -> Result<Value, Response> {
+    let wallet_name = args.get("wallet_name").and_then(|v| v.as_str());
+    let master_password = args
+        .get("master_password")
+        .and_then(|v| v.as_str())
+        .map(|p| Zeroizing::new(p.to_string()));
+    match (wallet_name, &master_password) {
+        (Some(name), Some(password)) => {
+            store_wallet(state, name, &wallet.private_key, &wallet.address, password, req_id).await?
+        }
+        (None, None) => {}
+        _ => {
+            return Err(Response::error(
+                req_id.clone(),
+                error_codes::INVALID_PARAMS,
+                "wallet_name and master_password must be given together".into(),
+            ))
+        }
+    }
+    let stored_text = wallet_name
+        .map(|name| format!("\nStored as wallet '{}'", name))
+        .unwrap_or_default();
+
+    if !reveal_keys(state, args) {
+        let summary = format!(
+            "{} EVM wallet\nAddress: {}{}\nThe private key and mnemonic are not returned.",
+            verb, wallet.address, stored_text
+        );
+        let payload = json!({
+            "address": wallet.address,
+            "wallet_name": wallet_name,
+            "chain_type": "evm",
+        });
+        return Ok(make_texty_result(summary, payload));
+    }
+
+    // Create a comprehensive response with all wallet details
+    let comprehensive_wallet = json!({
+        "address": wallet.address,
+        "private_key": wallet.private_key,
+        "mnemonic": wallet.mnemonic,
+        "wallet_name": wallet_name,
+        "chain_type": "evm",
+    });
+
+    let mnemonic_text = wallet
+        .mnemonic
+        .as_ref()
+        .map(|m| format!("\nMnemonic: {}", m))
+        .unwrap_or_else(|| "\nMnemonic: Not available".to_string());
+
+    let summary = format!(
+        "{} EVM wallet with complete details:\nAddress: {}\nPrivate Key: {}{}{}",
+        verb, wallet.address, wallet.private_key, mnemonic_text, stored_text
+    );
+    Ok(make_texty_result(summary, comprehensive_wallet))
+}
+
+// Helper: run a transfer's `send` at most once per `idempotency_key` argument (within the
+// store's TTL), scoped to the tool. A retry gets the first result back with `replayed: true`;
+// reusing the key with different arguments is an `INVALID_PARAMS` error.
+#[allow(clippy::result_large_err)]
+async fn send_idempotent<F, Fut>(
+    state: &AppState,
+    tool: &str,
+    args: &Value,
+    req_id: &Value,
+    send: F,
+) -> Result<Value, Response>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Value, Response>>,
+{
+    match args.get("idempotency_key").and_then(|v| v.as_str()) {
+        Some(key) => {
+            // Object keys serialize sorted, so equal arguments give equal fingerprints
+            let mut fingerprint = args.clone();
+            if let Some(map) = fingerprint.as_object_mut() {
+                map.remove("idempotency_key");
+            }
+            let run = state
+                .idempotency
+                .run(&format!("{}:{}", tool, key), &fingerprint.to_string(), send)
+                .await;
+            let (mut result, replayed) = match run {
+                Ok(run) => run,
+                Err(IdempotencyError::Send(resp)) => return Err(resp),
+                Err(IdempotencyError::KeyReused) => {
+                    return Err(Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        format!("idempotency_key '{}' was already used for a {} call with different arguments", key, tool),
+                    ))
+                }
+            };
+            if replayed {
+                result["replayed"] = json!(true);
+            }
+            Ok(result)
+        }
+        None => send().await,
+    }
+}
+
+// Helper: a speed-up/replacement result back from its (possibly replayed) JSON form, for the summary line.
+#[allow(clippy::result_large_err)]
+fn replacement_of(result: &Value, req_id: &Value) -> Result<transactions::ReplacementResponse, Response> {
+    serde_json::from_value(result.clone()).map_err(|e| {
+        Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, format!("Unexpected replacement result: {}", e))
+    })
+}
+
+// Helper: the optional `block` argument (number, hash or tag) as a JSON-RPC block parameter.
+// `None` when the argument is absent, so callers can tell "latest by default" apart.
+#[allow(clippy::result_large_err)]
+fn block_arg(args: &Value, req_id: &Value) -> Result<Option<Value>, Response> {
+    let Some(block) = args.get("block").filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+    let block = match block {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    rpc::block_param(Some(&block))
+        .map(Some)
+        .map_err(|e| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string()))
+}
+
+// Helper: per-send switches shared by every tool that broadcasts a transaction.
+// The send is pinned to `chain_id` unless ALLOW_CHAIN_ID_MISMATCH is set; a chain whose
+// numeric ID isn't known is refused rather than sent unchecked.
+#[allow(clippy::result_large_err)]
+fn send_options(state: &AppState, chain_id: &str, args: &Value, req_id: &Value) -> Result<SendOptions, Response> {
+    let expected_chain_id = if state.config.allow_chain_id_mismatch {
+        None
+    } else {
+        Some(chains::numeric_chain_id(chain_id).ok_or_else(|| {
+            Response::error(
+                req_id.clone(),
+                error_codes::INVALID_PARAMS,
+                format!(
+                    "Cannot tell the numeric chain ID of '{}' to check the RPC against; use the numeric ID or set ALLOW_CHAIN_ID_MISMATCH",
+                    chain_id
+                ),
+            )
+        })?)
+    };
+    let gwei = |key: &str| {
+        args.get(key)
+            .and_then(|v| v.as_str())
+            .map(|amount| {
+                utils::gwei_to_wei(amount).map_err(|e| {
+                    Response::error(req_id.clone(), error_codes::INVALID_PARAMS, format!("'{}': {}", key, e))
+                })
+            })
+            .transpose()
+    };
+    let max_fee_per_gas = gwei("max_fee_gwei")?;
+    let max_priority_fee_per_gas = gwei("priority_fee_gwei")?;
+    let gas_price_given = args.get("gas_price").is_some() || args.get("gas_price_gwei").is_some();
+    if gas_price_given && (max_fee_per_gas.is_some() || max_priority_fee_per_gas.is_some()) {
+        return Err(Response::error(
+            req_id.clone(),
+            error_codes::INVALID_PARAMS,
+            "Pass either a gas price or 'max_fee_gwei'/'priority_fee_gwei', not both".into(),
+        ));
+    }
+    Ok(SendOptions {
+        skip_balance_check: args.get("skip_balance_check").and_then(|v| v.as_bool()).unwrap_or(false),
+        expected_chain_id,
+        simulate: args.get("safe_send").and_then(|v| v.as_bool()).unwrap_or(false),
+        gas_buffer_percent: state.config.gas_limit_buffer_pct(),
+        max_gas_limit: state.config.max_gas_limits.get(chain_id).map(|g| U256::from(*g)),
+        gas_defaults: args
+            .get("use_config_gas_defaults")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(state.config.use_config_gas_defaults)
+            .then(|| GasDefaults::from_config(&state.config)),
+        fee_type: state.config.fee_type(chain_id),
+        fee_support: state.fee_support.clone(),
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        http_client: state.http_client.clone(),
+    })
+}
+
+// Helper: the amount of an ERC-20 tool in base units, either as given in `amount`
+// (`amount_wei`) or from `amount_decimal` scaled by the token's decimals().
+async fn erc20_amount(state: &AppState, chain_id: &str, token: &str, args: &Value, req_id: &Value) -> Result<String, Response> {
+    let base_units = args.get("amount").or_else(|| args.get("amount_wei")).and_then(|v| v.as_str());
+    let decimal = args.get("amount_decimal").and_then(|v| v.as_str());
+    let invalid = |message: String| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, message);
+    match (base_units, decimal) {
+        (Some(_), Some(_)) => Err(invalid("Pass either 'amount' (base units) or 'amount_decimal', not both".into())),
+        (Some(amount), None) => Ok(amount.to_string()),
+        (None, Some(amount)) => {
+            let rpc_url = state.config.chain_rpc_urls.get(chain_id).ok_or_else(|| {
+                Response::error_with_category(
+                    req_id.clone(),
+                    error_codes::INVALID_PARAMS,
+                    ErrorCategory::NotConfigured,
+                    format!("RPC URL not configured for chain_id '{}'", chain_id),
+                )
+            })?;
+            let decimals = token::erc20_decimals(&state.http_client, rpc_url, token)
+                .await
+                .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
+            units::parse_units(amount, decimals)
+                .map(|n| n.to_string())
+                .map_err(|e| invalid(format!("'amount_decimal': {}", e)))
+        }
+        (None, None) => utils::get_required_arg::<String>(args, "amount", req_id),
+    }
+}
+
+// Helper: `write_contract`'s `ensure_allowance` option. Reads the sender's allowance of
+// `token` for `spender` and, when it is below `amount`, sends an approve (for `amount`, or
+// the max uint256 with `approve: "max"`) and waits for it to be mined so the call that
+// follows sees it.
+#[allow(clippy::result_large_err)]
+async fn ensure_allowance(
+    state: &AppState,
+    chain_id: &str,
+    private_key: &str,
+    spender: &str,
+    spec: &Value,
+    args: &Value,
+    req_id: &Value,
+) -> Result<Value, Response> {
+    let invalid = |msg: String| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, msg);
+    let internal = |msg: String| Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, msg);
+    let token = spec
+        .get("token")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| invalid("'ensure_allowance' needs a 'token' address".into()))?;
+    let required = spec
+        .get("amount")
+        .and_then(|v| v.as_str())
+        .and_then(|a| U256::from_dec_str(a.trim()).ok())
+        .ok_or_else(|| invalid("'ensure_allowance' needs an 'amount' in token base units (decimal string)".into()))?;
+    let approve_max = match spec.get("approve").and_then(|v| v.as_str()).unwrap_or("exact") {
+        "exact" => false,
+        "max" => true,
+        other => return Err(invalid(format!("Unknown approve mode '{}'; expected 'exact' or 'max'", other))),
+    };
+    let owner = LocalWallet::from_str(private_key)
+        .map_err(|e| invalid(format!("Invalid private key: {}", e)))?
+        .address();
+    let rpc_url = state
+        .config
+        .chain_rpc_urls
+        .get(chain_id)
+        .ok_or_else(|| invalid(format!("RPC URL not configured for chain_id '{}'", chain_id)))?;
+
+    let started = Instant::now();
+    let client = state.http_client.clone();
+    let current = crate::blockchain::services::token::erc20_allowance(
+        &client,
+        rpc_url,
+        token,
+        &format!("{:?}", owner),
+        spender,
+        &json!("latest"),
+    )
+    .await
+    .map_err(|e| internal(format!("Failed to read allowance: {}", e)))?;
+    let current = current["decoded"]
+        .as_str()
+        .and_then(|a| U256::from_dec_str(a).ok())
+        .ok_or_else(|| internal(format!("{} did not return an allowance", token)))?;
+    let mut result = json!({
+        "token": token,
+        "spender": spender,
+        "required": required.to_string(),
+        "allowance": current.to_string(),
+        "approved": false,
+    });
+    if current >= required {
+        return Ok(result);
+    }
+
+    let approve_amount = if approve_max { U256::MAX } else { required };
+    let mut tx = crate::blockchain::services::token::erc20_approve_tx(token, spender, &approve_amount.to_string())
+        .map_err(|e| invalid(e.to_string()))?;
+    if let Some(gp) = utils::get_gas_price(args, req_id)? {
+        tx = tx.gas_price(gp);
+    }
+    let approval = state
+        .evm_client
+        .send_transaction_with_options(chain_id, private_key, tx, &state.nonce_manager, &send_options(state, chain_id, args, req_id)?)
+        .await
+        .map_err(|e| internal(format!("Approve failed: {}", e)))?;
+    let mined = transactions::wait_for_confirmations(
+        &client,
+        rpc_url,
+        &approval.tx_hash,
+        0,
+        approve_wait_budget(state.config.tool_timeout(), started.elapsed(), state.config.receipt_poll_interval()),
+        state.config.receipt_poll_interval(),
+    )
+    .await
+    .map_err(|e| internal(format!("Failed to check approve {}: {}", approval.tx_hash, e)))?;
+    if mined.reverted {
+        return Err(internal(format!("Approve {} reverted; the call was not sent", approval.tx_hash)));
+    }
+    if !mined.met {
+        return Err(internal(format!(
+            "Approve {} was not mined within {}s; the call was not sent",
+            approval.tx_hash,
+            mined.elapsed.as_secs()
+        )));
+    }
+    result["approved"] = json!(true);
+    result["approve_amount"] = json!(approve_amount.to_string());
+    result["approve_tx_hash"] = json!(approval.tx_hash);
+    Ok(result)
+}
+
+// Helper: how long `ensure_allowance` may wait for its approve to be mined. The wait stops
+// a poll short of the tool call time limit, minus what has been spent already and a quarter
+// of the limit kept for sending the call itself, so "not mined" is reported in time.
+fn approve_wait_budget(tool_timeout: Duration, elapsed: Duration, interval: Duration) -> Duration {
+    tool_timeout.saturating_sub(elapsed).saturating_sub(interval).saturating_sub(tool_timeout / 4)
+}
+
+// Helper: the chain an explorer lookup targets: explicit `chain_id`, else inferred from the
+// natural-language arguments, else Ethereum mainnet.
+fn explorer_chain_id(args: &Value) -> String {
+    let chain = args
+        .get("chain_id")
+        .and_then(|v| v.as_str())
+        .map(normalize_chain_id)
+        .or_else(|| infer_evm_chain_from_args(args));
+    normalize_chain_id(&chain.unwrap_or_else(|| "1".to_string()))
+}
+
+// Helper: the configured Etherscan API key, or the error telling the client it is missing.
+#[allow(clippy::result_large_err)]
+fn etherscan_api_key<'a>(state: &'a AppState, req_id: &Value) -> Result<&'a str, Response> {
+    state.config.etherscan_api_key.as_deref().ok_or_else(|| {
+        Response::error_with_category(
+            req_id.clone(),
+            error_codes::INVALID_PARAMS,
+            ErrorCategory::NotConfigured,
+            "ETHERSCAN_API_KEY is not configured".to_string(),
+        )
+    })
+}
+
+// Helper: Etherscan base URL for `chain_id` (ETHERSCAN_BASE_URLS or the built-in endpoint),
+// rejecting chains with neither.
+#[allow(clippy::result_large_err)]
+fn etherscan_base_url(state: &AppState, chain_id: &str, req_id: &Value) -> Result<String, Response> {
+    state.config.etherscan_base_url(chain_id)
+        .map_err(|e| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string()))
+}
+
+// Helper: the verified ABI of `contract` from Etherscan, for `read_contract`/`write_contract`
+// calls that name a function without passing `abi`. Cached per chain and address; the flag
+// is `true` on a cache hit. Unverified contracts still need an explicit ABI.
+#[allow(clippy::result_large_err)]
+async fn verified_abi(state: &AppState, chain_id: &str, contract: &str, req_id: &Value) -> Result<(String, bool), Response> {
+    state
+        .abi_cache
+        .get_or_fetch(chain_id, contract, || async {
+            let etherscan_api_key = etherscan_api_key(state, req_id)?;
+            let etherscan_base_url = etherscan_base_url(state, chain_id, req_id)?;
+            let record = contract::get_source_record(&state.http_client, &etherscan_base_url, chain_id, contract, etherscan_api_key)
+                .await
+                .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
+            // Unverified contracts come back with "Contract source code not verified" as the ABI
+            match record.get("ABI").and_then(|v| v.as_str()) {
+                Some(abi) if serde_json::from_str::<Vec<Value>>(abi).is_ok() => Ok(abi.to_string()),
+                _ => Err(Response::error(
+                    req_id.clone(),
+                    error_codes::INVALID_PARAMS,
+                    format!("Contract {} has no verified ABI on chain {}; pass 'abi' explicitly", contract, chain_id),
+                )),
+            }
+        })
+        .await
+}
+
 /// This is the main dispatcher for all incoming MCP requests.
+///
+/// Each request runs inside an `mcp_request` span carrying a generated `request_id`, so every
+/// log line it produces (services included) can be correlated. Error responses repeat the
+/// ID in `error.data.request_id` for the client to quote.
 pub async fn handle_mcp_request(req: Request, state: AppState) -> Option<Response> {
+    let request_id = Uuid::new_v4().to_string();
+    let span = info_span!("mcp_request", request_id = %request_id, method = %req.method, rpc_id = %req.id);
+    let mut response = dispatch_mcp_request(req, state).instrument(span).await?;
+    let error_data = match (response.error.as_mut(), response.result.as_mut()) {
+        (Some(e), _) => e.data.as_mut(),
+        // Tool failure reported as an `isError` result
+        (None, Some(result)) if result["isError"] == true => result.get_mut("error").and_then(|e| e.get_mut("data")),
+        _ => None,
+    };
+    if let Some(Value::Object(data)) = error_data {
+        data.insert("request_id".to_string(), json!(request_id));
+    }
+    Some(response)
+}
+
+/// Handles one incoming JSON-RPC message: a single request or a batch (array) of them.
+///
+/// Batch entries run concurrently and their responses come back as an array in request
+/// order, without entries for notifications. Returns `None` when nothing needs answering
+/// (a notification, or a batch of only notifications). An empty batch is an Invalid Request.
+pub async fn handle_mcp_message(msg: Value, state: AppState) -> Option<Value> {
+    match msg {
+        Value::Array(items) if items.is_empty() => serde_json::to_value(Response::error(
+            Value::Null,
+            error_codes::INVALID_REQUEST,
+            "Invalid request: empty batch".into(),
+        ))
+        .ok(),
+        Value::Array(items) => {
+            let responses: Vec<Value> = join_all(items.into_iter().map(|item| handle_single_message(item, state.clone())))
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        single => handle_single_message(single, state).await,
+    }
+}
+
+async fn handle_single_message(msg: Value, state: AppState) -> Option<Value> {
+    let response = match serde_json::from_value::<Request>(msg) {
+        Ok(req) => handle_mcp_request(req, state).await?,
+        Err(e) => Response::error(Value::Null, error_codes::INVALID_REQUEST, format!("Invalid request: {}", e)),
+    };
+    serde_json::to_value(response).ok()
+}
+
+async fn dispatch_mcp_request(req: Request, state: AppState) -> Option<Response> {
     info!("Handling MCP request for method: {}", req.method);
 
     if req.is_notification() {
         return None;
     }
+    let metrics = state.metrics.clone();
+    let mut method = req.method.clone();
 
     let response = match req.method.as_str() {
         "initialize" => handle_initialize(&req),
         "tools/list" => handle_tools_list(&req),
+        "prompts/list" => handle_prompts_list(&req),
+        "prompts/get" => handle_prompts_get(&req),
+        "resources/list" => Response::success(req.id.clone(), json!({ "resources": [] })),
         "tools/call" => handle_tool_call(req, state).await,
         // Convenience aliases to support direct method calls from CLI
         // They are rewritten into tools/call internally to reuse the same logic
@@ -174,18 +733,140 @@ pub async fn handle_mcp_request(req: Request, state: AppState) -> Option<Respons
             };
             handle_tool_call(wrapped, state).await
         }
-        _ => Response::error(
-            req.id,
-            error_codes::METHOD_NOT_FOUND,
-            format!("Method not found: {}", req.method),
-        ),
+        _ => {
+            // Counted as "unknown" so clients can't mint metric labels
+            method = "unknown".to_string();
+            Response::error(
+                req.id,
+                error_codes::METHOD_NOT_FOUND,
+                format!("Method not found: {}", req.method),
+            )
+        }
     };
+    metrics.record_request(&method);
 
     Some(response)
 }
 
-/// Handles a 'tools/call' request by dispatching it to the correct tool logic.
+// Tools that broadcast a transaction. A timed-out call to one of these is left to finish in
+// the background rather than cancelled, since the transaction may already be out: dropping
+// it then would skip the daily-spend and idempotency bookkeeping that follows the broadcast.
+const BROADCASTING_TOOLS: &[&str] = &[
+    "request_faucet",
+    "transfer_evm",
+    "transfer_nft_evm",
+    "transfer_from_wallet",
+    "transfer_token",
+    "approve_token_spending",
+    "transfer_token_from",
+    "modify_token_allowance",
+    "transfer_erc1155",
+    "transfer_erc1155_batch",
+    "write_contract",
+    "speed_up_from_wallet",
+    "replace_transaction",
+];
+
+/// Handles a 'tools/call' request and records its outcome in the metrics.
 async fn handle_tool_call(req: Request, state: AppState) -> Response {
+    let tool = req
+        .params
+        .as_ref()
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .unwrap_or("unknown")
+        .to_string();
+    let metrics = state.metrics.clone();
+    let errors_as_results = state.config.tool_errors_as_results;
+    let limit = state.config.tool_timeout();
+    let id = req.id.clone();
+    // The timeout keeps a hung RPC from holding the client's response forever; a read-only
+    // tool's task is aborted with it, a broadcasting one is left to finish its send
+    let mut task = tokio::spawn(dispatch_tool_call(req, state));
+    let response = match tokio::time::timeout(limit, &mut task).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => {
+            error!("Tool '{}' panicked: {}", tool, e);
+            Response::error(id, error_codes::INTERNAL_ERROR, format!("Tool '{}' failed unexpectedly", tool))
+        }
+        Err(_) if BROADCASTING_TOOLS.contains(&tool.as_str()) => Response::error_with_category(
+            id,
+            error_codes::INTERNAL_ERROR,
+            ErrorCategory::OutcomeUnknown,
+            format!(
+                "Tool '{}' did not finish within {}s and is still running; the transaction may already have been broadcast. \
+                 Check the sender's transactions before retrying, or retry with the same idempotency_key",
+                tool,
+                limit.as_secs_f64()
+            ),
+        ),
+        Err(_) => {
+            task.abort();
+            Response::error_with_category(
+                id,
+                error_codes::INTERNAL_ERROR,
+                ErrorCategory::RpcUnreachable,
+                format!("Tool '{}' timed out after {}s", tool, limit.as_secs_f64()),
+            )
+        }
+    };
+    let response = with_structured_content(&tool, response);
+    // Unknown tools are counted as "unknown" so clients can't mint metric labels
+    let not_found = response.error.as_ref().is_some_and(|e| e.code == error_codes::METHOD_NOT_FOUND);
+    let tool = if not_found { "unknown" } else { &tool };
+    metrics.record_tool_call(tool, response.error.is_none());
+    if errors_as_results {
+        tool_error_as_result(response)
+    } else {
+        response
+    }
+}
+
+// Tools that declare an `outputSchema` also answer with `structuredContent`: the result
+// fields minus the text `content`, which is what schema-aware clients validate. A payload
+// that has drifted from its schema is logged rather than failed; the result is still usable.
+fn with_structured_content(tool: &str, mut response: Response) -> Response {
+    let Some(schema) = tool_schema(tool, "outputSchema") else {
+        return response;
+    };
+    if let Some(Value::Object(result)) = response.result.as_mut() {
+        let mut structured = result.clone();
+        structured.remove("content");
+        let structured = Value::Object(structured);
+        let mismatches = validation::output_mismatches(&schema, &structured);
+        if !mismatches.is_empty() {
+            warn!(tool, ?mismatches, "tool result does not match its outputSchema");
+        }
+        result.insert("structuredContent".into(), structured);
+    }
+    response
+}
+
+// MCP convention (TOOL_ERRORS_AS_RESULTS): a tool that ran and failed answers with a result
+// flagged `isError`, so the model sees the failure text. Bad arguments and unknown tools stay
+// JSON-RPC errors. The original code and data are kept under `error`.
+fn tool_error_as_result(response: Response) -> Response {
+    let Some(err) = response.error else {
+        return response;
+    };
+    let category = err.data.as_ref().and_then(|d| d.get("category")).and_then(|c| c.as_str());
+    if matches!(category, Some("invalid_arg" | "method_not_found")) {
+        return Response { error: Some(err), ..response };
+    }
+    Response::success(
+        response.id,
+        json!({
+            "content": [{ "type": "text", "text": err.message }],
+            "isError": true,
+            "error": { "code": err.code, "data": err.data }
+        }),
+    )
+}
+
+/// Dispatches a 'tools/call' request to the correct tool logic.
+// Tool arms return `Result<Response, Response>` so `?` can short-circuit with a JSON-RPC error.
+#[allow(clippy::result_large_err)]
+async fn dispatch_tool_call(req: Request, state: AppState) -> Response {
     let params = match req.params.as_ref() {
         Some(p) => p,
         None => {
@@ -208,8 +889,31 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         }
     };
 
-    let empty_args = json!({});
-    let args = params.get("arguments").unwrap_or(&empty_args);
+    let mut owned_args = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+    utils::stringify_chain_ids(&mut owned_args);
+    if let Some(schema) = tool_schema(tool_name, "inputSchema") {
+        let problems = validation::validate_args(&schema, &mut owned_args);
+        if !problems.is_empty() {
+            let summary = problems
+                .iter()
+                .map(|p| {
+                    format!(
+                        "'{}': {}",
+                        p["field"].as_str().unwrap_or(""),
+                        p["problem"].as_str().unwrap_or("")
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+            return Response::error_with_data(
+                req.id,
+                error_codes::INVALID_PARAMS,
+                format!("Invalid arguments for tool '{}': {}", tool_name, summary),
+                json!({ "problems": problems }),
+            );
+        }
+    }
+    let args = &owned_args;
     let req_id = &req.id;
 
     // FIX: All tool logic is now wrapped in an async block for clean error handling
@@ -218,9 +922,10 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "discord_post_message" => {
             let res: Result<Response, Response> = (async {
                 let base = state.config.discord_api_url.clone().ok_or_else(|| {
-                    Response::error(
+                    Response::error_with_category(
                         req_id.clone(),
                         error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
                         "DISCORD_API_URL is not configured on the server".into(),
                     )
                 })?;
@@ -230,7 +935,7 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 let client = Client::new();
                 let payload = json!({ "message": message, "username": username });
                 let resp = client.post(url).json(&payload).send().await.map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                 })?;
                 let status = resp.status();
                 let body: Value = resp
@@ -238,9 +943,15 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                     .await
                     .unwrap_or_else(|_| json!({"ok": status.is_success()}));
                 if !status.is_success() {
-                    return Err(Response::error(
+                    let category = if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                        ErrorCategory::RateLimited
+                    } else {
+                        ErrorCategory::Internal
+                    };
+                    return Err(Response::error_with_category(
                         req_id.clone(),
                         error_codes::INTERNAL_ERROR,
+                        category,
                         format!("discord-api error {}: {}", status, body),
                     ));
                 }
@@ -260,16 +971,17 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "get_discord_service_info" => {
             let res: Result<Response, Response> = (async {
                 let base = state.config.discord_api_url.clone().ok_or_else(|| {
-                    Response::error(
+                    Response::error_with_category(
                         req_id.clone(),
                         error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
                         "DISCORD_API_URL is not configured on the server".into(),
                     )
                 })?;
                 let url = format!("{}/", base.trim_end_matches('/'));
                 let client = Client::new();
                 let resp = client.get(url).send().await.map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                 })?;
                 let status = resp.status();
                 let body: Value = resp
@@ -277,9 +989,15 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                     .await
                     .unwrap_or_else(|_| json!({"ok": status.is_success()}));
                 if !status.is_success() {
-                    return Err(Response::error(
+                    let category = if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                        ErrorCategory::RateLimited
+                    } else {
+                        ErrorCategory::Internal
+                    };
+                    return Err(Response::error_with_category(
                         req_id.clone(),
                         error_codes::INTERNAL_ERROR,
+                        category,
                         format!("discord-api error {}: {}", status, body),
                     ));
                 }
@@ -307,16 +1025,17 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "check_discord_health" => {
             let res: Result<Response, Response> = (async {
                 let base = state.config.discord_api_url.clone().ok_or_else(|| {
-                    Response::error(
+                    Response::error_with_category(
                         req_id.clone(),
                         error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
                         "DISCORD_API_URL is not configured on the server".into(),
                     )
                 })?;
                 let url = format!("{}/health", base.trim_end_matches('/'));
                 let client = Client::new();
                 let resp = client.get(url).send().await.map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                 })?;
                 let status = resp.status();
                 let body: Value = resp
@@ -324,9 +1043,15 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                     .await
                     .unwrap_or_else(|_| json!({"ok": status.is_success()}));
                 if !status.is_success() {
-                    return Err(Response::error(
+                    let category = if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                        ErrorCategory::RateLimited
+                    } else {
+                        ErrorCategory::Internal
+                    };
+                    return Err(Response::error_with_category(
                         req_id.clone(),
                         error_codes::INTERNAL_ERROR,
+                        category,
                         format!("discord-api error {}: {}", status, body),
                     ));
                 }
@@ -344,16 +1069,17 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "get_balance" => {
             let res: Result<Response, Response> = (async {
                 let address = utils::get_required_arg::<String>(args, "address", req_id)?;
-                let mut chain_id = utils::get_required_arg::<String>(args, "chain_id", req_id)?;
+                let mut chain_id = utils::get_chain_id(args, req_id)?;
                 chain_id = normalize_chain_id(&chain_id);
                 let rpc_url = match state.config.chain_rpc_urls.get(&chain_id) {
                     Some(u) => u,
                     None => {
                         let keys: Vec<String> =
                             state.config.chain_rpc_urls.keys().cloned().collect();
-                        return Err(Response::error(
+                        return Err(Response::error_with_category(
                             req_id.clone(),
                             error_codes::INVALID_PARAMS,
+                            ErrorCategory::NotConfigured,
                             format!(
                                 "RPC URL not configured for chain_id '{}'. Available: {}",
                                 chain_id,
@@ -362,28 +1088,47 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                         ));
                     }
                 };
-                let etherscan_api_key = match state.config.etherscan_api_key.as_ref() {
-                    Some(key) => key,
-                    None => {
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INVALID_PARAMS,
-                            "ETHERSCAN_API_KEY is not configured".to_string(),
-                        ));
-                    }
-                };
-
-                let client = Client::new();
-                let balance = crate::blockchain::services::balance::get_balance(
-                    &client,
-                    &chain_id,
-                    &address,
-                    etherscan_api_key,
-                )
-                .await
+                let block = block_arg(args, req_id)?;
+                let client = state.http_client.clone();
+                // Etherscan-indexed chains go through Etherscan for the latest balance; other
+                // chains and historical reads ask the node
+                let etherscan_base_url = state.config.etherscan_base_url(&chain_id).ok();
+                let balance = if let (Some(base_url), None) = (etherscan_base_url.as_deref(), &block) {
+                    let etherscan_api_key = match state.config.etherscan_api_key.as_ref() {
+                        Some(key) => key,
+                        None => {
+                            return Err(Response::error_with_category(
+                                req_id.clone(),
+                                error_codes::INVALID_PARAMS,
+                                ErrorCategory::NotConfigured,
+                                "ETHERSCAN_API_KEY is not configured".to_string(),
+                            ));
+                        }
+                    };
+                    crate::blockchain::services::balance::get_balance(
+                        &client,
+                        base_url,
+                        &chain_id,
+                        &address,
+                        etherscan_api_key,
+                    )
+                    .await
+                } else {
+                    crate::blockchain::services::balance::get_balance_rpc(
+                        &client,
+                        rpc_url,
+                        &address,
+                        &block.unwrap_or_else(|| json!("latest")),
+                    )
+                    .await
+                }
                 .map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                 })?;
+                let balance = crate::blockchain::services::balance::native_balance_json(
+                    &balance,
+                    &state.config.native_currency(&chain_id),
+                );
                 let debug_info = json!({
                     "chain_id_normalized": chain_id,
                     "rpc_url": rpc_url,
@@ -415,32 +1160,29 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "create_wallet" => {
             let res: Result<Response, Response> = (async {
                 // EVM-only wallet creation
-                let wallet = crate::blockchain::services::wallet::create_wallet().map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                let word_count = args
+                    .get("word_count")
+                    .and_then(|v| v.as_u64())
+                    .map(|n| n as usize)
+                    .unwrap_or(wallet::DEFAULT_WORD_COUNT);
+                wallet::entropy_len_for_word_count(word_count).map_err(|e| {
+                    Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
                 })?;
-
-                // Create a comprehensive response with all wallet details
-                let comprehensive_wallet = json!({
-                    "address": wallet.address,
-                    "private_key": wallet.private_key,
-                    "mnemonic": wallet.mnemonic,
-                    "chain_type": "evm",
-                });
-
-                let mnemonic_text = wallet
-                    .mnemonic
-                    .as_ref()
-                    .map(|m| format!("\nMnemonic: {}", m))
-                    .unwrap_or_else(|| "\nMnemonic: Not available".to_string());
-
-                let summary = format!(
-                    "Created EVM wallet with complete details:\nAddress: {}\nPrivate Key: {}{}",
-                    wallet.address, wallet.private_key, mnemonic_text
-                );
-                Ok(Response::success(
-                    req_id.clone(),
-                    make_texty_result(summary, comprehensive_wallet),
-                ))
+                let passphrase = Zeroizing::new(args.get("passphrase").and_then(|v| v.as_str()).unwrap_or("").to_string());
+                if !reveal_keys(&state, args)
+                    && (args.get("wallet_name").is_none() || args.get("master_password").is_none())
+                {
+                    return Err(Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        "Key output is suppressed; pass wallet_name and master_password so the new wallet is stored".into(),
+                    ));
+                }
+                let wallet = wallet::create_wallet_with_options(word_count, &passphrase).map_err(|e| {
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                })?;
+                let result = new_wallet_result(&state, args, req_id, "Created", &wallet).await?;
+                Ok(Response::success(req_id.clone(), result))
             })
             .await;
             res.unwrap_or_else(|err_resp| err_resp)
@@ -455,33 +1197,12 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                     } else {
                         utils::get_required_arg::<String>(args, "key", req_id)?
                     };
-                let wallet =
-                    crate::blockchain::services::wallet::import_wallet(&key).map_err(|e| {
-                        Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
-                    })?;
-
-                // Create a comprehensive response with all wallet details
-                let comprehensive_wallet = json!({
-                    "address": wallet.address,
-                    "private_key": wallet.private_key,
-                    "mnemonic": wallet.mnemonic,
-                    "chain_type": "evm",
-                });
-
-                let mnemonic_text = wallet
-                    .mnemonic
-                    .as_ref()
-                    .map(|m| format!("\nMnemonic: {}", m))
-                    .unwrap_or_else(|| "\nMnemonic: Not available".to_string());
-
-                let summary = format!(
-                    "Imported EVM wallet with complete details:\nAddress: {}\nPrivate Key: {}{}",
-                    wallet.address, wallet.private_key, mnemonic_text
-                );
-                Ok(Response::success(
-                    req_id.clone(),
-                    make_texty_result(summary, comprehensive_wallet),
-                ))
+                let passphrase = Zeroizing::new(args.get("passphrase").and_then(|v| v.as_str()).unwrap_or("").to_string());
+                let wallet = wallet::import_wallet_with_passphrase(&key, &passphrase).map_err(|e| {
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                })?;
+                let result = new_wallet_result(&state, args, req_id, "Imported", &wallet).await?;
+                Ok(Response::success(req_id.clone(), result))
             })
             .await;
             res.unwrap_or_else(|err_resp| err_resp)
@@ -490,16 +1211,17 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "request_faucet" => {
             let res: Result<Response, Response> = (async {
                 let address = utils::get_required_arg::<String>(args, "address", req_id)?;
-                let mut chain_id = utils::get_required_arg::<String>(args, "chain_id", req_id)?;
+                let mut chain_id = utils::get_chain_id(args, req_id)?;
                 chain_id = normalize_chain_id(&chain_id);
                 let rpc_url = match state.config.chain_rpc_urls.get(&chain_id) {
                     Some(u) => u,
                     None => {
                         let keys: Vec<String> =
                             state.config.chain_rpc_urls.keys().cloned().collect();
-                        return Err(Response::error(
+                        return Err(Response::error_with_category(
                             req_id.clone(),
                             error_codes::INVALID_PARAMS,
+                            ErrorCategory::NotConfigured,
                             format!(
                                 "RPC URL not configured for chain_id '{}'. Available: {}",
                                 chain_id,
@@ -508,19 +1230,22 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                         ));
                     }
                 };
-                let tx_hash = crate::blockchain::services::faucet::send_faucet_tokens(
-                    &state.config,
-                    &address,
-                    &state.nonce_manager,
-                    rpc_url,
-                    &chain_id,
-                )
-                .await
-                .map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
-                })?;
-                let payload = json!({ "transaction_hash": tx_hash });
-                let summary = format!("Faucet sent tokens: tx {}", tx_hash);
+                let payload = send_idempotent(&state, tool_name, args, req_id, || async {
+                    crate::blockchain::services::faucet::send_faucet_tokens(
+                        &state.config,
+                        &address,
+                        &state.nonce_manager,
+                        rpc_url,
+                        &chain_id,
+                    )
+                    .await
+                    .map(|tx_hash| json!({ "transaction_hash": tx_hash }))
+                    .map_err(|e| {
+                        Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                    })
+                })
+                .await?;
+                let summary = format!("Faucet sent tokens: tx {}", payload["transaction_hash"].as_str().unwrap_or_default());
                 Ok(Response::success(
                     req_id.clone(),
                     make_texty_result(summary, payload),
@@ -533,30 +1258,97 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         // --- Event tools ---
         "search_events" => {
             let res: Result<Response, Response> = (async {
-                let chain_id = utils::get_required_arg::<String>(args, "chain_id", req_id)?;
-                let etherscan_api_key = match state.config.etherscan_api_key.as_ref() {
-                    Some(key) => key,
-                    None => {
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INVALID_PARAMS,
-                            "ETHERSCAN_API_KEY is not configured".to_string(),
-                        ));
-                    }
+                let chain_id = utils::get_chain_id(args, req_id)?;
+
+                // Etherscan is used only when asked for, or by default when it can serve this chain;
+                // everything else goes straight to the configured RPC's eth_getLogs.
+                let etherscan_available = state.config.etherscan_api_key.is_some()
+                    && state.config.etherscan_base_url(&chain_id).is_ok();
+                let source = args
+                    .get("source")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(if etherscan_available { "etherscan" } else { "rpc" });
+
+                // topic0 can also be derived from `event_signature`; with an event ABI it defaults to the event's signature hash and logs are decoded
+                let event = match args.get("event_abi") {
+                    Some(abi) => Some(events::parse_event_abi(abi).map_err(|e| {
+                        Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
+                    })?),
+                    None => None,
+                };
+                let signature_topic = match args.get("event_signature").and_then(|v| v.as_str()) {
+                    Some(sig) => Some(events::compute_event_topic(sig).map_err(|e| {
+                        Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
+                    })?),
+                    None => None,
                 };
+                let topic0 = args
+                    .get("topic0")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+                    .or(signature_topic)
+                    .or_else(|| event.as_ref().filter(|e| !e.anonymous).map(events::event_topic));
+                let decode = |raw: &[serde_json::Value]| event.as_ref().map(|e| events::decode_logs(e, raw));
 
-                // Determine Etherscan base URL based on chain
-                let etherscan_base_url = match chain_id.as_str() {
-                    "1" => "https://api.etherscan.io/v2/api",
-                    "11155111" => "https://api-sepolia.etherscan.io/v2/api",
-                    _ => {
-                        return Err(Response::error(
+                if source == "rpc" {
+                    let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                        Response::error_with_category(
                             req_id.clone(),
                             error_codes::INVALID_PARAMS,
-                            format!("Etherscan API not supported for chain_id '{}'", chain_id),
-                        ));
-                    }
-                };
+                            ErrorCategory::NotConfigured,
+                            format!("RPC URL not configured for chain_id '{}'", chain_id),
+                        )
+                    })?;
+                    let address = utils::get_required_arg::<String>(args, "contract_address", req_id)?;
+                    let topics: Vec<Option<String>> = std::iter::once(topic0.clone())
+                        .chain(
+                            ["topic1", "topic2", "topic3"]
+                                .iter()
+                                .map(|k| args.get(*k).and_then(|v| v.as_str()).map(str::to_string)),
+                        )
+                        .collect();
+                    let client = state.http_client.clone();
+                    let invalid = |e: anyhow::Error| {
+                        Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
+                    };
+                    // eth_getLogs from genesis would mean thousands of requests; the start is required
+                    let from_block = utils::get_required_arg::<String>(args, "from_block", req_id)?;
+                    let from_block = logs::resolve_block(&client, rpc_url, Some(&from_block), false)
+                        .await
+                        .map_err(invalid)?;
+                    let to_block = logs::resolve_block(&client, rpc_url, args.get("to_block").and_then(|v| v.as_str()), true)
+                        .await
+                        .map_err(invalid)?;
+                    logs::check_range(from_block, to_block).map_err(invalid)?;
+                    let found = logs::get_logs(&client, rpc_url, &address, &topics, from_block, to_block)
+                        .await
+                        .map_err(|e| {
+                            Response::error_with_category(req_id.clone(), error_codes::INTERNAL_ERROR, ErrorCategory::of_error(error_codes::INTERNAL_ERROR, &*e), format!("eth_getLogs failed: {}", e),)
+                        })?;
+                    let summary = format!(
+                        "Found {} log(s) via eth_getLogs in blocks {}..={}",
+                        found.len(),
+                        from_block,
+                        to_block
+                    );
+                    let payload = json!({
+                        "logs": found,
+                        "decoded_logs": decode(&found),
+                        "from_block": from_block,
+                        "to_block": to_block,
+                        "source": "rpc",
+                    });
+                    return Ok(Response::success(req_id.clone(), make_texty_result(summary, payload)));
+                } else if source != "etherscan" {
+                    return Err(Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        format!("Unknown source '{}'; expected 'etherscan' or 'rpc'", source),
+                    ));
+                }
+
+                let etherscan_api_key = etherscan_api_key(&state, req_id)?;
+                let etherscan_base_url = etherscan_base_url(&state, &chain_id, req_id)?;
 
                 let address = args
                     .get("contract_address")
@@ -571,8 +1363,6 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
 
                 let from_block = args.get("from_block").and_then(|v| v.as_str());
                 let to_block = args.get("to_block").and_then(|v| v.as_str());
-                let topic0 = args.get("topic0").and_then(|v| v.as_str());
-
                 // Helper to normalize block tags: accept hex tags (latest/earliest/pending) or decimal block numbers.
                 fn normalize_block_tag(tag: &str) -> String {
                     let t = tag.trim();
@@ -591,6 +1381,7 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                     "{}?chainid={}&module=logs&action=getLogs",
                     etherscan_base_url, chain_id
                 );
+                metrics::record_etherscan_request("logs", "getLogs");
 
                 if let Some(fb) = from_block {
                     url.push_str(&format!("&fromBlock={}", normalize_block_tag(fb)));
@@ -598,7 +1389,7 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 if let Some(tb) = to_block {
                     url.push_str(&format!("&toBlock={}", normalize_block_tag(tb)));
                 }
-                if let Some(t0) = topic0 {
+                if let Some(t0) = topic0.as_deref() {
                     url.push_str(&format!("&topic0={}", t0));
                     // Add topic0_1_opr=and for additional topic filtering if needed
                     url.push_str("&topic0_1_opr=and");
@@ -611,15 +1402,16 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 url.push_str("&page=1&offset=1000");
                 url.push_str(&format!("&apikey={}", etherscan_api_key));
 
-                let client = Client::new();
+                let client = state.http_client.clone();
                 let resp: serde_json::Value = client
                     .get(&url)
                     .send()
                     .await
                     .map_err(|e| {
-                        Response::error(
+                        Response::error_with_category(
                             req_id.clone(),
                             error_codes::INTERNAL_ERROR,
+                            ErrorCategory::of_error(error_codes::INTERNAL_ERROR, &e),
                             format!("Etherscan API error: {}", e),
                         )
                     })?
@@ -633,28 +1425,17 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                         )
                     })?;
 
-                // Check for Etherscan API errors
-                if let Some(status) = resp.get("status").and_then(|v| v.as_str()) {
-                    if status != "1" {
-                        let message = resp
-                            .get("message")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("Unknown error");
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Etherscan API error: {}", message),
-                        ));
-                    }
-                }
-
-                // Extract logs from result
-                let logs = resp
-                    .get("result")
-                    .cloned()
-                    .unwrap_or_else(|| serde_json::Value::Array(vec![]));
+                // Check for Etherscan API errors; "No records found" is just an empty list
+                let logs = utils::etherscan_list_result(&resp).map_err(|message| {
+                    Response::error(
+                        req_id.clone(),
+                        error_codes::INTERNAL_ERROR,
+                        format!("Etherscan API error: {}", message),
+                    )
+                })?;
                 let count = logs.as_array().map(|a| a.len()).unwrap_or(0);
-                let payload = json!({ "logs": logs });
+                let decoded_logs = decode(logs.as_array().map(Vec::as_slice).unwrap_or_default());
+                let payload = json!({ "logs": logs, "decoded_logs": decoded_logs });
                 let summary = format!("Found {} log(s) via Etherscan API", count);
                 Ok(Response::success(
                     req_id.clone(),
@@ -669,8 +1450,9 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         // EVM value transfer using a provided private key
         "transfer_evm" => {
             let res: Result<Response, Response> = (async {
-                let private_key = utils::get_required_arg::<String>(args, "private_key", req_id)?;
-                let chain_id = utils::get_required_arg::<String>(args, "chain_id", req_id)?;
+                let private_key =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "private_key", req_id)?);
+                let chain_id = utils::get_chain_id(args, req_id)?;
                 let to_address = utils::get_required_arg::<String>(args, "to_address", req_id)?;
                 let amount_wei = utils::get_required_arg::<String>(args, "amount_wei", req_id)?;
 
@@ -690,29 +1472,31 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 })?;
 
                 let mut tx_request = TransactionRequest::new().to(to).value(value);
-                if let Some(g) = args.get("gas_limit").and_then(|v| v.as_str()) {
-                    tx_request =
-                        tx_request.gas(U256::from_dec_str(g).unwrap_or_else(|_| U256::from(0)));
+                if let Some(g) = utils::get_gas_limit(args, req_id)? {
+                    tx_request = tx_request.gas(g);
                 }
-                if let Some(gp) = args.get("gas_price").and_then(|v| v.as_str()) {
-                    tx_request = tx_request
-                        .gas_price(U256::from_dec_str(gp).unwrap_or_else(|_| U256::from(0)));
+                if let Some(gp) = utils::get_gas_price(args, req_id)? {
+                    tx_request = tx_request.gas_price(gp);
                 }
 
-                let response = state
-                    .evm_client
-                    .send_transaction(&chain_id, &private_key, tx_request, &state.nonce_manager)
-                    .await
-                    .map_err(|e| {
-                        Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
-                    })?;
+                let response = send_idempotent(&state, tool_name, args, req_id, || async {
+                    state
+                        .evm_client
+                        .send_transaction_with_options(&chain_id, &private_key, tx_request, &state.nonce_manager, &send_options(&state, &chain_id, args, req_id)?)
+                        .await
+                        .map(|r| json!(r))
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })
+                })
+                .await?;
                 let summary = match serde_json::to_string(&response) {
                     Ok(s) => format!("EVM tx sent: {}", s),
                     Err(_) => "EVM tx sent".to_string(),
                 };
                 Ok(Response::success(
                     req_id.clone(),
-                    make_texty_result(summary, json!(response)),
+                    make_texty_result(summary, response),
                 ))
             })
             .await;
@@ -722,8 +1506,9 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         // EVM ERC-721 transfer
         "transfer_nft_evm" => {
             let res: Result<Response, Response> = (async {
-                let private_key = utils::get_required_arg::<String>(args, "private_key", req_id)?;
-                let chain_id = utils::get_required_arg::<String>(args, "chain_id", req_id)?;
+                let private_key =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "private_key", req_id)?);
+                let chain_id = utils::get_chain_id(args, req_id)?;
                 let contract_address =
                     utils::get_required_arg::<String>(args, "contract_address", req_id)?;
                 let to_address = utils::get_required_arg::<String>(args, "to_address", req_id)?;
@@ -760,8 +1545,7 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 })?;
 
                 // Encode safeTransferFrom(address,address,uint256)
-                let selector =
-                    &keccak256("safeTransferFrom(address,address,uint256)".as_bytes())[0..4];
+                let selector = utils::function_selector("safeTransferFrom(address,address,uint256)");
                 let data_bytes = {
                     let mut encoded = selector.to_vec();
                     let tokens = vec![
@@ -778,21 +1562,23 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                     .to(contract)
                     .data(data_bytes)
                     .value(U256::zero());
-                if let Some(g) = args.get("gas_limit").and_then(|v| v.as_str()) {
-                    tx_request =
-                        tx_request.gas(U256::from_dec_str(g).unwrap_or_else(|_| U256::from(0)));
+                if let Some(g) = utils::get_gas_limit(args, req_id)? {
+                    tx_request = tx_request.gas(g);
                 }
-                if let Some(gp) = args.get("gas_price").and_then(|v| v.as_str()) {
-                    tx_request = tx_request
-                        .gas_price(U256::from_dec_str(gp).unwrap_or_else(|_| U256::from(0)));
+                if let Some(gp) = utils::get_gas_price(args, req_id)? {
+                    tx_request = tx_request.gas_price(gp);
                 }
-                let response = state
-                    .evm_client
-                    .send_transaction(&chain_id, &private_key, tx_request, &state.nonce_manager)
-                    .await
-                    .map_err(|e| {
-                        Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
-                    })?;
+                let response = send_idempotent(&state, tool_name, args, req_id, || async {
+                    state
+                        .evm_client
+                        .send_transaction_with_options(&chain_id, &private_key, tx_request, &state.nonce_manager, &send_options(&state, &chain_id, args, req_id)?)
+                        .await
+                        .map(|r| json!(r))
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })
+                })
+                .await?;
                 Ok(Response::success(req_id.clone(), json!(response)))
             })
             .await;
@@ -804,73 +1590,59 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
             let res: Result<Response, Response> = (async {
                 let wallet_name = utils::get_required_arg::<String>(args, "wallet_name", req_id)?;
                 let master_password =
-                    utils::get_required_arg::<String>(args, "master_password", req_id)?;
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "master_password", req_id)?);
 
                 // Accept either 'mnemonic_or_private_key' (preferred) or legacy 'private_key'
                 let key = if let Some(s) = args
                     .get("mnemonic_or_private_key")
                     .and_then(|v| v.as_str())
-                { s.to_string() } else {
-                    utils::get_required_arg::<String>(args, "private_key", req_id)?
+                { Zeroizing::new(s.to_string()) } else {
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "private_key", req_id)?)
                 };
 
+                let passphrase = Zeroizing::new(args.get("passphrase").and_then(|v| v.as_str()).unwrap_or("").to_string());
                 let wallet_info: WalletResponse =
-                    wallet::import_wallet(&key).map_err(|e| {
+                    wallet::import_wallet_with_passphrase(&key, &passphrase).map_err(|e| {
                         Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
                     })?;
 
-                // Lazy-initialize or load wallet storage from disk using the provided master password.
-                // If in-memory storage is empty (no master password set), replace it with the loaded one.
-                {
-                    let mut storage = state.wallet_storage.lock().await;
-                    if storage.is_master_password_hash_empty() {
-                        let loaded = wallet_storage::load_or_create_wallet_storage(&state.wallet_storage_path, &master_password)
-                            .map_err(|e| {
-                                Response::error(
-                                    req_id.clone(),
-                                    error_codes::INTERNAL_ERROR,
-                                    format!("Failed to initialize wallet storage: {}", e),
-                                )
-                            })?;
-                        *storage = loaded;
-                    } else if !storage.verify_master_password(&master_password) {
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            "Authentication failed".into(),
-                        ));
-                    }
-                }
+                unlock_wallet_storage(&state, &master_password, req_id).await?;
 
-                // Add wallet into storage
-                {
-                    let mut storage = state.wallet_storage.lock().await;
-                    storage
-                        .add_wallet(
-                            wallet_name.clone(),
-                            wallet_info.private_key.as_str(),
-                            wallet_info.address.clone(),
-                            &master_password,
-                        )
-                        .map_err(|e| {
-                            Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
-                        })?;
-                    // Persist to disk
-                    wallet_storage::save_wallet_storage(&state.wallet_storage_path, &storage).map_err(
-                        |e| {
-                            error!("Failed to save wallet storage: {}", e);
-                            Response::error(
-                                req_id.clone(),
-                                error_codes::INTERNAL_ERROR,
-                                "Failed to save wallet to disk".into(),
-                            )
-                        },
-                    )?;
-                }
+                // Add wallet into storage, replacing an existing entry only if asked to. Both
+                // write through to disk under the storage file lock.
+                let overwrite = args.get("overwrite").and_then(|v| v.as_bool()).unwrap_or(false);
+                let replaced = {
+                    let (name, address) = (wallet_name.clone(), wallet_info.address.clone());
+                    let private_key = Zeroizing::new(wallet_info.private_key.to_string());
+                    let master_password = master_password.clone();
+                    wallet_storage::update_blocking(&state.wallet_storage, move |storage| {
+                        if overwrite {
+                            storage.upsert_wallet(name, &private_key, address, &master_password)
+                        } else {
+                            storage.add_wallet(name, &private_key, address, &master_password).map(|_| None)
+                        }
+                    })
+                    .await
+                    .map_err(|e| match e.downcast_ref::<wallet_storage::StorageError>() {
+                        Some(exists @ wallet_storage::StorageError::WalletExists(_)) => Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            format!("{}; pass overwrite: true to replace it", exists),
+                        ),
+                        _ => Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e),
+                    })?
+                };
 
                 // Return the derived address too for convenience
-                let payload = json!({ "status": "success", "wallet_name": wallet_name, "address": wallet_info.address });
-                let summary = format!("Registered wallet {}", wallet_name);
+                let mut payload = json!({ "status": "success", "wallet_name": wallet_name, "address": wallet_info.address });
+                let summary = match replaced {
+                    Some(previous) => {
+                        payload["replaced"] = json!(true);
+                        payload["previous_address"] = json!(previous.public_address);
+                        format!("Updated wallet {}", wallet_name)
+                    }
+                    None => format!("Registered wallet {}", wallet_name),
+                };
                 Ok(Response::success(
                     req_id.clone(),
                     make_texty_result(summary, payload),
@@ -883,54 +1655,42 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "list_wallets" => {
             let res: Result<Response, Response> = (async {
                 let master_password =
-                    utils::get_required_arg::<String>(args, "master_password", req_id)?;
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "master_password", req_id)?);
                 // Lazy-load or initialize storage if needed using the provided master password
-                {
-                    let mut storage = state.wallet_storage.lock().await;
-                    if storage.is_master_password_hash_empty() {
-                        let loaded = wallet_storage::load_or_create_wallet_storage(
-                            &state.wallet_storage_path,
-                            &master_password,
-                        )
-                        .map_err(|e| {
-                            Response::error(
-                                req_id.clone(),
-                                error_codes::INTERNAL_ERROR,
-                                format!("Failed to initialize wallet storage: {}", e),
-                            )
-                        })?;
-                        *storage = loaded;
-                    }
-                }
+                unlock_wallet_storage(&state, &master_password, req_id).await?;
                 let storage = state.wallet_storage.lock().await;
-                if !storage.verify_master_password(&master_password) {
-                    return Err(Response::error(
-                        req_id.clone(),
-                        error_codes::INTERNAL_ERROR,
-                        "Authentication failed".into(),
-                    ));
-                }
-                // Return wallet names with their public addresses
-                let mut wallets: Vec<serde_json::Value> = Vec::new();
-                for w in storage.wallets().values() {
-                    wallets.push(json!({
-                        "wallet_name": w.wallet_name,
-                        "address": w.public_address,
-                    }));
+                // Return wallet names with their public addresses and creation times,
+                // by name or oldest first
+                let mut stored: Vec<_> = storage.wallets().values().collect();
+                match args.get("sort_by").and_then(|v| v.as_str()).unwrap_or("name") {
+                    "name" => stored.sort_by(|a, b| a.wallet_name.cmp(&b.wallet_name)),
+                    "created_at" => stored.sort_by(|a, b| {
+                        a.created_at.cmp(&b.created_at).then_with(|| a.wallet_name.cmp(&b.wallet_name))
+                    }),
+                    other => {
+                        return Err(Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            format!("sort_by must be 'name' or 'created_at', got '{}'", other),
+                        ))
+                    }
                 }
-                wallets.sort_by(|a, b| {
-                    a["wallet_name"]
-                        .as_str()
-                        .unwrap_or("")
-                        .cmp(b["wallet_name"].as_str().unwrap_or(""))
-                });
+                let wallets: Vec<serde_json::Value> = stored
+                    .iter()
+                    .map(|w| {
+                        json!({
+                            "wallet_name": w.wallet_name,
+                            "address": w.public_address,
+                            "created_at": w.created_at.to_rfc3339(),
+                        })
+                    })
+                    .collect();
                 let count = wallets.len();
                 // Build a human-readable list for MCP clients that only display text content
-                let mut lines: Vec<String> = Vec::new();
-                for w in storage.wallets().values() {
-                    lines.push(format!("• {} — {}", w.wallet_name, w.public_address));
-                }
-                lines.sort();
+                let lines: Vec<String> = stored
+                    .iter()
+                    .map(|w| format!("• {} — {} (created {})", w.wallet_name, w.public_address, w.created_at.to_rfc3339()))
+                    .collect();
                 let details_text = if lines.is_empty() {
                     "No wallets stored".to_string()
                 } else {
@@ -961,11 +1721,11 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "transfer_from_wallet" => {
             let res: Result<Response, Response> = (async {
                 let wallet_name = utils::get_required_arg::<String>(args, "wallet_name", req_id)?;
-                let chain_id = utils::get_required_arg::<String>(args, "chain_id", req_id)?;
+                let chain_id = utils::get_chain_id(args, req_id)?;
                 let to_address = utils::get_required_arg::<String>(args, "to_address", req_id)?;
                 let amount = utils::get_required_arg::<String>(args, "amount", req_id)?;
                 let master_password =
-                    utils::get_required_arg::<String>(args, "master_password", req_id)?;
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "master_password", req_id)?);
 
                 let private_key = {
                     // Scoped lock
@@ -996,22 +1756,66 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                     )
                 })?;
 
+                // Spending guards: global per-transfer cap and the wallet's daily limit
+                if let Some(max) = state.config.max_transfer_amount_wei {
+                    if value > max {
+                        return Err(Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            format!(
+                                "Transfer of {} wei exceeds the configured maximum of {} wei",
+                                value, max
+                            ),
+                        ));
+                    }
+                }
                 let tx_request = TransactionRequest::new().to(to).value(value);
 
-                let response = state
-                    .evm_client
-                    .send_transaction(&chain_id, &private_key, tx_request, &state.nonce_manager)
+                let response = send_idempotent(&state, tool_name, args, req_id, || async {
+                    // Reserve the amount before broadcasting so concurrent sends from the
+                    // same wallet cannot all pass the daily limit check
+                    let reserved_at = Utc::now();
+                    let name = wallet_name.clone();
+                    wallet_storage::update_blocking(&state.wallet_storage, move |storage| {
+                        storage.reserve_spend(&name, value, reserved_at)
+                    })
                     .await
                     .map_err(|e| {
-                        Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                        Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
                     })?;
+                    let sent = match send_options(&state, &chain_id, args, req_id) {
+                        Ok(options) => state
+                            .evm_client
+                            .send_transaction_with_options(&chain_id, &private_key, tx_request, &state.nonce_manager, &options)
+                            .await
+                            .map_err(|e| {
+                                Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                            }),
+                        Err(resp) => Err(resp),
+                    };
+                    match sent {
+                        Ok(response) => Ok(json!(response)),
+                        Err(resp) => {
+                            let name = wallet_name.clone();
+                            let released = wallet_storage::update_blocking(&state.wallet_storage, move |storage| {
+                                storage.release_spend(&name, value, reserved_at)
+                            })
+                            .await;
+                            if let Err(e) = released {
+                                error!("Failed to release spend for wallet {}: {}", wallet_name, e);
+                            }
+                            Err(resp)
+                        }
+                    }
+                })
+                .await?;
                 let summary = match serde_json::to_string(&response) {
                     Ok(s) => format!("Transfer sent: {}", s),
                     Err(_) => "Transfer sent".to_string(),
                 };
                 Ok(Response::success(
                     req_id.clone(),
-                    make_texty_result(summary, json!(response)),
+                    make_texty_result(summary, response),
                 ))
             })
             .await;
@@ -1020,89 +1824,19 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "get_contract" => {
             let res: Result<Response, Response> = (async {
                 let address = utils::get_required_arg::<String>(args, "address", req_id)?;
-                let etherscan_api_key = match state.config.etherscan_api_key.as_ref() {
-                    Some(key) => key,
-                    None => {
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INVALID_PARAMS,
-                            "ETHERSCAN_API_KEY is not configured".to_string(),
-                        ));
-                    }
-                };
-
+                let etherscan_api_key = etherscan_api_key(&state, req_id)?;
                 // Prefer explicit chain_id, else infer from NL, default to mainnet
-                let mut chain = args
-                    .get("chain_id")
-                    .and_then(|v| v.as_str())
-                    .map(normalize_chain_id);
-                if chain.is_none() {
-                    chain = infer_evm_chain_from_args(args);
-                }
-                let mut chain_id = chain.unwrap_or_else(|| "1".to_string());
-                chain_id = normalize_chain_id(&chain_id);
-
-                // Determine Etherscan base URL based on chain
-                let etherscan_base_url = match chain_id.as_str() {
-                    "1" => "https://api.etherscan.io/v2/api",
-                    "11155111" => "https://api-sepolia.etherscan.io/v2/api",
-                    _ => {
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INVALID_PARAMS,
-                            format!("Etherscan API not supported for chain_id '{}'", chain_id),
-                        ));
-                    }
-                };
-
-                // Build Etherscan API URL for getsourcecode
-                let url = format!(
-                    "{}?chainid={}&module=contract&action=getsourcecode&address={}&apikey={}",
-                    etherscan_base_url, chain_id, address, etherscan_api_key
-                );
+                let chain_id = explorer_chain_id(args);
+                let etherscan_base_url = etherscan_base_url(&state, &chain_id, req_id)?;
 
-                let client = Client::new();
-                let resp: serde_json::Value = client
-                    .get(&url)
-                    .send()
-                    .await
-                    .map_err(|e| {
-                        Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Etherscan API error: {}", e),
-                        )
-                    })?
-                    .json()
+                let mut result = contract::get_source_record(&state.http_client, &etherscan_base_url, &chain_id, &address, etherscan_api_key)
                     .await
-                    .map_err(|e| {
-                        Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Invalid Etherscan JSON response: {}", e),
-                        )
-                    })?;
-
-                // Check for Etherscan API errors
-                if let Some(status) = resp.get("status").and_then(|v| v.as_str()) {
-                    if status != "1" {
-                        let message = resp
-                            .get("message")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("Unknown error");
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Etherscan API error: {}", message),
-                        ));
-                    }
+                    .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
+                // Multi-file sources arrive as one (double-braced) JSON string; split them out
+                if let Some(source_code) = result.get("SourceCode").and_then(|v| v.as_str()).filter(|s| !s.is_empty()) {
+                    let contract_name = result.get("ContractName").and_then(|v| v.as_str()).unwrap_or("");
+                    result["sources"] = json!(contract::etherscan_source_files(source_code, contract_name));
                 }
-
-                // Extract contract info from result
-                let result = resp
-                    .get("result")
-                    .and_then(|v| v.as_array())
-                    .and_then(|arr| arr.get(0));
                 let summary = format!("Contract {} on {}", address, chain_id);
                 let pretty = serde_json::to_string_pretty(&result)
                     .unwrap_or_else(|_| "No contract data found".to_string());
@@ -1122,100 +1856,33 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "get_contract_code" => {
             let res: Result<Response, Response> = (async {
                 let address = utils::get_required_arg::<String>(args, "address", req_id)?;
-                let etherscan_api_key = match state.config.etherscan_api_key.as_ref() {
-                    Some(key) => key,
-                    None => {
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INVALID_PARAMS,
-                            "ETHERSCAN_API_KEY is not configured".to_string(),
-                        ));
-                    }
-                };
-
-                let mut chain = args
-                    .get("chain_id")
-                    .and_then(|v| v.as_str())
-                    .map(normalize_chain_id);
-                if chain.is_none() {
-                    chain = infer_evm_chain_from_args(args);
-                }
-                let chain_id = chain.unwrap_or_else(|| "1".to_string());
-
-                // Determine Etherscan base URL based on chain
-                let etherscan_base_url = match chain_id.as_str() {
-                    "1" => "https://api.etherscan.io/v2/api",
-                    "11155111" => "https://api-sepolia.etherscan.io/v2/api",
-                    _ => {
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INVALID_PARAMS,
-                            format!("Etherscan API not supported for chain_id '{}'", chain_id),
-                        ));
-                    }
-                };
 
-                // Build Etherscan API URL for getsourcecode
-                let url = format!(
-                    "{}?chainid={}&module=contract&action=getsourcecode&address={}&apikey={}",
-                    etherscan_base_url, chain_id, address, etherscan_api_key
-                );
+                let chain_id = explorer_chain_id(args);
 
-                let client = Client::new();
-                let resp: serde_json::Value = client
-                    .get(&url)
-                    .send()
-                    .await
-                    .map_err(|e| {
-                        Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Etherscan API error: {}", e),
-                        )
-                    })?
-                    .json()
+                // Runtime bytecode comes from the node; Etherscan's getsourcecode doesn't return it
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+                let code = contract::get_contract_code(&state.http_client, rpc_url, &address)
                     .await
-                    .map_err(|e| {
-                        Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Invalid Etherscan JSON response: {}", e),
-                        )
-                    })?;
-
-                // Check for Etherscan API errors
-                if let Some(status) = resp.get("status").and_then(|v| v.as_str()) {
-                    if status != "1" {
-                        let message = resp
-                            .get("message")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("Unknown error");
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Etherscan API error: {}", message),
-                        ));
-                    }
-                }
+                    .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
 
-                // Extract contract code from result
-                let result = resp
-                    .get("result")
-                    .and_then(|v| v.as_array())
-                    .and_then(|arr| arr.get(0));
-                let bytecode = result
-                    .and_then(|r| r.get("RuntimeCode"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("No bytecode found");
-                let summary = format!("Contract bytecode for {} on {}", address, chain_id);
+                let bytecode = code.get("code").and_then(|v| v.as_str()).unwrap_or("0x");
+                let size = bytecode.trim_start_matches("0x").len() / 2;
+                let summary = if size == 0 {
+                    format!("No bytecode at {} on {} (not a contract)", address, chain_id)
+                } else {
+                    format!("Contract bytecode for {} on {} ({} bytes)", address, chain_id, size)
+                };
 
                 Ok(Response::success(
                     req_id.clone(),
-                    json!({
-                        "content": [
-                            { "type": "text", "text": format!("{}\n\n{}", summary, bytecode) }
-                        ]
-                    }),
+                    make_texty_result(summary, json!({ "address": address, "chain_id": chain_id, "code": bytecode, "size": size })),
                 ))
             })
             .await;
@@ -1224,82 +1891,14 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "get_contract_transactions" => {
             let res: Result<Response, Response> = (async {
                 let address = utils::get_required_arg::<String>(args, "address", req_id)?;
-                let etherscan_api_key = match state.config.etherscan_api_key.as_ref() {
-                    Some(key) => key,
-                    None => {
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INVALID_PARAMS,
-                            "ETHERSCAN_API_KEY is not configured".to_string(),
-                        ));
-                    }
-                };
-
-                let mut chain = args
-                    .get("chain_id")
-                    .and_then(|v| v.as_str())
-                    .map(normalize_chain_id);
-                if chain.is_none() {
-                    chain = infer_evm_chain_from_args(args);
-                }
-                let mut chain_id = chain.unwrap_or_else(|| "1".to_string());
-                chain_id = normalize_chain_id(&chain_id);
-
-                // Determine Etherscan base URL based on chain
-                let etherscan_base_url = match chain_id.as_str() {
-                    "1" => "https://api.etherscan.io/v2/api",
-                    "11155111" => "https://api-sepolia.etherscan.io/v2/api",
-                    _ => {
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INVALID_PARAMS,
-                            format!("Etherscan API not supported for chain_id '{}'", chain_id),
-                        ));
-                    }
-                };
+                let etherscan_api_key = etherscan_api_key(&state, req_id)?;
+                let chain_id = explorer_chain_id(args);
+                let etherscan_base_url = etherscan_base_url(&state, &chain_id, req_id)?;
 
-                // Build Etherscan API URL for txlist
-                let url = format!(
-                    "{}?chainid={}&module=account&action=txlist&address={}&startblock=0&endblock=99999999&page=1&offset=10&sort=asc&apikey={}",
-                    etherscan_base_url, chain_id, address, etherscan_api_key
-                );
-
-                let client = Client::new();
-                let resp: serde_json::Value = client
-                    .get(&url)
-                    .send()
-                    .await
-                    .map_err(|e| {
-                        Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Etherscan API error: {}", e),
-                        )
-                    })?
-                    .json()
+                // "No transactions found" comes back as an empty list
+                let transactions = contract::get_txlist(&state.http_client, &etherscan_base_url, &chain_id, &address, 0, 99999999, etherscan_api_key)
                     .await
-                    .map_err(|e| {
-                        Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Invalid Etherscan JSON response: {}", e),
-                        )
-                    })?;
-
-                // Check for Etherscan API errors
-                if let Some(status) = resp.get("status").and_then(|v| v.as_str()) {
-                    if status != "1" {
-                        let message = resp.get("message").and_then(|v| v.as_str()).unwrap_or("Unknown error");
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Etherscan API error: {}", message),
-                        ));
-                    }
-                }
-
-                // Extract transactions from result
-                let transactions = resp.get("result").cloned().unwrap_or_else(|| serde_json::Value::Array(vec![]));
+                    .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
                 let count = transactions.as_array().map(|a| a.len()).unwrap_or(0);
                 let summary = format!("{} transaction(s) found for contract {} on {}", count, address, chain_id);
 
@@ -1318,93 +1917,118 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         "get_transaction_history" => {
             let res: Result<Response, Response> = (async {
                 let address = utils::get_required_arg::<String>(args, "address", req_id)?;
-                let etherscan_api_key = match state.config.etherscan_api_key.as_ref() {
-                    Some(key) => key,
-                    None => {
-                        return Err(Response::error(
+                let etherscan_api_key = etherscan_api_key(&state, req_id)?;
+                let chain_id = explorer_chain_id(args);
+                let etherscan_base_url = etherscan_base_url(&state, &chain_id, req_id)?;
+
+                // Optional filters, applied after fetching
+                let parse_date = |key: &str, end_of_day: bool| -> Result<Option<i64>, Response> {
+                    match args.get(key).and_then(|v| v.as_str()) {
+                        Some(s) => history::parse_iso_date(s, end_of_day).map(Some).map_err(|e| {
+                            Response::error(req_id.clone(), error_codes::INVALID_PARAMS, format!("{}: {}", key, e))
+                        }),
+                        None => Ok(None),
+                    }
+                };
+                let min_value_wei = match args.get("min_value_wei").and_then(|v| v.as_str()) {
+                    Some(s) => Some(U256::from_dec_str(s).map_err(|_| {
+                        Response::error(
                             req_id.clone(),
                             error_codes::INVALID_PARAMS,
-                            "ETHERSCAN_API_KEY is not configured".to_string(),
-                        ));
-                    }
+                            format!("min_value_wei must be a decimal wei amount, got '{}'", s),
+                        )
+                    })?),
+                    None => None,
+                };
+                let filter = history::HistoryFilter {
+                    from_ts: parse_date("from_date", false)?,
+                    to_ts: parse_date("to_date", true)?,
+                    min_value_wei,
                 };
 
-                let mut chain = args
-                    .get("chain_id")
-                    .and_then(|v| v.as_str())
-                    .map(normalize_chain_id);
-                if chain.is_none() {
-                    chain = infer_evm_chain_from_args(args);
-                }
-                let mut chain_id = chain.unwrap_or_else(|| "1".to_string());
-                chain_id = normalize_chain_id(&chain_id);
-
-                // Determine Etherscan base URL based on chain
-                let etherscan_base_url = match chain_id.as_str() {
-                    "1" => "https://api.etherscan.io/v2/api",
-                    "11155111" => "https://api-sepolia.etherscan.io/v2/api",
-                    _ => {
+                let csv = match args.get("format").and_then(|v| v.as_str()) {
+                    None | Some("json") => false,
+                    Some("csv") => true,
+                    Some(other) => {
                         return Err(Response::error(
                             req_id.clone(),
                             error_codes::INVALID_PARAMS,
-                            format!("Etherscan API not supported for chain_id '{}'", chain_id),
+                            format!("Unsupported format '{}'; expected 'json' or 'csv'", other),
                         ));
                     }
                 };
 
-                // Build Etherscan API URL for txlist
-                let url = format!(
-                    "{}?chainid={}&module=account&action=txlist&address={}&startblock=0&endblock=99999999&page=1&offset=10&sort=asc&apikey={}",
-                    etherscan_base_url, chain_id, address, etherscan_api_key
-                );
+                let client = state.http_client.clone();
 
-                let client = Client::new();
-                let resp: serde_json::Value = client
-                    .get(&url)
-                    .send()
+                // Narrow the fetched block range when the dates can be resolved to blocks;
+                // otherwise fall back to filtering the full result client-side
+                let mut start_block = 0u64;
+                let mut end_block = 99999999u64;
+                if let Some(ts) = filter.from_ts {
+                    if let Ok(b) = history::block_number_by_time(&client, &etherscan_base_url, &chain_id, ts, "after", etherscan_api_key).await {
+                        start_block = b;
+                    }
+                }
+                if let Some(ts) = filter.to_ts {
+                    if let Ok(b) = history::block_number_by_time(&client, &etherscan_base_url, &chain_id, ts, "before", etherscan_api_key).await {
+                        end_block = b;
+                    }
+                }
+
+                // "No transactions found" comes back as an empty list
+                let transactions = contract::get_txlist(&client, &etherscan_base_url, &chain_id, &address, start_block, end_block, etherscan_api_key)
                     .await
-                    .map_err(|e| {
-                        Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Etherscan API error: {}", e),
-                        )
-                    })?
-                    .json()
-                    .await
-                    .map_err(|e| {
-                        Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Invalid Etherscan JSON response: {}", e),
-                        )
-                    })?;
-
-                // Check for Etherscan API errors
-                if let Some(status) = resp.get("status").and_then(|v| v.as_str()) {
-                    if status != "1" {
-                        let message = resp.get("message").and_then(|v| v.as_str()).unwrap_or("Unknown error");
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Etherscan API error: {}", message),
-                        ));
-                    }
+                    .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
+                if csv {
+                    let fetched = transactions.as_array().cloned().unwrap_or_default();
+                    let filtered = history::filter_transactions(&fetched, &filter);
+                    return Ok(Response::success(
+                        req_id.clone(),
+                        json!({
+                            "content": [
+                                { "type": "text", "text": utils::transactions_to_csv(&filtered) }
+                            ]
+                        }),
+                    ));
                 }
+                if filter.is_empty() {
+                    let count = transactions.as_array().map(|a| a.len()).unwrap_or(0);
+                    let summary = format!("{} transaction(s) found for address {} on {}", count, address, chain_id);
 
-                // Extract transactions from result
-                let transactions = resp.get("result").cloned().unwrap_or_else(|| serde_json::Value::Array(vec![]));
-                let count = transactions.as_array().map(|a| a.len()).unwrap_or(0);
-                let summary = format!("{} transaction(s) found for address {} on {}", count, address, chain_id);
+                    return Ok(Response::success(
+                        req_id.clone(),
+                        json!({
+                            "content": [
+                                { "type": "text", "text": format!("{}\n\n{}", summary, serde_json::to_string_pretty(&transactions).unwrap_or_else(|_| "No transactions found".to_string())) }
+                            ]
+                        }),
+                    ));
+                }
 
-                Ok(Response::success(
-                    req_id.clone(),
-                    json!({
-                        "content": [
-                            { "type": "text", "text": format!("{}\n\n{}", summary, serde_json::to_string_pretty(&transactions).unwrap_or_else(|_| "No transactions found".to_string())) }
-                        ]
-                    }),
-                ))
+                let fetched = transactions.as_array().cloned().unwrap_or_default();
+                let filtered = history::filter_transactions(&fetched, &filter);
+                let summary = format!(
+                    "{} of {} transaction(s) match the filters for address {} on {}",
+                    filtered.len(),
+                    fetched.len(),
+                    address,
+                    chain_id
+                );
+                let payload = json!({
+                    "address": address,
+                    "chain_id": chain_id,
+                    "filters": {
+                        "from_date": args.get("from_date"),
+                        "to_date": args.get("to_date"),
+                        "min_value_wei": filter.min_value_wei.map(|v| v.to_string()),
+                        "start_block": start_block,
+                        "end_block": end_block,
+                    },
+                    "fetched_count": fetched.len(),
+                    "count": filtered.len(),
+                    "transactions": filtered,
+                });
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, payload)))
             })
             .await;
             res.unwrap_or_else(|err_resp| err_resp)
@@ -1425,17 +2049,19 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                         utils::get_required_arg::<String>(args, "token_address", req_id)
                     })?;
                 let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
-                    Response::error(
+                    Response::error_with_category(
                         req_id.clone(),
                         error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
                         format!("RPC URL not configured for chain_id '{}'", chain_id),
                     )
                 })?;
+                let block = block_arg(args, req_id)?.unwrap_or_else(|| json!("latest"));
                 let client = Client::new();
-                let v = crate::blockchain::services::token::erc20_info(&client, rpc_url, &token)
+                let v = crate::blockchain::services::token::erc20_info(&client, rpc_url, &token, &block)
                     .await
                     .map_err(|e| {
-                        Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                        Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                     })?;
                 Ok(Response::success(
                     req_id.clone(),
@@ -1448,6 +2074,49 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 Err(e) => e,
             }
         }
+        "detect_token_standard" | "detect-token-standard" => {
+            let res: Result<Response, Response> = (async {
+                let mut chain_id = args
+                    .get("chain_id")
+                    .or_else(|| args.get("network"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "1".to_string());
+                chain_id = normalize_chain_id(&chain_id);
+                let token = utils::get_required_arg::<String>(args, "tokenAddress", req_id)
+                    .or_else(|_| {
+                        utils::get_required_arg::<String>(args, "token_address", req_id)
+                    })?;
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+                let client = Client::new();
+                let v = crate::blockchain::services::token::detect_token_standard(&client, rpc_url, &token)
+                    .await
+                    .map_err(|e| {
+                        Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
+                    })?;
+                let standards: Vec<&str> = v["standards"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|s| s.as_str())
+                    .collect();
+                let summary = if standards.is_empty() {
+                    format!("{} on {} implements no recognised token standard", token, chain_id)
+                } else {
+                    format!("{} on {} is {}", token, chain_id, standards.join(" + "))
+                };
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, v)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
         "get_token_balance" | "get-token-balance" => {
             let res: Result<Response, Response> = (async {
                 let mut chain_id = args
@@ -1466,19 +2135,21 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                         utils::get_required_arg::<String>(args, "owner_address", req_id)
                     })?;
                 let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
-                    Response::error(
+                    Response::error_with_category(
                         req_id.clone(),
                         error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
                         format!("RPC URL not configured for chain_id '{}'", chain_id),
                     )
                 })?;
+                let block = block_arg(args, req_id)?.unwrap_or_else(|| json!("latest"));
                 let client = Client::new();
                 let v = crate::blockchain::services::token::erc20_balance_of(
-                    &client, rpc_url, &token, &owner,
+                    &client, rpc_url, &token, &owner, &block,
                 )
                 .await
                 .map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                 })?;
                 Ok(Response::success(
                     req_id.clone(),
@@ -1513,19 +2184,21 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                         utils::get_required_arg::<String>(args, "spender_address", req_id)
                     })?;
                 let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
-                    Response::error(
+                    Response::error_with_category(
                         req_id.clone(),
                         error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
                         format!("RPC URL not configured for chain_id '{}'", chain_id),
                     )
                 })?;
+                let block = block_arg(args, req_id)?.unwrap_or_else(|| json!("latest"));
                 let client = Client::new();
                 let v = crate::blockchain::services::token::erc20_allowance(
-                    &client, rpc_url, &token, &owner, &spender,
+                    &client, rpc_url, &token, &owner, &spender, &block,
                 )
                 .await
                 .map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                 })?;
                 Ok(Response::success(
                     req_id.clone(),
@@ -1540,7 +2213,8 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         }
         "transfer_token" | "transfer-token" => {
             let res: Result<Response, Response> = (async {
-                let private_key = utils::get_required_arg::<String>(args, "private_key", req_id)?;
+                let private_key =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "private_key", req_id)?);
                 let mut chain_id = args
                     .get("chain_id")
                     .or_else(|| args.get("network"))
@@ -1554,8 +2228,7 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                     })?;
                 let to = utils::get_required_arg::<String>(args, "toAddress", req_id)
                     .or_else(|_| utils::get_required_arg::<String>(args, "to_address", req_id))?;
-                let amount = utils::get_required_arg::<String>(args, "amount", req_id)
-                    .or_else(|_| utils::get_required_arg::<String>(args, "amount_wei", req_id))?;
+                let amount = erc20_amount(&state, &chain_id, &token, args, req_id).await?;
                 let mut tx =
                     crate::blockchain::services::token::erc20_transfer_tx(&token, &to, &amount)
                         .map_err(|e| {
@@ -1565,19 +2238,23 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                                 e.to_string(),
                             )
                         })?;
-                if let Some(g) = args.get("gas_limit").and_then(|v| v.as_str()) {
-                    tx = tx.gas(U256::from_dec_str(g).unwrap_or_else(|_| U256::from(0)));
+                if let Some(g) = utils::get_gas_limit(args, req_id)? {
+                    tx = tx.gas(g);
                 }
-                if let Some(gp) = args.get("gas_price").and_then(|v| v.as_str()) {
-                    tx = tx.gas_price(U256::from_dec_str(gp).unwrap_or_else(|_| U256::from(0)));
+                if let Some(gp) = utils::get_gas_price(args, req_id)? {
+                    tx = tx.gas_price(gp);
                 }
-                let resp = state
-                    .evm_client
-                    .send_transaction(&chain_id, &private_key, tx, &state.nonce_manager)
-                    .await
-                    .map_err(|e| {
-                        Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
-                    })?;
+                let resp = send_idempotent(&state, tool_name, args, req_id, || async {
+                    state
+                        .evm_client
+                        .send_transaction_with_options(&chain_id, &private_key, tx, &state.nonce_manager, &send_options(&state, &chain_id, args, req_id)?)
+                        .await
+                        .map(|r| json!(r))
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })
+                })
+                .await?;
                 Ok(Response::success(
                     req_id.clone(),
                     make_texty_result("ERC20 transfer sent".into(), json!(resp)),
@@ -1591,7 +2268,8 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         }
         "approve_token_spending" | "approve-token-spending" => {
             let res: Result<Response, Response> = (async {
-                let private_key = utils::get_required_arg::<String>(args, "private_key", req_id)?;
+                let private_key =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "private_key", req_id)?);
                 let mut chain_id = args
                     .get("chain_id")
                     .or_else(|| args.get("network"))
@@ -1607,8 +2285,7 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                     .or_else(|_| {
                         utils::get_required_arg::<String>(args, "spender_address", req_id)
                     })?;
-                let amount = utils::get_required_arg::<String>(args, "amount", req_id)
-                    .or_else(|_| utils::get_required_arg::<String>(args, "amount_wei", req_id))?;
+                let amount = erc20_amount(&state, &chain_id, &token, args, req_id).await?;
                 let mut tx =
                     crate::blockchain::services::token::erc20_approve_tx(&token, &spender, &amount)
                         .map_err(|e| {
@@ -1618,19 +2295,23 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                                 e.to_string(),
                             )
                         })?;
-                if let Some(g) = args.get("gas_limit").and_then(|v| v.as_str()) {
-                    tx = tx.gas(U256::from_dec_str(g).unwrap_or_else(|_| U256::from(0)));
+                if let Some(g) = utils::get_gas_limit(args, req_id)? {
+                    tx = tx.gas(g);
                 }
-                if let Some(gp) = args.get("gas_price").and_then(|v| v.as_str()) {
-                    tx = tx.gas_price(U256::from_dec_str(gp).unwrap_or_else(|_| U256::from(0)));
+                if let Some(gp) = utils::get_gas_price(args, req_id)? {
+                    tx = tx.gas_price(gp);
                 }
-                let resp = state
-                    .evm_client
-                    .send_transaction(&chain_id, &private_key, tx, &state.nonce_manager)
-                    .await
-                    .map_err(|e| {
-                        Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
-                    })?;
+                let resp = send_idempotent(&state, tool_name, args, req_id, || async {
+                    state
+                        .evm_client
+                        .send_transaction_with_options(&chain_id, &private_key, tx, &state.nonce_manager, &send_options(&state, &chain_id, args, req_id)?)
+                        .await
+                        .map(|r| json!(r))
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })
+                })
+                .await?;
                 Ok(Response::success(
                     req_id.clone(),
                     make_texty_result("ERC20 approve sent".into(), json!(resp)),
@@ -1642,6 +2323,119 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 Err(e) => e,
             }
         }
+        "transfer_token_from" | "transfer-token-from" => {
+            let res: Result<Response, Response> = (async {
+                let private_key =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "private_key", req_id)?);
+                let mut chain_id = args
+                    .get("chain_id")
+                    .or_else(|| args.get("network"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "1".to_string());
+                chain_id = normalize_chain_id(&chain_id);
+                let token = utils::get_required_arg::<String>(args, "tokenAddress", req_id)
+                    .or_else(|_| {
+                        utils::get_required_arg::<String>(args, "token_address", req_id)
+                    })?;
+                let from = utils::get_required_arg::<String>(args, "fromAddress", req_id)
+                    .or_else(|_| utils::get_required_arg::<String>(args, "from_address", req_id))?;
+                let to = utils::get_required_arg::<String>(args, "toAddress", req_id)
+                    .or_else(|_| utils::get_required_arg::<String>(args, "to_address", req_id))?;
+                let amount = erc20_amount(&state, &chain_id, &token, args, req_id).await?;
+                let mut tx =
+                    crate::blockchain::services::token::erc20_transfer_from_tx(&token, &from, &to, &amount)
+                        .map_err(|e| {
+                            Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
+                        })?;
+                if let Some(g) = utils::get_gas_limit(args, req_id)? {
+                    tx = tx.gas(g);
+                }
+                if let Some(gp) = utils::get_gas_price(args, req_id)? {
+                    tx = tx.gas_price(gp);
+                }
+                let resp = send_idempotent(&state, tool_name, args, req_id, || async {
+                    state
+                        .evm_client
+                        .send_transaction_with_options(&chain_id, &private_key, tx, &state.nonce_manager, &send_options(&state, &chain_id, args, req_id)?)
+                        .await
+                        .map(|r| json!(r))
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })
+                })
+                .await?;
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result("ERC20 transferFrom sent".into(), json!(resp)),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "modify_token_allowance" | "modify-token-allowance" => {
+            let res: Result<Response, Response> = (async {
+                let private_key =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "private_key", req_id)?);
+                let mut chain_id = args
+                    .get("chain_id")
+                    .or_else(|| args.get("network"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "1".to_string());
+                chain_id = normalize_chain_id(&chain_id);
+                let token = utils::get_required_arg::<String>(args, "tokenAddress", req_id)
+                    .or_else(|_| {
+                        utils::get_required_arg::<String>(args, "token_address", req_id)
+                    })?;
+                let spender = utils::get_required_arg::<String>(args, "spenderAddress", req_id)
+                    .or_else(|_| {
+                        utils::get_required_arg::<String>(args, "spender_address", req_id)
+                    })?;
+                let amount = erc20_amount(&state, &chain_id, &token, args, req_id).await?;
+                let operation = utils::get_required_arg::<String>(args, "operation", req_id)?;
+                let increase = match operation.as_str() {
+                    "increase" => true,
+                    "decrease" => false,
+                    other => {
+                        return Err(Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            format!("operation must be 'increase' or 'decrease', got '{}'", other),
+                        ))
+                    }
+                };
+                let mut tx = crate::blockchain::services::token::erc20_modify_allowance_tx(
+                    &token, &spender, &amount, increase,
+                )
+                .map_err(|e| {
+                    Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
+                })?;
+                if let Some(g) = utils::get_gas_limit(args, req_id)? {
+                    tx = tx.gas(g);
+                }
+                if let Some(gp) = utils::get_gas_price(args, req_id)? {
+                    tx = tx.gas_price(gp);
+                }
+                let resp = send_idempotent(&state, tool_name, args, req_id, || async {
+                    state
+                        .evm_client
+                        .send_transaction_with_options(&chain_id, &private_key, tx, &state.nonce_manager, &send_options(&state, &chain_id, args, req_id)?)
+                        .await
+                        .map(|r| json!(r))
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })
+                })
+                .await?;
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(format!("ERC20 {}Allowance sent", operation), json!(resp)),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
         "get_nft_info" | "get-nft-info" => {
             let res: Result<Response, Response> = (async {
                 let mut chain_id = args
@@ -1658,19 +2452,21 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 let token_id = utils::get_required_arg::<String>(args, "tokenId", req_id)
                     .or_else(|_| utils::get_required_arg::<String>(args, "token_id", req_id))?;
                 let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
-                    Response::error(
+                    Response::error_with_category(
                         req_id.clone(),
                         error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
                         format!("RPC URL not configured for chain_id '{}'", chain_id),
                     )
                 })?;
+                let block = block_arg(args, req_id)?.unwrap_or_else(|| json!("latest"));
                 let client = Client::new();
                 let uri = crate::blockchain::services::token::erc721_token_uri(
-                    &client, rpc_url, &token, &token_id,
+                    &client, rpc_url, &token, &token_id, &block,
                 )
                 .await
                 .map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                 })?;
                 Ok(Response::success(
                     req_id.clone(),
@@ -1690,10 +2486,11 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 let token = utils::get_required_arg::<String>(args, "tokenAddress", req_id).or_else(|_| utils::get_required_arg::<String>(args, "token_address", req_id))?;
                 let token_id = utils::get_required_arg::<String>(args, "tokenId", req_id).or_else(|_| utils::get_required_arg::<String>(args, "token_id", req_id))?;
                 let owner = utils::get_required_arg::<String>(args, "ownerAddress", req_id).or_else(|_| utils::get_required_arg::<String>(args, "owner_address", req_id))?;
-                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, format!("RPC URL not configured for chain_id '{}'", chain_id)))?;
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| Response::error_with_category(req_id.clone(), error_codes::INVALID_PARAMS, ErrorCategory::NotConfigured, format!("RPC URL not configured for chain_id '{}'", chain_id)))?;
+                let block = block_arg(args, req_id)?.unwrap_or_else(|| json!("latest"));
                 let client = Client::new();
-                let res_owner = crate::blockchain::services::token::erc721_owner_of(&client, rpc_url, &token, &token_id).await
-                    .map_err(|e| Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string()))?;
+                let res_owner = crate::blockchain::services::token::erc721_owner_of(&client, rpc_url, &token, &token_id, &block).await
+                    .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
                 Ok(Response::success(req_id.clone(), json!({
                     "data": res_owner,
                     "content": [{"type":"text","text": format!("ownerOf == {}? (raw hex encoded)", owner)}]
@@ -1708,10 +2505,10 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
             let res: Result<Response, Response> = (async {
                 let mut chain_id = args.get("chain_id").or_else(|| args.get("network")).and_then(|v| v.as_str()).map(|s| s.to_string()).unwrap_or_else(|| "1".to_string());
                 chain_id = normalize_chain_id(&chain_id);
-                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, format!("RPC URL not configured for chain_id '{}'", chain_id)))?;
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| Response::error_with_category(req_id.clone(), error_codes::INVALID_PARAMS, ErrorCategory::NotConfigured, format!("RPC URL not configured for chain_id '{}'", chain_id)))?;
                 let client = Client::new();
                 let block_number = crate::blockchain::services::token::get_block_number(&client, rpc_url).await
-                    .map_err(|e| Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string()))?;
+                    .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
                 Ok(Response::success(req_id.clone(), json!({
                     "data": block_number,
                     "content": [{"type":"text","text": format!("Current block number: {}", block_number)}]
@@ -1740,19 +2537,21 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                         utils::get_required_arg::<String>(args, "owner_address", req_id)
                     })?;
                 let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
-                    Response::error(
+                    Response::error_with_category(
                         req_id.clone(),
                         error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
                         format!("RPC URL not configured for chain_id '{}'", chain_id),
                     )
                 })?;
+                let block = block_arg(args, req_id)?.unwrap_or_else(|| json!("latest"));
                 let client = Client::new();
                 let v = crate::blockchain::services::token::erc721_balance_of(
-                    &client, rpc_url, &token, &owner,
+                    &client, rpc_url, &token, &owner, &block,
                 )
                 .await
                 .map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                 })?;
                 Ok(Response::success(
                     req_id.clone(),
@@ -1781,19 +2580,21 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 let token_id = utils::get_required_arg::<String>(args, "tokenId", req_id)
                     .or_else(|_| utils::get_required_arg::<String>(args, "token_id", req_id))?;
                 let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
-                    Response::error(
+                    Response::error_with_category(
                         req_id.clone(),
                         error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
                         format!("RPC URL not configured for chain_id '{}'", chain_id),
                     )
                 })?;
+                let block = block_arg(args, req_id)?.unwrap_or_else(|| json!("latest"));
                 let client = Client::new();
                 let v = crate::blockchain::services::token::erc1155_uri(
-                    &client, rpc_url, &token, &token_id,
+                    &client, rpc_url, &token, &token_id, &block,
                 )
                 .await
                 .map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                 })?;
                 Ok(Response::success(
                     req_id.clone(),
@@ -1826,19 +2627,21 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 let token_id = utils::get_required_arg::<String>(args, "tokenId", req_id)
                     .or_else(|_| utils::get_required_arg::<String>(args, "token_id", req_id))?;
                 let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
-                    Response::error(
+                    Response::error_with_category(
                         req_id.clone(),
                         error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
                         format!("RPC URL not configured for chain_id '{}'", chain_id),
                     )
                 })?;
+                let block = block_arg(args, req_id)?.unwrap_or_else(|| json!("latest"));
                 let client = Client::new();
                 let v = crate::blockchain::services::token::erc1155_balance_of(
-                    &client, rpc_url, &token, &owner, &token_id,
+                    &client, rpc_url, &token, &owner, &token_id, &block,
                 )
                 .await
                 .map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                 })?;
                 Ok(Response::success(
                     req_id.clone(),
@@ -1851,9 +2654,55 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 Err(e) => e,
             }
         }
+        "get_erc1155_balance_batch" | "get-erc1155-balance-batch" => {
+            let res: Result<Response, Response> = (async {
+                let mut chain_id = args
+                    .get("chain_id")
+                    .or_else(|| args.get("network"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "1".to_string());
+                chain_id = normalize_chain_id(&chain_id);
+                let token = utils::get_required_arg::<String>(args, "tokenAddress", req_id)
+                    .or_else(|_| {
+                        utils::get_required_arg::<String>(args, "token_address", req_id)
+                    })?;
+                let token_ids = utils::get_required_arg::<Vec<String>>(args, "tokenIds", req_id)
+                    .or_else(|_| utils::get_required_arg::<Vec<String>>(args, "token_ids", req_id))?;
+                // One owner for every id, or one owner per id
+                let owners = match utils::get_required_arg::<String>(args, "ownerAddress", req_id) {
+                    Ok(owner) => vec![owner; token_ids.len()],
+                    Err(_) => utils::get_required_arg::<Vec<String>>(args, "ownerAddresses", req_id)?,
+                };
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+                let block = block_arg(args, req_id)?.unwrap_or_else(|| json!("latest"));
+                let client = Client::new();
+                let v = crate::blockchain::services::token::erc1155_balance_of_batch(
+                    &client, rpc_url, &token, &owners, &token_ids, &block,
+                )
+                .await
+                .map_err(|e| {
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                })?;
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result("ERC1155 balanceOfBatch".into(), v),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
         "transfer_erc1155" | "transfer-erc1155" => {
             let res: Result<Response, Response> = (async {
-                let private_key = utils::get_required_arg::<String>(args, "private_key", req_id)?;
+                let private_key =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "private_key", req_id)?);
                 let mut chain_id = args
                     .get("chain_id")
                     .or_else(|| args.get("network"))
@@ -1878,19 +2727,23 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 .map_err(|e| {
                     Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
                 })?;
-                if let Some(g) = args.get("gas_limit").and_then(|v| v.as_str()) {
-                    tx = tx.gas(U256::from_dec_str(g).unwrap_or_else(|_| U256::from(0)));
+                if let Some(g) = utils::get_gas_limit(args, req_id)? {
+                    tx = tx.gas(g);
                 }
-                if let Some(gp) = args.get("gas_price").and_then(|v| v.as_str()) {
-                    tx = tx.gas_price(U256::from_dec_str(gp).unwrap_or_else(|_| U256::from(0)));
+                if let Some(gp) = utils::get_gas_price(args, req_id)? {
+                    tx = tx.gas_price(gp);
                 }
-                let resp = state
-                    .evm_client
-                    .send_transaction(&chain_id, &private_key, tx, &state.nonce_manager)
-                    .await
-                    .map_err(|e| {
-                        Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
-                    })?;
+                let resp = send_idempotent(&state, tool_name, args, req_id, || async {
+                    state
+                        .evm_client
+                        .send_transaction_with_options(&chain_id, &private_key, tx, &state.nonce_manager, &send_options(&state, &chain_id, args, req_id)?)
+                        .await
+                        .map(|r| json!(r))
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })
+                })
+                .await?;
                 Ok(Response::success(
                     req_id.clone(),
                     make_texty_result("ERC1155 transfer sent".into(), json!(resp)),
@@ -1902,94 +2755,130 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 Err(e) => e,
             }
         }
+        "transfer_erc1155_batch" | "transfer-erc1155-batch" => {
+            let res: Result<Response, Response> = (async {
+                let private_key =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "private_key", req_id)?);
+                let mut chain_id = args
+                    .get("chain_id")
+                    .or_else(|| args.get("network"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "1".to_string());
+                chain_id = normalize_chain_id(&chain_id);
+                let token = utils::get_required_arg::<String>(args, "tokenAddress", req_id)
+                    .or_else(|_| {
+                        utils::get_required_arg::<String>(args, "token_address", req_id)
+                    })?;
+                let from = utils::get_required_arg::<String>(args, "fromAddress", req_id)
+                    .or_else(|_| utils::get_required_arg::<String>(args, "from_address", req_id))?;
+                let to = utils::get_required_arg::<String>(args, "toAddress", req_id)
+                    .or_else(|_| utils::get_required_arg::<String>(args, "to_address", req_id))?;
+                let token_ids = utils::get_required_arg::<Vec<String>>(args, "tokenIds", req_id)
+                    .or_else(|_| utils::get_required_arg::<Vec<String>>(args, "token_ids", req_id))?;
+                let amounts = utils::get_required_arg::<Vec<String>>(args, "amounts", req_id)?;
+                let mut tx = crate::blockchain::services::token::erc1155_safe_batch_transfer_from_tx(
+                    &token, &from, &to, &token_ids, &amounts,
+                )
+                .map_err(|e| {
+                    Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
+                })?;
+                if let Some(g) = utils::get_gas_limit(args, req_id)? {
+                    tx = tx.gas(g);
+                }
+                if let Some(gp) = utils::get_gas_price(args, req_id)? {
+                    tx = tx.gas_price(gp);
+                }
+                let resp = send_idempotent(&state, tool_name, args, req_id, || async {
+                    state
+                        .evm_client
+                        .send_transaction_with_options(&chain_id, &private_key, tx, &state.nonce_manager, &send_options(&state, &chain_id, args, req_id)?)
+                        .await
+                        .map(|r| json!(r))
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })
+                })
+                .await?;
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result("ERC1155 batch transfer sent".into(), json!(resp)),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
         // --- Generic contract utils ---
         "is_contract" | "is-contract" => {
             let res: Result<Response, Response> = (async {
                 let mut chain_id = args.get("chain_id").or_else(|| args.get("network")).and_then(|v| v.as_str()).map(|s| s.to_string()).unwrap_or_else(|| "1".to_string());
                 chain_id = normalize_chain_id(&chain_id);
                 let address = utils::get_required_arg::<String>(args, "address", req_id)?;
-                let etherscan_api_key = match state.config.etherscan_api_key.as_ref() {
-                    Some(key) => key,
-                    None => {
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INVALID_PARAMS,
-                            "ETHERSCAN_API_KEY is not configured".to_string(),
-                        ));
-                    }
-                };
 
                 // For non-EVM addresses, return false
                 if !address.starts_with("0x") {
                     return Ok(Response::success(req_id.clone(), json!({"is_contract": false, "verified": false, "content": [{"type":"text","text": format!("{} is not an EVM address", address)}]})));
                 }
 
-                // Determine Etherscan base URL based on chain
-                let etherscan_base_url = match chain_id.as_str() {
-                    "1" => "https://api.etherscan.io/v2/api",
-                    "11155111" => "https://api-sepolia.etherscan.io/v2/api",
-                    _ => {
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INVALID_PARAMS,
-                            format!("Etherscan API not supported for chain_id '{}'", chain_id),
-                        ));
+                // Contract vs EOA comes from chain state when the chain has an RPC; Etherscan
+                // only knows about verification, so on its own it can't tell the two apart
+                let client = state.http_client.clone();
+                let code_size = match state.config.chain_rpc_urls.get(&chain_id) {
+                    Some(rpc_url) => {
+                        let code = contract::get_contract_code(&client, rpc_url, &address)
+                            .await
+                            .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
+                        let bytecode = code.get("code").and_then(|v| v.as_str()).unwrap_or("0x");
+                        Some(bytecode.trim_start_matches("0x").len() / 2)
                     }
+                    None => None,
                 };
+                let is_contract = code_size.is_none_or(|size| size > 0);
 
-                // Build Etherscan API URL for getsourcecode
-                let url = format!(
-                    "{}?chainid={}&module=contract&action=getsourcecode&address={}&apikey={}",
-                    etherscan_base_url, chain_id, address, etherscan_api_key
-                );
-
-                let client = Client::new();
-                let resp: serde_json::Value = client
-                    .get(&url)
-                    .send()
-                    .await
-                    .map_err(|e| {
-                        Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Etherscan API error: {}", e),
-                        )
-                    })?
-                    .json()
-                    .await
-                    .map_err(|e| {
-                        Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Invalid Etherscan JSON response: {}", e),
-                        )
-                    })?;
+                // Verification needs Etherscan; with chain state in hand it is optional
+                let verification = match (code_size, state.config.etherscan_api_key.as_deref()) {
+                    (Some(0), _) | (Some(_), None) => None,
+                    (Some(_), Some(key)) => match state.config.etherscan_base_url(&chain_id) {
+                        Ok(base_url) => Some((base_url, key)),
+                        Err(_) => None,
+                    },
+                    (None, _) => Some((etherscan_base_url(&state, &chain_id, req_id)?, etherscan_api_key(&state, req_id)?)),
+                };
+                let verification = match verification {
+                    Some((base_url, key)) => Some(
+                        contract::is_verified(&client, &base_url, &chain_id, &address, key)
+                            .await
+                            .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?,
+                    ),
+                    None => None,
+                };
 
-                // Check for Etherscan API errors
-                if let Some(status) = resp.get("status").and_then(|v| v.as_str()) {
-                    if status != "1" {
-                        let message = resp.get("message").and_then(|v| v.as_str()).unwrap_or("Unknown error");
-                        return Err(Response::error(
-                            req_id.clone(),
-                            error_codes::INTERNAL_ERROR,
-                            format!("Etherscan API error: {}", message),
-                        ));
+                let size_note = code_size.map(|n| format!(", {} bytes of code", n)).unwrap_or_default();
+                let message = match (&verification, is_contract) {
+                    (_, false) => format!("{} is an externally owned account (no contract code)", address),
+                    (Some(v), true) if v.verified => {
+                        format!("{} is a verified contract (ContractName: {}{})", address, v.contract_name, size_note)
                     }
-                }
-
-                // Check if contract has source code (verified contract)
-                let result = resp.get("result").and_then(|v| v.as_array()).and_then(|arr| arr.get(0));
-                let has_source_code = result.and_then(|r| r.get("SourceCode")).and_then(|v| v.as_str()).map(|s| !s.is_empty()).unwrap_or(false);
-                let contract_name = result.and_then(|r| r.get("ContractName")).and_then(|v| v.as_str()).unwrap_or("Unknown");
-
-                let is_verified = has_source_code && !contract_name.is_empty();
-                let message = if is_verified {
-                    format!("{} is a verified contract (ContractName: {})", address, contract_name)
-                } else {
-                    format!("{} has no verified source code on Etherscan", address)
+                    (Some(_), true) if code_size.is_some() => {
+                        format!("{} is a contract{} with no verified source code on Etherscan", address, size_note)
+                    }
+                    (Some(_), true) => format!("{} has no verified source code on Etherscan", address),
+                    (None, true) => format!("{} is a contract{}; Etherscan verification not checked", address, size_note),
                 };
 
-                Ok(Response::success(req_id.clone(), json!({"is_contract": true, "verified": is_verified, "contract_name": contract_name, "content": [{"type":"text","text": message}]})))
+                let mut payload = json!({
+                    "is_contract": is_contract,
+                    "verified": verification.as_ref().map(|v| v.verified).unwrap_or(false),
+                    "content": [{"type": "text", "text": message}]
+                });
+                if let Some(size) = code_size {
+                    payload["code_size"] = json!(size);
+                    payload["is_eoa"] = json!(size == 0);
+                }
+                if let Some(v) = &verification {
+                    payload["contract_name"] = json!(v.contract_name);
+                }
+                Ok(Response::success(req_id.clone(), payload))
             }).await;
             match res {
                 Ok(r) => r,
@@ -2009,26 +2898,61 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                     .or_else(|_| {
                     utils::get_required_arg::<String>(args, "contract_address", req_id)
                 })?;
-                let abi = utils::get_required_arg::<String>(args, "abi", req_id)?;
-                let function = utils::get_required_arg::<String>(args, "functionName", req_id)
-                    .or_else(|_| {
-                        utils::get_required_arg::<String>(args, "function_name", req_id)
-                    })?;
+                // A full ABI takes precedence; otherwise a human readable signature is enough
+                let abi = args.get("abi").and_then(|v| v.as_str()).map(str::to_string);
+                let signature = args.get("function_signature").and_then(|v| v.as_str()).map(str::to_string);
                 let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
-                    Response::error(
+                    Response::error_with_category(
                         req_id.clone(),
                         error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
                         format!("RPC URL not configured for chain_id '{}'", chain_id),
                     )
                 })?;
                 let args_vec = args.get("args").and_then(|v| v.as_array()).cloned();
+                let block = block_arg(args, req_id)?.unwrap_or_else(|| json!("latest"));
                 let client = Client::new();
-                let v = crate::blockchain::services::token::read_contract_via_abi(
-                    &client, rpc_url, &contract, &abi, &function, args_vec,
-                )
-                .await
-                .map_err(|e| {
-                    Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
+                let function_name = args
+                    .get("functionName")
+                    .or_else(|| args.get("function_name"))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string);
+                // With only a function name, the contract's verified ABI is fetched (and cached)
+                let abi = match (abi, &signature, &function_name) {
+                    (None, None, Some(_)) => Some(verified_abi(&state, &chain_id, &contract, req_id).await?.0),
+                    (abi, _, _) => abi,
+                };
+                let (function, read) = match (abi, signature) {
+                    (Some(abi), _) => {
+                        let function = function_name.ok_or_else(|| {
+                            Response::error(req_id.clone(), error_codes::INVALID_PARAMS, "Missing required argument: functionName".to_string())
+                        })?;
+                        let read = crate::blockchain::services::token::read_contract_via_abi(
+                            &client, rpc_url, &contract, &abi, &function, args_vec, &block,
+                        )
+                        .await;
+                        (function, read)
+                    }
+                    (None, Some(signature)) => {
+                        let function = abi::parse_function_signature(&signature)
+                            .map_err(|e| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string()))?
+                            .name;
+                        let read = crate::blockchain::services::token::read_contract_via_signature(
+                            &client, rpc_url, &contract, &signature, args_vec, &block,
+                        )
+                        .await;
+                        (function, read)
+                    }
+                    (None, None) => {
+                        return Err(Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            "Provide 'functionName' (with 'abi', or alone for a verified contract) or 'function_signature'".to_string(),
+                        ))
+                    }
+                };
+                let v = read.map_err(|e| {
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
                 })?;
                 Ok(Response::success(
                     req_id.clone(),
@@ -2043,7 +2967,8 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
         }
         "write_contract" | "write-contract" => {
             let res: Result<Response, Response> = (async {
-                let private_key = utils::get_required_arg::<String>(args, "private_key", req_id)?;
+                let private_key =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "private_key", req_id)?);
                 let mut chain_id = args
                     .get("chain_id")
                     .or_else(|| args.get("network"))
@@ -2055,35 +2980,56 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                     .or_else(|_| {
                     utils::get_required_arg::<String>(args, "contract_address", req_id)
                 })?;
-                let abi = utils::get_required_arg::<String>(args, "abi", req_id)?;
                 let function = utils::get_required_arg::<String>(args, "functionName", req_id)
                     .or_else(|_| {
                         utils::get_required_arg::<String>(args, "function_name", req_id)
                     })?;
+                // Without 'abi' the contract's verified ABI is fetched (and cached)
+                let abi = match args.get("abi").and_then(|v| v.as_str()) {
+                    Some(abi) => abi.to_string(),
+                    None => verified_abi(&state, &chain_id, &contract, req_id).await?.0,
+                };
                 let args_vec = args.get("args").and_then(|v| v.as_array()).cloned();
+                let value = args.get("value").and_then(|v| v.as_str());
                 let mut tx = crate::blockchain::services::token::write_contract_tx(
-                    &contract, &abi, &function, args_vec,
+                    &contract, &abi, &function, args_vec, value,
                 )
                 .map_err(|e| {
                     Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
                 })?;
-                if let Some(g) = args.get("gas_limit").and_then(|v| v.as_str()) {
-                    tx = tx.gas(U256::from_dec_str(g).unwrap_or_else(|_| U256::from(0)));
+                if let Some(g) = utils::get_gas_limit(args, req_id)? {
+                    tx = tx.gas(g);
                 }
-                if let Some(gp) = args.get("gas_price").and_then(|v| v.as_str()) {
-                    tx = tx.gas_price(U256::from_dec_str(gp).unwrap_or_else(|_| U256::from(0)));
+                if let Some(gp) = utils::get_gas_price(args, req_id)? {
+                    tx = tx.gas_price(gp);
                 }
-                let resp = state
-                    .evm_client
-                    .send_transaction(&chain_id, &private_key, tx, &state.nonce_manager)
-                    .await
-                    .map_err(|e| {
-                        Response::error(req_id.clone(), error_codes::INTERNAL_ERROR, e.to_string())
-                    })?;
-                Ok(Response::success(
-                    req_id.clone(),
-                    make_texty_result(format!("write {}.{} sent", contract, function), json!(resp)),
-                ))
+                // The approve and the call are replayed together under one idempotency_key
+                let result = send_idempotent(&state, tool_name, args, req_id, || async {
+                    let allowance = match args.get("ensure_allowance") {
+                        Some(spec) => Some(
+                            ensure_allowance(&state, &chain_id, &private_key, &contract, spec, args, req_id).await?,
+                        ),
+                        None => None,
+                    };
+                    let resp = state
+                        .evm_client
+                        .send_transaction_with_options(&chain_id, &private_key, tx, &state.nonce_manager, &send_options(&state, &chain_id, args, req_id)?)
+                        .await
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })?;
+                    let mut result = json!(resp);
+                    if let Some(allowance) = allowance {
+                        result["allowance"] = allowance;
+                    }
+                    Ok(result)
+                })
+                .await?;
+                let mut summary = format!("write {}.{} sent", contract, function);
+                if let Some(hash) = result["allowance"]["approve_tx_hash"].as_str() {
+                    summary = format!("Approved {} first ({}); {}", contract, hash, summary);
+                }
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, result)))
             })
             .await;
             match res {
@@ -2091,330 +3037,4531 @@ async fn handle_tool_call(req: Request, state: AppState) -> Response {
                 Err(e) => e,
             }
         }
-        _ => Response::error(
-            req.id,
-            error_codes::METHOD_NOT_FOUND,
-            format!("Tool not found: {}", tool_name),
-        ),
-    }
-}
+        "compare_gas_costs" => {
+            let res: Result<Response, Response> = (async {
+                let chain_ids = utils::get_required_arg::<Vec<String>>(args, "chain_ids", req_id)?;
+                let to_address = utils::get_required_arg::<String>(args, "to_address", req_id)?;
+                let to = Address::from_str(&to_address).map_err(|_| {
+                    Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        "Invalid 'to_address'".into(),
+                    )
+                })?;
 
-/// Handles the 'initialize' request.
-fn handle_initialize(req: &Request) -> Response {
-    let server_info = json!({
-        "name": "evm_mcp",
-        "version": "0.1.0"
-    });
-    let capabilities = json!({ "tools": { "listChanged": false } });
-    let instructions =
-        "EVM blockchain MCP server for secure wallet operations, balance queries, and transaction management.";
+                // Build the eth_estimateGas call object shared by every chain
+                let mut call = json!({ "to": format!("{:?}", to) });
+                if let Some(from) = args.get("from_address").and_then(|v| v.as_str()) {
+                    call["from"] = json!(from);
+                }
+                if let Some(v) = args.get("value").and_then(|v| v.as_str()) {
+                    let value = U256::from_dec_str(v).map_err(|_| {
+                        Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            "Invalid 'value'".into(),
+                        )
+                    })?;
+                    call["value"] = json!(format!("0x{:x}", value));
+                }
+                if let Some(data) = args.get("data").and_then(|v| v.as_str()) {
+                    call["data"] = json!(data);
+                }
 
-    Response::success(
-        req.id.clone(),
-        json!({
-            "serverInfo": server_info,
-            "protocolVersion": "2025-06-18",
-            "capabilities": capabilities,
-            "instructions": instructions
-        }),
-    )
-}
+                let mut targets = Vec::new();
+                let mut unconfigured = Vec::new();
+                for raw in &chain_ids {
+                    let chain_id = normalize_chain_id(raw);
+                    match state.config.chain_rpc_urls.get(&chain_id) {
+                        Some(url) => targets.push((chain_id, url.clone())),
+                        None => unconfigured.push(chain_id),
+                    }
+                }
 
-/// Handles the 'tools/list' request by returning a JSON definition of all available tools.
-// FIX: The tool list is now updated, secure, and functional.
-fn handle_tools_list(req: &Request) -> Response {
-    let tools = json!([
-        {
-            "name": "get_balance",
-            "description": "Get the EVM balance of an address on a specific chain.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "chain_id": {"type": "string", "description": "The blockchain chain ID (e.g., '1' for Ethereum mainnet)"},
-                    "address": {"type": "string", "description": "The 0x... EVM wallet address to check."}
-                },
-                "required": ["chain_id", "address"]
-            }
-        },
-        {
-            "name": "create_wallet",
-            "description": "Create a new EVM wallet. Returns address, private key, and mnemonic.",
-            "inputSchema": { "type": "object", "properties": {}, "additionalProperties": false }
-        },
-        {
-            "name": "import_wallet",
-            "description": "Import a wallet from a mnemonic phrase or private key.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "mnemonic_or_private_key": {"type": "string", "description": "Mnemonic phrase or private key."},
-                    "key": {"type": "string", "description": "Alias for mnemonic_or_private_key (back-compat)."},
-                    "chain_type": {"type": "string", "description": "'evm' (default) or 'native'"}
-                },
-                "oneOf": [
-                    {"required": ["mnemonic_or_private_key"]},
-                    {"required": ["key"]}
-                ],
-                "additionalProperties": false
-            }
-        },
-        {
-            "name": "search_events",
-            "description": "Search EVM logs via Etherscan API. Supports Ethereum Mainnet and Sepolia Testnet.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)"},
-                    "contract_address": {"type": "string", "description": "Contract address to search logs for"},
-                    "topic0": {"type": "string", "description": "Keccak topic0 (event signature hash)"},
-                    "from_block": {"type": "string", "description": "Starting block number (decimal or hex)"},
-                    "to_block": {"type": "string", "description": "Ending block number (decimal or hex)"}
-                },
-                "required": ["chain_id", "contract_address"],
-                "additionalProperties": false
-            }
-        },
-        {
-            "name": "request_faucet",
-            "description": "Request testnet tokens from the faucet for an EVM address.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "chain_id": {"type": "string", "description": "Target chain id configured in CHAIN_RPC_URLS."},
-                    "address": {"type": "string", "description": "The EVM (0x...) address to receive tokens."}
-                },
-                "required": ["chain_id", "address"],
-                "additionalProperties": false
-            }
-        },
-        {
-            "name": "register_wallet",
-            "description": "Encrypt and securely store a wallet under a name using a mnemonic or private key.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "wallet_name": {"type": "string", "description": "A unique name for the wallet (e.g., 'my-primary-wallet')."},
-                    "mnemonic_or_private_key": {"type": "string", "description": "Mnemonic phrase or private key to register."},
-                    "private_key": {"type": "string", "description": "Alias input for compatibility (private key)."},
-                    "master_password": {"type": "string", "description": "The master password to encrypt the wallet. This password will be required for any future actions with this wallet."},
-                    "chain_type": {"type": "string", "description": "'evm' (default) or 'native'"}
-                },
-                "oneOf": [
-                    {"required": ["wallet_name", "mnemonic_or_private_key", "master_password"]},
-                    {"required": ["wallet_name", "private_key", "master_password"]}
-                ],
-                "additionalProperties": false
-            }
-        },
-        {
-            "name": "list_wallets",
-            "description": "List the names of all wallets currently stored in the secure storage.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                     "master_password": {"type": "string", "description": "The master password for the wallet storage."}
-                },
-                "required": ["master_password"]
-            }
-        },
-        {
-            "name": "transfer_from_wallet",
-            "description": "Transfer tokens from a securely stored wallet.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "wallet_name": {"type": "string", "description": "The name of the stored wallet to transfer from."},
-                    "chain_id": {"type": "string", "description": "The blockchain chain ID (e.g., 'testnet')."},
-                    "to_address": {"type": "string", "description": "The recipient's 0x... EVM address."},
-                    "amount": {"type": "string", "description": "The amount to transfer in wei."},
-                    "master_password": {"type": "string", "description": "The master password to unlock the wallet for this transaction."}
-                },
-                "required": ["wallet_name", "chain_id", "to_address", "amount", "master_password"]
-            }
-        },
-        {
-            "name": "transfer_evm",
-            "description": "Send an EVM value transfer using a provided private key.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "private_key": {"type": "string"},
-                    "chain_id": {"type": "string"},
-                    "to_address": {"type": "string"},
-                    "amount_wei": {"type": "string"},
-                    "gas_limit": {"type": "string"},
-                    "gas_price": {"type": "string"}
-                },
-                "required": ["private_key", "chain_id", "to_address", "amount_wei"],
-                "additionalProperties": false
-            }
-        },
-        {
-            "name": "transfer_nft_evm",
-            "description": "Transfer an ERC-721 token.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "private_key": {"type": "string"},
-                    "chain_id": {"type": "string"},
-                    "contract_address": {"type": "string"},
-                    "to_address": {"type": "string"},
-                    "token_id": {"type": "string"}
-                },
-                "required": ["private_key", "chain_id", "contract_address", "to_address", "token_id"],
-                "additionalProperties": false
-            }
-        },
-         {
-             "name": "get_contract",
-             "description": "Get verified contract details from Etherscan API.",
-             "inputSchema": {
-                 "type": "object",
-                 "properties": {
-                     "address": {"type": "string", "description": "The address of the smart contract."},
-                     "chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)."}
-                 },
-                 "required": ["address"]
-             }
-         },
-        {
-            "name": "get_contract_code",
-            "description": "Get verified contract bytecode from Etherscan API.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "address": {"type": "string", "description": "The address of the smart contract."},
-                    "chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)."}
-                },
-                "required": ["address"]
-            }
-        },
-        {
-            "name": "discord_post_message",
-            "description": "Post a message to Discord via webhook or bot token (configured in server env).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "message": {"type": "string", "description": "Text to post. Code or natural language supported."},
-                    "username": {"type": "string", "description": "Optional display username (webhook mode)."}
-                },
-                "required": ["message"],
-                "additionalProperties": false
-            }
-        },
-        {
-            "name": "get_discord_service_info",
-            "description": "Fetch basic info from the external discord-api root endpoint (requires DISCORD_API_URL).",
-            "inputSchema": { "type": "object", "properties": {}, "additionalProperties": false }
-        },
-        {
-            "name": "check_discord_health",
-            "description": "Check health of the external discord-api (requires DISCORD_API_URL).",
-            "inputSchema": { "type": "object", "properties": {}, "additionalProperties": false }
-        },
+                let client = Client::new();
+                let mut ranked =
+                    crate::blockchain::services::gas::compare_gas_costs(&client, &targets, &call)
+                        .await;
+                for chain_id in unconfigured {
+                    ranked.push(json!({
+                        "chain_id": chain_id,
+                        "rank": ranked.len() + 1,
+                        "error": format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    }));
+                }
+
+                let summary = match ranked.first() {
+                    Some(best) if best.get("error").is_none() => format!(
+                        "Cheapest chain: {} ({} native, {} wei)",
+                        best["chain_id"].as_str().unwrap_or(""),
+                        best["total_fee"].as_str().unwrap_or(""),
+                        best["total_fee_wei"].as_str().unwrap_or("")
+                    ),
+                    _ => "No chain returned a gas estimate".to_string(),
+                };
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(summary, json!({ "ranking": ranked })),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "export_private_key" => {
+            let res: Result<Response, Response> = (async {
+                if state.config.key_export_disabled {
+                    return Err(Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_REQUEST,
+                        "Private key export is disabled on this server (ALLOW_KEY_EXPORT=false)"
+                            .into(),
+                    ));
+                }
+                let wallet_name = utils::get_required_arg::<String>(args, "wallet_name", req_id)?;
+                let master_password =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "master_password", req_id)?);
+
+                unlock_wallet_storage(&state, &master_password, req_id).await?;
+                let (private_key, address) = {
+                    let storage = state.wallet_storage.lock().await;
+                    let key = storage
+                        .get_private_key(&wallet_name, &master_password)
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })?;
+                    let address = storage
+                        .get_wallet(&wallet_name)
+                        .map(|w| w.public_address.clone())
+                        .unwrap_or_default();
+                    (key, address)
+                };
+                let private_key = if private_key.starts_with("0x") {
+                    private_key
+                } else {
+                    Zeroizing::new(format!("0x{}", private_key.as_str()))
+                };
+
+                let warning = "Anyone with this private key has full control of the wallet's funds. Never share it or paste it into untrusted tools.";
+                let summary = format!(
+                    "⚠️ WARNING: {}\nWallet: {}\nAddress: {}\nPrivate Key: {}",
+                    warning, wallet_name, address, private_key.as_str()
+                );
+                let payload = json!({
+                    "wallet_name": wallet_name,
+                    "address": address,
+                    "private_key": private_key.as_str(),
+                    "warning": warning,
+                });
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(summary, payload),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "get_chain_tip_health" => {
+            let res: Result<Response, Response> = (async {
+                let mut chain_id = args
+                    .get("chain_id")
+                    .or_else(|| args.get("network"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "1".to_string());
+                chain_id = normalize_chain_id(&chain_id);
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+                let max_lag_secs = args
+                    .get("max_lag_secs")
+                    .and_then(|v| v.as_u64())
+                    .or(state.config.chain_tip_max_lag_secs)
+                    .unwrap_or(crate::blockchain::services::chain::DEFAULT_MAX_TIP_LAG_SECS);
+
+                let client = Client::new();
+                let mut health = crate::blockchain::services::chain::chain_tip_health(
+                    &client,
+                    rpc_url,
+                    max_lag_secs,
+                )
+                .await
+                .map_err(|e| {
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                })?;
+                health["chain_id"] = json!(chain_id);
+
+                let summary = if health["stalled"].as_bool().unwrap_or(false) {
+                    format!(
+                        "RPC for chain {} looks STALLED: latest block {} is {}s behind (threshold {}s)",
+                        chain_id, health["block_number"], health["lag_secs"], max_lag_secs
+                    )
+                } else {
+                    format!(
+                        "RPC for chain {} is healthy: latest block {} is {}s behind",
+                        chain_id, health["block_number"], health["lag_secs"]
+                    )
+                };
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(summary, health),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "change_master_password" => {
+            let res: Result<Response, Response> = (async {
+                let master_password =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "master_password", req_id)?);
+                let new_master_password =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "new_master_password", req_id)?);
+
+                unlock_wallet_storage(&state, &master_password, req_id).await?;
+                let count = wallet_storage::update_blocking(&state.wallet_storage, move |storage| {
+                    storage
+                        .change_master_password(&master_password, &new_master_password)
+                        .map(|()| storage.wallets().len())
+                })
+                .await
+                .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
+
+                let summary = format!(
+                    "Master password changed; re-encrypted {} wallet(s). Update MASTER_PASSWORD before restarting the server.",
+                    count
+                );
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(summary, json!({ "status": "success", "wallets_reencrypted": count })),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "create_and_register_wallet" => {
+            let res: Result<Response, Response> = (async {
+                let wallet_name = utils::get_required_arg::<String>(args, "wallet_name", req_id)?;
+                let master_password =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "master_password", req_id)?);
+
+                // The private key is generated and stored server-side only; it never
+                // round-trips through the client.
+                let wallet = wallet::create_wallet().map_err(|e| {
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                })?;
+
+                store_wallet(&state, &wallet_name, &wallet.private_key, &wallet.address, &master_password, req_id)
+                    .await?;
+
+                // The mnemonic backup is held back like any other key material when
+                // the server or the caller asks for it
+                let mnemonic = wallet.mnemonic.as_ref().filter(|_| reveal_keys(&state, args));
+                let mnemonic_text = mnemonic
+                    .map(|m| format!("\nMnemonic (write this down as a backup): {}", m))
+                    .unwrap_or_default();
+                let summary = format!(
+                    "Created and registered wallet {}\nAddress: {}{}",
+                    wallet_name, wallet.address, mnemonic_text
+                );
+                let mut payload = json!({
+                    "status": "success",
+                    "wallet_name": wallet_name,
+                    "address": wallet.address,
+                });
+                if let Some(m) = mnemonic {
+                    payload["mnemonic"] = json!(m);
+                }
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(summary, payload),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "batch_read_contract" | "batch-read-contract" => {
+            let res: Result<Response, Response> = (async {
+                let mut chain_id = args
+                    .get("chain_id")
+                    .or_else(|| args.get("network"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "1".to_string());
+                chain_id = normalize_chain_id(&chain_id);
+                let contract = utils::get_required_arg::<String>(args, "contractAddress", req_id)
+                    .or_else(|_| {
+                        utils::get_required_arg::<String>(args, "contract_address", req_id)
+                    })?;
+                let abi = utils::get_required_arg::<String>(args, "abi", req_id)?;
+                let raw_calls = utils::get_required_arg::<Vec<Value>>(args, "calls", req_id)?;
+                let mut calls = Vec::new();
+                for call in &raw_calls {
+                    let name = call
+                        .get("functionName")
+                        .or_else(|| call.get("function_name"))
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| {
+                            Response::error(
+                                req_id.clone(),
+                                error_codes::INVALID_PARAMS,
+                                "Each entry in 'calls' needs a 'functionName'".into(),
+                            )
+                        })?;
+                    let call_args = call.get("args").and_then(|v| v.as_array()).cloned();
+                    calls.push((name.to_string(), call_args));
+                }
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+                let client = Client::new();
+                let results = crate::blockchain::services::token::batch_read_contract_via_abi(
+                    &client, rpc_url, &contract, &abi, calls,
+                )
+                .await
+                .map_err(|e| {
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                })?;
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(
+                        format!("Batch read of {} call(s) on {} (chain {})", raw_calls.len(), contract, chain_id),
+                        json!({ "results": results }),
+                    ),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "set_wallet_limit" => {
+            let res: Result<Response, Response> = (async {
+                let wallet_name = utils::get_required_arg::<String>(args, "wallet_name", req_id)?;
+                let master_password =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "master_password", req_id)?);
+                // A missing or null limit clears it
+                let limit = match args.get("daily_limit_wei").and_then(|v| v.as_str()) {
+                    Some(l) => Some(U256::from_dec_str(l).map_err(|_| {
+                        Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            "Invalid 'daily_limit_wei'".into(),
+                        )
+                    })?),
+                    None => None,
+                };
+
+                unlock_wallet_storage(&state, &master_password, req_id).await?;
+                let name = wallet_name.clone();
+                wallet_storage::update_blocking(&state.wallet_storage, move |storage| {
+                    storage.set_wallet_limit(&name, limit, &master_password)
+                })
+                .await
+                .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
+
+                let summary = match limit {
+                    Some(l) => format!("Daily limit for {} set to {} wei", wallet_name, l),
+                    None => format!("Daily limit for {} removed", wallet_name),
+                };
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(
+                        summary,
+                        json!({
+                            "status": "success",
+                            "wallet_name": wallet_name,
+                            "daily_limit_wei": limit.map(|l| l.to_string()),
+                        }),
+                    ),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "reencrypt_wallet" | "rotate_wallet_key" => {
+            let res: Result<Response, Response> = (async {
+                let wallet_name = utils::get_required_arg::<String>(args, "wallet_name", req_id)?;
+                let master_password =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "master_password", req_id)?);
+
+                unlock_wallet_storage(&state, &master_password, req_id).await?;
+                let name = wallet_name.clone();
+                let upgraded = wallet_storage::update_blocking(&state.wallet_storage, move |storage| {
+                    storage.reencrypt_wallet(&name, &master_password)
+                })
+                .await
+                .map_err(|e| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string()))?;
+
+                let summary = if upgraded {
+                    format!("Wallet '{}' upgraded from the legacy format and re-encrypted", wallet_name)
+                } else {
+                    format!("Wallet '{}' re-encrypted with a fresh salt", wallet_name)
+                };
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(
+                        summary,
+                        json!({ "status": "success", "wallet_name": wallet_name, "upgraded_legacy": upgraded }),
+                    ),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "get_wallet_token_balances" => {
+            let res: Result<Response, Response> = (async {
+                let wallet_name = utils::get_required_arg::<String>(args, "wallet_name", req_id)?;
+                let master_password =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "master_password", req_id)?);
+
+                unlock_wallet_storage(&state, &master_password, req_id).await?;
+                let public_address = {
+                    let storage = state.wallet_storage.lock().await;
+                    storage
+                        .get_wallet(&wallet_name)
+                        .map(|w| w.public_address.clone())
+                        .ok_or_else(|| {
+                            Response::error_with_category(
+                                req_id.clone(),
+                                error_codes::INVALID_PARAMS,
+                                ErrorCategory::NotFound,
+                                format!("Wallet '{}' not found", wallet_name),
+                            )
+                        })?
+                };
+                let owner = Address::from_str(&public_address).map_err(|e| {
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                })?;
+
+                // Default to every configured chain
+                let mut chain_ids: Vec<String> = match args.get("chain_ids").and_then(|v| v.as_array()) {
+                    Some(ids) => ids.iter().filter_map(|v| v.as_str()).map(normalize_chain_id).collect(),
+                    None => state.config.chain_rpc_urls.keys().cloned().collect(),
+                };
+                chain_ids.sort();
+                chain_ids.dedup();
+                let mut targets = Vec::new();
+                for chain_id in chain_ids {
+                    let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                        Response::error_with_category(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            ErrorCategory::NotConfigured,
+                            format!("RPC URL not configured for chain_id '{}'", chain_id),
+                        )
+                    })?;
+                    targets.push((chain_id, rpc_url.clone()));
+                }
+
+                let mut tokens = Vec::new();
+                for t in args.get("tokens").and_then(|v| v.as_array()).into_iter().flatten() {
+                    let s = t.as_str().unwrap_or_default();
+                    tokens.push(Address::from_str(s).map_err(|_| {
+                        Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            format!("Invalid token address '{}'", s),
+                        )
+                    })?);
+                }
+
+                let client = Client::new();
+                let chains = crate::blockchain::services::portfolio::wallet_token_balances(
+                    &client, owner, &targets, &tokens,
+                )
+                .await;
+                let found: usize = chains
+                    .iter()
+                    .filter_map(|c| c["balances"].as_array())
+                    .map(|b| b.len())
+                    .sum();
+                let summary = format!(
+                    "{} non-zero balance(s) for wallet '{}' ({}) across {} chain(s)",
+                    found,
+                    wallet_name,
+                    public_address,
+                    chains.len()
+                );
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(
+                        summary,
+                        json!({ "wallet_name": wallet_name, "address": public_address, "chains": chains }),
+                    ),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "speed_up_from_wallet" | "replace_or_speed_up_from_wallet" => {
+            let res: Result<Response, Response> = (async {
+                let wallet_name = utils::get_required_arg::<String>(args, "wallet_name", req_id)?;
+                let master_password =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "master_password", req_id)?);
+                let chain_id =
+                    normalize_chain_id(&utils::get_chain_id(args, req_id)?);
+                let tx_hash = args.get("tx_hash").and_then(|v| v.as_str());
+                let nonce = args.get("nonce").and_then(|v| v.as_u64());
+                if tx_hash.is_none() && nonce.is_none() {
+                    return Err(Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        "Either 'nonce' or 'tx_hash' is required".into(),
+                    ));
+                }
+                let bump_percent = args
+                    .get("bump_percent")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(transactions::DEFAULT_FEE_BUMP_PERCENT);
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+
+                unlock_wallet_storage(&state, &master_password, req_id).await?;
+                let private_key = {
+                    let storage = state.wallet_storage.lock().await;
+                    storage
+                        .get_private_key(&wallet_name, &master_password)
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })?
+                };
+                let wallet = LocalWallet::from_str(&private_key).map_err(|e| {
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                })?;
+
+                let result = send_idempotent(&state, tool_name, args, req_id, || async {
+                    transactions::speed_up_transaction(&state.http_client, rpc_url, wallet, tx_hash, nonce, bump_percent)
+                        .await
+                        .map(|r| json!(r))
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })
+                })
+                .await?;
+                let response = replacement_of(&result, req_id)?;
+                let summary = format!(
+                    "Rebroadcast nonce {} from wallet '{}' with gas price {} wei (was {}): {}",
+                    response.nonce, wallet_name, response.new_gas_price, response.old_gas_price, response.tx_hash
+                );
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(summary, result),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "list_chains" | "list_supported_chains" | "get_chains" => {
+            let mut chain_ids = state.config.supported_chains();
+            chain_ids.sort_by_key(|id| (id.parse::<u64>().unwrap_or(u64::MAX), id.clone()));
+            let list: Vec<Value> = chain_ids
+                .iter()
+                .map(|id| {
+                    let info = chains::chain_info(id);
+                    json!({
+                        "chain_id": id,
+                        "name": info.map(|c| c.name).unwrap_or("Unknown chain"),
+                        "etherscan_supported": state.config.etherscan_base_url(id).is_ok(),
+                        "aliases": info.map(|c| c.aliases).unwrap_or(&[]),
+                    })
+                })
+                .collect();
+            let summary = list
+                .iter()
+                .map(|c| format!("{} ({})", c["chain_id"].as_str().unwrap_or_default(), c["name"].as_str().unwrap_or_default()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Response::success(
+                req_id.clone(),
+                make_texty_result(
+                    format!("{} configured chain(s): {}", list.len(), summary),
+                    json!({ "chains": list }),
+                ),
+            )
+        }
+        "keccak256" => {
+            let res: Result<Response, Response> = (async {
+                let input = utils::get_required_arg::<String>(args, "input", req_id)?;
+                let encoding = args.get("encoding").and_then(|v| v.as_str()).unwrap_or("utf8");
+                let bytes = match encoding {
+                    "utf8" => input.clone().into_bytes(),
+                    "hex" => hex::decode(input.trim_start_matches("0x")).map_err(|e| {
+                        Response::error(req_id.clone(), error_codes::INVALID_PARAMS, format!("Invalid hex input: {}", e))
+                    })?,
+                    other => {
+                        return Err(Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            format!("Unknown encoding '{}'; expected 'utf8' or 'hex'", other),
+                        ))
+                    }
+                };
+                let hash = utils::keccak256_hex(&bytes);
+                let payload = json!({ "input": input, "encoding": encoding, "hash": hash });
+                Ok(Response::success(req_id.clone(), make_texty_result(format!("keccak256: {}", hash), payload)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "compute_selector" => {
+            let res: Result<Response, Response> = (async {
+                let signature = utils::get_required_arg::<String>(args, "signature", req_id)?;
+                let canonical = utils::canonical_signature(&signature);
+                if !(canonical.contains('(') && canonical.ends_with(')')) {
+                    return Err(Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        format!("Invalid function signature '{}'; expected e.g. 'transfer(address,uint256)'", signature),
+                    ));
+                }
+                let selector = format!("0x{}", hex::encode(utils::function_selector(&canonical)));
+                let payload = json!({ "signature": canonical, "selector": selector });
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(format!("{} => {}", canonical, selector), payload),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "abi_encode" => {
+            let res: Result<Response, Response> = (async {
+                let signature = utils::get_required_arg::<String>(args, "signature", req_id)?;
+                let call_args = args.get("args").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+                let invalid = |e: anyhow::Error| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, format!("{:#}", e));
+                let func = abi::parse_function_signature(&signature).map_err(invalid)?;
+                let data = abi::encode_function_call(&func, &call_args).map_err(invalid)?;
+                let canonical = abi::function_signature(&func);
+                let calldata = format!("0x{}", hex::encode(&data));
+                let payload = json!({
+                    "signature": canonical,
+                    "selector": format!("0x{}", hex::encode(&data[..4])),
+                    "calldata": calldata,
+                });
+                Ok(Response::success(req_id.clone(), make_texty_result(format!("{} calldata: {}", canonical, calldata), payload)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "abi_decode" => {
+            let res: Result<Response, Response> = (async {
+                let types = utils::get_required_arg::<Vec<String>>(args, "types", req_id)?;
+                let data = utils::get_required_arg::<String>(args, "data", req_id)?;
+                let invalid = |e: anyhow::Error| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, format!("{:#}", e));
+                let param_types = abi::parse_types(&types).map_err(invalid)?;
+                let mut bytes = hex::decode(data.trim().trim_start_matches("0x"))
+                    .map_err(|e| invalid(anyhow::anyhow!("invalid hex data: {}", e)))?;
+                // Calldata carries a 4-byte selector in front of the arguments
+                let strip_selector = args.get("has_selector").and_then(|v| v.as_bool()).unwrap_or(false);
+                if strip_selector {
+                    if bytes.len() < 4 {
+                        return Err(invalid(anyhow::anyhow!("data is shorter than a 4-byte selector")));
+                    }
+                    bytes.drain(..4);
+                }
+                let values = abi::decode_values(&param_types, &bytes).map_err(invalid)?;
+                let summary = format!("Decoded {} value(s)", values.len());
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, json!({ "types": types, "values": values }))))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "derive_accounts" => {
+            let res: Result<Response, Response> = (async {
+                let mnemonic = Zeroizing::new(utils::get_required_arg::<String>(args, "mnemonic", req_id)?);
+                let count = args.get("count").and_then(|v| v.as_u64()).unwrap_or(5);
+                let start_index = args.get("start_index").and_then(|v| v.as_u64()).unwrap_or(0);
+                let (count, start_index) = match (u32::try_from(count), u32::try_from(start_index)) {
+                    (Ok(c), Ok(s)) => (c, s),
+                    _ => {
+                        return Err(Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            "'count' and 'start_index' must fit in 32 bits".into(),
+                        ))
+                    }
+                };
+                let accounts = derive_accounts(mnemonic.trim(), start_index, count).map_err(|e| {
+                    Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
+                })?;
+                let summary = accounts
+                    .iter()
+                    .map(|a| format!("#{} {}", a.index, a.address))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(
+                        format!("Derived {} account(s):\n{}", accounts.len(), summary),
+                        json!({ "accounts": accounts }),
+                    ),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "validate_address" => {
+            let res: Result<Response, Response> = (async {
+                let address = utils::get_required_arg::<String>(args, "address", req_id)?;
+                let check = match utils::check_address(address.trim()) {
+                    Ok(check) => check,
+                    Err(reason) => {
+                        let payload = json!({ "address": address, "valid": false, "reason": reason });
+                        return Ok(Response::success(
+                            req_id.clone(),
+                            make_texty_result(format!("{} is not a valid address: {}", address, reason), payload),
+                        ));
+                    }
+                };
+                let mut payload = json!({
+                    "address": address,
+                    "valid": check.checksum != "invalid",
+                    "checksummed": check.checksummed,
+                    "checksum": check.checksum,
+                });
+                if check.checksum == "invalid" {
+                    payload["reason"] = json!("mixed-case address does not match its EIP-55 checksum");
+                }
+
+                if let Some(chain_id) = args.get("chain_id").and_then(|v| v.as_str()) {
+                    let chain_id = normalize_chain_id(chain_id);
+                    let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                        Response::error_with_category(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            ErrorCategory::NotConfigured,
+                            format!("RPC URL not configured for chain_id '{}'", chain_id),
+                        )
+                    })?;
+                    let is_contract = crate::blockchain::services::token::is_contract(&state.http_client, rpc_url, &check.checksummed)
+                        .await
+                        .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
+                    payload["chain_id"] = json!(chain_id);
+                    payload["is_contract"] = json!(is_contract);
+                }
+
+                let summary = match (payload["valid"].as_bool(), payload.get("is_contract").and_then(|v| v.as_bool())) {
+                    (Some(false), _) => format!("{} has an invalid checksum; expected {}", address, check.checksummed),
+                    (_, Some(true)) => format!("{} is a valid contract address", check.checksummed),
+                    (_, Some(false)) => format!("{} is a valid externally owned (or empty) address", check.checksummed),
+                    _ => format!("{} is a valid address", check.checksummed),
+                };
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, payload)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "replace_transaction" => {
+            let res: Result<Response, Response> = (async {
+                let private_key =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "private_key", req_id)?);
+                let chain_id = normalize_chain_id(&utils::get_chain_id(args, req_id)?);
+                let nonce = args.get("nonce").and_then(|v| v.as_u64()).ok_or_else(|| {
+                    Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        "Missing required argument: nonce".into(),
+                    )
+                })?;
+                let cancel = args.get("cancel").and_then(|v| v.as_bool()).unwrap_or(false);
+                let parse_wei = |key: &str| -> Result<Option<U256>, Response> {
+                    args.get(key)
+                        .and_then(|v| v.as_str())
+                        .map(|s| {
+                            U256::from_dec_str(s).map_err(|_| {
+                                Response::error(
+                                    req_id.clone(),
+                                    error_codes::INVALID_PARAMS,
+                                    format!("'{}' must be a decimal integer string", key),
+                                )
+                            })
+                        })
+                        .transpose()
+                };
+                let gas_price = utils::get_gas_price(args, req_id)?;
+                let gas_limit = parse_wei("gas_limit")?;
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+                let wallet = LocalWallet::from_str(&private_key).map_err(|e| {
+                    Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
+                })?;
+
+                let result = send_idempotent(&state, tool_name, args, req_id, || async {
+                    transactions::replace_transaction(&state.http_client, rpc_url, wallet, nonce, gas_price, gas_limit, cancel)
+                        .await
+                        .map(|r| json!(r))
+                        .map_err(|e| {
+                            Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                        })
+                })
+                .await?;
+                let response = replacement_of(&result, req_id)?;
+                let summary = format!(
+                    "{} nonce {} with gas price {} wei (was {}): {}",
+                    if cancel { "Cancelled" } else { "Replaced" },
+                    response.nonce, response.new_gas_price, response.old_gas_price, response.tx_hash
+                );
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(summary, result),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "lookup_selector" => {
+            let res: Result<Response, Response> = (async {
+                let selector = utils::get_required_arg::<String>(args, "selector", req_id)?;
+                signatures::parse_selector(&selector).map_err(|e| {
+                    Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string())
+                })?;
+                let db_url = state
+                    .config
+                    .signature_db_url
+                    .as_deref()
+                    .unwrap_or(signatures::DEFAULT_SIGNATURE_DB_URL);
+                let lookup = signatures::lookup_signature(&state.http_client, &state.signature_cache, db_url, &selector)
+                    .await
+                    .map_err(|e| {
+                        Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                    })?;
+                let summary = match lookup.candidates.first() {
+                    Some(best) => format!(
+                        "{} {}: {} ({} candidate(s))",
+                        lookup.kind, selector, best, lookup.candidates.len()
+                    ),
+                    None => format!("No known {} signature for {}", lookup.kind, selector),
+                };
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(summary, json!(lookup)),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "get_storage_at" => {
+            let res: Result<Response, Response> = (async {
+                let chain_id = normalize_chain_id(&utils::get_chain_id(args, req_id)?);
+                let address = utils::get_required_arg::<String>(args, "address", req_id)?;
+                let slot_arg = utils::get_required_arg::<String>(args, "slot", req_id)?;
+                let invalid = |e: anyhow::Error| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string());
+                let mut slot = contract::parse_slot(&slot_arg).map_err(invalid)?;
+                if let Some(key) = args.get("mapping_key").and_then(|v| v.as_str()) {
+                    slot = contract::mapping_slot(slot, key).map_err(invalid)?;
+                }
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+                let block = block_arg(args, req_id)?.unwrap_or_else(|| json!("latest"));
+                let v = contract::get_storage_at(&state.http_client, rpc_url, &address, slot, &block)
+                    .await
+                    .map_err(|e| {
+                        Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                    })?;
+                let summary = format!(
+                    "Storage of {} at slot {}: {}",
+                    v["address"].as_str().unwrap_or_default(),
+                    v["slot"].as_str().unwrap_or_default(),
+                    v["value"].as_str().unwrap_or_default()
+                );
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, v)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "get_portfolio" => {
+            let res: Result<Response, Response> = (async {
+                let chain_id = normalize_chain_id(&utils::get_chain_id(args, req_id)?);
+                let owner_address = utils::get_required_arg::<String>(args, "owner_address", req_id)?;
+                let owner = Address::from_str(&owner_address).map_err(|_| {
+                    Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        format!("Invalid owner address '{}'", owner_address),
+                    )
+                })?;
+                let token_addresses = utils::get_required_arg::<Vec<String>>(args, "token_addresses", req_id)?;
+                let mut tokens = Vec::new();
+                for s in &token_addresses {
+                    tokens.push(Address::from_str(s).map_err(|_| {
+                        Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            format!("Invalid token address '{}'", s),
+                        )
+                    })?);
+                }
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+
+                let client = Client::new();
+                let portfolio = crate::blockchain::services::portfolio::token_portfolio(
+                    &client, rpc_url, owner, &tokens,
+                )
+                .await
+                .map_err(|e| {
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                })?;
+                let failed = portfolio["tokens"]
+                    .as_array()
+                    .map_or(0, |t| t.iter().filter(|e| e.get("error").is_some()).count());
+                let mut summary = format!(
+                    "Balances of {} token(s) plus native for {} on chain {}",
+                    tokens.len(),
+                    owner_address,
+                    chain_id
+                );
+                if failed > 0 {
+                    summary.push_str(&format!(" ({} token(s) did not respond)", failed));
+                }
+                let mut payload = json!({ "chain_id": chain_id, "owner_address": owner_address });
+                payload["native"] = portfolio["native"].clone();
+                payload["tokens"] = portfolio["tokens"].clone();
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, payload)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "discover_tokens" => {
+            let res: Result<Response, Response> = (async {
+                let chain_id = normalize_chain_id(&utils::get_chain_id(args, req_id)?);
+                let address = utils::get_required_arg::<String>(args, "address", req_id)?;
+                let owner = Address::from_str(&address).map_err(|_| {
+                    Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        format!("Invalid address '{}'", address),
+                    )
+                })?;
+                let etherscan_api_key = etherscan_api_key(&state, req_id)?;
+                let etherscan_base_url = etherscan_base_url(&state, &chain_id, req_id)?;
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+
+                let client = state.http_client.clone();
+                let found = crate::blockchain::services::portfolio::discover_tokens(
+                    &client,
+                    &etherscan_base_url,
+                    &chain_id,
+                    rpc_url,
+                    owner,
+                    etherscan_api_key,
+                )
+                .await
+                .map_err(|e| {
+                    Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                })?;
+                let mut summary = format!(
+                    "{} of {} token(s) seen in transfer history still held by {} on chain {}",
+                    found["tokens"].as_array().map_or(0, |t| t.len()),
+                    found["tokens_seen"],
+                    address,
+                    chain_id
+                );
+                if found["truncated"] == true {
+                    summary.push_str(" (history too long to scan in full; older tokens may be missing)");
+                }
+                let mut payload = json!({ "chain_id": chain_id, "address": address });
+                for key in ["native", "tokens", "tokens_seen", "truncated"] {
+                    payload[key] = found[key].clone();
+                }
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, payload)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "wait_for_balance" => {
+            let res: Result<Response, Response> = (async {
+                use crate::blockchain::services::balance::{wait_for_balance, BalanceTarget};
+
+                let chain_id = normalize_chain_id(&utils::get_chain_id(args, req_id)?);
+                let address = utils::get_required_arg::<String>(args, "address", req_id)?;
+                let token = args.get("token_address").and_then(|v| v.as_str());
+                let amount = |key: &str| -> Result<Option<U256>, Response> {
+                    args.get(key)
+                        .and_then(|v| v.as_str())
+                        .map(|s| {
+                            U256::from_dec_str(s.trim()).map_err(|_| {
+                                Response::error(
+                                    req_id.clone(),
+                                    error_codes::INVALID_PARAMS,
+                                    format!("'{}' must be a whole number of base units, got '{}'", key, s),
+                                )
+                            })
+                        })
+                        .transpose()
+                };
+                let target = match (amount("min_balance")?, amount("increase_by")?) {
+                    (Some(min), None) => BalanceTarget::AtLeast(min),
+                    (None, Some(delta)) => BalanceTarget::IncreaseBy(delta),
+                    _ => {
+                        return Err(Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            "Exactly one of 'min_balance' or 'increase_by' is required".into(),
+                        ))
+                    }
+                };
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+                // Stop short of the tool call time limit so the last reading is still returned
+                let interval = state.config.balance_poll_interval();
+                let requested = Duration::from_secs(args.get("timeout_secs").and_then(|v| v.as_u64()).unwrap_or(60));
+                let timeout = requested.min(state.config.tool_timeout().saturating_sub(interval));
+
+                let client = Client::new();
+                let wait = wait_for_balance(&client, rpc_url, &address, token, target, timeout, interval)
+                    .await
+                    .map_err(|e| {
+                        Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                    })?;
+                let summary = format!(
+                    "{} after {:.1}s: balance of {} is {} (was {})",
+                    if wait.met { "Target reached" } else { "Timed out waiting" },
+                    wait.elapsed.as_secs_f64(),
+                    address,
+                    wait.balance,
+                    wait.initial
+                );
+                let payload = json!({
+                    "chain_id": chain_id,
+                    "address": address,
+                    "token_address": token,
+                    "met": wait.met,
+                    "initial_balance": wait.initial.to_string(),
+                    "balance": wait.balance.to_string(),
+                    "polls": wait.polls,
+                    "elapsed_secs": wait.elapsed.as_secs_f64(),
+                });
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, payload)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "decode_calldata" => {
+            let res: Result<Response, Response> = (async {
+                let data = utils::get_required_arg::<String>(args, "data", req_id)?;
+                let invalid = |e: anyhow::Error| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, format!("{:#}", e));
+                // A JSON ABI, or human readable signatures when the full ABI isn't at hand
+                let mut functions = match args.get("abi") {
+                    Some(Value::String(abi_json)) => abi::parse_abi_functions(abi_json).map_err(invalid)?,
+                    Some(abi_value @ Value::Array(_)) => abi::parse_abi_functions(&abi_value.to_string()).map_err(invalid)?,
+                    _ => Vec::new(),
+                };
+                for sig in args.get("signatures").and_then(|v| v.as_array()).into_iter().flatten() {
+                    let sig = sig.as_str().unwrap_or_default();
+                    functions.push(abi::parse_function_signature(sig).map_err(invalid)?);
+                }
+                if functions.is_empty() {
+                    return Err(Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        "Provide 'abi' or 'signatures' to decode against".into(),
+                    ));
+                }
+                let bytes = hex::decode(data.trim().trim_start_matches("0x"))
+                    .map_err(|e| invalid(anyhow::anyhow!("invalid hex data: {}", e)))?;
+                let decoded = abi::decode_calldata(&functions, &bytes).map_err(invalid)?;
+                let selector = format!("0x{}", hex::encode(&bytes[..4]));
+                let (summary, payload) = match decoded {
+                    Some(call) => {
+                        let args_text: Vec<String> = call["args"]
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .map(|a| format!("{}={}", a["name"].as_str().unwrap_or_default(), a["value"]))
+                            .collect();
+                        (format!("{}({})", call["function"].as_str().unwrap_or_default(), args_text.join(", ")), call)
+                    }
+                    None => (
+                        format!("No function in the given ABI has selector {}; try lookup_selector", selector),
+                        json!({ "matched": false, "selector": selector }),
+                    ),
+                };
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, payload)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "get_contract_code_normalized" => {
+            let res: Result<Response, Response> = (async {
+                let address = utils::get_required_arg::<String>(args, "address", req_id)?;
+                let etherscan_api_key = etherscan_api_key(&state, req_id)?;
+                let chain_id = explorer_chain_id(args);
+                let etherscan_base_url = etherscan_base_url(&state, &chain_id, req_id)?;
+
+                let client = state.http_client.clone();
+                let record = contract::get_source_record(&client, &etherscan_base_url, &chain_id, &address, etherscan_api_key)
+                    .await
+                    .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
+                if record.get("SourceCode").and_then(|v| v.as_str()).unwrap_or("").is_empty() {
+                    return Err(Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        format!("{} has no verified source code on Etherscan", address),
+                    ));
+                }
+                let mut normalized = contract::normalize_source_record(&record);
+
+                // Etherscan has no bytecode; take the deployed code from the node when we can
+                if let Some(rpc_url) = state.config.chain_rpc_urls.get(&chain_id) {
+                    if let Ok(code) = contract::get_contract_code(&client, rpc_url, &address).await {
+                        normalized["runtimeCode"] = code.get("code").cloned().unwrap_or_else(|| json!(""));
+                    }
+                }
+
+                let files = normalized["sources"].as_array().map(|a| a.len()).unwrap_or(0);
+                let summary = format!("Normalized source of {} on {}: {} file(s)", address, chain_id, files);
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, normalized)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "wait_for_confirmations" => {
+            let res: Result<Response, Response> = (async {
+                let chain_id = normalize_chain_id(&utils::get_chain_id(args, req_id)?);
+                let tx_hash = utils::get_required_arg::<String>(args, "tx_hash", req_id)?;
+                let confirmations = args.get("confirmations").and_then(|v| v.as_u64()).unwrap_or(1);
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+                // Stop short of the tool call time limit so the last reading is still returned
+                let interval = state.config.receipt_poll_interval();
+                let requested = Duration::from_secs(args.get("timeout_secs").and_then(|v| v.as_u64()).unwrap_or(60));
+                let timeout = requested.min(state.config.tool_timeout().saturating_sub(interval));
+
+                let wait = transactions::wait_for_confirmations(&state.http_client, rpc_url, &tx_hash, confirmations, timeout, interval)
+                    .await
+                    .map_err(|e| {
+                        Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e)
+                    })?;
+                let status = match (&wait.block_number, wait.reverted, wait.met) {
+                    (None, _, _) => "pending",
+                    (Some(_), true, _) => "reverted",
+                    (Some(_), false, true) => "confirmed",
+                    (Some(_), false, false) => "mined",
+                };
+                let summary = match status {
+                    "pending" => format!("{} is still pending after {:.1}s", tx_hash, wait.elapsed.as_secs_f64()),
+                    "reverted" => format!("{} reverted in block {}", tx_hash, wait.block_number.unwrap_or_default()),
+                    _ => format!(
+                        "{} has {} of {} confirmation(s){}",
+                        tx_hash,
+                        wait.confirmations,
+                        confirmations,
+                        if wait.met { "" } else { "; timed out waiting for more" }
+                    ),
+                };
+                let payload = json!({
+                    "chain_id": chain_id,
+                    "tx_hash": tx_hash,
+                    "status": status,
+                    "met": wait.met,
+                    "block_number": wait.block_number,
+                    "confirmations": wait.confirmations,
+                    "required_confirmations": confirmations,
+                    "receipt": wait.receipt,
+                    "polls": wait.polls,
+                    "elapsed_secs": wait.elapsed.as_secs_f64(),
+                });
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, payload)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "get_amounts_out" => {
+            let res: Result<Response, Response> = (async {
+                let chain_id = normalize_chain_id(&utils::get_chain_id(args, req_id)?);
+                let amount_in = utils::get_required_arg::<String>(args, "amount_in", req_id)?;
+                let amount_in = U256::from_dec_str(amount_in.trim()).map_err(|_| {
+                    Response::error(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        format!("'amount_in' must be a whole number of base units, got '{}'", amount_in),
+                    )
+                })?;
+                let path_arg = utils::get_required_arg::<Vec<String>>(args, "path", req_id)?;
+                let mut path = Vec::new();
+                for s in &path_arg {
+                    path.push(Address::from_str(s).map_err(|_| {
+                        Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            format!("Invalid token address '{}' in path", s),
+                        )
+                    })?);
+                }
+                let router = match args.get("router_address").and_then(|v| v.as_str()) {
+                    Some(router) => {
+                        Address::from_str(router).map_err(|_| {
+                            Response::error(
+                                req_id.clone(),
+                                error_codes::INVALID_PARAMS,
+                                format!("Invalid router address '{}'", router),
+                            )
+                        })?;
+                        router.to_string()
+                    }
+                    None => state.config.dex_router(&chain_id).ok_or_else(|| {
+                        Response::error(
+                            req_id.clone(),
+                            error_codes::INVALID_PARAMS,
+                            format!("No DEX router known for chain_id '{}'; pass 'router_address' or set DEX_ROUTERS", chain_id),
+                        )
+                    })?,
+                };
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+
+                let amounts = dex::get_amounts_out(&state.http_client, rpc_url, &router, amount_in, &path)
+                    .await
+                    .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
+                let amount_out = amounts.last().cloned().unwrap_or_default();
+                let summary = format!(
+                    "{} of {} => {} of {} via router {}",
+                    amount_in,
+                    path_arg[0],
+                    amount_out,
+                    path_arg[path_arg.len() - 1],
+                    router
+                );
+                let payload = json!({
+                    "chain_id": chain_id,
+                    "router": router,
+                    "path": path.iter().map(|a| to_checksum(a, None)).collect::<Vec<_>>(),
+                    "amount_in": amount_in.to_string(),
+                    "amount_out": amount_out,
+                    "amounts": amounts,
+                });
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, payload)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "server_info" => {
+            let mut chain_ids = state.config.supported_chains();
+            chain_ids.sort_by_key(|id| (id.parse::<u64>().unwrap_or(u64::MAX), id.clone()));
+            let config = &state.config;
+            let features = json!({
+                "discord": config.discord_api_url.is_some()
+                    || config.discord_webhook_url.is_some()
+                    || (config.discord_bot_token.is_some() && config.discord_channel_id.is_some()),
+                "faucet": config.faucet_api_url.is_some(),
+                "etherscan": config.etherscan_api_key.is_some(),
+            });
+            let enabled: Vec<&str> = features
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter(|(_, on)| on.as_bool() == Some(true))
+                .map(|(name, _)| name.as_str())
+                .collect();
+            let summary = format!(
+                "evm_mcp {} on {} chain(s); enabled: {}",
+                crate::version_string(),
+                chain_ids.len(),
+                if enabled.is_empty() { "none".to_string() } else { enabled.join(", ") }
+            );
+            let payload = json!({
+                "name": "evm_mcp",
+                "version": crate::VERSION,
+                "git_hash": crate::GIT_HASH,
+                "chains": chain_ids,
+                "features": features,
+            });
+            Response::success(req_id.clone(), make_texty_result(summary, payload))
+        }
+        "address_from_key" => {
+            let res: Result<Response, Response> = (async {
+                let key = Zeroizing::new(utils::get_required_arg::<String>(args, "mnemonic_or_private_key", req_id)?);
+                let derivation_path = args.get("derivation_path").and_then(|v| v.as_str());
+                let passphrase = Zeroizing::new(args.get("passphrase").and_then(|v| v.as_str()).unwrap_or("").to_string());
+                let account = address_from_key(&key, derivation_path, &passphrase)
+                    .map_err(|e| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, e.to_string()))?;
+                let summary = match &account.path {
+                    Some(path) => format!("Address: {} (mnemonic, {})", account.address, path),
+                    None => format!("Address: {} (private key)", account.address),
+                };
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, json!(account))))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "get_account_state" => {
+            let res: Result<Response, Response> = (async {
+                let address = utils::get_required_arg::<String>(args, "address", req_id)?;
+                let chain_id = normalize_chain_id(&utils::get_chain_id(args, req_id)?);
+                let parsed = Address::from_str(&address).map_err(|_| {
+                    Response::error(req_id.clone(), error_codes::INVALID_PARAMS, format!("Invalid address '{}'", address))
+                })?;
+                let rpc_url = state.config.chain_rpc_urls.get(&chain_id).ok_or_else(|| {
+                    Response::error_with_category(
+                        req_id.clone(),
+                        error_codes::INVALID_PARAMS,
+                        ErrorCategory::NotConfigured,
+                        format!("RPC URL not configured for chain_id '{}'", chain_id),
+                    )
+                })?;
+
+                let account = crate::blockchain::services::balance::get_account_state(&state.http_client, rpc_url, &address)
+                    .await
+                    .map_err(|e| Response::from_error(req_id.clone(), error_codes::INTERNAL_ERROR, e))?;
+                let balance = crate::blockchain::services::balance::native_balance_json(
+                    &crate::blockchain::models::BalanceResponse { amount: account.balance.to_string(), denom: "wei".to_string() },
+                    &state.config.native_currency(&chain_id),
+                );
+                let is_contract = account.code_size > 0;
+                let summary = format!(
+                    "{} on {}: {}, nonce {} ({} pending), {}",
+                    to_checksum(&parsed, None),
+                    chain_id,
+                    balance["formatted"].as_str().unwrap_or("balance unknown"),
+                    account.nonce,
+                    account.pending_nonce,
+                    if is_contract { "contract" } else { "externally owned account" }
+                );
+
+                Ok(Response::success(
+                    req_id.clone(),
+                    make_texty_result(
+                        summary,
+                        json!({
+                            "chain_id": chain_id,
+                            "address": to_checksum(&parsed, None),
+                            "balance": balance,
+                            "nonce": { "latest": account.nonce, "pending": account.pending_nonce },
+                            "is_contract": is_contract,
+                            "code_size": account.code_size,
+                        }),
+                    ),
+                ))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        "find_wallet_by_address" => {
+            let res: Result<Response, Response> = (async {
+                let address = utils::get_required_arg::<String>(args, "address", req_id)?;
+                let master_password =
+                    Zeroizing::new(utils::get_required_arg::<String>(args, "master_password", req_id)?);
+                let parsed = Address::from_str(address.trim()).map_err(|_| {
+                    Response::error(req_id.clone(), error_codes::INVALID_PARAMS, format!("Invalid address '{}'", address))
+                })?;
+                let checksummed = to_checksum(&parsed, None);
+
+                unlock_wallet_storage(&state, &master_password, req_id).await?;
+                let storage = state.wallet_storage.lock().await;
+                let (summary, payload) = match storage.get_wallet_by_address(&checksummed) {
+                    Some(wallet) => (
+                        format!("{} is stored as wallet '{}'", checksummed, wallet.wallet_name),
+                        json!({
+                            "found": true,
+                            "address": checksummed,
+                            "wallet_name": wallet.wallet_name,
+                            "created_at": wallet.created_at.to_rfc3339(),
+                        }),
+                    ),
+                    None => (
+                        format!("No stored wallet has address {}", checksummed),
+                        json!({ "found": false, "address": checksummed }),
+                    ),
+                };
+                Ok(Response::success(req_id.clone(), make_texty_result(summary, payload)))
+            })
+            .await;
+            res.unwrap_or_else(|err_resp| err_resp)
+        }
+        _ => Response::error(
+            req.id,
+            error_codes::METHOD_NOT_FOUND,
+            format!("Tool not found: {}", tool_name),
+        ),
+    }
+}
+
+/// Handles the 'initialize' request.
+fn handle_initialize(req: &Request) -> Response {
+    let server_info = json!({
+        "name": "evm_mcp",
+        "version": crate::VERSION
+    });
+    let capabilities = json!({
+        "tools": { "listChanged": false },
+        "prompts": { "listChanged": false },
+        "resources": { "listChanged": false }
+    });
+    let instructions =
+        "EVM blockchain MCP server for secure wallet operations, balance queries, and transaction management.";
+
+    Response::success(
+        req.id.clone(),
+        json!({
+            "serverInfo": server_info,
+            "protocolVersion": "2025-06-18",
+            "capabilities": capabilities,
+            "instructions": instructions
+        }),
+    )
+}
+
+/// Handles the 'tools/list' request by returning a JSON definition of all available tools.
+fn handle_tools_list(req: &Request) -> Response {
+    Response::success(req.id.clone(), json!({ "tools": tool_definitions() }))
+}
+
+/// A prompt template offered to clients. `{arg}` placeholders in `template` are replaced
+/// by the argument values; every argument is required.
+struct PromptTemplate {
+    name: &'static str,
+    description: &'static str,
+    arguments: &'static [(&'static str, &'static str)],
+    template: &'static str,
+}
+
+const PROMPTS: &[PromptTemplate] = &[
+    PromptTemplate {
+        name: "check_wallet_balance",
+        description: "Check the native and token balances of an address on one chain.",
+        arguments: &[("address", "The 0x... address to check"), ("chain_id", "Chain ID or name, e.g. 1 or polygon")],
+        template: "Check the native balance of {address} on chain {chain_id} with get_balance and report it in the chain's native currency. Then list its ERC-20 holdings with get_wallet_token_balances.",
+    },
+    PromptTemplate {
+        name: "inspect_contract",
+        description: "Summarize what a deployed contract is and which token standards it implements.",
+        arguments: &[("address", "The contract address"), ("chain_id", "Chain ID or name, e.g. 1 or polygon")],
+        template: "Inspect the contract at {address} on chain {chain_id}: confirm it is a contract with is_contract, classify it with detect_token_standard, and if it is a token summarize get_token_info.",
+    },
+];
+
+/// Handles the 'prompts/list' request.
+fn handle_prompts_list(req: &Request) -> Response {
+    let prompts: Vec<Value> = PROMPTS
+        .iter()
+        .map(|p| {
+            json!({
+                "name": p.name,
+                "description": p.description,
+                "arguments": p
+                    .arguments
+                    .iter()
+                    .map(|(arg, desc)| json!({ "name": arg, "description": desc, "required": true }))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    Response::success(req.id.clone(), json!({ "prompts": prompts }))
+}
+
+/// Handles the 'prompts/get' request by filling in a prompt template.
+fn handle_prompts_get(req: &Request) -> Response {
+    let params = req.params.clone().unwrap_or_else(|| json!({}));
+    let name = params.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+    let Some(prompt) = PROMPTS.iter().find(|p| p.name == name) else {
+        return Response::error(req.id.clone(), error_codes::INVALID_PARAMS, format!("Unknown prompt: '{}'", name));
+    };
+    let mut text = prompt.template.to_string();
+    for (arg, _) in prompt.arguments {
+        let Some(value) = params.get("arguments").and_then(|a| a.get(arg)).and_then(|v| v.as_str()) else {
+            return Response::error(
+                req.id.clone(),
+                error_codes::INVALID_PARAMS,
+                format!("Missing required argument '{}' for prompt '{}'", arg, name),
+            );
+        };
+        text = text.replace(&format!("{{{}}}", arg), value);
+    }
+    Response::success(
+        req.id.clone(),
+        json!({
+            "description": prompt.description,
+            "messages": [{ "role": "user", "content": { "type": "text", "text": text } }]
+        }),
+    )
+}
+
+/// Looks up a tool's `inputSchema` or `outputSchema`; dashed aliases (e.g.
+/// `get-token-info`) resolve too.
+fn tool_schema(tool_name: &str, kind: &str) -> Option<Value> {
+    let name = tool_name.replace('-', "_");
+    tool_definitions()
+        .as_array()?
+        .iter()
+        .find(|t| t["name"] == name.as_str())
+        .and_then(|t| t.get(kind).cloned())
+}
+
+/// JSON definitions (name, description, inputSchema and, for tools with structured
+/// results, outputSchema) of all available tools.
+// FIX: The tool list is now updated, secure, and functional.
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "get_balance",
+            "description": "Get the EVM balance of an address on a specific chain.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string", "description": "The blockchain chain ID (e.g., '1' for Ethereum mainnet)"},
+                    "address": {"type": "string", "description": "The 0x... EVM wallet address to check."},
+                    "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}
+                },
+                "required": ["chain_id", "address"]
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "balance": {"type": "object", "properties": {"amount": {"type": "string", "description": "Balance in the smallest unit (wei)"}, "denom": {"type": "string"}, "symbol": {"type": "string"}, "decimals": {"type": "integer"}, "formatted": {"type": ["string", "null"], "description": "Human readable amount with symbol"}}, "required": ["amount", "symbol", "decimals"]},
+                    "message": {"type": "string"},
+                    "debug": {"type": "object"}
+                },
+                "required": ["balance", "message"]
+            }
+        },
+        {
+            "name": "create_wallet",
+            "description": "Create a new EVM wallet. Returns address, private key, and mnemonic unless key output is suppressed; optionally stores it under wallet_name.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "word_count": {"type": "integer", "enum": [12, 15, 18, 21, 24], "default": 12, "description": "Number of mnemonic words"},
+                    "passphrase": {"type": "string", "description": "Optional BIP-39 passphrase (\"25th word\"). The same passphrase is required to re-derive the wallet from its mnemonic"},
+                    "wallet_name": {"type": "string", "description": "Store the wallet under this name (requires master_password)"},
+                    "master_password": {"type": "string", "description": "Master password for wallet storage (requires wallet_name)"},
+                    "reveal": {"type": "boolean", "default": true, "description": "Return the private key and mnemonic. Ignored (always false) when the server sets SUPPRESS_KEY_OUTPUT"}
+                },
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "import_wallet",
+            "description": "Import a wallet from a mnemonic phrase or private key; optionally stores it under wallet_name.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "mnemonic_or_private_key": {"type": "string", "description": "Mnemonic phrase or private key."},
+                    "key": {"type": "string", "description": "Alias for mnemonic_or_private_key (back-compat)."},
+                    "passphrase": {"type": "string", "description": "Optional BIP-39 passphrase for mnemonics; the same passphrase is required to re-derive the wallet"},
+                    "chain_type": {"type": "string", "description": "'evm' (default) or 'native'"},
+                    "wallet_name": {"type": "string", "description": "Store the wallet under this name (requires master_password)"},
+                    "master_password": {"type": "string", "description": "Master password for wallet storage (requires wallet_name)"},
+                    "reveal": {"type": "boolean", "default": true, "description": "Return the private key and mnemonic. Ignored (always false) when the server sets SUPPRESS_KEY_OUTPUT"}
+                },
+                "oneOf": [
+                    {"required": ["mnemonic_or_private_key"]},
+                    {"required": ["key"]}
+                ],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "search_events",
+            "description": "Search EVM logs. Uses the Etherscan API when ETHERSCAN_API_KEY is set and the chain has an explorer URL (built in, or from ETHERSCAN_BASE_URLS), otherwise the chain's RPC eth_getLogs (any configured chain).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string", "description": "Chain ID configured in CHAIN_RPC_URLS (Etherscan: any chain with a built-in or ETHERSCAN_BASE_URLS explorer URL)"},
+                    "contract_address": {"type": "string", "description": "Contract address to search logs for"},
+                    "topic0": {"type": "string", "description": "Keccak topic0 (event signature hash)"},
+                    "event_signature": {"type": "string", "description": "Event signature such as 'Transfer(address,address,uint256)'; topic0 is computed from it when topic0 is omitted"},
+                    "topic1": {"type": "string", "description": "Optional topic1 filter (rpc source)"},
+                    "topic2": {"type": "string", "description": "Optional topic2 filter (rpc source)"},
+                    "topic3": {"type": "string", "description": "Optional topic3 filter (rpc source)"},
+                    "from_block": {"type": "string", "description": "Starting block number (decimal or hex). Required for the rpc source, which scans at most 1,000,000 blocks"},
+                    "to_block": {"type": "string", "description": "Ending block number (decimal or hex)"},
+                    "source": {"type": "string", "enum": ["etherscan", "rpc"], "description": "Where to read logs from; defaults to etherscan when available, else rpc"},
+                    "event_abi": {"description": "Optional event ABI: a JSON fragment or a signature such as 'Transfer(address indexed from, address indexed to, uint256 value)'. Sets topic0 when omitted and adds decoded_logs to the result"}
+                },
+                "required": ["chain_id", "contract_address"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "request_faucet",
+            "description": "Request testnet tokens from the faucet for an EVM address.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string", "description": "Target chain id configured in CHAIN_RPC_URLS."},
+                    "address": {"type": "string", "description": "The EVM (0x...) address to receive tokens."},
+                    "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}
+                },
+                "required": ["chain_id", "address"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "register_wallet",
+            "description": "Encrypt and securely store a wallet under a name using a mnemonic or private key.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "wallet_name": {"type": "string", "description": "A unique name for the wallet (e.g., 'my-primary-wallet')."},
+                    "mnemonic_or_private_key": {"type": "string", "description": "Mnemonic phrase or private key to register."},
+                    "private_key": {"type": "string", "description": "Alias input for compatibility (private key)."},
+                    "passphrase": {"type": "string", "description": "Optional BIP-39 passphrase for mnemonics; the same passphrase is required to re-derive the wallet"},
+                    "master_password": {"type": "string", "description": "The master password to encrypt the wallet. This password will be required for any future actions with this wallet."},
+                    "chain_type": {"type": "string", "description": "'evm' (default) or 'native'"},
+                    "overwrite": {"type": "boolean", "description": "Replace a wallet already stored under this name instead of failing. Re-registering the same key keeps its daily limit. Default false."}
+                },
+                "oneOf": [
+                    {"required": ["wallet_name", "mnemonic_or_private_key", "master_password"]},
+                    {"required": ["wallet_name", "private_key", "master_password"]}
+                ],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "list_wallets",
+            "description": "List the wallets currently stored in the secure storage: name, address and when each was created.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                     "master_password": {"type": "string", "description": "The master password for the wallet storage."},
+                     "sort_by": {"type": "string", "enum": ["name", "created_at"], "description": "Order by wallet name (default) or creation time, oldest first."}
+                },
+                "required": ["master_password"]
+            }
+        },
+        {
+            "name": "transfer_from_wallet",
+            "description": "Transfer tokens from a securely stored wallet.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "wallet_name": {"type": "string", "description": "The name of the stored wallet to transfer from."},
+                    "chain_id": {"type": "string", "description": "The blockchain chain ID (e.g., 'testnet')."},
+                    "to_address": {"type": "string", "description": "The recipient's 0x... EVM address."},
+                    "amount": {"type": "string", "description": "The amount to transfer in wei."},
+                    "master_password": {"type": "string", "description": "The master password to unlock the wallet for this transaction."},
+                    "skip_balance_check": {"type": "boolean", "description": "Broadcast even if the sender balance does not cover value plus gas.", "default": false},
+                    "use_config_gas_defaults": {"type": "boolean", "description": "Fall back on DEFAULT_GAS_LIMIT/DEFAULT_GAS_PRICE if the node cannot estimate gas, instead of failing. Defaults to USE_CONFIG_GAS_DEFAULTS."},
+                    "safe_send": {"type": "boolean", "description": "Simulate first (eth_call + eth_estimateGas) and only broadcast if it would not revert; a revert is returned with its reason.", "default": false},
+                    "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}
+                },
+                "required": ["wallet_name", "chain_id", "to_address", "amount", "master_password"]
+            }
+        },
+        {
+            "name": "transfer_evm",
+            "description": "Send an EVM value transfer using a provided private key.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "private_key": {"type": "string"},
+                    "chain_id": {"type": "string"},
+                    "to_address": {"type": "string"},
+                    "amount_wei": {"type": "string"},
+                    "gas_limit": {"type": "string"},
+                    "gas_price": {"type": "string", "description": "Gas price in wei (decimal string)."},
+                    "gas_price_gwei": {"type": "string", "description": "Gas price in gwei, e.g. \"20\" or \"1.5\". Use instead of gas_price."},
+                    "max_fee_gwei": {"type": "string", "description": "EIP-1559 max fee per gas in gwei. Use instead of a gas price."},
+                    "priority_fee_gwei": {"type": "string", "description": "EIP-1559 priority fee (tip) per gas in gwei. Use instead of a gas price."},
+                    "skip_balance_check": {"type": "boolean", "description": "Broadcast even if the sender balance does not cover value plus gas.", "default": false},
+                    "use_config_gas_defaults": {"type": "boolean", "description": "Fall back on DEFAULT_GAS_LIMIT/DEFAULT_GAS_PRICE if the node cannot estimate gas, instead of failing. Defaults to USE_CONFIG_GAS_DEFAULTS."},
+                    "safe_send": {"type": "boolean", "description": "Simulate first (eth_call + eth_estimateGas) and only broadcast if it would not revert; a revert is returned with its reason.", "default": false},
+                    "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}
+                },
+                "required": ["private_key", "chain_id", "to_address", "amount_wei"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "transfer_nft_evm",
+            "description": "Transfer an ERC-721 token.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "private_key": {"type": "string"},
+                    "chain_id": {"type": "string"},
+                    "contract_address": {"type": "string"},
+                    "to_address": {"type": "string"},
+                    "token_id": {"type": "string"},
+                    "gas_limit": {"type": "string"},
+                    "gas_price": {"type": "string", "description": "Gas price in wei (decimal string)."},
+                    "gas_price_gwei": {"type": "string", "description": "Gas price in gwei, e.g. \"20\" or \"1.5\". Use instead of gas_price."},
+                    "max_fee_gwei": {"type": "string", "description": "EIP-1559 max fee per gas in gwei. Use instead of a gas price."},
+                    "priority_fee_gwei": {"type": "string", "description": "EIP-1559 priority fee (tip) per gas in gwei. Use instead of a gas price."},
+                    "skip_balance_check": {"type": "boolean", "description": "Broadcast even if the sender balance does not cover value plus gas.", "default": false},
+                    "use_config_gas_defaults": {"type": "boolean", "description": "Fall back on DEFAULT_GAS_LIMIT/DEFAULT_GAS_PRICE if the node cannot estimate gas, instead of failing. Defaults to USE_CONFIG_GAS_DEFAULTS."},
+                    "safe_send": {"type": "boolean", "description": "Simulate first (eth_call + eth_estimateGas) and only broadcast if it would not revert; a revert is returned with its reason.", "default": false},
+                    "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}
+                },
+                "required": ["private_key", "chain_id", "contract_address", "to_address", "token_id"],
+                "additionalProperties": false
+            }
+        },
+         {
+             "name": "get_contract",
+             "description": "Get verified contract details from Etherscan API.",
+             "inputSchema": {
+                 "type": "object",
+                 "properties": {
+                     "address": {"type": "string", "description": "The address of the smart contract."},
+                     "chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)."}
+                 },
+                 "required": ["address"]
+             }
+         },
+        {
+            "name": "get_contract_code",
+            "description": "Get a contract's deployed runtime bytecode via the chain's RPC (eth_getCode). Returns 0x for addresses with no code.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {"type": "string", "description": "The address of the smart contract."},
+                    "chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)."}
+                },
+                "required": ["address"]
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {"type": "string"},
+                    "chain_id": {"type": "string"},
+                    "code": {"type": "string", "description": "Runtime bytecode, 0x when none"},
+                    "size": {"type": "integer", "description": "Bytes of code"}
+                },
+                "required": ["address", "chain_id", "code", "size"]
+            }
+        },
+        {
+            "name": "discord_post_message",
+            "description": "Post a message to Discord via webhook or bot token (configured in server env).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "message": {"type": "string", "description": "Text to post. Code or natural language supported."},
+                    "username": {"type": "string", "description": "Optional display username (webhook mode)."}
+                },
+                "required": ["message"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "get_discord_service_info",
+            "description": "Fetch basic info from the external discord-api root endpoint (requires DISCORD_API_URL).",
+            "inputSchema": { "type": "object", "properties": {}, "additionalProperties": false }
+        },
+        {
+            "name": "check_discord_health",
+            "description": "Check health of the external discord-api (requires DISCORD_API_URL).",
+            "inputSchema": { "type": "object", "properties": {}, "additionalProperties": false }
+        },
+        {
+            "name": "get_contract_transactions",
+            "description": "Get the transactions of a smart contract via Etherscan API.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {"type": "string", "description": "The address of the smart contract."},
+                    "chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)."}
+                },
+                "required": ["address"]
+            }
+        },
+        {
+            "name": "get_transaction_history",
+            "description": "Get transaction history for any EVM address via Etherscan API.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {"type": "string", "description": "The EVM address to get transaction history for."},
+                    "chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)."},
+                    "from_date": {"type": "string", "description": "Optional ISO 8601 start date (inclusive), e.g. 2024-01-01."},
+                    "to_date": {"type": "string", "description": "Optional ISO 8601 end date (inclusive), e.g. 2024-01-31."},
+                    "min_value_wei": {"type": "string", "description": "Optional minimum transaction value in wei."},
+                    "format": {"type": "string", "enum": ["json", "csv"], "description": "Output format; 'csv' returns hash,timestamp,from,to,value,gas_used,fee,status rows.", "default": "json"}
+                },
+                "required": ["address"]
+            }
+        },
+        // --- Added: Token services (ERC20) ---
+        {
+            "name": "detect_token_standard",
+            "description": "Detect whether a contract is an ERC-20, ERC-721 or ERC-1155 token (ERC-165 supportsInterface checks plus a decimals()/symbol() probe for ERC-20).",
+            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["tokenAddress"], "additionalProperties": false}
+        },
+        {
+            "name": "get_token_info",
+            "description": "Get ERC20 token metadata: name, symbol, decimals and total supply (raw and formatted by decimals).",
+            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}}, "required": ["tokenAddress"], "additionalProperties": false}
+        },
+        {
+            "name": "get_token_balance",
+            "description": "Check ERC20 token balance.",
+            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "ownerAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}}, "required": ["tokenAddress", "ownerAddress"], "additionalProperties": false}
+        },
+        {
+            "name": "get_token_allowance",
+            "description": "Check ERC20 allowance between owner and spender.",
+            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "ownerAddress": {"type": "string"}, "spenderAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}}, "required": ["tokenAddress", "ownerAddress", "spenderAddress"], "additionalProperties": false}
+        },
+        {
+            "name": "transfer_token",
+            "description": "Transfer ERC20 tokens.",
+            "inputSchema": {"type": "object", "properties": {"private_key": {"type": "string"}, "tokenAddress": {"type": "string"}, "toAddress": {"type": "string"}, "amount": {"type": "string", "description": "Amount in the token's base units (decimal string)"}, "amount_decimal": {"type": "string", "description": "Amount in whole tokens, e.g. \"1.5\", scaled by the token's decimals(). Use instead of amount."}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "gas_limit": {"type": "string"}, "gas_price": {"type": "string"}, "gas_price_gwei": {"type": "string"}, "max_fee_gwei": {"type": "string", "description": "EIP-1559 max fee per gas in gwei. Use instead of a gas price."}, "priority_fee_gwei": {"type": "string", "description": "EIP-1559 priority fee (tip) per gas in gwei. Use instead of a gas price."}, "skip_balance_check": {"type": "boolean"}, "use_config_gas_defaults": {"type": "boolean", "description": "Fall back on DEFAULT_GAS_LIMIT/DEFAULT_GAS_PRICE if the node cannot estimate gas, instead of failing. Defaults to USE_CONFIG_GAS_DEFAULTS."}, "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}}, "required": ["private_key", "tokenAddress", "toAddress"], "oneOf": [{"required": ["amount"]}, {"required": ["amount_decimal"]}]}
+        },
+        {
+            "name": "approve_token_spending",
+            "description": "Approve ERC20 allowances.",
+            "inputSchema": {"type": "object", "properties": {"private_key": {"type": "string"}, "tokenAddress": {"type": "string"}, "spenderAddress": {"type": "string"}, "amount": {"type": "string", "description": "Amount in the token's base units (decimal string)"}, "amount_decimal": {"type": "string", "description": "Amount in whole tokens, e.g. \"1.5\", scaled by the token's decimals(). Use instead of amount."}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "gas_limit": {"type": "string"}, "gas_price": {"type": "string"}, "gas_price_gwei": {"type": "string"}, "max_fee_gwei": {"type": "string", "description": "EIP-1559 max fee per gas in gwei. Use instead of a gas price."}, "priority_fee_gwei": {"type": "string", "description": "EIP-1559 priority fee (tip) per gas in gwei. Use instead of a gas price."}, "skip_balance_check": {"type": "boolean"}, "use_config_gas_defaults": {"type": "boolean", "description": "Fall back on DEFAULT_GAS_LIMIT/DEFAULT_GAS_PRICE if the node cannot estimate gas, instead of failing. Defaults to USE_CONFIG_GAS_DEFAULTS."}, "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}}, "required": ["private_key", "tokenAddress", "spenderAddress"], "oneOf": [{"required": ["amount"]}, {"required": ["amount_decimal"]}]}
+        },
+        {
+            "name": "transfer_token_from",
+            "description": "Transfer ERC20 tokens on behalf of fromAddress using an existing allowance (transferFrom). The private_key is the approved spender's.",
+            "inputSchema": {"type": "object", "properties": {"private_key": {"type": "string"}, "tokenAddress": {"type": "string"}, "fromAddress": {"type": "string"}, "toAddress": {"type": "string"}, "amount": {"type": "string", "description": "Amount in the token's base units (decimal string)"}, "amount_decimal": {"type": "string", "description": "Amount in whole tokens, e.g. \"1.5\", scaled by the token's decimals(). Use instead of amount."}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "gas_limit": {"type": "string"}, "gas_price": {"type": "string"}, "gas_price_gwei": {"type": "string"}, "max_fee_gwei": {"type": "string", "description": "EIP-1559 max fee per gas in gwei. Use instead of a gas price."}, "priority_fee_gwei": {"type": "string", "description": "EIP-1559 priority fee (tip) per gas in gwei. Use instead of a gas price."}, "skip_balance_check": {"type": "boolean"}, "use_config_gas_defaults": {"type": "boolean", "description": "Fall back on DEFAULT_GAS_LIMIT/DEFAULT_GAS_PRICE if the node cannot estimate gas, instead of failing. Defaults to USE_CONFIG_GAS_DEFAULTS."}, "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}}, "required": ["private_key", "tokenAddress", "fromAddress", "toAddress"], "oneOf": [{"required": ["amount"]}, {"required": ["amount_decimal"]}]}
+        },
+        {
+            "name": "modify_token_allowance",
+            "description": "Increase or decrease an ERC20 allowance by amount (increaseAllowance/decreaseAllowance). Only tokens implementing these OpenZeppelin extensions support it.",
+            "inputSchema": {"type": "object", "properties": {"private_key": {"type": "string"}, "tokenAddress": {"type": "string"}, "spenderAddress": {"type": "string"}, "amount": {"type": "string", "description": "Amount in the token's base units (decimal string)"}, "amount_decimal": {"type": "string", "description": "Amount in whole tokens, e.g. \"1.5\", scaled by the token's decimals(). Use instead of amount."}, "operation": {"type": "string", "enum": ["increase", "decrease"]}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "gas_limit": {"type": "string"}, "gas_price": {"type": "string"}, "gas_price_gwei": {"type": "string"}, "max_fee_gwei": {"type": "string", "description": "EIP-1559 max fee per gas in gwei. Use instead of a gas price."}, "priority_fee_gwei": {"type": "string", "description": "EIP-1559 priority fee (tip) per gas in gwei. Use instead of a gas price."}, "skip_balance_check": {"type": "boolean"}, "use_config_gas_defaults": {"type": "boolean", "description": "Fall back on DEFAULT_GAS_LIMIT/DEFAULT_GAS_PRICE if the node cannot estimate gas, instead of failing. Defaults to USE_CONFIG_GAS_DEFAULTS."}, "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}}, "required": ["private_key", "tokenAddress", "spenderAddress", "operation"], "oneOf": [{"required": ["amount"]}, {"required": ["amount_decimal"]}]}
+        },
+        // --- Added: ERC721 ---
+        {
+            "name": "get_nft_info",
+            "description": "Get ERC721 token metadata (tokenURI).",
+            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "tokenId": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}}, "required": ["tokenAddress", "tokenId"]}
+        },
+        {
+            "name": "check_nft_ownership",
+            "description": "Verify ERC721 NFT ownership (ownerOf).",
+            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "tokenId": {"type": "string"}, "ownerAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}}, "required": ["tokenAddress", "tokenId", "ownerAddress"]}
+        },
+        {
+            "name": "get_nft_balance",
+            "description": "Count ERC721 NFTs owned (balanceOf).",
+            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "ownerAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}}, "required": ["tokenAddress", "ownerAddress"]}
+        },
+        // --- Added: ERC1155 ---
+        {
+            "name": "get_erc1155_token_uri",
+            "description": "Get ERC1155 token URI.",
+            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "tokenId": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}}, "required": ["tokenAddress", "tokenId"]}
+        },
+        {
+            "name": "get_erc1155_balance",
+            "description": "Check ERC1155 token balance.",
+            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "tokenId": {"type": "string"}, "ownerAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}}, "required": ["tokenAddress", "tokenId", "ownerAddress"]}
+        },
+        {
+            "name": "transfer_erc1155",
+            "description": "Transfer ERC1155 tokens (safeTransferFrom).",
+            "inputSchema": {"type": "object", "properties": {"private_key": {"type": "string"}, "tokenAddress": {"type": "string"}, "fromAddress": {"type": "string"}, "toAddress": {"type": "string"}, "tokenId": {"type": "string"}, "amount": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "gas_limit": {"type": "string"}, "gas_price": {"type": "string"}, "gas_price_gwei": {"type": "string"}, "max_fee_gwei": {"type": "string", "description": "EIP-1559 max fee per gas in gwei. Use instead of a gas price."}, "priority_fee_gwei": {"type": "string", "description": "EIP-1559 priority fee (tip) per gas in gwei. Use instead of a gas price."}, "skip_balance_check": {"type": "boolean"}, "use_config_gas_defaults": {"type": "boolean", "description": "Fall back on DEFAULT_GAS_LIMIT/DEFAULT_GAS_PRICE if the node cannot estimate gas, instead of failing. Defaults to USE_CONFIG_GAS_DEFAULTS."}, "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}}, "required": ["private_key", "tokenAddress", "fromAddress", "toAddress", "tokenId", "amount"]}
+        },
+        {
+            "name": "get_erc1155_balance_batch",
+            "description": "Check several ERC1155 balances in one call (balanceOfBatch). Pass ownerAddress to use one owner for every id, or ownerAddresses with one owner per id.",
+            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "tokenIds": {"type": "array", "items": {"type": "string"}}, "ownerAddress": {"type": "string"}, "ownerAddresses": {"type": "array", "items": {"type": "string"}}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}}, "required": ["tokenAddress", "tokenIds"]}
+        },
+        {
+            "name": "transfer_erc1155_batch",
+            "description": "Transfer several ERC1155 token ids at once (safeBatchTransferFrom). tokenIds and amounts are matched by position.",
+            "inputSchema": {"type": "object", "properties": {"private_key": {"type": "string"}, "tokenAddress": {"type": "string"}, "fromAddress": {"type": "string"}, "toAddress": {"type": "string"}, "tokenIds": {"type": "array", "items": {"type": "string"}}, "amounts": {"type": "array", "items": {"type": "string"}}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "gas_limit": {"type": "string"}, "gas_price": {"type": "string"}, "gas_price_gwei": {"type": "string"}, "max_fee_gwei": {"type": "string", "description": "EIP-1559 max fee per gas in gwei. Use instead of a gas price."}, "priority_fee_gwei": {"type": "string", "description": "EIP-1559 priority fee (tip) per gas in gwei. Use instead of a gas price."}, "skip_balance_check": {"type": "boolean"}, "use_config_gas_defaults": {"type": "boolean", "description": "Fall back on DEFAULT_GAS_LIMIT/DEFAULT_GAS_PRICE if the node cannot estimate gas, instead of failing. Defaults to USE_CONFIG_GAS_DEFAULTS."}, "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}}, "required": ["private_key", "tokenAddress", "fromAddress", "toAddress", "tokenIds", "amounts"]}
+        },
+        // --- Added: contract utils ---
+        {
+            "name": "is_contract",
+            "description": "Check whether an address is a contract or an externally owned account (code_size and is_eoa from eth_getCode when the chain has an RPC), and whether its source is verified on Etherscan (when ETHERSCAN_API_KEY is set).",
+            "inputSchema": {"type": "object", "properties": {"address": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["address"]},
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "is_contract": {"type": "boolean"},
+                    "verified": {"type": "boolean", "description": "Source verified on Etherscan (false when not checked)"},
+                    "code_size": {"type": "integer", "description": "Deployed code size in bytes, when read from the RPC"},
+                    "is_eoa": {"type": "boolean"},
+                    "contract_name": {"type": "string"}
+                },
+                "required": ["is_contract", "verified"]
+            }
+        },
+        {
+            "name": "read_contract",
+            "description": "Read a contract function (eth_call) given a full ABI plus functionName, or just a function_signature like 'balanceOf(address) returns (uint256)'. With functionName alone, the verified ABI is fetched from Etherscan and cached. Returns the raw result and decoded outputs.",
+            "inputSchema": {"type": "object", "properties": {"contractAddress": {"type": "string"}, "abi": {"type": "string"}, "functionName": {"type": "string"}, "function_signature": {"type": "string", "description": "Human readable signature, e.g. 'balanceOf(address) returns (uint256)'; used when abi is omitted"}, "args": {"type": "array"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}}, "required": ["contractAddress"], "oneOf": [{"required": ["functionName"]}, {"required": ["function_signature"]}]}
+        },
+        {
+            "name": "write_contract",
+            "description": "Write to a contract via ABI (signed tx). Without abi, the contract's verified ABI is fetched from Etherscan and cached.",
+            "inputSchema": {"type": "object", "properties": {"private_key": {"type": "string"}, "contractAddress": {"type": "string"}, "abi": {"type": "string"}, "functionName": {"type": "string"}, "args": {"type": "array"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "gas_limit": {"type": "string"}, "gas_price": {"type": "string"}, "gas_price_gwei": {"type": "string"}, "max_fee_gwei": {"type": "string", "description": "EIP-1559 max fee per gas in gwei. Use instead of a gas price."}, "priority_fee_gwei": {"type": "string", "description": "EIP-1559 priority fee (tip) per gas in gwei. Use instead of a gas price."}, "skip_balance_check": {"type": "boolean"}, "use_config_gas_defaults": {"type": "boolean", "description": "Fall back on DEFAULT_GAS_LIMIT/DEFAULT_GAS_PRICE if the node cannot estimate gas, instead of failing. Defaults to USE_CONFIG_GAS_DEFAULTS."}, "safe_send": {"type": "boolean", "description": "Simulate first (eth_call + eth_estimateGas) and only broadcast if it would not revert; a revert is returned with its reason.", "default": false}, "value": {"type": "string", "description": "Wei to send with the call (decimal string), for payable functions"}, "ensure_allowance": {"type": "object", "description": "Before the call, make sure the contract may spend this ERC-20 from the sender; if the allowance is lower, an approve is sent and mined first (its hash is returned under 'allowance')", "properties": {"token": {"type": "string", "description": "ERC-20 token address"}, "amount": {"type": "string", "description": "Allowance the call needs, in token base units (decimal string)"}, "approve": {"type": "string", "enum": ["exact", "max"], "default": "exact", "description": "Approve exactly 'amount' or the max uint256"}}, "required": ["token", "amount"]}, "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}}, "required": ["private_key", "contractAddress", "functionName"]}
+        },
+        {
+            "name": "get_block_number",
+            "description": "Get the current block number for a blockchain network.",
+            "inputSchema": {"type": "object", "properties": {"chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)"}, "network": {"type": "string", "description": "Alternative to chain_id"}}, "additionalProperties": false}
+        },
+        {
+            "name": "compare_gas_costs",
+            "description": "Estimate the fee of the same transfer or call on several chains concurrently and rank them from cheapest to most expensive. Chains with EIP-1559 fees also report base_fee, max_fee_per_gas and max_priority_fee_per_gas (wei).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_ids": {"type": "array", "items": {"type": "string"}, "description": "Chains to compare (IDs or aliases configured in CHAIN_RPC_URLS)."},
+                    "to_address": {"type": "string", "description": "Recipient or contract address."},
+                    "from_address": {"type": "string", "description": "Optional sender address used for estimation."},
+                    "value": {"type": "string", "description": "Optional value in wei."},
+                    "data": {"type": "string", "description": "Optional 0x-prefixed calldata."}
+                },
+                "required": ["chain_ids", "to_address"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "export_private_key",
+            "description": "Reveal the private key of a stored wallet. Requires the master password; can be disabled by the operator with ALLOW_KEY_EXPORT=false.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "wallet_name": {"type": "string", "description": "The name of the stored wallet."},
+                    "master_password": {"type": "string", "description": "The master password for the wallet storage."}
+                },
+                "required": ["wallet_name", "master_password"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "get_chain_tip_health",
+            "description": "Check whether a chain's RPC endpoint is up to date by comparing the latest block timestamp with wall-clock time.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)"},
+                    "network": {"type": "string", "description": "Alternative to chain_id"},
+                    "max_lag_secs": {"type": "integer", "description": "Lag in seconds above which the endpoint is flagged as stalled (default 120 or CHAIN_TIP_MAX_LAG_SECS)."}
+                },
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "change_master_password",
+            "description": "Rotate the wallet storage master password, re-encrypting every stored wallet under the new password. MASTER_PASSWORD must be updated before the next restart.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "master_password": {"type": "string", "description": "The current master password."},
+                    "new_master_password": {"type": "string", "description": "The new master password (at least 8 characters)."}
+                },
+                "required": ["master_password", "new_master_password"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "create_and_register_wallet",
+            "description": "Generate a new EVM wallet and store it encrypted in one step. Returns only the address and mnemonic; the private key never leaves the server.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "wallet_name": {"type": "string", "description": "A unique name for the new wallet."},
+                    "master_password": {"type": "string", "description": "The master password for the wallet storage."},
+                    "reveal": {"type": "boolean", "default": true, "description": "Return the mnemonic backup. Ignored (always false) when the server sets SUPPRESS_KEY_OUTPUT"}
+                },
+                "required": ["wallet_name", "master_password"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "batch_read_contract",
+            "description": "Read many view functions of one contract in a single Multicall3 eth_call; results are keyed by function name.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "contractAddress": {"type": "string", "description": "Address of the contract to read."},
+                    "abi": {"type": "string", "description": "Contract ABI as a JSON string; must include every function in 'calls'."},
+                    "calls": {
+                        "type": "array",
+                        "description": "View functions to call, in order.",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "functionName": {"type": "string", "description": "Name of the view function."},
+                                "args": {"type": "array", "description": "Arguments in ABI order."}
+                            },
+                            "required": ["functionName"]
+                        }
+                    },
+                    "chain_id": {"type": "string", "description": "Chain to read from (default 1)."},
+                    "network": {"type": "string", "description": "Alias of 'chain_id'."}
+                },
+                "required": ["contractAddress", "abi", "calls"]
+            }
+        },
+        {
+            "name": "set_wallet_limit",
+            "description": "Set or clear a per-wallet daily spending limit enforced by transfer_from_wallet (resets each UTC day).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "wallet_name": {"type": "string", "description": "The name of the stored wallet."},
+                    "master_password": {"type": "string", "description": "The master password for the wallet storage."},
+                    "daily_limit_wei": {"type": ["string", "null"], "description": "Maximum total wei per UTC day. Omit or null to remove the limit."}
+                },
+                "required": ["wallet_name", "master_password"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "reencrypt_wallet",
+            "description": "Re-encrypt a single stored wallet's private key under a fresh per-wallet salt. Upgrades legacy entries written before per-wallet encryption.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "wallet_name": {"type": "string", "description": "Name of the stored wallet."},
+                    "master_password": {"type": "string", "description": "The wallet storage master password."}
+                },
+                "required": ["wallet_name", "master_password"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "get_wallet_token_balances",
+            "description": "Portfolio view for a stored wallet: every non-zero native and ERC20 balance, formatted by decimals and grouped by chain. Reads each chain with Multicall3.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "wallet_name": {"type": "string", "description": "Name of the stored wallet whose address is queried."},
+                    "master_password": {"type": "string", "description": "The wallet storage master password."},
+                    "chain_ids": {"type": "array", "items": {"type": "string"}, "description": "Chains to query. Defaults to every configured chain."},
+                    "tokens": {"type": "array", "items": {"type": "string"}, "description": "ERC20 token addresses to check on each chain."}
+                },
+                "required": ["wallet_name", "master_password"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "speed_up_from_wallet",
+            "description": "Speed up a stuck transaction from a stored wallet: rebroadcast it at the same nonce with a bumped gas price. Identify it by the original tx_hash or by its nonce. The private key never leaves the server.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "wallet_name": {"type": "string", "description": "Name of the stored wallet that sent the transaction."},
+                    "master_password": {"type": "string", "description": "The wallet storage master password."},
+                    "chain_id": {"type": "string", "description": "Chain the transaction was sent on."},
+                    "tx_hash": {"type": "string", "description": "Hash of the stuck transaction."},
+                    "nonce": {"type": "integer", "description": "Nonce of the stuck transaction (looked up in the pending pool)."},
+                    "bump_percent": {"type": "integer", "description": "Gas price increase over the original, in percent (minimum 10).", "default": 12},
+                    "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}
+                },
+                "oneOf": [
+                    {"required": ["wallet_name", "master_password", "chain_id", "tx_hash"]},
+                    {"required": ["wallet_name", "master_password", "chain_id", "nonce"]}
+                ],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "list_chains",
+            "description": "List the chains this server is configured for, with their numeric chain ID, name, Etherscan support and the aliases accepted as chain_id (e.g. 'sepolia'). Call this before other tools if unsure which chain_id to use.",
+            "inputSchema": {"type": "object", "properties": {}, "additionalProperties": false},
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "chains": {"type": "array", "items": {"type": "object", "properties": {"chain_id": {"type": "string"}, "name": {"type": "string"}, "etherscan_supported": {"type": "boolean"}, "aliases": {"type": "array", "items": {"type": "string"}}}, "required": ["chain_id", "name", "etherscan_supported", "aliases"]}}
+                },
+                "required": ["chains"]
+            }
+        },
+        {
+            "name": "keccak256",
+            "description": "Compute the keccak256 hash of a string, e.g. an event signature like 'Transfer(address,address,uint256)' to get its topic0, or a function signature for its selector (first 4 bytes).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "input": {"type": "string", "description": "Text to hash, or 0x-prefixed hex when encoding is 'hex'"},
+                    "encoding": {"type": "string", "enum": ["utf8", "hex"], "description": "How to interpret input (default utf8)"}
+                },
+                "required": ["input"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "input": {"type": "string"},
+                    "encoding": {"type": "string"},
+                    "hash": {"type": "string", "description": "0x-prefixed 32-byte hash"}
+                },
+                "required": ["input", "encoding", "hash"]
+            }
+        },
+        {
+            "name": "compute_selector",
+            "description": "Compute the 4-byte function selector of a function signature, e.g. 'transfer(address,uint256)' => 0xa9059cbb. Use parameter types only, without names.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "signature": {"type": "string", "description": "Function signature such as 'transfer(address,uint256)'"}
+                },
+                "required": ["signature"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "signature": {"type": "string", "description": "Canonical signature"},
+                    "selector": {"type": "string", "description": "0x-prefixed 4-byte selector"}
+                },
+                "required": ["signature", "selector"]
+            }
+        },
+        {
+            "name": "abi_encode",
+            "description": "ABI-encode a function call without sending it. Returns the full calldata (selector + encoded arguments) for a signature like 'transfer(address,uint256)'.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "signature": {"type": "string", "description": "Function signature, e.g. 'transfer(address,uint256)' (names optional)"},
+                    "args": {"type": "array", "description": "Arguments in order. Pass integers as decimal strings, bytes as 0x-hex, arrays/tuples as JSON arrays"}
+                },
+                "required": ["signature"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "signature": {"type": "string"},
+                    "selector": {"type": "string"},
+                    "calldata": {"type": "string", "description": "0x-prefixed calldata"}
+                },
+                "required": ["signature", "selector", "calldata"]
+            }
+        },
+        {
+            "name": "abi_decode",
+            "description": "Decode ABI-encoded hex data (e.g. eth_call return data or calldata) into JSON values given a list of Solidity types.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "types": {"type": "array", "items": {"type": "string"}, "description": "Solidity types in order, e.g. ['address','uint256']"},
+                    "data": {"type": "string", "description": "0x-prefixed hex data"},
+                    "has_selector": {"type": "boolean", "description": "Set true when data is calldata starting with a 4-byte selector (default false)"}
+                },
+                "required": ["types", "data"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "types": {"type": "array", "items": {"type": "string"}},
+                    "values": {"type": "array", "description": "Decoded values, in the order of types"}
+                },
+                "required": ["types", "values"]
+            }
+        },
+        {
+            "name": "derive_accounts",
+            "description": "List the addresses derived from a BIP-39 mnemonic along m/44'/60'/0'/0/{index}, to pick the right account before importing. No private keys are returned.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "mnemonic": {"type": "string", "description": "BIP-39 mnemonic phrase"},
+                    "count": {"type": "integer", "default": 5, "description": "Number of accounts to derive (1-50)"},
+                    "start_index": {"type": "integer", "default": 0, "description": "First account index"}
+                },
+                "required": ["mnemonic"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "accounts": {"type": "array", "items": {"type": "object", "properties": {"index": {"type": "integer"}, "path": {"type": "string"}, "address": {"type": "string"}}, "required": ["index", "path", "address"]}}
+                },
+                "required": ["accounts"]
+            }
+        },
+        {
+            "name": "validate_address",
+            "description": "Pre-flight check of an EVM address: well-formed 20-byte hex, its EIP-55 checksummed form and whether a mixed-case checksum is valid. With chain_id, also reports whether it is a contract.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {"type": "string", "description": "Address to check"},
+                    "chain_id": {"type": "string", "description": "Optional chain to check for contract code"}
+                },
+                "required": ["address"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {"type": "string"},
+                    "valid": {"type": "boolean"},
+                    "checksummed": {"type": "string"},
+                    "checksum": {"type": "string"},
+                    "reason": {"type": "string"},
+                    "chain_id": {"type": "string"},
+                    "is_contract": {"type": "boolean"}
+                },
+                "required": ["address", "valid"]
+            }
+        },
+        {
+            "name": "replace_transaction",
+            "description": "Replace a pending transaction by signing a new one at the same nonce with a higher gas price, or cancel it with a 0-value self-transfer (cancel: true). The gas price must be at least 10% above the pending one; defaults to a 12% bump.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "private_key": {"type": "string", "description": "Private key of the account that sent the stuck transaction."},
+                    "chain_id": {"type": "string", "description": "Chain the transaction was sent on."},
+                    "nonce": {"type": "integer", "description": "Nonce of the stuck transaction."},
+                    "gas_price": {"type": "string", "description": "New gas price in wei (decimal string)."},
+                    "gas_price_gwei": {"type": "string", "description": "New gas price in gwei. Use instead of gas_price."},
+                    "gas_limit": {"type": "string", "description": "Optional gas limit override (decimal string)."},
+                    "cancel": {"type": "boolean", "description": "Send a 0-value transfer to yourself instead of the original transaction.", "default": false},
+                    "idempotency_key": {"type": "string", "description": "Optional client-chosen key; repeating a transfer with the same key returns the first transaction instead of sending again."}
+                },
+                "required": ["private_key", "chain_id", "nonce"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "lookup_selector",
+            "description": "Reverse a 4-byte function selector (e.g. 0xa9059cbb) or 32-byte event topic to candidate signatures such as 'transfer(address,uint256)', using the 4byte.directory signature database. Candidates are ordered oldest first; selectors can collide, so check them against the calldata.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "selector": {"type": "string", "description": "0x-prefixed 4-byte selector or 32-byte event topic"}
+                },
+                "required": ["selector"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "kind": {"type": "string", "description": "'function' or 'event'"},
+                    "candidates": {"type": "array", "items": {"type": "string"}},
+                    "source": {"type": "string"},
+                    "warning": {"type": "string"}
+                },
+                "required": ["kind", "candidates", "source"]
+            }
+        },
         {
-            "name": "get_contract_transactions",
-            "description": "Get the transactions of a smart contract via Etherscan API.",
+            "name": "get_storage_at",
+            "description": "Read a raw 32-byte storage slot of a contract (eth_getStorageAt), e.g. to inspect proxy implementation slots or mapping entries. Returns the word as hex plus its uint256 and address readings. With mapping_key, reads mapping[mapping_key] for a mapping declared at slot.",
             "inputSchema": {
                 "type": "object",
                 "properties": {
-                    "address": {"type": "string", "description": "The address of the smart contract."},
+                    "chain_id": {"type": "string", "description": "Chain ID or alias"},
+                    "address": {"type": "string", "description": "Contract address"},
+                    "slot": {"type": "string", "description": "Slot number as 0x-hex or decimal (the mapping's base slot when mapping_key is set)"},
+                    "mapping_key": {"type": "string", "description": "Optional mapping key (address, 0x-hex or decimal integer)"},
+                    "block": {"type": ["string", "integer"], "description": "Block number, hash or tag to read at (default latest)"}
+                },
+                "required": ["chain_id", "address", "slot"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {"type": "string"},
+                    "slot": {"type": "string"},
+                    "block": {"type": "string"},
+                    "value": {"type": "string", "description": "Raw 32-byte word"},
+                    "as_uint256": {"type": "string"},
+                    "as_address": {"type": "string"}
+                },
+                "required": ["address", "slot", "block", "value", "as_uint256", "as_address"]
+            }
+        },
+        {
+            "name": "get_portfolio",
+            "description": "Balances of any address for a list of ERC20 tokens plus the native coin on one chain: symbol, decimals, raw and formatted balance per token, read with Multicall3. Tokens that fail to respond are flagged with an error instead of failing the call.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string", "description": "Chain to query."},
+                    "owner_address": {"type": "string", "description": "Address whose balances are read."},
+                    "token_addresses": {"type": "array", "items": {"type": "string"}, "description": "ERC20 token contract addresses."}
+                },
+                "required": ["chain_id", "owner_address", "token_addresses"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "native": {"type": "object"},
+                    "tokens": {"type": "array", "items": {"type": "object"}}
+                },
+                "required": ["native", "tokens"]
+            }
+        },
+        {
+            "name": "discover_tokens",
+            "description": "Find the ERC20 tokens an address holds without knowing them in advance: scans its token transfer history on Etherscan, newest first, then reads the current balance of each token seen (plus native) with Multicall3. Zero balances are left out; 'truncated' is true when the history was too long to scan in full. Requires ETHERSCAN_API_KEY.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string", "description": "Chain to query (must be Etherscan-indexed)."},
+                    "address": {"type": "string", "description": "Address whose tokens are discovered."}
+                },
+                "required": ["chain_id", "address"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string"},
+                    "address": {"type": "string"},
+                    "native": {"type": "object"},
+                    "tokens": {"type": "array", "items": {"type": "object"}},
+                    "tokens_seen": {"type": "integer"},
+                    "truncated": {"type": "boolean"}
+                },
+                "required": ["chain_id", "address"]
+            }
+        },
+        {
+            "name": "wait_for_balance",
+            "description": "Wait for a deposit or faucet payout: polls the native or ERC20 balance of an address until it reaches 'min_balance' or grows by 'increase_by' (base units), or until the timeout passes. Returns the final balance, whether the target was met and how long it took.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string", "description": "Chain to watch."},
+                    "address": {"type": "string", "description": "Address whose balance is watched."},
+                    "token_address": {"type": "string", "description": "ERC20 token to watch instead of the native balance."},
+                    "min_balance": {"type": "string", "description": "Wait until the balance is at least this many base units (wei for native)."},
+                    "increase_by": {"type": "string", "description": "Wait until the balance has grown by this many base units over the first reading."},
+                    "timeout_secs": {"type": "integer", "description": "Give up after this many seconds (capped below the server's tool time limit).", "default": 60}
+                },
+                "required": ["chain_id", "address"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string"},
+                    "address": {"type": "string"},
+                    "token_address": {"type": ["string", "null"]},
+                    "met": {"type": "boolean"},
+                    "initial_balance": {"type": "string"},
+                    "balance": {"type": "string"},
+                    "polls": {"type": "integer"},
+                    "elapsed_secs": {"type": "number"}
+                },
+                "required": ["chain_id", "address", "met", "initial_balance", "balance", "polls", "elapsed_secs"]
+            }
+        },
+        {
+            "name": "decode_calldata",
+            "description": "Decode a transaction's input data: matches the 4-byte selector against a contract ABI (or a list of function signatures) and returns the function called with its named arguments.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "data": {"type": "string", "description": "0x-prefixed calldata (selector followed by the encoded arguments)."},
+                    "abi": {"type": ["string", "array"], "description": "Contract ABI as a JSON string or array."},
+                    "signatures": {"type": "array", "items": {"type": "string"}, "description": "Function signatures such as 'transfer(address to, uint256 amount)', instead of or in addition to the ABI."}
+                },
+                "required": ["data"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "matched": {"type": "boolean", "description": "false when no function has the selector"},
+                    "function": {"type": "string"},
+                    "signature": {"type": "string"},
+                    "selector": {"type": "string"},
+                    "args": {"type": "array", "items": {"type": "object", "properties": {"name": {"type": "string"}, "type": {"type": "string"}, "value": {}}, "required": ["name", "type", "value"]}}
+                },
+                "required": ["selector"]
+            }
+        },
+        {
+            "name": "get_contract_code_normalized",
+            "description": "Fetch a verified contract's source from Etherscan in a fixed schema: {abi, compilerSettings, externalLibraries, runtimeCode, creationCode, sources: [{name, sourceCode}]}. Multi-file (standard JSON input) sources are split into files. runtimeCode is read from the chain's RPC when configured; creationCode is not available. Requires ETHERSCAN_API_KEY.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {"type": "string", "description": "The address of the verified contract."},
                     "chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)."}
                 },
-                "required": ["address"]
+                "required": ["address"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "abi": {"type": "array", "items": {"type": "string"}},
+                    "compilerSettings": {"type": "array", "items": {"type": "object"}},
+                    "externalLibraries": {"type": "array", "items": {"type": "object"}},
+                    "runtimeCode": {"type": "string"},
+                    "creationCode": {"type": "string"},
+                    "sources": {"type": "array", "items": {"type": "object", "properties": {"name": {"type": "string"}, "sourceCode": {"type": "string"}}, "required": ["name", "sourceCode"]}}
+                },
+                "required": ["abi", "compilerSettings", "externalLibraries", "runtimeCode", "creationCode", "sources"]
+            }
+        },
+        {
+            "name": "wait_for_confirmations",
+            "description": "Wait until a transaction has the given number of block confirmations: polls its receipt and the chain head until head - receipt block >= confirmations, the transaction reverts, or the timeout passes. Returns status (pending, mined, confirmed or reverted), the confirmation count and the receipt.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string", "description": "Chain the transaction was sent on."},
+                    "tx_hash": {"type": "string", "description": "Transaction hash to watch."},
+                    "confirmations": {"type": "integer", "description": "Blocks required on top of the one the transaction was mined in.", "default": 1, "minimum": 0},
+                    "timeout_secs": {"type": "integer", "description": "Give up after this many seconds (capped below the server's tool time limit).", "default": 60}
+                },
+                "required": ["chain_id", "tx_hash"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string"},
+                    "tx_hash": {"type": "string"},
+                    "status": {"type": "string", "description": "pending, mined, confirmed or reverted", "enum": ["pending", "mined", "confirmed", "reverted"]},
+                    "met": {"type": "boolean"},
+                    "block_number": {"type": ["integer", "null"]},
+                    "confirmations": {"type": "integer"},
+                    "required_confirmations": {"type": "integer"},
+                    "receipt": {"type": ["object", "null"]},
+                    "polls": {"type": "integer"},
+                    "elapsed_secs": {"type": "number"}
+                },
+                "required": ["chain_id", "tx_hash", "status", "met", "confirmations", "required_confirmations", "polls", "elapsed_secs"]
+            }
+        },
+        {
+            "name": "get_amounts_out",
+            "description": "Preview a swap on a UniswapV2-style DEX: calls the router's getAmountsOut(amountIn, path) and returns the amount received at each hop. Read-only; nothing is sent. The router defaults to the one configured for the chain (DEX_ROUTERS; Uniswap V2 on Ethereum mainnet).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string", "description": "Chain to quote on."},
+                    "amount_in": {"type": "string", "description": "Amount of the first token in path, in its base units."},
+                    "path": {"type": "array", "items": {"type": "string"}, "description": "Token addresses to swap through, input token first and output token last."},
+                    "router_address": {"type": "string", "description": "Router to ask instead of the chain's configured one."}
+                },
+                "required": ["chain_id", "amount_in", "path"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string"},
+                    "router": {"type": "string"},
+                    "path": {"type": "array", "items": {"type": "string"}},
+                    "amount_in": {"type": "string"},
+                    "amount_out": {"type": "string", "description": "Amount of the last token in path, in its base units"},
+                    "amounts": {"type": "array", "items": {"type": "string"}, "description": "Amount at each token of path"}
+                },
+                "required": ["chain_id", "router", "path", "amount_in", "amount_out", "amounts"]
+            }
+        },
+        {
+            "name": "server_info",
+            "description": "Report this server's version, build commit, configured chain IDs and which optional integrations (discord, faucet, etherscan) are enabled.",
+            "inputSchema": {"type": "object", "properties": {}, "additionalProperties": false},
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "version": {"type": "string"},
+                    "git_hash": {"type": ["string", "null"]},
+                    "chains": {"type": "array", "items": {"type": "string"}},
+                    "features": {"type": "object", "properties": {"discord": {"type": "boolean"}, "faucet": {"type": "boolean"}, "etherscan": {"type": "boolean"}}, "required": ["discord", "faucet", "etherscan"]}
+                },
+                "required": ["name", "version", "chains", "features"]
+            }
+        },
+        {
+            "name": "address_from_key",
+            "description": "Show which address a private key or mnemonic controls, without storing it. Only the checksummed address is returned, never the key.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "mnemonic_or_private_key": {"type": "string", "description": "0x-hex private key or BIP-39 mnemonic phrase"},
+                    "derivation_path": {"type": "string", "description": "BIP-32 path for mnemonics (default m/44'/60'/0'/0/0); not allowed with a private key"},
+                    "passphrase": {"type": "string", "description": "Optional BIP-39 passphrase the mnemonic was created with; not allowed with a private key"}
+                },
+                "required": ["mnemonic_or_private_key"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {"type": "string"},
+                    "key_type": {"type": "string", "enum": ["private_key", "mnemonic"]},
+                    "path": {"type": "string"}
+                },
+                "required": ["address", "key_type"]
+            }
+        },
+        {
+            "name": "get_account_state",
+            "description": "Everything needed before sending from an address, in one call: native balance (wei and formatted), latest and pending nonce, and whether the address holds contract code. Reads from the chain's RPC.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {"type": "string", "description": "Account address (0x...)."},
+                    "chain_id": {"type": "string", "description": "Chain ID or alias (1 for Ethereum, 11155111 for Sepolia)."}
+                },
+                "required": ["address", "chain_id"]
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "chain_id": {"type": "string"},
+                    "address": {"type": "string", "description": "EIP-55 checksummed"},
+                    "balance": {
+                        "type": "object",
+                        "properties": {
+                            "amount": {"type": "string", "description": "Balance in wei"},
+                            "denom": {"type": "string"},
+                            "symbol": {"type": "string"},
+                            "decimals": {"type": "integer"},
+                            "formatted": {"type": "string"}
+                        },
+                        "required": ["amount", "denom", "symbol", "decimals"]
+                    },
+                    "nonce": {
+                        "type": "object",
+                        "properties": {
+                            "latest": {"type": "integer", "description": "Transactions mined from the address"},
+                            "pending": {"type": "integer", "description": "Next nonce, counting pending transactions"}
+                        },
+                        "required": ["latest", "pending"]
+                    },
+                    "is_contract": {"type": "boolean"},
+                    "code_size": {"type": "integer", "description": "Bytes of deployed code"}
+                },
+                "required": ["chain_id", "address", "balance", "nonce", "is_contract", "code_size"]
+            }
+        },
+        {
+            "name": "find_wallet_by_address",
+            "description": "Find which stored wallet holds an address. Returns the wallet name and when it was stored, never the key.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {"type": "string", "description": "The 0x... address to look up, in any letter case."},
+                    "master_password": {"type": "string", "description": "The master password for the wallet storage."}
+                },
+                "required": ["address", "master_password"],
+                "additionalProperties": false
+            },
+            "outputSchema": {
+                "type": "object",
+                "properties": {
+                    "found": {"type": "boolean"},
+                    "address": {"type": "string", "description": "EIP-55 checksummed"},
+                    "wallet_name": {"type": "string"},
+                    "created_at": {"type": "string", "description": "RFC 3339 time the wallet was stored"}
+                },
+                "required": ["found", "address"]
+            }
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::mcp::wallet_storage::WalletStorage;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const TEST_PASSWORD: &str = "correct-horse-battery";
+
+    fn test_state(config: Config, dir: &Path) -> AppState {
+        let wallet_storage_path = dir.join("wallets.json");
+        let wallet_storage = WalletStorage::new(wallet_storage_path.clone());
+        AppState::new(config, wallet_storage, wallet_storage_path)
+    }
+
+    async fn call_tool(state: &AppState, name: &str, arguments: Value) -> Response {
+        let req = Request {
+            jsonrpc: "2.0".to_string(),
+            id: json!(1),
+            method: "tools/call".to_string(),
+            params: Some(json!({ "name": name, "arguments": arguments })),
+        };
+        handle_mcp_request(req, state.clone()).await.unwrap()
+    }
+
+    async fn register_test_wallet(state: &AppState) {
+        let resp = call_tool(
+            state,
+            "register_wallet",
+            json!({
+                "wallet_name": "alice",
+                "private_key": TEST_KEY,
+                "master_password": TEST_PASSWORD,
+            }),
+        )
+        .await;
+        assert!(resp.error.is_none(), "register failed: {:?}", resp.error);
+    }
+
+    #[tokio::test]
+    async fn test_register_wallet_overwrite() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        register_test_wallet(&state).await;
+
+        let other_key = "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        let args = json!({"wallet_name": "alice", "private_key": other_key, "master_password": TEST_PASSWORD});
+        let resp = call_tool(&state, "register_wallet", args.clone()).await;
+        let err = resp.error.expect("duplicate name should be refused");
+        assert_eq!(err.code, error_codes::INVALID_PARAMS);
+        assert!(err.message.contains("already exists") && err.message.contains("overwrite"), "{}", err.message);
+
+        let mut overwrite = args;
+        overwrite["overwrite"] = json!(true);
+        let resp = call_tool(&state, "register_wallet", overwrite).await;
+        let result = resp.result.expect("overwrite should succeed");
+        assert_eq!(result["replaced"], true);
+        let address = result["address"].as_str().unwrap().to_lowercase();
+        assert_ne!(result["previous_address"].as_str().unwrap(), address);
+
+        let stored = wallet_storage::load_or_create_wallet_storage(&state.wallet_storage_path, TEST_PASSWORD).unwrap();
+        assert_eq!(stored.get_wallet("alice").unwrap().public_address, address);
+    }
+
+    #[tokio::test]
+    async fn test_find_wallet_by_address() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        register_test_wallet(&state).await;
+        let stored = state.wallet_storage.lock().await.get_wallet("alice").unwrap().public_address.clone();
+        let checksummed = to_checksum(&Address::from_str(&stored).unwrap(), None);
+
+        // Any letter case finds the wallet
+        for address in [stored.to_uppercase().replace("0X", "0x"), checksummed.clone()] {
+            let resp = call_tool(
+                &state,
+                "find_wallet_by_address",
+                json!({"address": address, "master_password": TEST_PASSWORD}),
+            )
+            .await;
+            let result = resp.result.expect("lookup should succeed");
+            assert_eq!(result["found"], true);
+            assert_eq!(result["wallet_name"], "alice");
+            assert_eq!(result["address"], checksummed);
+            assert!(result.get("private_key").is_none());
+            let schema = tool_schema("find_wallet_by_address", "outputSchema").unwrap();
+            assert_eq!(validation::output_mismatches(&schema, &result), Vec::<String>::new());
+        }
+
+        let resp = call_tool(
+            &state,
+            "find_wallet_by_address",
+            json!({"address": "0x000000000000000000000000000000000000dEaD", "master_password": TEST_PASSWORD}),
+        )
+        .await;
+        let result = resp.result.unwrap();
+        assert_eq!(result["found"], false);
+        assert!(result["content"][0]["text"].as_str().unwrap().starts_with("No stored wallet"));
+    }
+
+    #[tokio::test]
+    async fn test_list_wallets_includes_created_at_and_sorts() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        for (name, key) in [
+            ("zed", TEST_KEY),
+            ("amy", "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"),
+        ] {
+            let args = json!({"wallet_name": name, "private_key": key, "master_password": TEST_PASSWORD});
+            assert!(call_tool(&state, "register_wallet", args).await.error.is_none());
+        }
+
+        let names = |result: &Value| -> Vec<String> {
+            result["wallets"].as_array().unwrap().iter().map(|w| w["wallet_name"].as_str().unwrap().to_string()).collect()
+        };
+        let resp = call_tool(&state, "list_wallets", json!({"master_password": TEST_PASSWORD})).await;
+        let result = resp.result.expect("list_wallets should succeed");
+        assert_eq!(names(&result), ["amy", "zed"]);
+        for wallet in result["wallets"].as_array().unwrap() {
+            let created_at = wallet["created_at"].as_str().unwrap();
+            assert!(chrono::DateTime::parse_from_rfc3339(created_at).is_ok(), "{}", created_at);
+        }
+        assert!(result["content"][1]["text"].as_str().unwrap().contains("(created "));
+
+        let resp = call_tool(&state, "list_wallets", json!({"master_password": TEST_PASSWORD, "sort_by": "created_at"})).await;
+        assert_eq!(names(&resp.result.unwrap()), ["zed", "amy"]);
+    }
+
+    #[tokio::test]
+    async fn test_export_private_key_with_correct_password() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        register_test_wallet(&state).await;
+
+        let resp = call_tool(
+            &state,
+            "export_private_key",
+            json!({ "wallet_name": "alice", "master_password": TEST_PASSWORD }),
+        )
+        .await;
+        let result = resp.result.expect("export should succeed");
+        assert_eq!(result["private_key"], TEST_KEY);
+        assert!(result["address"].as_str().unwrap().starts_with("0x"));
+        assert!(result["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("WARNING"));
+    }
+
+    #[tokio::test]
+    async fn test_export_private_key_with_wrong_password() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        register_test_wallet(&state).await;
+
+        let resp = call_tool(
+            &state,
+            "export_private_key",
+            json!({ "wallet_name": "alice", "master_password": "not-the-password" }),
+        )
+        .await;
+        assert!(resp.result.is_none());
+        assert_eq!(resp.error.unwrap().message, "Authentication failed");
+    }
+
+    #[tokio::test]
+    async fn test_export_private_key_disabled_by_config() {
+        let dir = tempdir().unwrap();
+        let config = Config {
+            key_export_disabled: true,
+            ..Config::default()
+        };
+        let state = test_state(config, dir.path());
+        register_test_wallet(&state).await;
+
+        let resp = call_tool(
+            &state,
+            "export_private_key",
+            json!({ "wallet_name": "alice", "master_password": TEST_PASSWORD }),
+        )
+        .await;
+        let err = resp.error.expect("export should be rejected");
+        assert_eq!(err.code, error_codes::INVALID_REQUEST);
+        assert!(err.message.contains("ALLOW_KEY_EXPORT"));
+    }
+
+    #[tokio::test]
+    async fn test_validation_reports_every_missing_field() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+
+        let resp = call_tool(&state, "transfer_evm", json!({ "private_key": TEST_KEY, "chain_id": "1" })).await;
+        let err = resp.error.expect("validation should fail");
+        assert_eq!(err.code, error_codes::INVALID_PARAMS);
+        let problems = err.data.unwrap()["problems"].clone();
+        let fields: Vec<&str> = problems
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(fields, vec!["to_address", "amount_wei"]);
+        assert!(err.message.contains("to_address") && err.message.contains("amount_wei"));
+    }
+
+    #[tokio::test]
+    async fn test_create_and_register_wallet_is_retrievable() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+
+        let resp = call_tool(
+            &state,
+            "create_and_register_wallet",
+            json!({ "wallet_name": "bob", "master_password": TEST_PASSWORD }),
+        )
+        .await;
+        let result = resp.result.expect("create_and_register_wallet should succeed");
+        assert!(result.get("private_key").is_none());
+        assert!(result["mnemonic"].is_string());
+        let address = result["address"].as_str().unwrap().to_lowercase();
+
+        let storage = state.wallet_storage.lock().await;
+        let key = storage.get_private_key("bob", TEST_PASSWORD).unwrap();
+        let derived = LocalWallet::from_str(&key).unwrap().address();
+        assert_eq!(format!("{:?}", derived), address);
+    }
+
+    #[tokio::test]
+    async fn test_create_and_register_wallet_output_is_redacted_for_logs() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+
+        let resp = call_tool(
+            &state,
+            "create_and_register_wallet",
+            json!({ "wallet_name": "bob", "master_password": TEST_PASSWORD }),
+        )
+        .await;
+        let result = resp.result.expect("create_and_register_wallet should succeed");
+        let mnemonic = result["mnemonic"].as_str().unwrap().to_string();
+
+        let logged = utils::redact_secrets(&result).to_string();
+        assert!(!logged.contains(&mnemonic), "{}", logged);
+        assert!(logged.contains("Mnemonic (write this down as a backup): [REDACTED]"), "{}", logged);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_from_wallet_over_daily_limit_is_rejected() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        register_test_wallet(&state).await;
+
+        let resp = call_tool(
+            &state,
+            "set_wallet_limit",
+            json!({ "wallet_name": "alice", "master_password": TEST_PASSWORD, "daily_limit_wei": "1000" }),
+        )
+        .await;
+        assert!(resp.error.is_none(), "set_wallet_limit failed: {:?}", resp.error);
+
+        let resp = call_tool(
+            &state,
+            "transfer_from_wallet",
+            json!({
+                "wallet_name": "alice",
+                "chain_id": "1",
+                "to_address": "0x000000000000000000000000000000000000dEaD",
+                "amount": "1001",
+                "master_password": TEST_PASSWORD,
+            }),
+        )
+        .await;
+        let err = resp.error.expect("transfer should be rejected");
+        assert!(err.message.contains("daily limit"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn test_failed_transfer_releases_daily_limit_reservation() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        // Nothing listens here, so every send fails
+        config.chain_rpc_urls.insert("31337".into(), "http://127.0.0.1:1".into());
+        let state = test_state(config, dir.path());
+        register_test_wallet(&state).await;
+
+        let resp = call_tool(
+            &state,
+            "set_wallet_limit",
+            json!({ "wallet_name": "alice", "master_password": TEST_PASSWORD, "daily_limit_wei": "1000" }),
+        )
+        .await;
+        assert!(resp.error.is_none(), "set_wallet_limit failed: {:?}", resp.error);
+
+        for _ in 0..2 {
+            let resp = call_tool(
+                &state,
+                "transfer_from_wallet",
+                json!({
+                    "wallet_name": "alice",
+                    "chain_id": "31337",
+                    "to_address": "0x000000000000000000000000000000000000dEaD",
+                    "amount": "600",
+                    "master_password": TEST_PASSWORD,
+                }),
+            )
+            .await;
+            let err = resp.error.expect("send to an unreachable node should fail");
+            assert!(!err.message.contains("daily limit"), "{}", err.message);
+        }
+        let storage = state.wallet_storage.lock().await;
+        assert_eq!(storage.get_wallet("alice").unwrap().spent_on(Utc::now()), U256::zero());
+    }
+
+    #[tokio::test]
+    async fn test_transfer_from_wallet_over_configured_max_is_rejected() {
+        let dir = tempdir().unwrap();
+        let config = Config {
+            max_transfer_amount_wei: Some(U256::from(10)),
+            ..Config::default()
+        };
+        let state = test_state(config, dir.path());
+        register_test_wallet(&state).await;
+
+        let resp = call_tool(
+            &state,
+            "transfer_from_wallet",
+            json!({
+                "wallet_name": "alice",
+                "chain_id": "1",
+                "to_address": "0x000000000000000000000000000000000000dEaD",
+                "amount": "11",
+                "master_password": TEST_PASSWORD,
+            }),
+        )
+        .await;
+        let err = resp.error.expect("transfer should be rejected");
+        assert!(err.message.contains("configured maximum"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn test_get_wallet_token_balances_groups_by_chain() {
+        use ethers_core::abi::encode;
+        use mockito::{mock, Matcher};
+
+        let aggregate = |balances: [u64; 3]| {
+            let entry = |data: Vec<u8>| Token::Tuple(vec![Token::Bool(true), Token::Bytes(data)]);
+            let uint = |n: u64| encode(&[Token::Uint(U256::from(n))]);
+            let sym = |s: &str| encode(&[Token::String(s.to_string())]);
+            let items = vec![
+                entry(uint(balances[0])),
+                entry(uint(balances[1])), entry(uint(6)), entry(sym("USDC")),
+                entry(uint(balances[2])), entry(uint(18)), entry(sym("DAI")),
+            ];
+            json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{}", hex::encode(encode(&[Token::Array(items)])))}).to_string()
+        };
+        let _a = mock("POST", "/wallet-portfolio-a")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call"})))
+            .with_body(aggregate([0, 3_000_000, 5_000_000_000_000_000_000]))
+            .create();
+        let _b = mock("POST", "/wallet-portfolio-b")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call"})))
+            .with_body(aggregate([1_000_000_000_000_000_000, 0, 0]))
+            .create();
+
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("1".into(), format!("{}/wallet-portfolio-a", mockito::server_url()));
+        config.chain_rpc_urls.insert("8453".into(), format!("{}/wallet-portfolio-b", mockito::server_url()));
+        let state = test_state(config, dir.path());
+        register_test_wallet(&state).await;
+
+        let resp = call_tool(
+            &state,
+            "get_wallet_token_balances",
+            json!({
+                "wallet_name": "alice",
+                "master_password": TEST_PASSWORD,
+                "tokens": ["0x0000000000000000000000000000000000000010", "0x0000000000000000000000000000000000000020"],
+            }),
+        )
+        .await;
+        let result = resp.result.expect("portfolio query should succeed");
+        let chains = result["chains"].as_array().unwrap();
+        assert_eq!(chains.len(), 2);
+        assert_eq!(chains[0]["chain_id"], "1");
+        let mainnet: Vec<&str> = chains[0]["balances"].as_array().unwrap().iter().map(|b| b["balance"].as_str().unwrap()).collect();
+        assert_eq!(mainnet, vec!["3", "5"]);
+        assert_eq!(chains[1]["chain_id"], "8453");
+        assert_eq!(chains[1]["balances"][0]["address"], "native");
+        assert_eq!(chains[1]["balances"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_portfolio_reads_three_tokens_and_native() {
+        use ethers_core::abi::{encode, Token};
+        use mockito::{mock, Matcher};
+
+        let entry = |data: Vec<u8>| Token::Tuple(vec![Token::Bool(true), Token::Bytes(data)]);
+        let uint = |n: u64| encode(&[Token::Uint(U256::from(n))]);
+        let sym = |s: &str| encode(&[Token::String(s.to_string())]);
+        let items = vec![
+            entry(uint(1_500_000_000_000_000_000)),
+            entry(uint(3_000_000)), entry(uint(6)), entry(sym("USDC")),
+            entry(uint(0)), entry(uint(18)), entry(sym("DAI")),
+            entry(uint(25_000_000)), entry(uint(8)), entry(sym("WBTC")),
+        ];
+        let result = format!("0x{}", hex::encode(encode(&[Token::Array(items)])));
+        let m = mock("POST", "/get-portfolio")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string())
+            .expect(1)
+            .create();
+
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("1".into(), format!("{}/get-portfolio", mockito::server_url()));
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(
+            &state,
+            "get_portfolio",
+            json!({
+                "chain_id": "1",
+                "owner_address": "0x000000000000000000000000000000000000dEaD",
+                "token_addresses": [
+                    "0x0000000000000000000000000000000000000010",
+                    "0x0000000000000000000000000000000000000020",
+                    "0x0000000000000000000000000000000000000030"
+                ],
+            }),
+        )
+        .await;
+        let result = resp.result.expect("portfolio query should succeed");
+        m.assert();
+        assert_eq!(result["native"]["balance"], "1.5");
+        let tokens = result["tokens"].as_array().unwrap();
+        let rows: Vec<(&str, &str, &str)> = tokens
+            .iter()
+            .map(|t| (t["symbol"].as_str().unwrap(), t["balance_wei"].as_str().unwrap(), t["balance"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("USDC", "3000000", "3"),
+                ("DAI", "0", "0"),
+                ("WBTC", "25000000", "0.25"),
+            ]
+        );
+        assert_eq!(tokens[2]["decimals"], 8);
+    }
+
+    #[tokio::test]
+    async fn test_search_events_falls_back_to_rpc_without_etherscan() {
+        use mockito::{mock, Matcher};
+
+        let topic0 = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+        let _m = mock("POST", "/search-events-rpc")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_getLogs",
+                "params": [{"address": "0xabc", "fromBlock": "0x64", "toBlock": "0xc8", "topics": [topic0]}]
+            })))
+            .with_body(
+                json!({"jsonrpc": "2.0", "id": 1, "result": [{
+                    "topics": [
+                        topic0,
+                        "0x0000000000000000000000000000000000000000000000000000000000000001",
+                        "0x0000000000000000000000000000000000000000000000000000000000000002"
+                    ],
+                    "data": format!("0x{:064x}", 500),
+                }]})
+                .to_string(),
+            )
+            .create();
+
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("137".into(), format!("{}/search-events-rpc", mockito::server_url()));
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(
+            &state,
+            "search_events",
+            json!({
+                "chain_id": "137",
+                "contract_address": "0xabc",
+                "event_abi": "Transfer(address indexed from, address indexed to, uint256 value)",
+                "from_block": "100",
+                "to_block": "0xc8"
+            }),
+        )
+        .await;
+        let result = resp.result.expect("rpc search should succeed");
+        assert_eq!(result["source"], "rpc");
+        assert_eq!(result["logs"].as_array().unwrap().len(), 1);
+        let decoded = &result["decoded_logs"][0]["params"];
+        assert_eq!(decoded["from"], "0x0000000000000000000000000000000000000001");
+        assert_eq!(decoded["value"], "500");
+    }
+
+    #[tokio::test]
+    async fn test_search_events_defaults_to_configured_etherscan_url() {
+        use mockito::{mock, Matcher};
+
+        let explorer = mock("GET", "/search-events-explorer")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("chainid".into(), "424242".into()),
+                Matcher::UrlEncoded("action".into(), "getLogs".into()),
+            ]))
+            .with_body(json!({"status": "1", "message": "OK", "result": [{"data": "0x"}]}).to_string())
+            .expect(1)
+            .create();
+        let dir = tempdir().unwrap();
+        let mut config = Config { etherscan_api_key: Some("test-key".into()), ..Config::default() };
+        config
+            .etherscan_base_urls
+            .insert("424242".into(), format!("{}/search-events-explorer", mockito::server_url()));
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(&state, "search_events", json!({"chain_id": "424242", "contract_address": "0xabc"})).await;
+        let result = resp.result.expect("configured explorer should answer");
+        assert_eq!(result["logs"].as_array().unwrap().len(), 1);
+        explorer.assert();
+    }
+
+    #[tokio::test]
+    async fn test_search_events_rpc_needs_a_bounded_range() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        // Never contacted: both requests are refused before any eth_getLogs
+        config.chain_rpc_urls.insert("137".into(), "http://127.0.0.1:1".into());
+        let state = test_state(config, dir.path());
+
+        let search = |range: Value| {
+            let mut args = json!({"chain_id": "137", "contract_address": "0xabc", "source": "rpc"});
+            args.as_object_mut().unwrap().extend(range.as_object().unwrap().clone());
+            args
+        };
+        let resp = call_tool(&state, "search_events", search(json!({"to_block": "200"}))).await;
+        let err = resp.error.expect("from_block is required");
+        assert_eq!(err.code, error_codes::INVALID_PARAMS);
+        assert!(err.message.contains("from_block"), "{}", err.message);
+
+        let resp = call_tool(&state, "search_events", search(json!({"from_block": "0", "to_block": "5000000"}))).await;
+        let err = resp.error.expect("range is too wide");
+        assert_eq!(err.code, error_codes::INVALID_PARAMS);
+        assert!(err.message.contains("spans more than"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_accepts_numeric_and_string_chain_id() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("1".into(), "http://localhost:1".into());
+        let state = test_state(config, dir.path());
+
+        // Without an Etherscan key both requests get past argument parsing and chain
+        // lookup, and stop at the same configuration error.
+        let numeric = call_tool(&state, "get_balance", json!({"chain_id": 1, "address": "0x000000000000000000000000000000000000dEaD"})).await;
+        let string = call_tool(&state, "get_balance", json!({"chain_id": "1", "address": "0x000000000000000000000000000000000000dEaD"})).await;
+        let numeric = numeric.error.expect("no Etherscan key configured");
+        let string = string.error.expect("no Etherscan key configured");
+        assert_eq!(numeric.message, "ETHERSCAN_API_KEY is not configured");
+        assert_eq!(numeric.message, string.message);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_with_same_idempotency_key_broadcasts_once() {
+        use mockito::{mock, Matcher};
+
+        let path = "/transfer-idempotent";
+        let rpc = |method: &str, result: Value| {
+            mock("POST", path)
+                .match_body(Matcher::PartialJson(json!({"method": method})))
+                .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string())
+        };
+        let _count = rpc("eth_getTransactionCount", json!("0x0")).create();
+        let _chain = rpc("eth_chainId", json!("0x7a69")).create();
+        let _balance = rpc("eth_getBalance", json!("0xde0b6b3a7640000")).create();
+        let send = rpc("eth_sendRawTransaction", json!("0xsent")).expect(1).create();
+
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("31337".into(), format!("{}{}", mockito::server_url(), path));
+        let state = test_state(config, dir.path());
+
+        let args = json!({
+            "private_key": "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+            "chain_id": "31337",
+            "to_address": "0x000000000000000000000000000000000000dEaD",
+            "amount_wei": "1",
+            "gas_limit": "21000",
+            "gas_price": "1000000000",
+            "idempotency_key": "retry-1"
+        });
+        let first = call_tool(&state, "transfer_evm", args.clone()).await.result.expect("first send");
+        let second = call_tool(&state, "transfer_evm", args.clone()).await.result.expect("replayed send");
+        // The same key for another transfer is refused, not replayed
+        let mut other = args;
+        other["amount_wei"] = json!("2");
+        let reused = call_tool(&state, "transfer_evm", other).await.error.expect("key reuse is refused");
+
+        send.assert();
+        assert_eq!(first["tx_hash"], "0xsent");
+        assert_eq!(second["tx_hash"], "0xsent");
+        assert_eq!(second["replayed"], true);
+        assert_eq!(reused.code, error_codes::INVALID_PARAMS);
+        assert!(reused.message.contains("different arguments"), "{}", reused.message);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_rejects_gas_price_in_both_units() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let resp = call_tool(
+            &state,
+            "transfer_evm",
+            json!({
+                "private_key": TEST_KEY,
+                "chain_id": "1",
+                "to_address": "0x000000000000000000000000000000000000dEaD",
+                "amount_wei": "1",
+                "gas_price": "20000000000",
+                "gas_price_gwei": "20"
+            }),
+        )
+        .await;
+        let err = resp.error.expect("conflicting gas prices should be rejected");
+        assert_eq!(err.code, error_codes::INVALID_PARAMS);
+        assert!(err.message.contains("not both"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn test_nft_transfer_accepts_gas_price_gwei() {
+        let args = json!({
+            "private_key": TEST_KEY,
+            "chain_id": "1",
+            "contract_address": "0x00000000000000000000000000000000000000e7",
+            "to_address": "0x000000000000000000000000000000000000dEaD",
+            "token_id": "1",
+            "gas_price_gwei": "20"
+        });
+        let schema = tool_schema("transfer_nft_evm", "inputSchema").unwrap();
+        assert!(validation::validate_args(&schema, &mut args.clone()).is_empty());
+        let mut safe = args.clone();
+        safe["safe_send"] = json!(true);
+        assert!(validation::validate_args(&schema, &mut safe).is_empty());
+
+        // The fee fields reach the send options: mixing units is refused there, not by the schema
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let mut mixed = args;
+        mixed["priority_fee_gwei"] = json!("2");
+        let err = call_tool(&state, "transfer_nft_evm", mixed).await.error.expect("mixed fees should be rejected");
+        assert_eq!(err.code, error_codes::INVALID_PARAMS);
+        assert!(err.message.contains("not both"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn test_erc20_amount_scales_decimal_amount_by_token_decimals() {
+        use mockito::{mock, Matcher};
+
+        let path = "/erc20-amount-decimals";
+        let _decimals = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call", "params": [{"data": "0x313ce567"}]})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{:064x}", 6)}).to_string())
+            .create();
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("31337".into(), format!("{}{}", mockito::server_url(), path));
+        let state = test_state(config, dir.path());
+        let token = "0x00000000000000000000000000000000000000e6";
+        let req_id = json!(1);
+
+        let amount = erc20_amount(&state, "31337", token, &json!({"amount_decimal": "1.5"}), &req_id).await.unwrap();
+        assert_eq!(amount, "1500000");
+        let amount = erc20_amount(&state, "31337", token, &json!({"amount": "42"}), &req_id).await.unwrap();
+        assert_eq!(amount, "42");
+
+        let too_precise = erc20_amount(&state, "31337", token, &json!({"amount_decimal": "0.0000001"}), &req_id).await;
+        assert_eq!(too_precise.unwrap_err().error.unwrap().code, error_codes::INVALID_PARAMS);
+        let both = erc20_amount(&state, "31337", token, &json!({"amount": "1", "amount_decimal": "1"}), &req_id).await;
+        let err = both.unwrap_err().error.unwrap();
+        assert_eq!(err.code, error_codes::INVALID_PARAMS);
+        assert!(err.message.contains("not both"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_rejects_gas_price_with_eip1559_fees() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let resp = call_tool(
+            &state,
+            "transfer_evm",
+            json!({
+                "private_key": TEST_KEY,
+                "chain_id": "1",
+                "to_address": "0x000000000000000000000000000000000000dEaD",
+                "amount_wei": "1",
+                "gas_price_gwei": "20",
+                "priority_fee_gwei": "2"
+            }),
+        )
+        .await;
+        let err = resp.error.expect("a gas price and EIP-1559 fees should be rejected together");
+        assert_eq!(err.code, error_codes::INVALID_PARAMS);
+        assert!(err.message.contains("not both"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn test_reverted_transfer_is_error_result_when_enabled() {
+        use mockito::{mock, Matcher};
+
+        let path = "/transfer-reverted";
+        let rpc = |method: &str, body: Value| {
+            mock("POST", path)
+                .match_body(Matcher::PartialJson(json!({"method": method})))
+                .with_body(body.to_string())
+                .create()
+        };
+        let _count = rpc("eth_getTransactionCount", json!({"jsonrpc": "2.0", "id": 1, "result": "0x0"}));
+        let _chain = rpc("eth_chainId", json!({"jsonrpc": "2.0", "id": 1, "result": "0x7a69"}));
+        let _balance = rpc("eth_getBalance", json!({"jsonrpc": "2.0", "id": 1, "result": "0xde0b6b3a7640000"}));
+        let _send = rpc(
+            "eth_sendRawTransaction",
+            json!({"jsonrpc": "2.0", "id": 1, "error": {"code": 3, "message": "execution reverted"}}),
+        );
+
+        let dir = tempdir().unwrap();
+        let mut config = Config { tool_errors_as_results: true, ..Config::default() };
+        config.chain_rpc_urls.insert("31337".into(), format!("{}{}", mockito::server_url(), path));
+        let state = test_state(config, dir.path());
+
+        let args = json!({
+            "private_key": "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+            "chain_id": "31337",
+            "to_address": "0x000000000000000000000000000000000000dEaD",
+            "amount_wei": "1",
+            "gas_limit": "21000",
+            "gas_price": "1000000000"
+        });
+        let resp = call_tool(&state, "transfer_evm", args).await;
+        assert!(resp.error.is_none(), "{:?}", resp.error);
+        let result = resp.result.expect("result");
+        assert_eq!(result["isError"], true);
+        assert!(result["content"][0]["text"].as_str().unwrap().contains("reverted"), "{}", result);
+        assert_eq!(result["error"]["data"]["category"], "reverted");
+        assert!(result["error"]["data"]["request_id"].is_string());
+
+        // Bad arguments are still a JSON-RPC error
+        let resp = call_tool(&state, "transfer_evm", json!({"chain_id": "31337"})).await;
+        assert_eq!(resp.error.expect("error").code, error_codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_send_on_chain_without_numeric_id_is_refused() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        // Nothing is listening here; the send must be refused before any RPC call
+        config.chain_rpc_urls.insert("devnet".into(), "http://127.0.0.1:9".into());
+        let state = test_state(config, dir.path());
+
+        let args = json!({
+            "private_key": TEST_KEY,
+            "chain_id": "devnet",
+            "to_address": "0x000000000000000000000000000000000000dEaD",
+            "amount_wei": "1"
+        });
+        let resp = call_tool(&state, "transfer_evm", args).await;
+        let error = resp.error.expect("unknown numeric chain ID should be refused");
+        assert_eq!(error.code, error_codes::INVALID_PARAMS);
+        assert!(error.message.contains("numeric chain ID of 'devnet'"), "{}", error.message);
+        assert_eq!(chains::numeric_chain_id("Sepolia"), Some(11155111));
+    }
+
+    #[tokio::test]
+    async fn test_write_contract_approves_before_call_when_allowance_is_short() {
+        use mockito::{mock, Matcher};
+
+        let path = "/write-ensure-allowance";
+        let rpc = |method: &str, result: Value| {
+            mock("POST", path)
+                .match_body(Matcher::PartialJson(json!({"method": method})))
+                .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string())
+                .create()
+        };
+        let approve_hash = format!("0x{}", "aa".repeat(32));
+        let call_hash = format!("0x{}", "bb".repeat(32));
+        let _allowance = rpc("eth_call", json!(format!("0x{:064x}", 0)));
+        let _count = rpc("eth_getTransactionCount", json!("0x0"));
+        let _chain = rpc("eth_chainId", json!("0x7a69"));
+        let _balance = rpc("eth_getBalance", json!("0xde0b6b3a7640000"));
+        let _gas_price = rpc("eth_gasPrice", json!("0x3b9aca00"));
+        let _estimate = rpc("eth_estimateGas", json!("0xea60"));
+        let _receipt = rpc("eth_getTransactionReceipt", json!({"status": "0x1", "blockNumber": "0x10", "transactionHash": approve_hash}));
+        let _head = rpc("eth_blockNumber", json!("0x10"));
+        // The signed approve carries the approve(address,uint256) selector; it must go out once, first
+        let approve = mock("POST", path)
+            .match_body(Matcher::AllOf(vec![
+                Matcher::PartialJson(json!({"method": "eth_sendRawTransaction"})),
+                Matcher::Regex("095ea7b3".into()),
+            ]))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": approve_hash}).to_string())
+            .expect(1)
+            .create();
+        let call = rpc("eth_sendRawTransaction", json!(call_hash));
+
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("31337".into(), format!("{}{}", mockito::server_url(), path));
+        let state = test_state(config, dir.path());
+
+        let vault = "0x00000000000000000000000000000000000000c1";
+        let token = "0x00000000000000000000000000000000000000e2";
+        let abi = r#"[{"type":"function","name":"deposit","stateMutability":"nonpayable","inputs":[{"name":"amount","type":"uint256"}],"outputs":[]}]"#;
+        let resp = call_tool(
+            &state,
+            "write_contract",
+            json!({
+                "private_key": TEST_KEY,
+                "chain_id": "31337",
+                "contractAddress": vault,
+                "abi": abi,
+                "functionName": "deposit",
+                "args": ["1000"],
+                "gas_limit": "100000",
+                "gas_price": "1000000000",
+                "ensure_allowance": {"token": token, "amount": "1000", "approve": "max"}
+            }),
+        )
+        .await;
+        let result = resp.result.unwrap_or_else(|| panic!("write_contract failed: {:?}", resp.error));
+        approve.assert();
+        call.assert();
+        assert_eq!(result["tx_hash"], call_hash);
+        assert_eq!(result["allowance"]["approved"], true);
+        assert_eq!(result["allowance"]["allowance"], "0");
+        assert_eq!(result["allowance"]["approve_tx_hash"], approve_hash);
+        assert_eq!(result["allowance"]["approve_amount"], U256::MAX.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_write_contract_reports_unmined_approve_before_tool_timeout() {
+        use mockito::{mock, Matcher};
+
+        let path = "/write-approve-unmined";
+        let rpc = |method: &str, result: Value| {
+            mock("POST", path)
+                .match_body(Matcher::PartialJson(json!({"method": method})))
+                .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string())
+                .create()
+        };
+        let _mocks = [
+            rpc("eth_call", json!(format!("0x{:064x}", 0))),
+            rpc("eth_getTransactionCount", json!("0x0")),
+            rpc("eth_chainId", json!("0x7a69")),
+            rpc("eth_getBalance", json!("0xde0b6b3a7640000")),
+            rpc("eth_estimateGas", json!("0xea60")),
+            rpc("eth_getTransactionReceipt", Value::Null),
+            rpc("eth_blockNumber", json!("0x10")),
+        ];
+        // Only the approve may go out
+        let send = mock("POST", path)
+            .match_body(Matcher::PartialJson(json!({"method": "eth_sendRawTransaction"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{}", "aa".repeat(32))}).to_string())
+            .expect(1)
+            .create();
+
+        let dir = tempdir().unwrap();
+        let mut config = Config {
+            tool_timeout: Some(Duration::from_millis(800)),
+            receipt_poll_interval: Some(Duration::from_millis(50)),
+            ..Config::default()
+        };
+        config.chain_rpc_urls.insert("31337".into(), format!("{}{}", mockito::server_url(), path));
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(
+            &state,
+            "write_contract",
+            json!({
+                "private_key": TEST_KEY,
+                "chain_id": "31337",
+                "contractAddress": "0x00000000000000000000000000000000000000c1",
+                "abi": r#"[{"type":"function","name":"deposit","stateMutability":"nonpayable","inputs":[{"name":"amount","type":"uint256"}],"outputs":[]}]"#,
+                "functionName": "deposit",
+                "args": ["1000"],
+                "gas_limit": "100000",
+                "gas_price": "1000000000",
+                "ensure_allowance": {"token": "0x00000000000000000000000000000000000000e2", "amount": "1000"}
+            }),
+        )
+        .await;
+        let err = resp.error.expect("unmined approve should fail the call");
+        assert!(err.message.contains("was not mined within"), "{}", err.message);
+        send.assert();
+    }
+
+    #[tokio::test]
+    async fn test_hung_rpc_times_out_tool_call() {
+        // Accepts connections but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
             }
-        },
-        {
-            "name": "get_transaction_history",
-            "description": "Get transaction history for any EVM address via Etherscan API.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "address": {"type": "string", "description": "The EVM address to get transaction history for."},
-                    "chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)."}
-                },
-                "required": ["address"]
+        });
+
+        let dir = tempdir().unwrap();
+        let mut config = Config { tool_timeout: Some(Duration::from_millis(300)), ..Config::default() };
+        config.chain_rpc_urls.insert("137".into(), format!("http://{}", addr));
+        let state = test_state(config, dir.path());
+
+        let started = Instant::now();
+        let resp = call_tool(&state, "get_balance", json!({"chain_id": "137", "address": "0x000000000000000000000000000000000000dEaD"})).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let err = resp.error.expect("timeout error");
+        assert!(err.message.contains("timed out after 0.3s"), "{}", err.message);
+        assert_eq!(err.data.expect("data")["category"], "rpc_unreachable");
+    }
+
+    // A JSON-RPC node on a local port answering `results` by method, holding back the
+    // answer to `slow_method` for `delay`; the counter tracks how often `slow_method` was called
+    async fn slow_node(results: Value, slow_method: &'static str, delay: Duration) -> (String, Arc<AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let results = results.clone();
+                let counter = counter.clone();
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    loop {
+                        let Ok(n) = socket.read(&mut chunk).await else { return };
+                        if n == 0 {
+                            return;
+                        }
+                        buf.extend_from_slice(&chunk[..n]);
+                        let text = String::from_utf8_lossy(&buf).to_string();
+                        let Some((head, body)) = text.split_once("\r\n\r\n") else { continue };
+                        let len = head
+                            .lines()
+                            .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                            .unwrap_or(0);
+                        if body.len() < len {
+                            continue;
+                        }
+                        let request: Value = serde_json::from_str(&body[..len]).unwrap();
+                        let method = request["method"].as_str().unwrap_or_default();
+                        if method == slow_method {
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(delay).await;
+                        }
+                        let reply = json!({"jsonrpc": "2.0", "id": request["id"], "result": results[method]}).to_string();
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                            reply.len(),
+                            reply
+                        );
+                        let _ = socket.write_all(response.as_bytes()).await;
+                        buf.clear();
+                    }
+                });
             }
-        },
-        // --- Added: Token services (ERC20) ---
-        {
-            "name": "get_token_info",
-            "description": "Get ERC20 token metadata.",
-            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["tokenAddress"], "additionalProperties": false}
-        },
-        {
-            "name": "get_token_balance",
-            "description": "Check ERC20 token balance.",
-            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "ownerAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["tokenAddress", "ownerAddress"], "additionalProperties": false}
-        },
-        {
-            "name": "get_token_allowance",
-            "description": "Check ERC20 allowance between owner and spender.",
-            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "ownerAddress": {"type": "string"}, "spenderAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["tokenAddress", "ownerAddress", "spenderAddress"], "additionalProperties": false}
-        },
-        {
-            "name": "transfer_token",
-            "description": "Transfer ERC20 tokens.",
-            "inputSchema": {"type": "object", "properties": {"private_key": {"type": "string"}, "tokenAddress": {"type": "string"}, "toAddress": {"type": "string"}, "amount": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "gas_limit": {"type": "string"}, "gas_price": {"type": "string"}}, "required": ["private_key", "tokenAddress", "toAddress", "amount"]}
-        },
-        {
-            "name": "approve_token_spending",
-            "description": "Approve ERC20 allowances.",
-            "inputSchema": {"type": "object", "properties": {"private_key": {"type": "string"}, "tokenAddress": {"type": "string"}, "spenderAddress": {"type": "string"}, "amount": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "gas_limit": {"type": "string"}, "gas_price": {"type": "string"}}, "required": ["private_key", "tokenAddress", "spenderAddress", "amount"]}
-        },
-        // --- Added: ERC721 ---
-        {
-            "name": "get_nft_info",
-            "description": "Get ERC721 token metadata (tokenURI).",
-            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "tokenId": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["tokenAddress", "tokenId"]}
-        },
-        {
-            "name": "check_nft_ownership",
-            "description": "Verify ERC721 NFT ownership (ownerOf).",
-            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "tokenId": {"type": "string"}, "ownerAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["tokenAddress", "tokenId", "ownerAddress"]}
-        },
-        {
-            "name": "get_nft_balance",
-            "description": "Count ERC721 NFTs owned (balanceOf).",
-            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "ownerAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["tokenAddress", "ownerAddress"]}
-        },
-        // --- Added: ERC1155 ---
-        {
-            "name": "get_erc1155_token_uri",
-            "description": "Get ERC1155 token URI.",
-            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "tokenId": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["tokenAddress", "tokenId"]}
-        },
-        {
-            "name": "get_erc1155_balance",
-            "description": "Check ERC1155 token balance.",
-            "inputSchema": {"type": "object", "properties": {"tokenAddress": {"type": "string"}, "tokenId": {"type": "string"}, "ownerAddress": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["tokenAddress", "tokenId", "ownerAddress"]}
-        },
-        {
-            "name": "transfer_erc1155",
-            "description": "Transfer ERC1155 tokens (safeTransferFrom).",
-            "inputSchema": {"type": "object", "properties": {"private_key": {"type": "string"}, "tokenAddress": {"type": "string"}, "fromAddress": {"type": "string"}, "toAddress": {"type": "string"}, "tokenId": {"type": "string"}, "amount": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "gas_limit": {"type": "string"}, "gas_price": {"type": "string"}}, "required": ["private_key", "tokenAddress", "fromAddress", "toAddress", "tokenId", "amount"]}
-        },
-        // --- Added: contract utils ---
-        {
-            "name": "is_contract",
-            "description": "Check if an address is a verified contract on Etherscan.",
-            "inputSchema": {"type": "object", "properties": {"address": {"type": "string"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["address"]}
-        },
-        {
-            "name": "read_contract",
-            "description": "Read a contract function via ABI (eth_call).",
-            "inputSchema": {"type": "object", "properties": {"contractAddress": {"type": "string"}, "abi": {"type": "string"}, "functionName": {"type": "string"}, "args": {"type": "array"}, "chain_id": {"type": "string"}, "network": {"type": "string"}}, "required": ["contractAddress", "abi", "functionName"]}
-        },
-        {
-            "name": "write_contract",
-            "description": "Write to a contract via ABI (signed tx).",
-            "inputSchema": {"type": "object", "properties": {"private_key": {"type": "string"}, "contractAddress": {"type": "string"}, "abi": {"type": "string"}, "functionName": {"type": "string"}, "args": {"type": "array"}, "chain_id": {"type": "string"}, "network": {"type": "string"}, "gas_limit": {"type": "string"}, "gas_price": {"type": "string"}}, "required": ["private_key", "contractAddress", "abi", "functionName"]}
-        },
-        {
-            "name": "get_block_number",
-            "description": "Get the current block number for a blockchain network.",
-            "inputSchema": {"type": "object", "properties": {"chain_id": {"type": "string", "description": "Chain ID (1 for Ethereum, 11155111 for Sepolia)"}, "network": {"type": "string", "description": "Alternative to chain_id"}}, "additionalProperties": false}
-        },
-    ]);
-    Response::success(req.id.clone(), json!({ "tools": tools }))
+        });
+        (url, hits)
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_broadcast_finishes_and_reports_outcome_unknown() {
+        let (url, _) = slow_node(
+            json!({
+                "eth_chainId": "0x7a69",
+                "eth_getTransactionCount": "0x0",
+                "eth_getBlockByNumber": {"number": "0x1"},
+                "eth_sendRawTransaction": "0xslow"
+            }),
+            "eth_sendRawTransaction",
+            Duration::from_millis(600),
+        )
+        .await;
+        let dir = tempdir().unwrap();
+        let mut config = Config { tool_timeout: Some(Duration::from_millis(200)), ..Config::default() };
+        config.chain_rpc_urls.insert("31337".into(), url);
+        let state = test_state(config, dir.path());
+
+        let args = json!({
+            "private_key": TEST_KEY,
+            "chain_id": "31337",
+            "to_address": "0x000000000000000000000000000000000000dEaD",
+            "amount_wei": "1",
+            "gas_limit": "21000",
+            "gas_price": "1000000000",
+            "skip_balance_check": true,
+            "idempotency_key": "slow-1"
+        });
+        let resp = call_tool(&state, "transfer_evm", args.clone()).await;
+        let err = resp.error.expect("timeout error");
+        assert_eq!(err.data.expect("data")["category"], "outcome_unknown");
+        assert!(err.message.contains("may already have been broadcast"), "{}", err.message);
+
+        // The send was not cancelled: it completes and the retry gets its result
+        tokio::time::sleep(Duration::from_millis(700)).await;
+        let result = call_tool(&state, "transfer_evm", args).await.result.expect("replayed result");
+        assert_eq!(result["replayed"], true);
+        assert_eq!(result["tx_hash"], "0xslow");
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_token_transfer_retry_broadcasts_once() {
+        let (url, broadcasts) = slow_node(
+            json!({
+                "eth_chainId": "0x7a69",
+                "eth_getTransactionCount": "0x0",
+                "eth_getBlockByNumber": {"number": "0x1"},
+                "eth_sendRawTransaction": "0xslowtoken"
+            }),
+            "eth_sendRawTransaction",
+            Duration::from_millis(600),
+        )
+        .await;
+        let dir = tempdir().unwrap();
+        let mut config = Config { tool_timeout: Some(Duration::from_millis(200)), ..Config::default() };
+        config.chain_rpc_urls.insert("31337".into(), url);
+        let state = test_state(config, dir.path());
+
+        let args = json!({
+            "private_key": TEST_KEY,
+            "chain_id": "31337",
+            "tokenAddress": "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+            "toAddress": "0x000000000000000000000000000000000000dEaD",
+            "amount": "1000",
+            "gas_limit": "60000",
+            "gas_price": "1000000000",
+            "skip_balance_check": true,
+            "idempotency_key": "slow-token-1"
+        });
+        let err = call_tool(&state, "transfer_token", args.clone()).await.error.expect("timeout error");
+        assert_eq!(err.data.expect("data")["category"], "outcome_unknown");
+
+        // Retried as the timeout message advises: the first send was not cancelled, and the
+        // retry gets its result instead of broadcasting again
+        tokio::time::sleep(Duration::from_millis(700)).await;
+        let result = call_tool(&state, "transfer_token", args).await.result.expect("replayed result");
+        assert_eq!(result["replayed"], true);
+        assert_eq!(result["tx_hash"], "0xslowtoken");
+        assert_eq!(broadcasts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_selector_uses_configured_directory() {
+        use mockito::{mock, Matcher};
+
+        let _m = mock("GET", "/handler-sigdb/api/v1/signatures/")
+            .match_query(Matcher::UrlEncoded("hex_signature".into(), "0x095ea7b3".into()))
+            .with_body(json!({"results": [{"id": 149, "text_signature": "approve(address,uint256)"}]}).to_string())
+            .create();
+        let dir = tempdir().unwrap();
+        let config = Config {
+            signature_db_url: Some(format!("{}/handler-sigdb", mockito::server_url())),
+            ..Config::default()
+        };
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(&state, "lookup_selector", json!({"selector": "0x095ea7b3"})).await;
+        let result = resp.result.expect("lookup result");
+        assert_eq!(result["kind"], "function");
+        assert_eq!(result["candidates"], json!(["approve(address,uint256)"]));
+
+        let bad = call_tool(&state, "lookup_selector", json!({"selector": "0x1234"})).await;
+        assert_eq!(bad.error.expect("invalid selector").code, error_codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_tool_errors_carry_category() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        // Nothing listens on port 1
+        config.chain_rpc_urls.insert("137".into(), "http://127.0.0.1:1".into());
+        let state = test_state(config, dir.path());
+        let category = |resp: Response| resp.error.expect("error").data.expect("data")["category"].clone();
+
+        let missing = call_tool(&state, "get_balance", json!({"chain_id": "137"})).await;
+        assert_eq!(category(missing), "invalid_arg");
+        let unconfigured = call_tool(&state, "get_balance", json!({"chain_id": "8453", "address": "0x000000000000000000000000000000000000dEaD"})).await;
+        assert_eq!(category(unconfigured), "not_configured");
+        let unreachable = call_tool(&state, "get_balance", json!({"chain_id": "137", "address": "0x000000000000000000000000000000000000dEaD"})).await;
+        assert_eq!(category(unreachable), "rpc_unreachable");
+        let unknown = call_tool(&state, "no_such_tool", json!({})).await;
+        assert_eq!(category(unknown), "method_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_prompts_and_resources_are_advertised() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let call = |method: &str, params: Value| Request {
+            jsonrpc: "2.0".to_string(),
+            id: json!(1),
+            method: method.to_string(),
+            params: Some(params),
+        };
+
+        let init = handle_mcp_request(call("initialize", json!({})), state.clone()).await.unwrap();
+        let capabilities = &init.result.expect("initialize")["capabilities"];
+        assert!(capabilities["prompts"].is_object());
+        assert!(capabilities["resources"].is_object());
+
+        let list = handle_mcp_request(call("prompts/list", json!({})), state.clone()).await.unwrap();
+        let prompts = list.result.expect("prompts")["prompts"].as_array().cloned().unwrap();
+        let balance = prompts.iter().find(|p| p["name"] == "check_wallet_balance").expect("prompt");
+        assert_eq!(balance["arguments"][0]["name"], "address");
+        assert_eq!(balance["arguments"][0]["required"], true);
+
+        let params = json!({"name": "check_wallet_balance", "arguments": {"address": "0xabc", "chain_id": "137"}});
+        let got = handle_mcp_request(call("prompts/get", params), state.clone()).await.unwrap();
+        let text = got.result.expect("prompt")["messages"][0]["content"]["text"].as_str().unwrap().to_string();
+        assert!(text.contains("0xabc on chain 137"), "{}", text);
+
+        let missing = json!({"name": "check_wallet_balance", "arguments": {"address": "0xabc"}});
+        let resp = handle_mcp_request(call("prompts/get", missing), state.clone()).await.unwrap();
+        assert_eq!(resp.error.expect("error").code, error_codes::INVALID_PARAMS);
+
+        let resources = handle_mcp_request(call("resources/list", json!({})), state).await.unwrap();
+        assert_eq!(resources.result.expect("resources")["resources"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_batch_answers_calls_and_skips_notifications() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "tools/list"},
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "id": "b", "method": "initialize", "params": {}},
+            {"jsonrpc": "2.0", "id": 3}
+        ]);
+
+        let responses = handle_mcp_message(batch, state.clone()).await.expect("responses");
+        let responses = responses.as_array().expect("array");
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["id"], 1);
+        assert!(responses[0]["result"]["tools"].is_array());
+        assert_eq!(responses[1]["id"], "b");
+        assert!(responses[1]["result"].is_object());
+        // Missing method: answered as an invalid request
+        assert_eq!(responses[2]["error"]["code"], error_codes::INVALID_REQUEST);
+
+        let notifications = json!([{"jsonrpc": "2.0", "method": "notifications/initialized"}]);
+        assert!(handle_mcp_message(notifications, state.clone()).await.is_none());
+
+        let empty = handle_mcp_message(json!([]), state).await.expect("error");
+        assert_eq!(empty["error"]["code"], error_codes::INVALID_REQUEST);
+        assert!(empty["id"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_request_span_and_error_carry_request_id() {
+        use std::sync::Mutex as StdMutex;
+        use tracing::field::{Field, Visit};
+        use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+        #[derive(Default)]
+        struct Fields(Vec<(String, String)>);
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0.push((field.name().to_string(), format!("{:?}", value)));
+            }
+        }
+        // Records the fields of every `mcp_request` span
+        struct SpanCapture(Arc<StdMutex<Vec<Fields>>>);
+        impl<S: tracing::Subscriber> Layer<S> for SpanCapture {
+            fn on_new_span(&self, attrs: &tracing::span::Attributes<'_>, _: &tracing::span::Id, _: Context<'_, S>) {
+                if attrs.metadata().name() == "mcp_request" {
+                    let mut fields = Fields::default();
+                    attrs.record(&mut fields);
+                    self.0.lock().unwrap().push(fields);
+                }
+            }
+        }
+        let spans = Arc::new(StdMutex::new(Vec::new()));
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(SpanCapture(spans.clone())));
+
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let resp = call_tool(&state, "get_balance", json!({})).await;
+        let request_id = resp.error.expect("error").data.expect("data")["request_id"]
+            .as_str()
+            .expect("request_id")
+            .to_string();
+
+        let spans = spans.lock().unwrap();
+        let span = spans
+            .iter()
+            .find(|f| f.0.contains(&("request_id".to_string(), request_id.clone())))
+            .expect("span with the response's request_id");
+        assert!(span.0.contains(&("method".to_string(), "tools/call".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_reports_native_currency_of_chain() {
+        use mockito::{mock, Matcher};
+
+        let _m = mock("POST", "/balance-polygon")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_getBalance"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": "0x14d1120d7b160000"}).to_string())
+            .create();
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("137".into(), format!("{}/balance-polygon", mockito::server_url()));
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(&state, "get_balance", json!({"chain_id": "polygon", "address": "0x000000000000000000000000000000000000dEaD"})).await;
+        let balance = &resp.result.expect("balance")["balance"];
+        assert_eq!(balance["amount"], "1500000000000000000");
+        assert_eq!(balance["symbol"], "MATIC");
+        assert_eq!(balance["formatted"], "1.5 MATIC");
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_at_block_reads_from_node() {
+        use mockito::{mock, Matcher};
+
+        let address = "0x000000000000000000000000000000000000dEaD";
+        let _m = mock("POST", "/balance-at-block")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_getBalance", "params": [address, "0x112a880"]})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": "0xde0b6b3a7640000"}).to_string())
+            .create();
+        let dir = tempdir().unwrap();
+        // Mainnet normally goes through Etherscan, which isn't configured here
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("1".into(), format!("{}/balance-at-block", mockito::server_url()));
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(&state, "get_balance", json!({"chain_id": "1", "address": address, "block": 18000000})).await;
+        assert_eq!(resp.result.expect("balance")["balance"]["formatted"], "1 ETH");
+
+        let resp = call_tool(&state, "get_balance", json!({"chain_id": "1", "address": address, "block": "yesterday"})).await;
+        assert_eq!(resp.error.expect("invalid block").code, error_codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_read_contract_with_function_signature_only() {
+        use mockito::{mock, Matcher};
+
+        let _m = mock("POST", "/read-contract-signature")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{:064x}", 18)}).to_string())
+            .create();
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("1".into(), format!("{}/read-contract-signature", mockito::server_url()));
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(
+            &state,
+            "read_contract",
+            json!({"contractAddress": "0x000000000000000000000000000000000000dEaD", "function_signature": "decimals() returns (uint8)"}),
+        )
+        .await;
+        let result = resp.result.expect("signature read should succeed");
+        assert_eq!(result["decoded"], json!(["18"]));
+
+        let resp = call_tool(&state, "read_contract", json!({"contractAddress": "0x000000000000000000000000000000000000dEaD"})).await;
+        assert_eq!(resp.error.unwrap().code, error_codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_read_contract_fetches_verified_abi_once() {
+        use mockito::{mock, Matcher};
+
+        let token = "0x000000000000000000000000000000000000dEaD";
+        let abi = json!([{"type": "function", "name": "decimals", "inputs": [], "outputs": [{"name": "", "type": "uint8"}], "stateMutability": "view"}]);
+        let source = mock("GET", "/abi-cache-etherscan")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("action".into(), "getsourcecode".into()),
+                Matcher::UrlEncoded("address".into(), token.into()),
+            ]))
+            .with_body(json!({"status": "1", "message": "OK", "result": [{"ContractName": "Token", "SourceCode": "contract Token {}", "ABI": abi.to_string()}]}).to_string())
+            .expect(1)
+            .create();
+        let unverified = mock("GET", "/abi-cache-etherscan")
+            .match_query(Matcher::UrlEncoded("address".into(), "0x000000000000000000000000000000000000bEEF".into()))
+            .with_body(json!({"status": "1", "message": "OK", "result": [{"ContractName": "", "SourceCode": "", "ABI": "Contract source code not verified"}]}).to_string())
+            .expect(2)
+            .create();
+        let _call = mock("POST", "/abi-cache-rpc")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_call"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{:064x}", 6)}).to_string())
+            .expect(2)
+            .create();
+        let dir = tempdir().unwrap();
+        let mut state = etherscan_state("/abi-cache-etherscan", dir.path());
+        state.config.chain_rpc_urls.insert("1".into(), format!("{}/abi-cache-rpc", mockito::server_url()));
+
+        for _ in 0..2 {
+            let resp = call_tool(&state, "read_contract", json!({"contractAddress": token, "functionName": "decimals"})).await;
+            let result = resp.result.unwrap_or_else(|| panic!("read failed: {:?}", resp.error));
+            assert_eq!(result["decoded"], json!(["6"]));
+        }
+        source.assert();
+
+        // Unverified contracts are not cached and still need an explicit ABI
+        for _ in 0..2 {
+            let resp = call_tool(
+                &state,
+                "read_contract",
+                json!({"contractAddress": "0x000000000000000000000000000000000000bEEF", "functionName": "decimals"}),
+            )
+            .await;
+            let err = resp.error.expect("unverified contract needs an ABI");
+            assert_eq!(err.code, error_codes::INVALID_PARAMS);
+            assert!(err.message.contains("pass 'abi' explicitly"), "{}", err.message);
+        }
+        unverified.assert();
+    }
+
+    #[tokio::test]
+    async fn test_create_wallet_word_count() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let resp = call_tool(&state, "create_wallet", json!({"word_count": 24})).await;
+        let result = resp.result.expect("create_wallet should succeed");
+        assert_eq!(result["mnemonic"].as_str().unwrap().split_whitespace().count(), 24);
+
+        let resp = call_tool(&state, "create_wallet", json!({})).await;
+        assert_eq!(resp.result.unwrap()["mnemonic"].as_str().unwrap().split_whitespace().count(), 12);
+
+        let resp = call_tool(&state, "create_wallet", json!({"word_count": 13})).await;
+        assert_eq!(resp.error.unwrap().code, error_codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_suppress_key_output_hides_keys_and_stores_wallet() {
+        let dir = tempdir().unwrap();
+        let config = Config {
+            suppress_key_output: true,
+            ..Config::default()
+        };
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(&state, "create_wallet", json!({})).await;
+        assert_eq!(resp.error.unwrap().code, error_codes::INVALID_PARAMS);
+
+        let resp = call_tool(
+            &state,
+            "create_wallet",
+            json!({"wallet_name": "alice", "master_password": TEST_PASSWORD, "reveal": true}),
+        )
+        .await;
+        let result = resp.result.expect("create_wallet should succeed");
+        assert!(result.get("private_key").is_none());
+        assert!(result.get("mnemonic").is_none());
+        assert_eq!(result["wallet_name"], "alice");
+        let address = result["address"].as_str().unwrap().to_string();
+        let text = result["content"][0]["text"].as_str().unwrap();
+        assert!(!text.contains("Private Key:") && !text.contains("Mnemonic:"));
+        let stored = state.wallet_storage.lock().await.get_wallet("alice").map(|w| w.public_address.clone());
+        assert_eq!(stored.as_deref(), Some(address.as_str()));
+
+        let resp = call_tool(&state, "import_wallet", json!({"mnemonic_or_private_key": TEST_KEY})).await;
+        let result = resp.result.expect("import_wallet should succeed");
+        assert!(result.get("private_key").is_none());
+        assert!(result.get("mnemonic").is_none());
+        assert!(!result.to_string().contains(&TEST_KEY[2..]));
+
+        let resp = call_tool(
+            &state,
+            "create_and_register_wallet",
+            json!({"wallet_name": "bob", "master_password": TEST_PASSWORD}),
+        )
+        .await;
+        let result = resp.result.expect("create_and_register_wallet should succeed");
+        assert!(result.get("mnemonic").is_none());
+        assert!(!result["content"][0]["text"].as_str().unwrap().contains("Mnemonic"));
+        assert!(state.wallet_storage.lock().await.get_wallet("bob").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_import_wallet_reveal_false_omits_keys() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let mnemonic = "test test test test test test test test test test test junk";
+        let resp = call_tool(&state, "import_wallet", json!({"mnemonic_or_private_key": mnemonic, "reveal": false})).await;
+        let result = resp.result.expect("import_wallet should succeed");
+        assert!(result.get("private_key").is_none());
+        assert!(result.get("mnemonic").is_none());
+        assert!(!result.to_string().contains("junk"));
+
+        let resp = call_tool(&state, "import_wallet", json!({"mnemonic_or_private_key": mnemonic, "wallet_name": "bob"})).await;
+        assert_eq!(resp.error.unwrap().code, error_codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_import_wallet_with_passphrase_derives_different_address() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let mnemonic = "test test test test test test test test test test test junk";
+        let plain = call_tool(&state, "import_wallet", json!({"mnemonic_or_private_key": mnemonic})).await;
+        let protected = call_tool(&state, "import_wallet", json!({"mnemonic_or_private_key": mnemonic, "passphrase": "hunter2"})).await;
+        assert_ne!(plain.result.unwrap()["address"], protected.result.unwrap()["address"]);
+    }
+
+    #[tokio::test]
+    async fn test_derive_accounts_lists_addresses_without_keys() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let mnemonic = "test test test test test test test test test test test junk";
+        let resp = call_tool(&state, "derive_accounts", json!({"mnemonic": mnemonic, "count": 2, "start_index": 1})).await;
+        let result = resp.result.expect("derive_accounts should succeed");
+        let accounts = result["accounts"].as_array().unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0]["index"], 1);
+        assert_eq!(accounts[0]["address"], "0x70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        assert!(accounts[0].get("private_key").is_none());
+
+        let resp = call_tool(&state, "derive_accounts", json!({"mnemonic": "definitely not valid"})).await;
+        assert!(resp.error.unwrap().message.contains("Invalid mnemonic phrase"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_address_reports_checksum_and_contract() {
+        use mockito::{mock, Matcher};
+
+        let _m = mock("POST", "/validate-address")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_getCode"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": "0x6080"}).to_string())
+            .create();
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("1".into(), format!("{}/validate-address", mockito::server_url()));
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(&state, "validate_address", json!({"address": "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266", "chain_id": 1})).await;
+        let result = resp.result.expect("validate_address should succeed");
+        assert_eq!(result["valid"], true);
+        assert_eq!(result["checksummed"], "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        assert_eq!(result["is_contract"], true);
+
+        let resp = call_tool(&state, "validate_address", json!({"address": "0xF39Fd6e51aad88F6F4ce6aB8827279cffFb92266"})).await;
+        let result = resp.result.unwrap();
+        assert_eq!(result["valid"], false);
+        assert_eq!(result["checksum"], "invalid");
+
+        let resp = call_tool(&state, "validate_address", json!({"address": "0x1234"})).await;
+        let result = resp.result.unwrap();
+        assert_eq!(result["valid"], false);
+        assert!(result["reason"].as_str().unwrap().contains("40 hex characters"));
+    }
+
+    #[tokio::test]
+    async fn test_get_contract_code_returns_runtime_bytecode() {
+        use mockito::{mock, Matcher};
+
+        let runtime = "0x6080604052348015600f57600080fd5b50";
+        let _m = mock("POST", "/get-contract-code")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_getCode",
+                "params": ["0x5FbDB2315678afecb367f032d93F642f64180aa3", "latest"]
+            })))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": runtime}).to_string())
+            .create();
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("1".into(), format!("{}/get-contract-code", mockito::server_url()));
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(
+            &state,
+            "get_contract_code",
+            json!({"address": "0x5FbDB2315678afecb367f032d93F642f64180aa3", "chain_id": "1"}),
+        )
+        .await;
+        let result = resp.result.expect("get_contract_code should succeed");
+        assert_eq!(result["code"], runtime);
+        assert_eq!(result["size"], 17);
+        assert!(result["content"][0]["text"].as_str().unwrap().contains("(17 bytes)"));
+    }
+
+    #[tokio::test]
+    async fn test_get_account_state_populates_all_fields() {
+        use mockito::{mock, Matcher};
+
+        let address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+        let rpc = |body: Value, result: &str| {
+            mock("POST", "/get-account-state")
+                .match_body(Matcher::PartialJson(body))
+                .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string())
+                .create()
+        };
+        let _mocks = [
+            rpc(json!({"method": "eth_getBalance", "params": [address, "latest"]}), "0x14d1120d7b160000"),
+            rpc(json!({"method": "eth_getTransactionCount", "params": [address, "latest"]}), "0x3"),
+            rpc(json!({"method": "eth_getTransactionCount", "params": [address, "pending"]}), "0x5"),
+            rpc(json!({"method": "eth_getCode", "params": [address, "latest"]}), "0x"),
+        ];
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("1".into(), format!("{}/get-account-state", mockito::server_url()));
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(&state, "get_account_state", json!({"address": address, "chain_id": "1"})).await;
+        let result = resp.result.expect("get_account_state should succeed");
+        assert_eq!(result["address"], address);
+        assert_eq!(result["balance"]["amount"], "1500000000000000000");
+        assert_eq!(result["balance"]["formatted"], "1.5 ETH");
+        assert_eq!(result["nonce"], json!({"latest": 3, "pending": 5}));
+        assert_eq!(result["is_contract"], false);
+        assert_eq!(result["code_size"], 0);
+        let schema = tool_schema("get_account_state", "outputSchema").unwrap();
+        assert_eq!(validation::output_mismatches(&schema, &result), Vec::<String>::new());
+
+        let resp = call_tool(&state, "get_account_state", json!({"address": "0x1234", "chain_id": "1"})).await;
+        assert_eq!(resp.error.unwrap().code, error_codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_is_contract_reports_code_size_and_eoa() {
+        use mockito::{mock, Matcher};
+
+        let contract = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+        let wallet = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+        let _code = mock("POST", "/is-contract")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_getCode", "params": [contract, "latest"]})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": "0x60806040"}).to_string())
+            .create();
+        let _empty = mock("POST", "/is-contract")
+            .match_body(Matcher::PartialJson(json!({"method": "eth_getCode", "params": [wallet, "latest"]})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": "0x"}).to_string())
+            .create();
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("1".into(), format!("{}/is-contract", mockito::server_url()));
+        let state = test_state(config, dir.path());
+
+        // No ETHERSCAN_API_KEY: chain state alone answers the question
+        let resp = call_tool(&state, "is_contract", json!({"address": contract, "chain_id": "1"})).await;
+        let result = resp.result.expect("is_contract should succeed without Etherscan");
+        assert_eq!(result["is_contract"], true);
+        assert_eq!(result["is_eoa"], false);
+        assert_eq!(result["code_size"], 4);
+        assert!(result.get("contract_name").is_none());
+
+        let resp = call_tool(&state, "is_contract", json!({"address": wallet, "chain_id": "1"})).await;
+        let result = resp.result.unwrap();
+        assert_eq!(result["is_contract"], false);
+        assert_eq!(result["is_eoa"], true);
+        assert_eq!(result["code_size"], 0);
+        assert_eq!(result["verified"], false);
+    }
+
+    #[tokio::test]
+    async fn test_etherscan_base_url_override_is_used() {
+        use mockito::{mock, Matcher};
+
+        let address = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+        let explorer = mock("GET", "/etherscan-override")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("chainid".into(), "1".into()),
+                Matcher::UrlEncoded("action".into(), "txlist".into()),
+                Matcher::UrlEncoded("address".into(), address.into()),
+            ]))
+            .with_body(json!({"status": "1", "message": "OK", "result": [{"hash": "0xabc"}]}).to_string())
+            .expect(1)
+            .create();
+        let dir = tempdir().unwrap();
+        let state = etherscan_state("/etherscan-override", dir.path());
+
+        let resp = call_tool(&state, "get_contract_transactions", json!({"address": address, "chain_id": "1"})).await;
+        let result = resp.result.expect("override explorer should answer");
+        assert!(result["content"][0]["text"].as_str().unwrap().contains("1 transaction(s) found"));
+        explorer.assert();
+    }
+
+    // State whose Etherscan calls for chain 1 go to the mock server under `path`.
+    fn etherscan_state(path: &str, dir: &Path) -> AppState {
+        let mut config = Config { etherscan_api_key: Some("test-key".into()), ..Config::default() };
+        config.etherscan_base_urls.insert("1".into(), format!("{}{}", mockito::server_url(), path));
+        test_state(config, dir)
+    }
+
+    fn etherscan_query(params: &[(&str, &str)]) -> mockito::Matcher {
+        mockito::Matcher::AllOf(
+            params.iter().map(|(k, v)| mockito::Matcher::UrlEncoded(k.to_string(), v.to_string())).collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_history_queries_etherscan() {
+        use mockito::mock;
+
+        let address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+        let txlist = mock("GET", "/etherscan-history")
+            .match_query(etherscan_query(&[
+                ("chainid", "1"),
+                ("module", "account"),
+                ("action", "txlist"),
+                ("address", address),
+                ("startblock", "0"),
+                ("endblock", "99999999"),
+                ("sort", "asc"),
+                ("apikey", "test-key"),
+            ]))
+            .with_body(
+                json!({"status": "1", "message": "OK", "result": [
+                    {"hash": "0xaa", "timeStamp": "1704067200", "value": "1"},
+                    {"hash": "0xbb", "timeStamp": "1704153600", "value": "2"},
+                ]})
+                .to_string(),
+            )
+            .expect(1)
+            .create();
+        let dir = tempdir().unwrap();
+        let state = etherscan_state("/etherscan-history", dir.path());
+
+        let resp = call_tool(&state, "get_transaction_history", json!({"address": address, "chain_id": "1"})).await;
+        let text = resp.result.expect("history should succeed")["content"][0]["text"].as_str().unwrap().to_string();
+        assert!(text.starts_with(&format!("2 transaction(s) found for address {} on 1", address)), "{}", text);
+        assert!(text.contains("0xbb"));
+        txlist.assert();
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_history_resolves_dates_to_blocks() {
+        use mockito::mock;
+
+        let address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+        let path = "/etherscan-history-dates";
+        let _after = mock("GET", path)
+            .match_query(etherscan_query(&[("action", "getblocknobytime"), ("timestamp", "1704067200"), ("closest", "after")]))
+            .with_body(json!({"status": "1", "message": "OK", "result": "18908895"}).to_string())
+            .create();
+        let _before = mock("GET", path)
+            .match_query(etherscan_query(&[("action", "getblocknobytime"), ("timestamp", "1706745599"), ("closest", "before")]))
+            .with_body(json!({"status": "1", "message": "OK", "result": "19129888"}).to_string())
+            .create();
+        let txlist = mock("GET", path)
+            .match_query(etherscan_query(&[("action", "txlist"), ("startblock", "18908895"), ("endblock", "19129888")]))
+            .with_body(
+                json!({"status": "1", "message": "OK", "result": [
+                    {"hash": "0xaa", "timeStamp": "1704067200", "value": "500"},
+                    {"hash": "0xbb", "timeStamp": "1704153600", "value": "5000"},
+                    {"hash": "0xcc", "timeStamp": "1706745600", "value": "5000"},
+                ]})
+                .to_string(),
+            )
+            .expect(1)
+            .create();
+        let dir = tempdir().unwrap();
+        let state = etherscan_state(path, dir.path());
+
+        let resp = call_tool(
+            &state,
+            "get_transaction_history",
+            json!({"address": address, "chain_id": "1", "from_date": "2024-01-01", "to_date": "2024-01-31", "min_value_wei": "1000"}),
+        )
+        .await;
+        let result = resp.result.expect("filtered history should succeed");
+        assert!(result["content"][0]["text"].as_str().unwrap().starts_with("1 of 3 transaction(s) match the filters"));
+        assert_eq!(result["filters"]["start_block"], 18908895);
+        assert_eq!(result["filters"]["end_block"], 19129888);
+        assert_eq!(result["fetched_count"], 3);
+        assert_eq!(result["transactions"][0]["hash"], "0xbb");
+        txlist.assert();
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_history_reports_etherscan_errors() {
+        let _m = mockito::mock("GET", "/etherscan-history-error")
+            .match_query(etherscan_query(&[("action", "txlist")]))
+            .with_body(json!({"status": "0", "message": "NOTOK", "result": "Invalid API Key"}).to_string())
+            .create();
+        let dir = tempdir().unwrap();
+        let state = etherscan_state("/etherscan-history-error", dir.path());
+
+        let resp = call_tool(
+            &state,
+            "get_transaction_history",
+            json!({"address": "0x70997970C51812dc3A010C7d01b50e0d17dc79C8", "chain_id": "1"}),
+        )
+        .await;
+        let err = resp.error.expect("an Etherscan error should fail the call");
+        assert_eq!(err.code, error_codes::INTERNAL_ERROR);
+        assert!(err.message.contains("NOTOK"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_output_schemas_are_object_schemas() {
+        let tools = tool_definitions();
+        let with_output: Vec<&Value> = tools.as_array().unwrap().iter().filter(|t| t.get("outputSchema").is_some()).collect();
+        assert!(!with_output.is_empty());
+        for tool in with_output {
+            let schema = &tool["outputSchema"];
+            assert_eq!(schema["type"], "object", "{}", tool["name"]);
+            let properties = schema["properties"].as_object().unwrap();
+            for field in schema["required"].as_array().into_iter().flatten() {
+                assert!(properties.contains_key(field.as_str().unwrap()), "{}: {}", tool["name"], field);
+            }
+        }
+    }
+
+    #[test]
+    fn test_initialize_reports_crate_version() {
+        let req: Request = serde_json::from_value(json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"})).unwrap();
+        let result = handle_initialize(&req).result.unwrap();
+        assert_eq!(result["serverInfo"]["version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[tokio::test]
+    async fn test_server_info_reports_version_chains_and_features() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("11155111".into(), "http://127.0.0.1:1".into());
+        config.chain_rpc_urls.insert("1".into(), "http://127.0.0.1:1".into());
+        config.faucet_api_url = Some("http://127.0.0.1:1".into());
+        let state = test_state(config, dir.path());
+        let result = call_tool(&state, "server_info", json!({})).await.result.unwrap();
+        assert_eq!(result["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(result["chains"], json!(["1", "11155111"]));
+        assert_eq!(result["features"], json!({"discord": false, "faucet": true, "etherscan": false}));
+    }
+
+    #[tokio::test]
+    async fn test_address_from_key_returns_only_the_address() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+
+        let resp = call_tool(&state, "address_from_key", json!({"mnemonic_or_private_key": TEST_KEY})).await;
+        let result = resp.result.expect("a valid key should resolve");
+        assert_eq!(result["address"], "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23");
+        assert!(!result.to_string().contains(TEST_KEY.trim_start_matches("0x")));
+        assert!(state.wallet_storage.lock().await.list_wallets().is_empty());
+
+        let mnemonic = "test test test test test test test test test test test junk";
+        let resp = call_tool(
+            &state,
+            "address_from_key",
+            json!({"mnemonic_or_private_key": mnemonic, "derivation_path": "m/44'/60'/0'/0/2"}),
+        )
+        .await;
+        let result = resp.result.unwrap();
+        assert_eq!(result["address"], "0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        assert_eq!(result["path"], "m/44'/60'/0'/0/2");
+        assert!(!result.to_string().contains("junk"));
+
+        let resp = call_tool(&state, "address_from_key", json!({"mnemonic_or_private_key": "0x1234"})).await;
+        assert_eq!(resp.error.unwrap().code, error_codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_structured_results_match_output_schemas() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let transfer = "0xa9059cbb000000000000000000000000000000000000000000000000000000000000dead00000000000000000000000000000000000000000000000000000000000f4240";
+        let samples = [
+            ("keccak256", json!({"input": "Transfer(address,address,uint256)"})),
+            ("compute_selector", json!({"signature": "transfer(address,uint256)"})),
+            ("abi_encode", json!({"signature": "transfer(address,uint256)", "args": ["0x000000000000000000000000000000000000dEaD", "1000000"]})),
+            ("abi_decode", json!({"types": ["address", "uint256"], "data": transfer, "has_selector": true})),
+            ("decode_calldata", json!({"signatures": ["transfer(address to, uint256 amount)"], "data": transfer})),
+            ("decode_calldata", json!({"signatures": ["approve(address,uint256)"], "data": transfer})),
+            ("derive_accounts", json!({"mnemonic": "test test test test test test test test test test test junk", "count": 2})),
+            ("address_from_key", json!({"mnemonic_or_private_key": "test test test test test test test test test test test junk"})),
+            ("address_from_key", json!({"mnemonic_or_private_key": TEST_KEY})),
+            ("validate_address", json!({"address": "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"})),
+            ("validate_address", json!({"address": "0x1234"})),
+            ("list_chains", json!({})),
+            ("server_info", json!({})),
+        ];
+        for (tool, args) in samples {
+            let schema = tool_schema(tool, "outputSchema").unwrap_or_else(|| panic!("{} has no outputSchema", tool));
+            let resp = call_tool(&state, tool, args).await;
+            let result = resp.result.unwrap_or_else(|| panic!("{} failed: {:?}", tool, resp.error));
+            let structured = result.get("structuredContent").unwrap_or_else(|| panic!("{} has no structuredContent", tool));
+            assert!(structured.get("content").is_none());
+            assert_eq!(validation::output_mismatches(&schema, structured), Vec::<String>::new(), "{}", tool);
+        }
+
+        // Tools without an outputSchema are left as they were
+        let resp = call_tool(&state, "get_discord_service_info", json!({})).await;
+        assert!(resp.result.is_none_or(|r| r.get("structuredContent").is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_get_amounts_out_decodes_router_quote() {
+        use mockito::{mock, Matcher};
+
+        let router = "0x1111111111111111111111111111111111111111";
+        let weth = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+        let usdc = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+        let amount_in = U256::exp10(18);
+        let quoted = U256::from(2_500_000_000u64);
+        let data = abi::encode_function_call(
+            &abi::parse_function_signature(dex::GET_AMOUNTS_OUT).unwrap(),
+            &[json!(amount_in.to_string()), json!([weth, usdc])],
+        )
+        .unwrap();
+        let returned = encode(&[Token::Array(vec![Token::Uint(amount_in), Token::Uint(quoted)])]);
+        let _m = mock("POST", "/get-amounts-out")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_call",
+                "params": [{"to": router, "data": format!("0x{}", hex::encode(&data))}]
+            })))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": format!("0x{}", hex::encode(returned))}).to_string())
+            .create();
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("137".into(), format!("{}/get-amounts-out", mockito::server_url()));
+        config.dex_routers.insert("137".into(), router.into());
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(
+            &state,
+            "get_amounts_out",
+            json!({"chain_id": "137", "amount_in": "1000000000000000000", "path": [weth, usdc]}),
+        )
+        .await;
+        let result = resp.result.expect("get_amounts_out should succeed");
+        assert_eq!(result["amounts"], json!(["1000000000000000000", "2500000000"]));
+        assert_eq!(result["amount_out"], "2500000000");
+        assert_eq!(result["router"], router);
+
+        // No router configured or passed for this chain
+        let resp = call_tool(&state, "get_amounts_out", json!({"chain_id": "10", "amount_in": "1", "path": [weth, usdc]})).await;
+        assert!(resp.error.unwrap().message.contains("No DEX router known for chain_id '10'"));
+    }
+
+    #[tokio::test]
+    async fn test_keccak256_tool_hashes_event_signature() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let resp = call_tool(&state, "keccak256", json!({"input": "Transfer(address,address,uint256)"})).await;
+        let result = resp.result.expect("keccak256 should succeed");
+        assert_eq!(result["hash"], "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+
+        let resp = call_tool(&state, "keccak256", json!({"input": "0x", "encoding": "hex"})).await;
+        assert_eq!(
+            resp.result.unwrap()["hash"],
+            "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_abi_encode_then_decode_roundtrip() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let to = "0x000000000000000000000000000000000000dEaD";
+        let resp = call_tool(
+            &state,
+            "abi_encode",
+            json!({"signature": "transfer(address to, uint256 amount)", "args": [to, "1000000000000000000"]}),
+        )
+        .await;
+        let result = resp.result.expect("abi_encode should succeed");
+        assert_eq!(result["selector"], "0xa9059cbb");
+        let calldata = result["calldata"].as_str().unwrap().to_string();
+
+        let resp = call_tool(
+            &state,
+            "abi_decode",
+            json!({"types": ["address", "uint256"], "data": calldata, "has_selector": true}),
+        )
+        .await;
+        let result = resp.result.expect("abi_decode should succeed");
+        assert_eq!(result["values"], json!([to, "1000000000000000000"]));
+
+        let resp = call_tool(&state, "abi_encode", json!({"signature": "transfer(address,uint256)", "args": [to, true]})).await;
+        let err = resp.error.expect("type mismatch should fail");
+        assert!(err.message.contains("argument 1 (uint256)"), "{}", err.message);
+
+        let resp = call_tool(&state, "abi_decode", json!({"types": ["uint256"], "data": "0xzz"})).await;
+        assert!(resp.error.unwrap().message.contains("invalid hex"));
+    }
+
+    #[tokio::test]
+    async fn test_compute_selector_tool() {
+        let dir = tempdir().unwrap();
+        let state = test_state(Config::default(), dir.path());
+        let resp = call_tool(&state, "compute_selector", json!({"signature": "transfer(address, uint256)"})).await;
+        let result = resp.result.expect("compute_selector should succeed");
+        assert_eq!(result["selector"], "0xa9059cbb");
+        assert_eq!(result["signature"], "transfer(address,uint256)");
+
+        let resp = call_tool(&state, "compute_selector", json!({"signature": "transfer"})).await;
+        assert_eq!(resp.error.unwrap().code, error_codes::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_list_chains_reports_configured_chains_and_aliases() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.chain_rpc_urls.insert("11155111".into(), "http://localhost:1".into());
+        config.chain_rpc_urls.insert("1".into(), "http://localhost:2".into());
+        config.chain_rpc_urls.insert("31337".into(), "http://localhost:3".into());
+        let state = test_state(config, dir.path());
+
+        let resp = call_tool(&state, "list_chains", json!({})).await;
+        let result = resp.result.expect("list_chains should succeed");
+        let chains = result["chains"].as_array().unwrap();
+        let ids: Vec<&str> = chains.iter().map(|c| c["chain_id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["1", "31337", "11155111"]);
+
+        assert_eq!(chains[0]["name"], "Ethereum Mainnet");
+        assert_eq!(chains[0]["etherscan_supported"], true);
+        assert!(chains[0]["aliases"].as_array().unwrap().contains(&json!("mainnet")));
+        assert_eq!(chains[1]["name"], "Unknown chain");
+        assert_eq!(chains[1]["aliases"], json!([]));
+        assert!(chains[2]["aliases"].as_array().unwrap().contains(&json!("sepolia")));
+        for alias in chains[2]["aliases"].as_array().unwrap() {
+            assert_eq!(normalize_chain_id(alias.as_str().unwrap()), "11155111");
+        }
+    }
+
+    #[test]
+    fn test_normalize_chain_id_aliases() {
+        let cases = [
+            ("mainnet", "1"), ("main", "1"), ("m", "1"), ("eth", "1"), ("ethereum", "1"),
+            ("sepolia", "11155111"), ("testnet", "11155111"), ("test", "11155111"), ("t", "11155111"),
+            ("zksync-sepolia", "11155111"), ("zk-sepolia", "11155111"),
+            ("holesky", "17000"),
+            ("zksync", "324"), ("zk", "324"),
+            ("polygon", "137"), ("matic", "137"),
+            ("polygon-amoy", "80002"), ("matic-amoy", "80002"), ("amoy", "80002"),
+            ("arbitrum", "42161"), ("arb", "42161"), ("arbitrum-one", "42161"),
+            ("arbitrum-sepolia", "421614"), ("arb-sepolia", "421614"),
+            ("optimism", "10"), ("op", "10"),
+            ("optimism-sepolia", "11155420"), ("op-sepolia", "11155420"),
+            ("base", "8453"), ("base-sepolia", "84532"),
+            ("bsc", "56"), ("bnb", "56"),
+            ("bsc-testnet", "97"), ("bnb-testnet", "97"),
+            ("avalanche", "43114"), ("avax", "43114"),
+            ("avalanche-fuji", "43113"), ("avax-fuji", "43113"), ("fuji", "43113"),
+        ];
+        for (alias, expected) in cases {
+            assert_eq!(normalize_chain_id(alias), expected, "alias {}", alias);
+        }
+        // Every alias in the table is covered above
+        let alias_count: usize = chains::KNOWN_CHAINS.iter().map(|c| c.aliases.len()).sum();
+        assert_eq!(alias_count, cases.len());
+
+        // Case and separators are normalized; unknown input is passed through
+        assert_eq!(normalize_chain_id("  Arbitrum_Sepolia "), "421614");
+        assert_eq!(normalize_chain_id("Base  Sepolia"), "84532");
+        assert_eq!(normalize_chain_id("31337"), "31337");
+        assert_eq!(normalize_chain_id("unknown-chain"), "unknown-chain");
+    }
 }
@@ -0,0 +1,165 @@
+// src/mcp/idempotency.rs
+
+//! Duplicate-send protection for transfer tools.
+//!
+//! A client that retries a transfer after a timeout passes the same `idempotency_key`;
+//! the first successful result is remembered for `ttl` and handed back to the retry
+//! instead of broadcasting a second transaction. Entries live in memory only.
+//! A key reused with different arguments is refused rather than replayed.
+
+use dashmap::DashMap;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long a key is remembered after its transfer succeeds.
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug)]
+struct Entry {
+    stored_at: Instant,
+    // What the remembered send was for, compared on every reuse of the key
+    fingerprint: String,
+    result: Value,
+}
+
+/// Why `IdempotencyStore::run` gave no result.
+#[derive(Debug)]
+pub enum IdempotencyError<E> {
+    /// The key already belongs to a send with different arguments.
+    KeyReused,
+    /// The send itself failed.
+    Send(E),
+}
+
+#[derive(Debug, Clone)]
+pub struct IdempotencyStore {
+    // One lock per key, held while the first request sends, so a concurrent retry
+    // waits for its result instead of racing it.
+    entries: Arc<DashMap<String, Arc<Mutex<Option<Entry>>>>>,
+    ttl: Duration,
+}
+
+impl Default for IdempotencyStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDEMPOTENCY_TTL)
+    }
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Arc::new(DashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Runs `send` unless `key` already has an unexpired result, in which case that
+    /// result is returned. The flag is `true` when the result was replayed.
+    /// `fingerprint` identifies the request; a result stored under another fingerprint
+    /// is not replayed but refused with `KeyReused`.
+    /// Failed sends are not remembered, so the caller may retry them with the same key.
+    pub async fn run<F, Fut, E>(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        send: F,
+    ) -> Result<(Value, bool), IdempotencyError<E>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Value, E>>,
+    {
+        self.evict_expired();
+        let slot = self
+            .entries
+            .entry(key.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(None)))
+            .clone();
+        let mut entry = slot.lock().await;
+        if let Some(e) = entry.as_ref().filter(|e| e.stored_at.elapsed() < self.ttl) {
+            if e.fingerprint != fingerprint {
+                return Err(IdempotencyError::KeyReused);
+            }
+            return Ok((e.result.clone(), true));
+        }
+        let result = send().await.map_err(IdempotencyError::Send)?;
+        *entry = Some(Entry {
+            stored_at: Instant::now(),
+            fingerprint: fingerprint.to_string(),
+            result: result.clone(),
+        });
+        Ok((result, false))
+    }
+
+    fn evict_expired(&self) {
+        self.entries.retain(|_, slot| {
+            // Another handle means a request holds or is about to wait on this slot;
+            // dropping it would let a retry start a second send under a fresh slot.
+            if Arc::strong_count(slot) > 1 {
+                return true;
+            }
+            match slot.try_lock() {
+                Ok(entry) => entry.as_ref().is_some_and(|e| e.stored_at.elapsed() < self.ttl),
+                Err(_) => true,
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_repeat_key_replays_result_until_expiry() {
+        let store = IdempotencyStore::new(Duration::from_millis(50));
+        let sends = AtomicUsize::new(0);
+        let send = || async {
+            sends.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, String>(json!({"tx_hash": "0xabc"}))
+        };
+
+        assert_eq!(store.run("k", "args", send).await.unwrap(), (json!({"tx_hash": "0xabc"}), false));
+        assert_eq!(store.run("k", "args", send).await.unwrap(), (json!({"tx_hash": "0xabc"}), true));
+        assert_eq!(sends.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(!store.run("k", "args", send).await.unwrap().1);
+        assert_eq!(sends.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_send_is_not_remembered() {
+        let store = IdempotencyStore::default();
+        assert!(store.run("k", "args", || async { Err::<Value, _>("timeout") }).await.is_err());
+        let (_, replayed) = store.run("k", "args", || async { Ok::<_, &str>(json!("0x1")) }).await.unwrap();
+        assert!(!replayed);
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_with_other_arguments_is_refused() {
+        let store = IdempotencyStore::default();
+        store.run("k", "to=0xa", || async { Ok::<_, &str>(json!("0x1")) }).await.unwrap();
+        let reused = store.run("k", "to=0xb", || async { Ok::<_, &str>(json!("0x2")) }).await;
+        assert!(matches!(reused, Err(IdempotencyError::KeyReused)));
+    }
+
+    #[tokio::test]
+    async fn test_expired_slot_waited_on_is_not_evicted() {
+        let store = IdempotencyStore::new(Duration::from_millis(10));
+        store.run("k", "args", || async { Ok::<_, &str>(json!("0x1")) }).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // A request that has taken the slot but not yet locked it keeps it alive
+        let held = store.entries.get("k").unwrap().clone();
+        store.evict_expired();
+        assert!(store.entries.contains_key("k"));
+        drop(held);
+        store.evict_expired();
+        assert!(!store.entries.contains_key("k"));
+    }
+}
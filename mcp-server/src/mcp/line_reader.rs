@@ -0,0 +1,157 @@
+// src/mcp/line_reader.rs
+
+//! Newline-delimited input with a cap on line length.
+//!
+//! `AsyncBufReadExt::lines` buffers a whole line before returning it, so a client that
+//! sends a huge line (or never sends a newline) can grow that buffer without limit.
+//! `BoundedLines` keeps at most `max_len` bytes of a line; anything longer is reported
+//! once as `Line::TooLong` and the rest of it is discarded as it arrives.
+
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// One line of input.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Line {
+    /// A complete line without its line ending
+    Text(String),
+    /// A line longer than the limit; its bytes were dropped
+    TooLong,
+}
+
+pub struct BoundedLines<R> {
+    reader: R,
+    buf: Vec<u8>,
+    max_len: usize,
+    /// Skipping the remainder of a line already reported as too long
+    discarding: bool,
+    /// Bytes of an oversized `next_exact` message dropped so far
+    skipped: usize,
+}
+
+impl<R: AsyncBufRead + Unpin> BoundedLines<R> {
+    pub fn new(reader: R, max_len: usize) -> Self {
+        BoundedLines { reader, buf: Vec::new(), max_len, discarding: false, skipped: 0 }
+    }
+
+    /// The next line, or `None` at end of input. Cancel safe: a partly read line is kept
+    /// and completed by the next call.
+    pub async fn next_line(&mut self) -> io::Result<Option<Line>> {
+        loop {
+            let available = self.reader.fill_buf().await?;
+            if available.is_empty() {
+                self.discarding = false;
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                return self.take_line().map(Some);
+            }
+
+            let newline = available.iter().position(|b| *b == b'\n');
+            let chunk_len = newline.unwrap_or(available.len());
+            let consumed = newline.map_or(chunk_len, |i| i + 1);
+
+            if self.discarding {
+                self.reader.consume(consumed);
+                if newline.is_some() {
+                    self.discarding = false;
+                }
+                continue;
+            }
+
+            if self.buf.len() + chunk_len > self.max_len {
+                self.reader.consume(consumed);
+                self.buf = Vec::new();
+                self.discarding = newline.is_none();
+                return Ok(Some(Line::TooLong));
+            }
+
+            self.buf.extend_from_slice(&available[..chunk_len]);
+            self.reader.consume(consumed);
+            if newline.is_some() {
+                return self.take_line().map(Some);
+            }
+        }
+    }
+
+    /// The next `len` bytes as one message, such as a `Content-Length` framed body. A
+    /// message over the limit is dropped as it arrives and reported as `Line::TooLong`.
+    /// Cancel safe as long as the next call asks for the same `len`.
+    pub async fn next_exact(&mut self, len: usize) -> io::Result<Line> {
+        let oversized = len > self.max_len;
+        loop {
+            let read = if oversized { self.skipped } else { self.buf.len() };
+            if read == len {
+                self.skipped = 0;
+                if oversized {
+                    return Ok(Line::TooLong);
+                }
+                return String::from_utf8(std::mem::take(&mut self.buf))
+                    .map(Line::Text)
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"));
+            }
+
+            let available = self.reader.fill_buf().await?;
+            if available.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "input ended inside a message"));
+            }
+            let n = available.len().min(len - read);
+            if oversized {
+                self.skipped += n;
+            } else {
+                self.buf.extend_from_slice(&available[..n]);
+            }
+            self.reader.consume(n);
+        }
+    }
+
+    fn take_line(&mut self) -> io::Result<Line> {
+        let mut bytes = std::mem::take(&mut self.buf);
+        if bytes.last() == Some(&b'\r') {
+            bytes.pop();
+        }
+        String::from_utf8(bytes)
+            .map(Line::Text)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bounded_lines_skips_oversized_line() {
+        let input = format!("{}\r\n{}\nnext\nlast", "a".repeat(8), "b".repeat(100));
+        // A small internal buffer so the long line arrives in several chunks
+        let reader = tokio::io::BufReader::with_capacity(16, input.as_bytes());
+        let mut lines = BoundedLines::new(reader, 10);
+
+        assert_eq!(lines.next_line().await.unwrap(), Some(Line::Text("a".repeat(8))));
+        assert_eq!(lines.next_line().await.unwrap(), Some(Line::TooLong));
+        assert!(lines.buf.capacity() <= 16);
+        assert_eq!(lines.next_line().await.unwrap(), Some(Line::Text("next".into())));
+        assert_eq!(lines.next_line().await.unwrap(), Some(Line::Text("last".into())));
+        assert_eq!(lines.next_line().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_lines_oversized_line_without_newline() {
+        let input = "c".repeat(1000);
+        let reader = tokio::io::BufReader::with_capacity(64, input.as_bytes());
+        let mut lines = BoundedLines::new(reader, 100);
+        assert_eq!(lines.next_line().await.unwrap(), Some(Line::TooLong));
+        assert_eq!(lines.next_line().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_next_exact_reads_body_bytes() {
+        let input = format!("{{\"a\":1}}{}ok", "d".repeat(50));
+        let reader = tokio::io::BufReader::with_capacity(8, input.as_bytes());
+        let mut lines = BoundedLines::new(reader, 20);
+        assert_eq!(lines.next_exact(7).await.unwrap(), Line::Text("{\"a\":1}".into()));
+        assert_eq!(lines.next_exact(50).await.unwrap(), Line::TooLong);
+        assert_eq!(lines.next_exact(2).await.unwrap(), Line::Text("ok".into()));
+        assert_eq!(lines.next_exact(1).await.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -1,5 +1,10 @@
 // Simple mod.rs to expose wallet storage and encryption modules
+pub mod abi_cache;
 pub mod encryption;
 pub mod handler;
+pub mod idempotency;
+pub mod line_reader;
 pub mod protocol;
+pub mod transport;
+pub mod validation;
 pub mod wallet_storage;
\ No newline at end of file
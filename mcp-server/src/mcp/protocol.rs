@@ -1,7 +1,10 @@
 // src/mcp/protocol.rs
 
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
+
+use crate::blockchain::services::rpc::RpcError;
+use crate::mcp::wallet_storage::StorageError;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Request {
@@ -52,7 +55,21 @@ impl Response {
         }
     }
 
+    /// Error response whose `data` carries the category implied by `code`,
+    /// e.g. `{"category": "invalid_arg", "detail": "Invalid 'to_address'"}`.
     pub fn error(id: Value, code: i32, message: String) -> Self {
+        Self::error_with_category(id, code, ErrorCategory::for_code(code), message)
+    }
+
+    /// Error response for `err`, categorised by the typed error it was raised as.
+    pub fn from_error(id: Value, code: i32, err: impl Into<anyhow::Error>) -> Self {
+        let err = err.into();
+        Self::error_with_category(id, code, ErrorCategory::of_error(code, &*err), err.to_string())
+    }
+
+    /// Error response with an explicit category, for sites where the message alone is ambiguous.
+    pub fn error_with_category(id: Value, code: i32, category: ErrorCategory, message: String) -> Self {
+        let data = json!({ "category": category, "detail": message });
         Self {
             jsonrpc: "2.0".to_string(),
             id,
@@ -60,12 +77,17 @@ impl Response {
             error: Some(ErrorObject { 
                 code, 
                 message, 
-                data: None 
+                data: Some(data) 
             }),
         }
     }
 
-    pub fn error_with_data(id: Value, code: i32, message: String, data: Value) -> Self {
+    /// Error response with extra `data`; a `category` is added to object data that lacks one.
+    pub fn error_with_data(id: Value, code: i32, message: String, mut data: Value) -> Self {
+        if let Some(obj) = data.as_object_mut() {
+            obj.entry("category")
+                .or_insert_with(|| json!(ErrorCategory::for_code(code)));
+        }
         Self {
             jsonrpc: "2.0".to_string(),
             id,
@@ -79,6 +101,76 @@ impl Response {
     }
 }
 
+/// Machine-readable kind of a tool failure, reported as `error.data.category` so clients
+/// can react (retry, fix config, fix arguments) without parsing the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// Missing or malformed argument
+    InvalidArg,
+    /// Server-side setting (RPC URL, API key, ...) is missing
+    NotConfigured,
+    /// The node or API could not be reached
+    RpcUnreachable,
+    /// The call or transaction reverted
+    Reverted,
+    /// The node or API is throttling requests
+    RateLimited,
+    /// The sender can't cover value plus gas
+    InsufficientFunds,
+    /// Wrong master password or locked storage
+    AuthFailed,
+    /// The wallet, transaction or contract doesn't exist
+    NotFound,
+    /// Unknown method or tool
+    MethodNotFound,
+    /// A transaction may or may not have been broadcast; check before retrying
+    OutcomeUnknown,
+    Internal,
+}
+
+impl ErrorCategory {
+    /// The category implied by a JSON-RPC error code alone.
+    pub fn for_code(code: i32) -> Self {
+        match code {
+            error_codes::METHOD_NOT_FOUND => ErrorCategory::MethodNotFound,
+            error_codes::INVALID_PARAMS | error_codes::INVALID_REQUEST | error_codes::PARSE_ERROR => {
+                ErrorCategory::InvalidArg
+            }
+            _ => ErrorCategory::Internal,
+        }
+    }
+
+    /// The category of `err`, taken from the typed error it was raised as anywhere in its
+    /// chain of sources, falling back to `for_code`.
+    pub fn of_error(code: i32, err: &(dyn std::error::Error + 'static)) -> Self {
+        for cause in std::iter::successors(Some(err), |e| e.source()) {
+            if let Some(e) = cause.downcast_ref::<RpcError>() {
+                return match e {
+                    RpcError::Reverted(_) => ErrorCategory::Reverted,
+                    RpcError::RateLimited(_) => ErrorCategory::RateLimited,
+                    RpcError::InsufficientFunds(_) => ErrorCategory::InsufficientFunds,
+                };
+            }
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                return match e.status() {
+                    Some(reqwest::StatusCode::TOO_MANY_REQUESTS) => ErrorCategory::RateLimited,
+                    Some(_) => ErrorCategory::Internal,
+                    None => ErrorCategory::RpcUnreachable,
+                };
+            }
+            if let Some(e) = cause.downcast_ref::<StorageError>() {
+                return match e {
+                    StorageError::InvalidPassword | StorageError::IncorrectPassword => ErrorCategory::AuthFailed,
+                    StorageError::WalletNotFound(_) => ErrorCategory::NotFound,
+                    StorageError::WalletExists(_) => ErrorCategory::InvalidArg,
+                };
+            }
+        }
+        Self::for_code(code)
+    }
+}
+
 // Standard JSON-RPC error codes
 pub mod error_codes {
     pub const PARSE_ERROR: i32 = -32700;
@@ -88,4 +180,53 @@ pub mod error_codes {
     pub const INTERNAL_ERROR: i32 = -32603;
     pub const SERVER_ERROR_START: i32 = -32099;
     pub const SERVER_ERROR_END: i32 = -32000;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_for_code() {
+        use error_codes::*;
+        assert_eq!(ErrorCategory::for_code(METHOD_NOT_FOUND), ErrorCategory::MethodNotFound);
+        assert_eq!(ErrorCategory::for_code(INVALID_PARAMS), ErrorCategory::InvalidArg);
+        assert_eq!(ErrorCategory::for_code(PARSE_ERROR), ErrorCategory::InvalidArg);
+        assert_eq!(ErrorCategory::for_code(INTERNAL_ERROR), ErrorCategory::Internal);
+    }
+
+    #[test]
+    fn test_category_of_typed_errors() {
+        use anyhow::Context;
+        use error_codes::*;
+        let cases = [
+            (anyhow::Error::from(RpcError::Reverted("execution reverted".into())), ErrorCategory::Reverted),
+            (RpcError::RateLimited("too many requests".into()).into(), ErrorCategory::RateLimited),
+            (RpcError::InsufficientFunds("insufficient funds".into()).into(), ErrorCategory::InsufficientFunds),
+            (StorageError::InvalidPassword.into(), ErrorCategory::AuthFailed),
+            (StorageError::WalletNotFound("main".into()).into(), ErrorCategory::NotFound),
+            // The category survives context added on the way up
+            (
+                Err::<(), _>(RpcError::Reverted("execution reverted".into())).context("simulating").unwrap_err(),
+                ErrorCategory::Reverted,
+            ),
+            // Messages that merely mention a category no longer decide it
+            (anyhow::anyhow!("Token 'not found' in 429 results"), ErrorCategory::Internal),
+        ];
+        for (err, expected) in cases {
+            assert_eq!(ErrorCategory::of_error(INTERNAL_ERROR, &*err), expected, "{}", err);
+        }
+        let other = anyhow::anyhow!("bad input");
+        assert_eq!(ErrorCategory::of_error(INVALID_PARAMS, &*other), ErrorCategory::InvalidArg);
+    }
+
+    #[test]
+    fn test_error_data_carries_category_and_detail() {
+        let resp = Response::error(json!(1), error_codes::INVALID_PARAMS, "Invalid 'to_address'".into());
+        let data = resp.error.unwrap().data.unwrap();
+        assert_eq!(data, json!({"category": "invalid_arg", "detail": "Invalid 'to_address'"}));
+
+        let resp = Response::error_with_data(json!(1), error_codes::INVALID_PARAMS, "bad".into(), json!({"problems": []}));
+        assert_eq!(resp.error.unwrap().data.unwrap()["category"], "invalid_arg");
+    }
+}
@@ -0,0 +1,199 @@
+// src/mcp/transport.rs
+
+//! Message framing for the stdio MCP transport.
+//!
+//! The default is one JSON message per line. Some hosts frame messages the way LSP
+//! does instead: `Content-Length: <n>` (and optionally other headers), a blank line,
+//! then exactly `n` bytes of JSON. The framing is either fixed up front or detected
+//! from the first message, and responses are written back the same way.
+
+use std::io;
+use tokio::io::AsyncBufRead;
+
+use super::line_reader::{BoundedLines, Line};
+
+/// How messages are delimited on the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Framing {
+    /// One message per line
+    #[default]
+    Newline,
+    /// LSP-style `Content-Length` headers followed by the body
+    ContentLength,
+}
+
+impl Framing {
+    /// `message` ready to be written with this framing.
+    pub fn encode(self, message: &str) -> Vec<u8> {
+        match self {
+            Framing::Newline => format!("{}\n", message).into_bytes(),
+            Framing::ContentLength => {
+                format!("Content-Length: {}\r\n\r\n{}", message.len(), message).into_bytes()
+            }
+        }
+    }
+}
+
+// The value of a `Content-Length` header line, if that is what `line` is.
+fn content_length_header(line: &str) -> Option<&str> {
+    let (name, value) = line.split_once(':')?;
+    name.trim().eq_ignore_ascii_case("content-length").then(|| value.trim())
+}
+
+/// Reads whole messages in either framing, each capped at `max_len` bytes.
+pub struct MessageReader<R> {
+    lines: BoundedLines<R>,
+    /// `None` until the first message shows which framing the peer uses
+    framing: Option<Framing>,
+    /// Length announced by the headers read so far
+    content_length: Option<usize>,
+    /// Headers are complete; the body of this many bytes comes next
+    body_len: Option<usize>,
+    /// A malformed header left the body length unknown; skip to the next `Content-Length`
+    resyncing: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> MessageReader<R> {
+    /// A reader using `framing`, or detecting it from the first message when `None`.
+    pub fn new(reader: R, framing: Option<Framing>, max_len: usize) -> Self {
+        MessageReader {
+            lines: BoundedLines::new(reader, max_len),
+            framing,
+            content_length: None,
+            body_len: None,
+            resyncing: false,
+        }
+    }
+
+    /// The framing in use; newline until something else has been seen.
+    pub fn framing(&self) -> Framing {
+        self.framing.unwrap_or_default()
+    }
+
+    /// The next message, or `None` at end of input. Cancel safe.
+    ///
+    /// An `InvalidData` error concerns one message only (bad UTF-8, a malformed
+    /// `Content-Length`); the reader moves past it and the next call carries on.
+    pub async fn next_message(&mut self) -> io::Result<Option<Line>> {
+        loop {
+            if let Some(len) = self.body_len {
+                let body = self.lines.next_exact(len).await;
+                self.body_len = None;
+                return body.map(Some);
+            }
+
+            let Some(line) = self.lines.next_line().await? else {
+                return Ok(None);
+            };
+            let text = match line {
+                Line::Text(text) => text,
+                // An oversized line is never a valid header either
+                Line::TooLong => return Ok(Some(Line::TooLong)),
+            };
+
+            let framing = match self.framing {
+                Some(framing) => framing,
+                None if text.trim().is_empty() => continue,
+                None => {
+                    let detected = if content_length_header(&text).is_some() {
+                        Framing::ContentLength
+                    } else {
+                        Framing::Newline
+                    };
+                    *self.framing.insert(detected)
+                }
+            };
+            if framing == Framing::Newline {
+                return Ok(Some(Line::Text(text)));
+            }
+
+            // The unread body runs into the next header line without a newline, so pick
+            // the stream up again where the next `Content-Length` starts
+            let text = if self.resyncing {
+                match text.to_ascii_lowercase().find("content-length") {
+                    Some(start) => {
+                        self.resyncing = false;
+                        text[start..].to_string()
+                    }
+                    None => continue,
+                }
+            } else {
+                text
+            };
+
+            if text.trim().is_empty() {
+                // A blank line ends the headers; stray blank lines between messages are skipped
+                self.body_len = self.content_length.take();
+            } else if let Some(value) = content_length_header(&text) {
+                let Ok(len) = value.parse::<usize>() else {
+                    self.content_length = None;
+                    self.resyncing = true;
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid Content-Length '{}'", value),
+                    ));
+                };
+                self.content_length = Some(len);
+            }
+            // Other headers (Content-Type, ...) carry nothing we need
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_detects_content_length_framing() {
+        let first = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        let second = r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#;
+        let input = format!(
+            "Content-Length: {}\r\n\r\n{}Content-Length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}",
+            first.len(),
+            first,
+            second.len(),
+            second
+        );
+        let mut reader = MessageReader::new(input.as_bytes(), None, 1024);
+        assert_eq!(reader.next_message().await.unwrap(), Some(Line::Text(first.into())));
+        assert_eq!(reader.framing(), Framing::ContentLength);
+        assert_eq!(reader.next_message().await.unwrap(), Some(Line::Text(second.into())));
+        assert_eq!(reader.next_message().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_newline_framing_by_default() {
+        let input = "\n{\"id\":1}\n{\"id\":2}\n";
+        let mut reader = MessageReader::new(input.as_bytes(), None, 1024);
+        assert_eq!(reader.next_message().await.unwrap(), Some(Line::Text("{\"id\":1}".into())));
+        assert_eq!(reader.framing(), Framing::Newline);
+        assert_eq!(reader.next_message().await.unwrap(), Some(Line::Text("{\"id\":2}".into())));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_framed_body_is_skipped() {
+        let input = format!("Content-Length: 40\r\n\r\n{}Content-Length: 2\r\n\r\n{{}}", "x".repeat(40));
+        let mut reader = MessageReader::new(input.as_bytes(), Some(Framing::ContentLength), 20);
+        assert_eq!(reader.next_message().await.unwrap(), Some(Line::TooLong));
+        assert_eq!(reader.next_message().await.unwrap(), Some(Line::Text("{}".into())));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_content_length_resyncs_at_next_message() {
+        let input = "Content-Length: ten\r\n\r\n{\"id\":1}Content-Length: 2\r\n\r\n{}";
+        let mut reader = MessageReader::new(input.as_bytes(), Some(Framing::ContentLength), 1024);
+        let err = reader.next_message().await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(reader.next_message().await.unwrap(), Some(Line::Text("{}".into())));
+        assert_eq!(reader.next_message().await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_encode_content_length() {
+        let framed = Framing::ContentLength.encode(r#"{"id":1,"result":"é"}"#);
+        // The length counts bytes, not characters
+        assert_eq!(framed, "Content-Length: 22\r\n\r\n{\"id\":1,\"result\":\"é\"}".as_bytes());
+        assert_eq!(Framing::Newline.encode("{}"), b"{}\n");
+    }
+}
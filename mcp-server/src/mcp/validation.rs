@@ -0,0 +1,333 @@
+//! Up-front validation of `tools/call` arguments against a tool's `inputSchema`, and a
+//! check of tool results against its `outputSchema`.
+//!
+//! Only the subset of JSON Schema used by our tool definitions is supported:
+//! `properties` with `type`/`default`, `required`, `oneOf` of `required` sets and
+//! `additionalProperties: false`, plus `items` for output arrays. All problems are
+//! collected so a client can fix every argument in one round trip instead of one error
+//! at a time.
+
+use serde_json::{json, Map, Value};
+
+/// Alternative argument names the tool handlers accept in place of a schema property
+/// (besides the automatic camelCase <-> snake_case spelling).
+const ARG_ALIASES: &[(&str, &str)] = &[("amount", "amount_wei"), ("chain_id", "network")];
+
+fn camel_to_snake(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 4);
+    for c in s.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('_');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn snake_to_camel(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut upper = false;
+    for c in s.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// All spellings under which `key` may appear in the arguments, `key` itself first.
+fn spellings(key: &str) -> Vec<String> {
+    let mut names = vec![key.to_string(), camel_to_snake(key), snake_to_camel(key)];
+    for (a, b) in ARG_ALIASES {
+        if key == *a {
+            names.push(b.to_string());
+        } else if key == *b {
+            names.push(a.to_string());
+        }
+    }
+    names.dedup();
+    names
+}
+
+fn is_present(args: &Map<String, Value>, key: &str) -> bool {
+    spellings(key)
+        .iter()
+        .any(|k| args.get(k).map(|v| !v.is_null()).unwrap_or(false))
+}
+
+fn json_type_name(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn type_matches(expected: &str, v: &Value) -> bool {
+    let actual = json_type_name(v);
+    actual == expected || (expected == "number" && actual == "integer")
+}
+
+fn missing_fields(args: &Map<String, Value>, required: &Value) -> Vec<String> {
+    required
+        .as_array()
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(|f| f.as_str())
+                .filter(|f| !is_present(args, f))
+                .map(|f| f.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn problem(field: &str, message: String) -> Value {
+    json!({ "field": field, "problem": message })
+}
+
+/// Applies schema defaults to `args` and returns every validation problem found.
+/// An empty vector means the arguments are acceptable.
+pub fn validate_args(schema: &Value, args: &mut Value) -> Vec<Value> {
+    let args = match args.as_object_mut() {
+        Some(map) => map,
+        None => {
+            return vec![problem(
+                "arguments",
+                format!("expected an object, got {}", json_type_name(args)),
+            )]
+        }
+    };
+    let empty = Map::new();
+    let properties = schema
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .unwrap_or(&empty);
+
+    // Fill in defaults before checking required fields
+    for (name, prop) in properties {
+        if let Some(default) = prop.get("default") {
+            if !is_present(args, name) {
+                args.insert(name.clone(), default.clone());
+            }
+        }
+    }
+
+    let mut problems = Vec::new();
+
+    // Required fields: plain `required`, or the closest matching `oneOf` branch
+    let mut missing = schema
+        .get("required")
+        .map(|r| missing_fields(args, r))
+        .unwrap_or_default();
+    if let Some(branches) = schema.get("oneOf").and_then(|v| v.as_array()) {
+        let closest = branches
+            .iter()
+            .filter_map(|b| b.get("required"))
+            .map(|r| missing_fields(args, r))
+            .min_by_key(|m| m.len());
+        if let Some(branch_missing) = closest {
+            for field in branch_missing {
+                if !missing.contains(&field) {
+                    missing.push(field);
+                }
+            }
+        }
+    }
+    for field in missing {
+        problems.push(problem(&field, "missing required field".to_string()));
+    }
+
+    // Types and unknown fields
+    let allow_unknown = schema.get("additionalProperties") != Some(&Value::Bool(false));
+    for (key, value) in args.iter() {
+        let prop = spellings(key)
+            .iter()
+            .find_map(|k| properties.get(k.as_str()));
+        match prop {
+            Some(prop) => {
+                if value.is_null() {
+                    continue;
+                }
+                let expected: Vec<&str> = match prop.get("type") {
+                    Some(Value::String(t)) => vec![t.as_str()],
+                    Some(Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str()).collect(),
+                    _ => continue,
+                };
+                if !expected.iter().any(|t| type_matches(t, value)) {
+                    problems.push(problem(
+                        key,
+                        format!(
+                            "expected {}, got {}",
+                            expected.join(" or "),
+                            json_type_name(value)
+                        ),
+                    ));
+                }
+            }
+            None if !allow_unknown => {
+                problems.push(problem(key, "unknown field".to_string()));
+            }
+            None => {}
+        }
+    }
+
+    problems
+}
+
+/// Every place `value` departs from an `outputSchema` (`type`, `required`, nested
+/// `properties` and array `items`), as `path: problem` strings. Empty when it conforms.
+pub fn output_mismatches(schema: &Value, value: &Value) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    check_output(schema, value, "$", &mut mismatches);
+    mismatches
+}
+
+fn check_output(schema: &Value, value: &Value, path: &str, mismatches: &mut Vec<String>) {
+    let expected: Vec<&str> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str()).collect(),
+        _ => vec![],
+    };
+    if !expected.is_empty() && !expected.iter().any(|t| type_matches(t, value)) {
+        mismatches.push(format!("{}: expected {}, got {}", path, expected.join(" or "), json_type_name(value)));
+        return;
+    }
+    match value {
+        Value::Object(map) => {
+            for field in schema.get("required").and_then(|r| r.as_array()).into_iter().flatten() {
+                if let Some(field) = field.as_str().filter(|f| !map.contains_key(*f)) {
+                    mismatches.push(format!("{}.{}: missing required field", path, field));
+                }
+            }
+            if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
+                for (key, prop) in properties {
+                    if let Some(v) = map.get(key) {
+                        check_output(prop, v, &format!("{}.{}", path, key), mismatches);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check_output(item_schema, item, &format!("{}[{}]", path, i), mismatches);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "tokenAddress": {"type": "string"},
+                "ownerAddress": {"type": "string"},
+                "chain_id": {"type": "string", "default": "1"},
+                "count": {"type": "integer"}
+            },
+            "required": ["tokenAddress", "ownerAddress"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn test_reports_all_missing_required_fields() {
+        let mut args = json!({});
+        let problems = validate_args(&schema(), &mut args);
+        let fields: Vec<&str> = problems.iter().map(|p| p["field"].as_str().unwrap()).collect();
+        assert_eq!(fields, vec!["tokenAddress", "ownerAddress"]);
+    }
+
+    #[test]
+    fn test_reports_wrong_types_and_unknown_fields() {
+        let mut args = json!({
+            "tokenAddress": "0x1",
+            "ownerAddress": 5,
+            "count": "ten",
+            "bogus": true
+        });
+        let problems = validate_args(&schema(), &mut args);
+        assert_eq!(problems.len(), 3);
+        assert!(problems.contains(&json!({"field": "ownerAddress", "problem": "expected string, got integer"})));
+        assert!(problems.contains(&json!({"field": "count", "problem": "expected integer, got string"})));
+        assert!(problems.contains(&json!({"field": "bogus", "problem": "unknown field"})));
+    }
+
+    #[test]
+    fn test_accepts_snake_case_aliases_and_applies_defaults() {
+        let mut args = json!({ "token_address": "0x1", "owner_address": "0x2" });
+        assert!(validate_args(&schema(), &mut args).is_empty());
+        assert_eq!(args["chain_id"], "1");
+
+        let mut args = json!({ "tokenAddress": "0x1", "ownerAddress": "0x2", "network": "sepolia" });
+        assert!(validate_args(&schema(), &mut args).is_empty());
+        assert_eq!(args.get("chain_id"), None);
+    }
+
+    #[test]
+    fn test_one_of_reports_closest_branch() {
+        let schema = json!({
+            "properties": {
+                "wallet_name": {"type": "string"},
+                "private_key": {"type": "string"},
+                "mnemonic_or_private_key": {"type": "string"},
+                "master_password": {"type": "string"}
+            },
+            "oneOf": [
+                {"required": ["wallet_name", "mnemonic_or_private_key", "master_password"]},
+                {"required": ["wallet_name", "private_key", "master_password"]}
+            ]
+        });
+        let mut args = json!({ "wallet_name": "w", "private_key": "0x1" });
+        let problems = validate_args(&schema, &mut args);
+        assert_eq!(problems, vec![json!({"field": "master_password", "problem": "missing required field"})]);
+    }
+
+    #[test]
+    fn test_output_mismatches_walk_nested_objects_and_arrays() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "chains": {"type": "array", "items": {
+                    "type": "object",
+                    "properties": {"chain_id": {"type": "string"}, "aliases": {"type": "array", "items": {"type": "string"}}},
+                    "required": ["chain_id"]
+                }},
+                "note": {"type": ["string", "null"]}
+            },
+            "required": ["chains"]
+        });
+        let good = json!({"chains": [{"chain_id": "1", "aliases": ["mainnet"]}], "note": null, "extra": 1});
+        assert!(output_mismatches(&schema, &good).is_empty());
+
+        let bad = json!({"chains": [{"aliases": ["mainnet", 2]}, "x"], "note": 5});
+        assert_eq!(
+            output_mismatches(&schema, &bad),
+            vec![
+                "$.chains[0].chain_id: missing required field",
+                "$.chains[0].aliases[1]: expected string, got integer",
+                "$.chains[1]: expected object, got string",
+                "$.note: expected string or null, got integer",
+            ]
+        );
+        assert_eq!(output_mismatches(&schema, &json!({})), vec!["$.chains: missing required field"]);
+    }
+}
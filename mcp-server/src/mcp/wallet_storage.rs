@@ -1,10 +1,11 @@
 //! Wallet storage for EVM wallets
 //! 
 //! This module provides secure storage for EVM wallet private keys using encryption.
-//! Private keys are encrypted with a master password before being stored on disk.
+//! Private keys are encrypted before being stored on disk, each with its own key derived
+//! from the master password and a per-wallet random salt.
 
 use anyhow::{anyhow, Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use ethers::{
     core::k256::ecdsa::SigningKey,
     prelude::*,
@@ -15,23 +16,71 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::{Duration, Instant};
+use fs2::FileExt;
+use thiserror::Error;
 use zeroize::Zeroizing;
 
 use crate::blockchain::models::WalletResponse;
+use crate::mcp::encryption;
+
+/// Failures callers need to tell apart from other storage errors
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("Invalid master password")]
+    InvalidPassword,
+    #[error("Incorrect master password")]
+    IncorrectPassword,
+    #[error("Wallet '{0}' not found")]
+    WalletNotFound(String),
+    #[error("Wallet with name '{0}' already exists")]
+    WalletExists(String),
+}
 
 /// Represents a stored EVM wallet with encrypted private key
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredWallet {
     /// User-defined wallet name
     pub wallet_name: String,
-    /// Encrypted private key in the format "salt.payload"
+    /// Encrypted private key (Base64 AES-GCM payload, or plaintext for legacy entries without a salt)
     pub encrypted_private_key: String,
+    /// Per-wallet Argon2 salt used with the master password to derive this wallet's key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
     /// EVM address (0x-prefixed hex string)
     pub public_address: String,
     /// When this wallet was created
     pub created_at: DateTime<Utc>,
+    /// Optional daily spending limit in wei (decimal string)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_limit_wei: Option<String>,
+    /// Cumulative spend for the current UTC day, used to enforce `daily_limit_wei`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_spend: Option<DailySpend>,
+}
+
+/// Amount spent from a wallet on a given UTC day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailySpend {
+    /// The UTC day this total applies to
+    pub day: NaiveDate,
+    /// Total spent that day in wei (decimal string)
+    pub spent_wei: String,
+}
+
+impl StoredWallet {
+    /// Amount already spent on the UTC day of `now` (zero once the day has rolled over)
+    pub fn spent_on(&self, now: DateTime<Utc>) -> U256 {
+        match &self.daily_spend {
+            Some(spend) if spend.day == now.date_naive() => {
+                U256::from_dec_str(&spend.spent_wei).unwrap_or_default()
+            }
+            _ => U256::zero(),
+        }
+    }
 }
 
 /// In-memory representation of the wallet storage
@@ -113,9 +162,34 @@ impl WalletStorage {
         public_address: String,
         master_password: &str,
     ) -> Result<()> {
+        self.insert_wallet(wallet_name, private_key, public_address, master_password, false)
+            .map(|_| ())
+    }
+
+    /// Add a wallet, or replace the one already stored under `wallet_name`. Returns the
+    /// replaced entry. Re-registering the same address keeps its daily limit and spend;
+    /// a different address starts afresh.
+    pub fn upsert_wallet(
+        &mut self,
+        wallet_name: String,
+        private_key: &str,
+        public_address: String,
+        master_password: &str,
+    ) -> Result<Option<StoredWallet>> {
+        self.insert_wallet(wallet_name, private_key, public_address, master_password, true)
+    }
+
+    fn insert_wallet(
+        &mut self,
+        wallet_name: String,
+        private_key: &str,
+        public_address: String,
+        master_password: &str,
+        overwrite: bool,
+    ) -> Result<Option<StoredWallet>> {
         // Verify master password if set
         if !self.master_password_hash.is_empty() && !self.verify_master_password(master_password) {
-            return Err(anyhow!("Invalid master password"));
+            return Err(StorageError::InvalidPassword.into());
         }
         
         // Validate wallet name
@@ -123,57 +197,69 @@ impl WalletStorage {
             return Err(anyhow!("Wallet name cannot be empty"));
         }
         
-        // Check if wallet name already exists
-        if self.wallets.contains_key(&wallet_name) {
-            return Err(anyhow!("Wallet with name '{}' already exists", wallet_name));
-        }
-        
-        // Validate private key format
+        // Validate private key format and that it is a valid secp256k1 scalar
         let private_key = private_key.trim_start_matches("0x");
-        hex::decode(private_key)
-            .map_err(|_| anyhow!("Invalid private key format"))?;
+        let key_bytes = Zeroizing::new(
+            hex::decode(private_key).map_err(|_| anyhow!("Invalid private key format"))?,
+        );
+        crate::blockchain::services::wallet::validate_private_key(&key_bytes)
+            .map_err(|e| anyhow!("Invalid private key: {}", e))?;
             
         // Validate address format
         if !public_address.starts_with("0x") || public_address.len() != 42 {
             return Err(anyhow!("Invalid Ethereum address format"));
         }
         
-        // Encrypt the private key
-        let encrypted_key = self.encrypt_private_key(private_key, master_password)?;
-
-        // Store the wallet
-        let wallet = StoredWallet {
-            wallet_name: wallet_name.clone(),
-            encrypted_private_key: encrypted_key,
-            public_address: public_address.to_lowercase(),
-            created_at: Utc::now(),
-        };
-        
-        self.wallets.insert(wallet_name, wallet);
-        self.updated_at = Utc::now();
-        
-        // Save to disk
-        self.save()?;
-        
-        Ok(())
+        self.locked_update(|storage| {
+            // Check if wallet name already exists (possibly added by another process)
+            let existing = storage.wallets.get(&wallet_name);
+            if existing.is_some() && !overwrite {
+                return Err(StorageError::WalletExists(wallet_name).into());
+            }
+            // Re-registering the same address keeps its history; a new key starts afresh
+            let public_address = public_address.to_lowercase();
+            let same_wallet = existing.filter(|w| w.public_address.eq_ignore_ascii_case(&public_address));
+            let created_at = same_wallet.map_or_else(Utc::now, |w| w.created_at);
+            let (daily_limit_wei, daily_spend) = same_wallet
+                .map(|w| (w.daily_limit_wei.clone(), w.daily_spend.clone()))
+                .unwrap_or_default();
+
+            // Encrypt the private key under its own salt
+            let (salt, encrypted_key) = storage.encrypt_private_key(private_key, master_password)?;
+
+            let wallet = StoredWallet {
+                wallet_name: wallet_name.clone(),
+                encrypted_private_key: encrypted_key,
+                salt: Some(salt),
+                public_address,
+                created_at,
+                daily_limit_wei,
+                daily_spend,
+            };
+            Ok(storage.wallets.insert(wallet_name, wallet))
+        })
     }
 
-    /// Encrypt a private key with the master password
-    fn encrypt_private_key(&self, private_key: &str, _master_password: &str) -> Result<String> {
-        // In a real implementation, you would use a proper encryption scheme like AES-GCM
-        // For now, we'll just return the key as-is (NOT SECURE FOR PRODUCTION)
-        Ok(private_key.to_string())
+    /// Encrypt a private key with a key derived from the master password and a fresh
+    /// per-wallet salt. Returns `(salt, encrypted_payload)`.
+    fn encrypt_private_key(&self, private_key: &str, master_password: &str) -> Result<(String, String)> {
+        let salt = encryption::generate_salt();
+        let payload = encryption::encrypt_with_salt(private_key, master_password, &salt)?;
+        Ok((salt.as_str().to_string(), payload))
     }
     
-    /// Decrypt a private key with the master password
-    pub fn decrypt_private_key(&self, encrypted_key: &str, master_password: &str) -> Result<String> {
+    /// Decrypt a stored wallet's private key with the master password.
+    /// The plaintext is wiped from memory when the returned value is dropped.
+    pub fn decrypt_private_key(&self, wallet: &StoredWallet, master_password: &str) -> Result<Zeroizing<String>> {
         if !self.verify_master_password(master_password) {
-            return Err(anyhow!("Invalid master password"));
+            return Err(StorageError::InvalidPassword.into());
+        }
+
+        match &wallet.salt {
+            Some(salt) => encryption::decrypt_with_salt(&wallet.encrypted_private_key, master_password, salt),
+            // Legacy entries written before per-wallet encryption hold the key as-is
+            None => Ok(Zeroizing::new(wallet.encrypted_private_key.clone())),
         }
-        
-        // In a real implementation, you would use proper encryption/decryption here
-        // For now, we'll just return the key as-is (not secure for production!)
-        Ok(encrypted_key.to_string())
     }
     
     /// Get a decrypted private key for a wallet
@@ -181,18 +267,129 @@ impl WalletStorage {
         &self,
         wallet_name: &str,
         master_password: &str,
-    ) -> Result<String> {
+    ) -> Result<Zeroizing<String>> {
         // Verify master password if set
         if !self.master_password_hash.is_empty() && !self.verify_master_password(master_password) {
-            return Err(anyhow!("Invalid master password"));
+            return Err(StorageError::InvalidPassword.into());
         }
 
         // Get the wallet
         let wallet = self.wallets.get(wallet_name)
-            .ok_or_else(|| anyhow!("Wallet '{}' not found", wallet_name))?;
+            .ok_or_else(|| StorageError::WalletNotFound(wallet_name.to_string()))?;
 
         // Decrypt the private key
-        self.decrypt_private_key(&wallet.encrypted_private_key, master_password)
+        self.decrypt_private_key(wallet, master_password)
+    }
+    
+    /// Rotate the master password, re-encrypting every stored key under the new one.
+    ///
+    /// The rotated storage is written to disk before it replaces the in-memory copy,
+    /// so if any step fails both the file and this instance are left untouched.
+    pub fn change_master_password(&mut self, old_password: &str, new_password: &str) -> Result<()> {
+        if !self.verify_master_password(old_password) {
+            return Err(StorageError::InvalidPassword.into());
+        }
+
+        self.locked_update(|storage| {
+            let mut reencrypted = Vec::with_capacity(storage.wallets.len());
+            for (name, wallet) in &storage.wallets {
+                let private_key = storage.decrypt_private_key(wallet, old_password)?;
+                reencrypted.push((name.clone(), storage.encrypt_private_key(&private_key, new_password)?));
+            }
+            storage.set_master_password(new_password)?;
+            for (name, (salt, encrypted_key)) in reencrypted {
+                let wallet = storage.wallets.get_mut(&name).expect("wallet exists");
+                wallet.salt = Some(salt);
+                wallet.encrypted_private_key = encrypted_key;
+            }
+            Ok(())
+        })
+        .context("Failed to persist re-encrypted wallet storage")
+    }
+    
+    /// Re-encrypt a single wallet's key under a fresh per-wallet salt.
+    /// Returns `true` when the entry was a legacy (unsalted) wallet that got upgraded.
+    pub fn reencrypt_wallet(&mut self, wallet_name: &str, master_password: &str) -> Result<bool> {
+        self.locked_update(|storage| {
+            let wallet = storage.wallets.get(wallet_name)
+                .ok_or_else(|| StorageError::WalletNotFound(wallet_name.to_string()))?;
+            let was_legacy = wallet.salt.is_none();
+            let private_key = storage.decrypt_private_key(wallet, master_password)?;
+            let (salt, encrypted_key) = storage.encrypt_private_key(&private_key, master_password)?;
+
+            let wallet = storage.wallets.get_mut(wallet_name).expect("wallet exists");
+            wallet.salt = Some(salt);
+            wallet.encrypted_private_key = encrypted_key;
+            Ok(was_legacy)
+        })
+    }
+
+    /// Set (or clear with `None`) the daily spending limit of a wallet
+    pub fn set_wallet_limit(
+        &mut self,
+        wallet_name: &str,
+        daily_limit_wei: Option<U256>,
+        master_password: &str,
+    ) -> Result<()> {
+        if !self.verify_master_password(master_password) {
+            return Err(StorageError::InvalidPassword.into());
+        }
+        self.locked_update(|storage| {
+            let wallet = storage.wallets.get_mut(wallet_name)
+                .ok_or_else(|| StorageError::WalletNotFound(wallet_name.to_string()))?;
+            wallet.daily_limit_wei = daily_limit_wei.map(|l| l.to_string());
+            Ok(())
+        })
+    }
+
+    /// Check that spending `amount` wei at `now` stays within the wallet's daily limit
+    pub fn check_spend(&self, wallet_name: &str, amount: U256, now: DateTime<Utc>) -> Result<()> {
+        let wallet = self.wallets.get(wallet_name)
+            .ok_or_else(|| StorageError::WalletNotFound(wallet_name.to_string()))?;
+        let limit = match &wallet.daily_limit_wei {
+            Some(l) => U256::from_dec_str(l).map_err(|_| anyhow!("Stored daily limit is invalid"))?,
+            None => return Ok(()),
+        };
+        let spent = wallet.spent_on(now);
+        if spent.saturating_add(amount) > limit {
+            return Err(anyhow!(
+                "Transfer of {} wei exceeds the daily limit of wallet '{}' (limit {} wei, already spent {} wei today)",
+                amount, wallet_name, limit, spent
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check `amount` wei against the wallet's daily limit and, if it fits, add it to the
+    /// spend for the UTC day of `now`, all under the storage lock so concurrent sends cannot
+    /// both pass the check. Undo with `release_spend` if the transfer is not sent.
+    pub fn reserve_spend(&mut self, wallet_name: &str, amount: U256, now: DateTime<Utc>) -> Result<()> {
+        self.locked_update(|storage| {
+            storage.check_spend(wallet_name, amount, now)?;
+            let wallet = storage.wallets.get_mut(wallet_name).expect("wallet exists");
+            let spent = wallet.spent_on(now).saturating_add(amount);
+            wallet.daily_spend = Some(DailySpend {
+                day: now.date_naive(),
+                spent_wei: spent.to_string(),
+            });
+            Ok(())
+        })
+    }
+
+    /// Give back `amount` wei reserved by `reserve_spend` at `now`. Nothing is released once
+    /// the UTC day has rolled over, since that day's counter is gone already.
+    pub fn release_spend(&mut self, wallet_name: &str, amount: U256, now: DateTime<Utc>) -> Result<()> {
+        self.locked_update(|storage| {
+            let wallet = storage.wallets.get_mut(wallet_name)
+                .ok_or_else(|| StorageError::WalletNotFound(wallet_name.to_string()))?;
+            if let Some(spend) = wallet.daily_spend.as_mut() {
+                if spend.day == now.date_naive() {
+                    let spent = U256::from_dec_str(&spend.spent_wei).unwrap_or_default();
+                    spend.spent_wei = spent.saturating_sub(amount).to_string();
+                }
+            }
+            Ok(())
+        })
     }
     
     /// Get a wallet by name
@@ -200,10 +397,9 @@ impl WalletStorage {
         self.wallets.get(wallet_name)
     }
     
-    /// Get a wallet by address
+    /// Get a wallet by address, in any letter case
     pub fn get_wallet_by_address(&self, address: &str) -> Option<&StoredWallet> {
-        let address = address.to_lowercase();
-        self.wallets.values().find(|w| w.public_address == address)
+        self.wallets.values().find(|w| w.public_address.eq_ignore_ascii_case(address))
     }
     
     /// List all wallet names
@@ -228,28 +424,127 @@ impl WalletStorage {
         Ok(result)
     }
 
-    /// Save the wallet storage to disk
+    /// Save the wallet storage to disk, overwriting the file with this instance's state
     pub fn save(&mut self) -> Result<()> {
+        let _lock = StorageLock::acquire(&self.storage_path, STORAGE_LOCK_TIMEOUT)?;
         self.updated_at = Utc::now();
         save_wallet_storage(&self.storage_path, self)
     }
+
+    /// Write the storage back to disk under the inter-process lock, first picking up
+    /// wallets other processes saved, so a stale in-memory copy never overwrites them.
+    pub fn flush(&mut self) -> Result<()> {
+        self.locked_update(|_| Ok(()))
+    }
+
+    /// Run a read-modify-write cycle while holding the inter-process storage lock.
+    ///
+    /// The latest on-disk state is loaded first so wallets written by other processes
+    /// sharing the file are not lost, `update` is applied to a copy, and the copy is
+    /// persisted before it replaces this instance. On error nothing is changed.
+    fn locked_update<T>(&mut self, update: impl FnOnce(&mut WalletStorage) -> Result<T>) -> Result<T> {
+        let _lock = StorageLock::acquire(&self.storage_path, STORAGE_LOCK_TIMEOUT)?;
+
+        let mut next = self.clone();
+        next.reload_from_disk()?;
+        let out = update(&mut next)?;
+        next.updated_at = Utc::now();
+        save_wallet_storage(&next.storage_path, &next)?;
+        *self = next;
+        Ok(out)
+    }
+
+    /// Replace the wallets with the on-disk copy, if the file exists
+    fn reload_from_disk(&mut self) -> Result<()> {
+        if !self.storage_path.exists() {
+            return Ok(());
+        }
+        let content = fs::read_to_string(&self.storage_path)
+            .context("Failed to read wallet storage file")?;
+        let on_disk: WalletStorage = serde_json::from_str(&content)
+            .context("Failed to parse wallet storage")?;
+        if !self.master_password_hash.is_empty()
+            && on_disk.master_password_hash != self.master_password_hash
+        {
+            return Err(anyhow!(
+                "Wallet storage master password was changed by another process; reload the storage"
+            ));
+        }
+        self.wallets = on_disk.wallets;
+        self.master_password_hash = on_disk.master_password_hash;
+        self.created_at = on_disk.created_at;
+        Ok(())
+    }
     
     /// Remove a wallet from storage
     pub fn remove_wallet(&mut self, wallet_name: &str, master_password: &str) -> Result<bool> {
         // Verify master password
         if !self.verify_master_password(master_password) {
-            return Err(anyhow!("Invalid master password"));
+            return Err(StorageError::InvalidPassword.into());
         }
 
-        if self.wallets.remove(wallet_name).is_some() {
-            self.updated_at = Utc::now();
-            Ok(true)
-        } else {
-            Ok(false)
+        self.locked_update(|storage| Ok(storage.wallets.remove(wallet_name).is_some()))
+    }
+}
+
+/// How long to wait for another process to release the storage lock
+const STORAGE_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Advisory exclusive lock on `<storage file>.lock`, shared by every process using
+/// the same wallet file. Released when dropped.
+struct StorageLock {
+    file: fs::File,
+}
+
+impl StorageLock {
+    fn acquire(storage_path: &Path, timeout: Duration) -> Result<Self> {
+        let mut lock_path = storage_path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .context("Failed to open wallet storage lock file")?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match file.try_lock_exclusive() {
+                Ok(()) => return Ok(Self { file }),
+                Err(e) if e.raw_os_error() == fs2::lock_contended_error().raw_os_error() => {
+                    if Instant::now() >= deadline {
+                        return Err(anyhow!(
+                            "Wallet storage {} is locked by another process",
+                            storage_path.display()
+                        ));
+                    }
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                Err(e) => return Err(e).context("Failed to lock wallet storage"),
+            }
         }
     }
 }
 
+impl Drop for StorageLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+/// Runs `update` against the shared storage on a blocking thread. Anything that saves takes
+/// the file lock above, which may wait up to `STORAGE_LOCK_TIMEOUT` on another process, so it
+/// must not run on an async worker.
+pub async fn update_blocking<T: Send + 'static>(
+    storage: &std::sync::Arc<tokio::sync::Mutex<WalletStorage>>,
+    update: impl FnOnce(&mut WalletStorage) -> T + Send + 'static,
+) -> T {
+    let mut storage = storage.clone().lock_owned().await;
+    tokio::task::spawn_blocking(move || update(&mut storage))
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
 /// Get the default path for the wallet storage file.
 /// 
 /// On Linux: ~/.local/share/mcp/wallets.json
@@ -285,7 +580,7 @@ pub fn load_or_create_wallet_storage(
         
         // Verify the master password
         if !storage.verify_master_password(master_password) {
-            return Err(anyhow!("Incorrect master password"));
+            return Err(StorageError::IncorrectPassword.into());
         }
         
         Ok(storage)
@@ -308,28 +603,36 @@ pub fn load_or_create_wallet_storage(
     }
 }
 
-/// Save the wallet storage to a file
+/// Save the wallet storage to a file atomically.
+///
+/// The JSON is written to a temporary file in the same directory, fsynced, and then
+/// moved over the destination (`MoveFileEx` with replace on Windows, `rename` elsewhere),
+/// so a crash leaves either the old or the new file, never a truncated one. The
+/// temporary file is removed if any step fails.
 pub fn save_wallet_storage(file_path: &Path, storage: &WalletStorage) -> Result<()> {
-    // Create a temporary file for atomic write
-    let temp_path = file_path.with_extension("tmp");
-    
-    // Write to temp file
     let content = serde_json::to_string_pretty(storage)
         .context("Failed to serialize wallet storage")?;
-    
-    std::fs::write(&temp_path, content)
+
+    let dir = match file_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut temp = tempfile::NamedTempFile::new_in(dir)
+        .context("Failed to create temporary wallet storage file")?;
+    temp.write_all(content.as_bytes())
         .context("Failed to write wallet storage file")?;
-    
-    // Rename temp file to final location (atomic on Unix-like systems)
-    std::fs::rename(&temp_path, file_path)
-        .or_else(|_| {
-            // If rename fails (e.g., cross-device), try copy + remove
-            std::fs::copy(&temp_path, file_path)?;
-            std::fs::remove_file(&temp_path)?;
-            Ok::<(), std::io::Error>(())
-        })
+    temp.as_file().sync_all()
+        .context("Failed to sync wallet storage file")?;
+    temp.persist(file_path)
+        .map_err(|e| e.error)
         .context("Failed to finalize wallet storage file")?;
-    
+
+    // Make the rename itself durable
+    #[cfg(unix)]
+    fs::File::open(dir)
+        .and_then(|d| d.sync_all())
+        .context("Failed to sync wallet storage directory")?;
+
     Ok(())
 }
 
@@ -377,8 +680,8 @@ mod tests {
         assert_eq!(wallet.public_address, public_address);
         
         // Retrieve the private key
-        let stored_private_key = storage.get_private_key(&wallet_name, master_password).unwrap();
-        assert_eq!(stored_private_key, private_key);
+        let stored_private_key: Zeroizing<String> = storage.get_private_key(&wallet_name, master_password).unwrap();
+        assert_eq!(stored_private_key.as_str(), private_key);
         
         // Verify the wallet exists in the list
         let wallets = storage.list_wallets();
@@ -400,6 +703,64 @@ mod tests {
         assert_eq!(reloaded.list_wallets().len(), 1);
     }
     
+    #[test]
+    fn test_add_wallet_rejects_invalid_secp256k1_keys() {
+        let temp_dir = tempdir().unwrap();
+        let mut storage = WalletStorage::with_encryption("test_password", temp_dir.path().join("wallets.json")).unwrap();
+        let address = "0x1234567890abcdef1234567890abcdef12345678".to_string();
+
+        let zero = "0".repeat(64);
+        let err = storage.add_wallet("zero".to_string(), &zero, address.clone(), "test_password").unwrap_err();
+        assert!(err.to_string().contains("must not be zero"), "{}", err);
+
+        let curve_order = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+        let err = storage.add_wallet("order".to_string(), curve_order, address.clone(), "test_password").unwrap_err();
+        assert!(err.to_string().contains("curve order"), "{}", err);
+
+        let short = "0123456789abcdef";
+        assert!(storage.add_wallet("short".to_string(), short, address.clone(), "test_password").is_err());
+        assert!(storage.list_wallets().is_empty());
+
+        let valid = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        storage.add_wallet("ok".to_string(), valid, address, "test_password").unwrap();
+        assert_eq!(storage.list_wallets().len(), 1);
+    }
+
+    #[test]
+    fn test_upsert_replaces_existing_wallet() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("wallets.json");
+        let mut storage = WalletStorage::with_encryption("test_password", storage_path.clone()).unwrap();
+        let first_key = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        let second_key = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+        let first = "0x1234567890abcdef1234567890abcdef12345678".to_string();
+        let second = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23".to_string();
+
+        storage.add_wallet("main".into(), first_key, first.clone(), "test_password").unwrap();
+        storage.set_wallet_limit("main", Some(U256::from(1000u64)), "test_password").unwrap();
+        let err = storage.add_wallet("main".into(), second_key, second.clone(), "test_password").unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert!(storage.upsert_wallet("main".into(), second_key, second.clone(), "bad_password").is_err());
+
+        assert!(matches!(err.downcast_ref::<StorageError>(), Some(StorageError::WalletExists(name)) if name == "main"));
+        let created_at = storage.get_wallet("main").unwrap().created_at;
+
+        // Same address again keeps the limit and the original creation time
+        let previous = storage.upsert_wallet("main".into(), first_key, first.clone(), "test_password").unwrap();
+        assert_eq!(previous.unwrap().public_address, first);
+        assert_eq!(storage.get_wallet("main").unwrap().daily_limit_wei.as_deref(), Some("1000"));
+        assert_eq!(storage.get_wallet("main").unwrap().created_at, created_at);
+
+        // A different key replaces the entry, on disk too
+        storage.upsert_wallet("main".into(), second_key, second.clone(), "test_password").unwrap();
+        let reloaded = load_or_create_wallet_storage(&storage_path, "test_password").unwrap();
+        let wallet = reloaded.get_wallet("main").unwrap();
+        assert_eq!(wallet.public_address, second);
+        assert!(wallet.daily_limit_wei.is_none());
+        assert_eq!(reloaded.get_private_key("main", "test_password").unwrap().as_str(), second_key);
+        assert!(storage.upsert_wallet("new".into(), first_key, first, "test_password").unwrap().is_none());
+    }
+
     #[test]
     fn test_wallet_removal() {
         let temp_dir = tempdir().unwrap();
@@ -442,4 +803,234 @@ mod tests {
         assert!(reloaded.get_wallet(&wallet_name).is_none());
         assert!(reloaded.list_wallets().is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_change_master_password_reencrypts_all_wallets() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("wallets.json");
+        let old_password = "old_password";
+        let new_password = "new_password";
+
+        let mut storage = WalletStorage::with_encryption(old_password, storage_path.clone())
+            .expect("Failed to create wallet storage");
+        let key_a = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        let key_b = "fedcba9876543210fedcba9876543210fedcba9876543210fedcba9876543210";
+        storage.add_wallet("a".to_string(), key_a, "0x1234567890abcdef1234567890abcdef12345678".to_string(), old_password)
+            .unwrap();
+        storage.add_wallet("b".to_string(), key_b, "0xabcdef1234567890abcdef1234567890abcdef12".to_string(), old_password)
+            .unwrap();
+
+        // Wrong old password is rejected and nothing changes
+        assert!(storage.change_master_password("wrong_password", new_password).is_err());
+        assert!(storage.verify_master_password(old_password));
+
+        storage.change_master_password(old_password, new_password).unwrap();
+        assert!(storage.get_private_key("a", old_password).is_err());
+        assert_eq!(storage.get_private_key("a", new_password).unwrap().as_str(), key_a);
+        assert_eq!(storage.get_private_key("b", new_password).unwrap().as_str(), key_b);
+
+        // The rotation was persisted
+        let reloaded = load_or_create_wallet_storage(&storage_path, new_password).unwrap();
+        assert_eq!(reloaded.get_private_key("b", new_password).unwrap().as_str(), key_b);
+        assert!(load_or_create_wallet_storage(&storage_path, old_password).is_err());
+    }
+
+    #[test]
+    fn test_daily_limit_rejects_overspend_and_resets_next_day() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("wallets.json");
+        let master_password = "test_password";
+        let mut storage = WalletStorage::with_encryption(master_password, storage_path)
+            .expect("Failed to create wallet storage");
+        let private_key = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        storage.add_wallet("w".to_string(), private_key, "0x1234567890abcdef1234567890abcdef12345678".to_string(), master_password)
+            .unwrap();
+        storage.set_wallet_limit("w", Some(U256::from(100)), master_password).unwrap();
+
+        let day1 = DateTime::parse_from_rfc3339("2024-05-01T23:00:00Z").unwrap().with_timezone(&Utc);
+        let day2 = DateTime::parse_from_rfc3339("2024-05-02T00:30:00Z").unwrap().with_timezone(&Utc);
+
+        storage.reserve_spend("w", U256::from(60), day1).unwrap();
+        // 60 + 50 > 100 on the same day, and the refused amount is not reserved
+        assert!(storage.reserve_spend("w", U256::from(50), day1).is_err());
+        assert!(storage.check_spend("w", U256::from(40), day1).is_ok());
+        // A released reservation frees the room again
+        storage.release_spend("w", U256::from(60), day1).unwrap();
+        assert!(storage.check_spend("w", U256::from(100), day1).is_ok());
+        // The counter resets after the UTC day boundary
+        storage.check_spend("w", U256::from(100), day2).unwrap();
+        assert!(storage.check_spend("w", U256::from(101), day2).is_err());
+    }
+
+    #[test]
+    fn test_same_key_encrypts_differently_per_wallet() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("wallets.json");
+        let master_password = "test_password";
+        let mut storage = WalletStorage::with_encryption(master_password, storage_path)
+            .expect("Failed to create wallet storage");
+        let private_key = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        let address = "0x1234567890abcdef1234567890abcdef12345678".to_string();
+        storage.add_wallet("one".to_string(), private_key, address.clone(), master_password).unwrap();
+        storage.add_wallet("two".to_string(), private_key, address, master_password).unwrap();
+
+        let one = storage.get_wallet("one").unwrap();
+        let two = storage.get_wallet("two").unwrap();
+        assert_ne!(one.salt, two.salt);
+        assert_ne!(one.encrypted_private_key, two.encrypted_private_key);
+        assert!(!one.encrypted_private_key.contains(private_key));
+        assert_eq!(storage.get_private_key("one", master_password).unwrap().as_str(), private_key);
+        assert_eq!(storage.get_private_key("two", master_password).unwrap().as_str(), private_key);
+    }
+
+    #[test]
+    fn test_reencrypt_upgrades_legacy_wallet() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("wallets.json");
+        let master_password = "test_password";
+        let mut storage = WalletStorage::with_encryption(master_password, storage_path.clone())
+            .expect("Failed to create wallet storage");
+        let private_key = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        storage.add_wallet(
+            "legacy".to_string(),
+            private_key,
+            "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+            master_password,
+        ).unwrap();
+
+        // Downgrade the entry to the pre-salt format (key stored as-is)
+        {
+            let wallet = storage.wallets.get_mut("legacy").unwrap();
+            wallet.salt = None;
+            wallet.encrypted_private_key = private_key.to_string();
+        }
+        storage.save().unwrap();
+        assert!(storage.reencrypt_wallet("legacy", "wrong").is_err());
+
+        assert!(storage.reencrypt_wallet("legacy", master_password).unwrap());
+        let wallet = storage.get_wallet("legacy").unwrap();
+        assert!(wallet.salt.is_some());
+        assert_ne!(wallet.encrypted_private_key, private_key);
+
+        let reloaded = load_or_create_wallet_storage(&storage_path, master_password).unwrap();
+        assert_eq!(reloaded.get_private_key("legacy", master_password).unwrap().as_str(), private_key);
+        assert!(!storage.reencrypt_wallet("legacy", master_password).unwrap());
+    }
+
+    #[test]
+    fn test_save_replaces_existing_file_with_valid_json() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("wallets.json");
+        let mut storage = WalletStorage::with_encryption("test_password", storage_path.clone()).unwrap();
+        storage.add_wallet(
+            "w".to_string(),
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+            "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+            "test_password",
+        ).unwrap();
+        fs::write(&storage_path, "stale contents that are not json").unwrap();
+        save_wallet_storage(&storage_path, &storage).unwrap();
+
+        let content = fs::read_to_string(&storage_path).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&content).expect("valid JSON");
+        assert!(parsed["wallets"]["w"].is_object());
+        // No temporary files are left behind
+        let mut entries: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec!["wallets.json", "wallets.json.lock"]);
+    }
+
+    #[test]
+    fn test_concurrent_instances_do_not_lose_wallets() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("wallets.json");
+        let master_password = "test_password";
+        load_or_create_wallet_storage(&storage_path, master_password).unwrap();
+
+        // Two independent instances of the same file, like two server processes
+        let handles: Vec<_> = ["a", "b"]
+            .into_iter()
+            .map(|prefix| {
+                let path = storage_path.clone();
+                std::thread::spawn(move || {
+                    let mut storage = load_or_create_wallet_storage(&path, master_password).unwrap();
+                    for i in 0..3 {
+                        storage.add_wallet(
+                            format!("{}{}", prefix, i),
+                            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+                            "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+                            master_password,
+                        ).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let reloaded = load_or_create_wallet_storage(&storage_path, master_password).unwrap();
+        let mut names = reloaded.list_wallets();
+        names.sort();
+        assert_eq!(names, vec!["a0", "a1", "a2", "b0", "b1", "b2"]);
+    }
+
+    #[test]
+    fn test_flush_keeps_wallets_saved_by_another_instance() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("wallets.json");
+        let master_password = "test_password";
+        let mut stale = load_or_create_wallet_storage(&storage_path, master_password).unwrap();
+        let mut other = load_or_create_wallet_storage(&storage_path, master_password).unwrap();
+        other.add_wallet(
+            "other".into(),
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+            "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+            master_password,
+        ).unwrap();
+
+        stale.flush().unwrap();
+        assert!(stale.get_wallet("other").is_some());
+        let reloaded = load_or_create_wallet_storage(&storage_path, master_password).unwrap();
+        assert_eq!(reloaded.list_wallets(), vec!["other"]);
+    }
+
+    #[test]
+    fn test_held_lock_reports_locked_storage() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("wallets.json");
+        let _held = StorageLock::acquire(&storage_path, Duration::from_millis(50)).unwrap();
+
+        let err = StorageLock::acquire(&storage_path, Duration::from_millis(50))
+            .err()
+            .expect("second lock should fail");
+        assert!(err.to_string().contains("locked by another process"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_update_waiting_on_lock_leaves_runtime_free() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("wallets.json");
+        let storage = std::sync::Arc::new(tokio::sync::Mutex::new(
+            WalletStorage::with_encryption("test_password", storage_path.clone()).unwrap(),
+        ));
+        let held = StorageLock::acquire(&storage_path, Duration::from_millis(50)).unwrap();
+
+        let waiting = tokio::spawn({
+            let storage = storage.clone();
+            async move { update_blocking(&storage, |s| s.flush()).await }
+        });
+        // The single-threaded test runtime still runs other tasks while the save waits
+        tokio::time::timeout(Duration::from_secs(1), tokio::time::sleep(Duration::from_millis(50)))
+            .await
+            .expect("runtime was parked by the storage lock");
+        assert!(!waiting.is_finished());
+
+        drop(held);
+        waiting.await.unwrap().unwrap();
+        assert!(storage_path.exists());
+    }
+}
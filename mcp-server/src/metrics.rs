@@ -0,0 +1,190 @@
+// src/metrics.rs
+
+//! Prometheus metrics served on `GET /metrics` when METRICS_ENABLED is set.
+//!
+//! MCP request and tool-call counters live in `AppState.metrics`. Upstream calls (JSON-RPC
+//! latency, Etherscan requests) are made from service functions that have no access to the
+//! state, so they are recorded in a process-wide registry and appended when rendering.
+
+use dashmap::DashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Upper bounds (seconds) of the RPC latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+#[derive(Debug, Default)]
+struct Histogram {
+    // Cumulative count per bucket in LATENCY_BUCKETS, then +Inf
+    buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        for (bucket, le) in self.buckets.iter().zip(LATENCY_BUCKETS.iter()) {
+            if secs <= *le {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.buckets[LATENCY_BUCKETS.len()].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Counters for requests handled by this server.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests: DashMap<String, AtomicU64>,
+    tool_calls: DashMap<(String, &'static str), AtomicU64>,
+}
+
+#[derive(Debug, Default)]
+struct UpstreamMetrics {
+    rpc_latency: DashMap<String, Histogram>,
+    etherscan_requests: DashMap<(String, String), AtomicU64>,
+}
+
+fn upstream() -> &'static UpstreamMetrics {
+    static UPSTREAM: OnceLock<UpstreamMetrics> = OnceLock::new();
+    UPSTREAM.get_or_init(Default::default)
+}
+
+/// Records how long a JSON-RPC call to a node took, by method.
+pub fn record_rpc_latency(method: &str, elapsed: Duration) {
+    upstream().rpc_latency.entry(method.to_string()).or_default().observe(elapsed);
+}
+
+/// Counts a request to the Etherscan API by `module` and `action`.
+pub fn record_etherscan_request(module: &str, action: &str) {
+    upstream()
+        .etherscan_requests
+        .entry((module.to_string(), action.to_string()))
+        .or_default()
+        .fetch_add(1, Ordering::Relaxed);
+}
+
+impl Metrics {
+    /// Counts an MCP request. Callers pass "unknown" for methods the server doesn't
+    /// implement, so clients can't grow the label set without bound.
+    pub fn record_request(&self, method: &str) {
+        self.requests.entry(method.to_string()).or_default().fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a finished tool call as `success` or `error` (same "unknown" rule as above).
+    pub fn record_tool_call(&self, tool: &str, success: bool) {
+        let outcome = if success { "success" } else { "error" };
+        self.tool_calls
+            .entry((tool.to_string(), outcome))
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Renders these counters plus the upstream metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        header(&mut out, "mcp_requests_total", "counter", "MCP requests by JSON-RPC method.");
+        for (method, n) in sorted(&self.requests, |m| vec![("method", m.clone())]) {
+            sample(&mut out, "mcp_requests_total", &method, n);
+        }
+
+        header(&mut out, "mcp_tool_calls_total", "counter", "Tool calls by tool and outcome.");
+        for (labels, n) in sorted(&self.tool_calls, |(tool, outcome)| {
+            vec![("tool", tool.clone()), ("outcome", outcome.to_string())]
+        }) {
+            sample(&mut out, "mcp_tool_calls_total", &labels, n);
+        }
+
+        let up = upstream();
+        header(&mut out, "rpc_request_duration_seconds", "histogram", "JSON-RPC call latency by method.");
+        let mut methods: Vec<String> = up.rpc_latency.iter().map(|e| e.key().clone()).collect();
+        methods.sort();
+        for method in methods {
+            let Some(h) = up.rpc_latency.get(&method) else { continue };
+            for (i, bucket) in h.buckets.iter().enumerate() {
+                let le = LATENCY_BUCKETS.get(i).map_or("+Inf".to_string(), |b| b.to_string());
+                let labels = format_labels(&[("method", method.clone()), ("le", le)]);
+                sample(&mut out, "rpc_request_duration_seconds_bucket", &labels, bucket.load(Ordering::Relaxed));
+            }
+            let labels = format_labels(&[("method", method.clone())]);
+            let sum = h.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+            let _ = writeln!(out, "rpc_request_duration_seconds_sum{} {}", labels, sum);
+            let count = h.buckets[LATENCY_BUCKETS.len()].load(Ordering::Relaxed);
+            sample(&mut out, "rpc_request_duration_seconds_count", &labels, count);
+        }
+
+        header(&mut out, "etherscan_requests_total", "counter", "Etherscan API requests by module and action.");
+        for (labels, n) in sorted(&up.etherscan_requests, |(module, action)| {
+            vec![("module", module.clone()), ("action", action.clone())]
+        }) {
+            sample(&mut out, "etherscan_requests_total", &labels, n);
+        }
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: u64) {
+    let _ = writeln!(out, "{}{} {}", name, labels, value);
+}
+
+// Formatted label sets with their values, in a stable order
+fn sorted<K, F>(map: &DashMap<K, AtomicU64>, labels: F) -> Vec<(String, u64)>
+where
+    K: std::hash::Hash + Eq,
+    F: Fn(&K) -> Vec<(&'static str, String)>,
+{
+    let mut rows: Vec<(String, u64)> = map
+        .iter()
+        .map(|e| (format_labels(&labels(e.key())), e.value().load(Ordering::Relaxed)))
+        .collect();
+    rows.sort();
+    rows
+}
+
+fn format_labels(labels: &[(&str, String)]) -> String {
+    let inner: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| {
+            let v = v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            format!("{}=\"{}\"", k, v)
+        })
+        .collect();
+    format!("{{{}}}", inner.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counters_and_histogram() {
+        let metrics = Metrics::default();
+        metrics.record_request("tools/call");
+        metrics.record_request("tools/call");
+        metrics.record_tool_call("get_balance", true);
+        metrics.record_tool_call("get_balance", false);
+        record_rpc_latency("metrics_test_method", Duration::from_millis(200));
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE mcp_requests_total counter"), "{}", text);
+        assert!(text.contains("mcp_requests_total{method=\"tools/call\"} 2"), "{}", text);
+        assert!(text.contains("mcp_tool_calls_total{tool=\"get_balance\",outcome=\"success\"} 1"), "{}", text);
+        assert!(text.contains("mcp_tool_calls_total{tool=\"get_balance\",outcome=\"error\"} 1"), "{}", text);
+        assert!(text.contains("rpc_request_duration_seconds_bucket{method=\"metrics_test_method\",le=\"0.1\"} 0"), "{}", text);
+        assert!(text.contains("rpc_request_duration_seconds_bucket{method=\"metrics_test_method\",le=\"0.25\"} 1"), "{}", text);
+        assert!(text.contains("rpc_request_duration_seconds_count{method=\"metrics_test_method\"} 1"), "{}", text);
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(format_labels(&[("tool", "a\"b\\c".into())]), "{tool=\"a\\\"b\\\\c\"}");
+    }
+}
@@ -1,9 +1,12 @@
 //! Utility functions for the SEI MCP server
 
+use ethers_core::types::U256;
 use serde::de::DeserializeOwned;
 use serde_json::{Value, from_value};
 use crate::mcp::protocol::{Response, error_codes};
 
+pub mod units;
+
 /// Helper function to extract a required argument from a JSON object
 pub fn get_required_arg<T: DeserializeOwned>(
     args: &Value,
@@ -19,7 +22,447 @@ pub fn get_required_arg<T: DeserializeOwned>(
     })
 }
 
+/// Argument names that carry a chain ID (or chain alias)
+pub const CHAIN_ID_ARGS: [&str; 3] = ["chain_id", "chainId", "network"];
+
+/// Reads a chain ID given either as a JSON string (`"1"`, `"sepolia"`) or an integer (`1`)
+fn chain_id_value(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) if n.is_u64() => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Rewrites integer chain IDs in tool arguments as strings, so `"chain_id": 1` is
+/// treated like `"chain_id": "1"` by schema validation and every tool.
+pub fn stringify_chain_ids(args: &mut Value) {
+    if let Some(map) = args.as_object_mut() {
+        for key in CHAIN_ID_ARGS {
+            if let Some(v) = map.get_mut(key) {
+                if v.is_u64() {
+                    *v = Value::String(v.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Extracts the required `chain_id` argument, accepting a string or an integer
+#[allow(clippy::result_large_err)]
+pub fn get_chain_id(args: &Value, req_id: &Value) -> Result<String, Response> {
+    args.get("chain_id").and_then(chain_id_value).ok_or_else(|| {
+        Response::error(
+            req_id.clone(),
+            error_codes::INVALID_PARAMS,
+            "Missing or invalid required argument: 'chain_id'".to_string(),
+        )
+    })
+}
+
+/// Converts a gwei amount such as `"20"` or `"1.5"` to wei. At most 9 decimal places are
+/// accepted (1 wei = 1e-9 gwei); amounts that don't fit in a uint256 are rejected.
+pub fn gwei_to_wei(gwei: &str) -> anyhow::Result<U256> {
+    units::parse_units(gwei, 9).map_err(|e| anyhow::anyhow!("Invalid gwei amount: {}", e))
+}
+
+/// Reads the optional gas price from `gas_price` (wei) or `gas_price_gwei`; giving both is
+/// an error since they would silently disagree.
+#[allow(clippy::result_large_err)]
+pub fn get_gas_price(args: &Value, req_id: &Value) -> Result<Option<U256>, Response> {
+    let invalid = |msg: String| Response::error(req_id.clone(), error_codes::INVALID_PARAMS, msg);
+    let wei = args.get("gas_price").and_then(|v| v.as_str());
+    let gwei = args.get("gas_price_gwei").and_then(|v| v.as_str());
+    match (wei, gwei) {
+        (Some(_), Some(_)) => Err(invalid("Pass either 'gas_price' (wei) or 'gas_price_gwei', not both".into())),
+        (Some(wei), None) => U256::from_dec_str(wei.trim())
+            .map(Some)
+            .map_err(|_| invalid(format!("'gas_price' must be a decimal wei amount, got '{}'", wei))),
+        (None, Some(gwei)) => gwei_to_wei(gwei).map(Some).map_err(|e| invalid(e.to_string())),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Reads an optional `gas_limit` argument as a decimal gas amount, rejecting anything
+/// unparsable or zero instead of sending with a limit the node would refuse.
+#[allow(clippy::result_large_err)]
+pub fn get_gas_limit(args: &Value, req_id: &Value) -> Result<Option<U256>, Response> {
+    let Some(limit) = args.get("gas_limit").and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    match U256::from_dec_str(limit.trim()) {
+        Ok(gas) if !gas.is_zero() => Ok(Some(gas)),
+        _ => Err(Response::error(
+            req_id.clone(),
+            error_codes::INVALID_PARAMS,
+            format!("'gas_limit' must be a positive decimal gas amount, got '{}'", limit),
+        )),
+    }
+}
+
 /// Helper function to convert any value to a string
 pub fn to_string<T: std::fmt::Display>(value: T) -> String {
     value.to_string()
 }
+
+/// keccak256 of arbitrary bytes as a 0x-prefixed hex string
+pub fn keccak256_hex(data: &[u8]) -> String {
+    format!("0x{}", hex::encode(ethers_core::utils::keccak256(data)))
+}
+
+/// Strips whitespace and an optional leading `function` keyword from a function signature,
+/// so `function transfer(address to, uint256)` style spacing doesn't change the selector.
+/// Parameter names are not removed; pass types only.
+pub fn canonical_signature(signature: &str) -> String {
+    let s = signature.trim();
+    let s = s.strip_prefix("function ").unwrap_or(s);
+    s.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// 4-byte function selector: the first four bytes of keccak256 of the signature
+pub fn function_selector(signature: &str) -> [u8; 4] {
+    let mut sel = [0u8; 4];
+    sel.copy_from_slice(&ethers_core::utils::keccak256(canonical_signature(signature).as_bytes())[0..4]);
+    sel
+}
+
+/// Result of checking an address string with `check_address`
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct AddressCheck {
+    /// EIP-55 checksummed form
+    pub checksummed: String,
+    /// `valid` or `invalid` for mixed-case input, `not_checksummed` for all-lower/upper case
+    pub checksum: &'static str,
+}
+
+/// Checks that `input` is a well-formed 0x-prefixed 20-byte hex address and, when it is
+/// mixed-case, that its EIP-55 checksum matches. Returns why the address is malformed otherwise.
+pub fn check_address(input: &str) -> Result<AddressCheck, String> {
+    let hex_part = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .ok_or_else(|| "address must start with 0x".to_string())?;
+    if hex_part.len() != 40 {
+        return Err(format!("expected 40 hex characters after 0x, got {}", hex_part.len()));
+    }
+    if !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("address contains non-hex characters".to_string());
+    }
+    let address = ethers_core::types::Address::from_slice(&hex::decode(hex_part).map_err(|e| e.to_string())?);
+    let checksummed = ethers_core::utils::to_checksum(&address, None);
+    let mixed_case = hex_part.chars().any(|c| c.is_ascii_lowercase()) && hex_part.chars().any(|c| c.is_ascii_uppercase());
+    let checksum = if !mixed_case {
+        "not_checksummed"
+    } else if checksummed[2..] == *hex_part {
+        "valid"
+    } else {
+        "invalid"
+    };
+    Ok(AddressCheck { checksummed, checksum })
+}
+
+/// Column order used when exporting transaction lists as CSV
+pub const TX_CSV_HEADER: [&str; 8] = ["hash", "timestamp", "from", "to", "value", "gas_used", "fee", "status"];
+
+/// Quotes a CSV field when it contains a comma, quote or line break (RFC 4180)
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Serializes rows into CSV text with a header line
+pub fn to_csv(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut out = String::new();
+    let line = |fields: Vec<String>| fields.join(",") + "\n";
+    out.push_str(&line(header.iter().map(|h| csv_field(h)).collect()));
+    for row in rows {
+        out.push_str(&line(row.iter().map(|f| csv_field(f)).collect()));
+    }
+    out
+}
+
+/// Maps Etherscan-style transaction objects onto `TX_CSV_HEADER` columns and renders CSV.
+/// The fee is `gasUsed * gasPrice` in wei; status comes from `isError`/`txreceipt_status`.
+pub fn transactions_to_csv(transactions: &[Value]) -> String {
+    let field = |tx: &Value, key: &str| tx.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let rows: Vec<Vec<String>> = transactions
+        .iter()
+        .map(|tx| {
+            let gas_used = field(tx, "gasUsed");
+            let fee = match (
+                ethers_core::types::U256::from_dec_str(&gas_used),
+                ethers_core::types::U256::from_dec_str(&field(tx, "gasPrice")),
+            ) {
+                // A fee that overflows uint256 is garbage input; leave the cell blank like an unparsable one
+                (Ok(g), Ok(p)) => g.checked_mul(p).map(|f| f.to_string()).unwrap_or_default(),
+                _ => String::new(),
+            };
+            let status = if field(tx, "isError") == "1" || field(tx, "txreceipt_status") == "0" {
+                "failed"
+            } else {
+                "success"
+            };
+            vec![
+                field(tx, "hash"),
+                field(tx, "timeStamp"),
+                field(tx, "from"),
+                field(tx, "to"),
+                field(tx, "value"),
+                gas_used,
+                fee,
+                status.to_string(),
+            ]
+        })
+        .collect();
+    to_csv(&TX_CSV_HEADER, &rows)
+}
+
+/// Messages Etherscan sends with `status: "0"` when a list query simply has nothing in it.
+const ETHERSCAN_EMPTY_MESSAGES: [&str; 5] = [
+    "No transactions found",
+    "No records found",
+    "No logs found",
+    "No token transfers found",
+    "No internal transactions found",
+];
+
+/// The `result` list of an Etherscan list query (txlist, getLogs, ...). Etherscan answers an
+/// empty query with `status: "0"` too, so the known "no records" messages become an empty
+/// list rather than an error. `Err` carries the message of a genuine failure.
+pub fn etherscan_list_result(resp: &Value) -> Result<Value, String> {
+    let text = |key: &str| resp.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    if resp.get("status").and_then(|v| v.as_str()).is_some_and(|status| status != "1") {
+        let empty = ETHERSCAN_EMPTY_MESSAGES
+            .iter()
+            .any(|m| text("message").eq_ignore_ascii_case(m) || text("result").eq_ignore_ascii_case(m));
+        if !empty {
+            let message = if text("message").is_empty() { "Unknown error" } else { text("message") };
+            return Err(message.to_string());
+        }
+        return Ok(Value::Array(vec![]));
+    }
+    Ok(resp.get("result").cloned().unwrap_or_else(|| Value::Array(vec![])))
+}
+
+/// Object fields whose values are secrets and never belong in logs.
+const SECRET_FIELDS: [&str; 7] = [
+    "private_key",
+    "mnemonic",
+    "master_password",
+    "new_master_password",
+    "mnemonic_or_private_key",
+    "passphrase",
+    "key",
+];
+
+/// Label prefixes of secret lines in tool summaries, matched case-insensitively, e.g.
+/// `Private Key: 0x...` or `Mnemonic (write this down as a backup): ...`.
+const SECRET_TEXT_LABELS: [&str; 2] = ["private key", "mnemonic"];
+
+const REDACTED: &str = "[REDACTED]";
+
+/// A copy of `value` safe to log: secret fields are masked wherever they appear, and
+/// summary text lines such as `Private Key: ...` keep their label but lose the value.
+pub fn redact_secrets(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(name, v)| {
+                    let v = if SECRET_FIELDS.contains(&name.as_str()) && !v.is_null() {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        redact_secrets(v)
+                    };
+                    (name.clone(), v)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact_secrets).collect()),
+        Value::String(text) if text.lines().any(|line| secret_line_label(line).is_some()) => {
+            let lines: Vec<String> = text
+                .split('\n')
+                .map(|line| match secret_line_label(line) {
+                    Some(label) => format!("{} {}", label, REDACTED),
+                    None => line.to_string(),
+                })
+                .collect();
+            Value::String(lines.join("\n"))
+        }
+        other => other.clone(),
+    }
+}
+
+/// The `Label:` part of a summary line that carries a secret, if it is one.
+fn secret_line_label(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let (label, _) = line.split_once(':')?;
+    let lower = label.to_ascii_lowercase();
+    SECRET_TEXT_LABELS
+        .iter()
+        .any(|prefix| lower.starts_with(prefix))
+        .then(|| &line[..=label.len()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_chain_id_accepts_string_or_number() {
+        let id = json!(null);
+        assert_eq!(get_chain_id(&json!({"chain_id": 1}), &id).unwrap(), "1");
+        assert_eq!(get_chain_id(&json!({"chain_id": "1"}), &id).unwrap(), "1");
+        assert!(get_chain_id(&json!({"chain_id": 1.5}), &id).is_err());
+        assert!(get_chain_id(&json!({}), &id).is_err());
+
+        let mut args = json!({"chain_id": 11155111, "network": 1, "amount": 5});
+        stringify_chain_ids(&mut args);
+        assert_eq!(args, json!({"chain_id": "11155111", "network": "1", "amount": 5}));
+    }
+
+    #[test]
+    fn test_gwei_to_wei() {
+        assert_eq!(gwei_to_wei("20").unwrap(), U256::from(20_000_000_000u64));
+        assert_eq!(gwei_to_wei("1.5").unwrap(), U256::from(1_500_000_000u64));
+        assert_eq!(gwei_to_wei("0.000000001").unwrap(), U256::one());
+        assert!(gwei_to_wei("0.0000000001").is_err());
+        assert!(gwei_to_wei("1e9").is_err());
+        assert!(gwei_to_wei(".").is_err());
+        assert!(gwei_to_wei(&U256::MAX.to_string()).unwrap_err().to_string().contains("uint256"));
+    }
+
+    #[test]
+    fn test_gas_price_accepts_wei_or_gwei_but_not_both() {
+        let id = json!(null);
+        assert_eq!(get_gas_price(&json!({"gas_price_gwei": "20"}), &id).unwrap(), Some(U256::from(20_000_000_000u64)));
+        assert_eq!(get_gas_price(&json!({"gas_price": "20000000000"}), &id).unwrap(), Some(U256::from(20_000_000_000u64)));
+        assert_eq!(get_gas_price(&json!({}), &id).unwrap(), None);
+        let both = get_gas_price(&json!({"gas_price": "1", "gas_price_gwei": "1"}), &id).unwrap_err();
+        assert!(both.error.unwrap().message.contains("not both"));
+        assert!(get_gas_price(&json!({"gas_price": "20 gwei"}), &id).is_err());
+    }
+
+    #[test]
+    fn test_gas_limit_rejects_garbage_and_zero() {
+        let id = json!(null);
+        assert_eq!(get_gas_limit(&json!({"gas_limit": "21000"}), &id).unwrap(), Some(U256::from(21_000)));
+        assert_eq!(get_gas_limit(&json!({}), &id).unwrap(), None);
+        for bad in ["0", "21k", "-1"] {
+            let err = get_gas_limit(&json!({"gas_limit": bad}), &id).unwrap_err();
+            assert!(err.error.unwrap().message.contains("'gas_limit'"));
+        }
+    }
+
+    #[test]
+    fn test_check_address() {
+        let ok = check_address("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+        assert_eq!(ok.checksum, "valid");
+        let lower = check_address("0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266").unwrap();
+        assert_eq!(lower.checksum, "not_checksummed");
+        assert_eq!(lower.checksummed, "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let bad = check_address("0xF39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+        assert_eq!(bad.checksum, "invalid");
+
+        assert!(check_address("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb9226").unwrap_err().contains("got 39"));
+        assert!(check_address("0xg39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap_err().contains("non-hex"));
+        assert!(check_address("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap_err().contains("0x"));
+    }
+
+    const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+    #[test]
+    fn test_keccak256_hex_of_event_signature() {
+        assert_eq!(keccak256_hex(b"Transfer(address,address,uint256)"), TRANSFER_TOPIC);
+    }
+
+    #[test]
+    fn test_function_selector_known_values() {
+        assert_eq!(hex::encode(function_selector("transfer(address,uint256)")), "a9059cbb");
+        assert_eq!(hex::encode(function_selector("balanceOf(address)")), "70a08231");
+        assert_eq!(hex::encode(function_selector("function approve(address, uint256)")), "095ea7b3");
+        assert_eq!(hex::encode(function_selector("safeTransferFrom(address,address,uint256)")), "42842e0e");
+    }
+
+    #[test]
+    fn test_transactions_to_csv() {
+        let txs = vec![json!({
+            "hash": "0xabc",
+            "timeStamp": "1704067200",
+            "from": "0x1",
+            "to": "0x2",
+            "value": "1000",
+            "gasUsed": "21000",
+            "gasPrice": "2",
+            "isError": "0",
+            "txreceipt_status": "1"
+        })];
+        let csv = transactions_to_csv(&txs);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "hash,timestamp,from,to,value,gas_used,fee,status");
+        assert_eq!(lines[1], "0xabc,1704067200,0x1,0x2,1000,21000,42000,success");
+
+        let max = ethers_core::types::U256::MAX.to_string();
+        let csv = transactions_to_csv(&[json!({"hash": "0xdef", "gasUsed": max, "gasPrice": "2"})]);
+        assert!(csv.lines().nth(1).unwrap().contains(",,success"));
+    }
+
+    #[test]
+    fn test_to_csv_escapes_commas_and_quotes() {
+        let rows = vec![vec!["a,b".to_string(), "say \"hi\"".to_string(), "plain".to_string()]];
+        assert_eq!(to_csv(&["x", "y", "z"], &rows), "x,y,z\n\"a,b\",\"say \"\"hi\"\"\",plain\n");
+    }
+
+    #[test]
+    fn test_etherscan_empty_list_is_not_an_error() {
+        let no_txs = json!({"status": "0", "message": "No transactions found", "result": []});
+        assert_eq!(etherscan_list_result(&no_txs).unwrap(), json!([]));
+
+        let no_records = json!({"status": "0", "message": "NOTOK", "result": "No records found"});
+        let records = etherscan_list_result(&no_records).unwrap();
+        assert_eq!(records.as_array().map(|a| a.len()), Some(0));
+
+        let rate_limited = json!({"status": "0", "message": "NOTOK", "result": "Max rate limit reached"});
+        assert_eq!(etherscan_list_result(&rate_limited).unwrap_err(), "NOTOK");
+
+        let ok = json!({"status": "1", "message": "OK", "result": [{"hash": "0x1"}]});
+        assert_eq!(etherscan_list_result(&ok).unwrap(), json!([{"hash": "0x1"}]));
+    }
+
+    #[test]
+    fn test_redact_secrets_masks_keys_and_summary_lines() {
+        let key = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+        let response = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "address": "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23",
+                "private_key": key,
+                "mnemonic": null,
+                "content": [{
+                    "type": "text",
+                    "text": format!("Imported EVM wallet with complete details:\nAddress: 0x2c75\nPrivate Key: {}\nMnemonic: Not available", key),
+                }],
+            }
+        });
+        let redacted = redact_secrets(&response);
+        let logged = redacted.to_string();
+        assert!(!logged.contains(&key[2..]));
+        assert_eq!(redacted["result"]["private_key"], REDACTED);
+        assert_eq!(redacted["result"]["mnemonic"], Value::Null);
+        assert_eq!(
+            redacted["result"]["content"][0]["text"],
+            "Imported EVM wallet with complete details:\nAddress: 0x2c75\nPrivate Key: [REDACTED]\nMnemonic: [REDACTED]"
+        );
+        assert_eq!(redacted["result"]["address"], response["result"]["address"]);
+
+        let request = json!({"params": {"arguments": {"key": "test test junk", "master_password": "hunter2", "wallet_name": "main"}}});
+        let redacted = redact_secrets(&request);
+        assert_eq!(redacted["params"]["arguments"]["key"], REDACTED);
+        assert_eq!(redacted["params"]["arguments"]["master_password"], REDACTED);
+        assert_eq!(redacted["params"]["arguments"]["wallet_name"], "main");
+    }
+}
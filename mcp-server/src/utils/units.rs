@@ -0,0 +1,107 @@
+// src/utils/units.rs
+
+//! Conversion between integer base units (wei, token units) and decimal amounts.
+//!
+//! `format_units(1500000000000000000, 18)` is `"1.5"` and `parse_units("1.5", 18)` gives
+//! the integer back. Parsing never rounds: an amount finer than `decimals` allows is an
+//! error rather than being silently truncated.
+
+use anyhow::{anyhow, bail, Result};
+use ethers_core::types::U256;
+
+/// `value` scaled down by `decimals` as a plain decimal string, without trailing zeros:
+/// `1500000000000000000` with 18 decimals is `"1.5"`, zero is `"0"`.
+pub fn format_units(value: U256, decimals: u8) -> String {
+    let digits = value.to_string();
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return digits;
+    }
+    let padded = format!("{:0>width$}", digits, width = decimals + 1);
+    let (whole, frac) = padded.split_at(padded.len() - decimals);
+    let frac = frac.trim_end_matches('0');
+    if frac.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, frac)
+    }
+}
+
+/// Parses a decimal amount such as `"20"`, `"1.5"` or `".25"` into base units with
+/// `decimals` places. Digits beyond `decimals` are accepted only if they are zeros;
+/// signs, exponents and amounts that don't fit in a uint256 are rejected.
+pub fn parse_units(amount: &str, decimals: u8) -> Result<U256> {
+    let amount = amount.trim();
+    let (whole, frac) = amount.split_once('.').unwrap_or((amount, ""));
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && frac.is_empty()) || !digits(whole) || !digits(frac) {
+        bail!("'{}' is not a decimal amount", amount);
+    }
+    let places = decimals as usize;
+    let (frac, excess) = frac.split_at(frac.len().min(places));
+    if excess.chars().any(|c| c != '0') {
+        bail!("'{}' has more than {} decimal places", amount, decimals);
+    }
+    let combined = format!("{}{:0<places$}", whole, frac, places = places);
+    let combined = combined.trim_start_matches('0');
+    if combined.is_empty() {
+        return Ok(U256::zero());
+    }
+    U256::from_dec_str(combined).map_err(|_| anyhow!("'{}' does not fit in a uint256", amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_units() {
+        assert_eq!(format_units(U256::exp10(18) * 1_000_000_000u64, 18), "1000000000");
+        assert_eq!(format_units(U256::from(1_500_000u64), 6), "1.5");
+        assert_eq!(format_units(U256::one(), 18), "0.000000000000000001");
+        assert_eq!(format_units(U256::zero(), 18), "0");
+        assert_eq!(format_units(U256::zero(), 0), "0");
+        assert_eq!(format_units(U256::from(42u64), 0), "42");
+        assert_eq!(format_units(U256::from(1050u64), 2), "10.5");
+        // Beyond what 10^decimals fits in a uint256
+        assert_eq!(format_units(U256::from(42u64), 100), format!("0.{}42", "0".repeat(98)));
+        assert_eq!(
+            format_units(U256::MAX, 18),
+            "115792089237316195423570985008687907853269984665640564039457.584007913129639935"
+        );
+    }
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_units("1.5", 18).unwrap(), U256::exp10(17) * 15u64);
+        assert_eq!(parse_units("20", 9).unwrap(), U256::from(20_000_000_000u64));
+        assert_eq!(parse_units(".25", 2).unwrap(), U256::from(25u64));
+        assert_eq!(parse_units("7.", 0).unwrap(), U256::from(7u64));
+        assert_eq!(parse_units(" 0.000000000000000001 ", 18).unwrap(), U256::one());
+        assert_eq!(parse_units("0", 18).unwrap(), U256::zero());
+        assert_eq!(parse_units("000.000", 6).unwrap(), U256::zero());
+        // Trailing zeros past the precision are harmless
+        assert_eq!(parse_units("1.50000", 2).unwrap(), U256::from(150u64));
+        assert_eq!(parse_units("42.000", 0).unwrap(), U256::from(42u64));
+    }
+
+    #[test]
+    fn test_parse_units_rejects_bad_input() {
+        // No rounding: 1.005 with 2 decimals would lose half a unit
+        assert!(parse_units("1.005", 2).unwrap_err().to_string().contains("more than 2 decimal places"));
+        assert!(parse_units("0.5", 0).is_err());
+        for bad in ["", ".", "-1", "+1", "1e18", "1.2.3", "abc", "1,5"] {
+            assert!(parse_units(bad, 18).is_err(), "{:?} should be rejected", bad);
+        }
+        let too_big = format!("{}0", U256::MAX);
+        assert!(parse_units(&too_big, 0).unwrap_err().to_string().contains("uint256"));
+        assert!(parse_units("1", 78).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for (raw, decimals) in [(U256::MAX, 18u8), (U256::from(123_456_789u64), 6), (U256::from(5u64), 0)] {
+            assert_eq!(parse_units(&format_units(raw, decimals), decimals).unwrap(), raw);
+        }
+    }
+}